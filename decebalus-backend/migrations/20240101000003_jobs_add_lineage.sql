@@ -0,0 +1,5 @@
+-- Track which job spawned which, and the pipeline run a job belongs to
+ALTER TABLE jobs ADD COLUMN parent_job_id TEXT NULL;
+ALTER TABLE jobs ADD COLUMN run_id TEXT NULL;
+
+CREATE INDEX idx_jobs_parent_job_id ON jobs(parent_job_id);
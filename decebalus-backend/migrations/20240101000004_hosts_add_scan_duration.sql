@@ -0,0 +1,2 @@
+-- Wall-clock time of the most recent port scan, to spot slow or filtered hosts
+ALTER TABLE hosts ADD COLUMN last_scan_duration_ms INTEGER NULL;
@@ -0,0 +1,2 @@
+-- When the host was last port-scanned (RFC 3339), used by the rescan scheduler
+ALTER TABLE hosts ADD COLUMN last_port_scan TEXT NULL;
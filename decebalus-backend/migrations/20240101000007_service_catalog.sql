@@ -0,0 +1,10 @@
+-- Every distinct service/version ever fingerprinted, independent of current host state.
+-- Unknown versions are stored as '' so they take part in the primary key.
+CREATE TABLE IF NOT EXISTS service_catalog (
+    name TEXT NOT NULL,
+    version TEXT NOT NULL DEFAULT '',
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    sightings INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (name, version)
+);
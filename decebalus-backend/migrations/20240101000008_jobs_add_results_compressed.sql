@@ -0,0 +1,2 @@
+-- 1 when `results` holds base64-encoded gzip instead of plain text
+ALTER TABLE jobs ADD COLUMN results_compressed INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Automatic retries: attempts used so far, and the per-job limit (NULL = config default)
+ALTER TABLE jobs ADD COLUMN retries INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE jobs ADD COLUMN max_retries INTEGER NULL;
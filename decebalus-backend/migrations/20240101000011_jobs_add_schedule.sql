@@ -0,0 +1,2 @@
+-- Recurring jobs: cron expression to re-schedule by after each run (NULL = one-shot)
+ALTER TABLE jobs ADD COLUMN schedule TEXT NULL;
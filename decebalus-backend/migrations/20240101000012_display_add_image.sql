@@ -0,0 +1,2 @@
+-- Last image pushed to the e-paper display (file under data/display)
+ALTER TABLE display_status ADD COLUMN image_path TEXT NULL;
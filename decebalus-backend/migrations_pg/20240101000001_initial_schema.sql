@@ -0,0 +1,86 @@
+-- Postgres schema, equivalent to the SQLite migrations in ../migrations.
+-- Timestamps exposed to the API are kept as TEXT in the same formats SQLite produces.
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    seq BIGSERIAL NOT NULL, -- insertion order, tie-breaker when claiming
+    job_type TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued',
+    priority INTEGER NOT NULL DEFAULT 1,
+    results TEXT,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    scheduled_at BIGINT,
+    config TEXT NOT NULL,
+    parent_job_id TEXT NULL,
+    run_id TEXT NULL
+);
+
+CREATE INDEX idx_jobs_status ON jobs(status);
+CREATE INDEX idx_jobs_created_at ON jobs(created_at DESC);
+CREATE INDEX idx_jobs_parent_job_id ON jobs(parent_job_id);
+
+CREATE TABLE IF NOT EXISTS hosts (
+    ip TEXT PRIMARY KEY NOT NULL,
+    ports TEXT NOT NULL DEFAULT '[]',
+    banners TEXT NOT NULL DEFAULT '[]',
+    last_seen TEXT NOT NULL,
+    first_seen TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"'),
+    os TEXT NULL,
+    os_version TEXT NULL,
+    device_type TEXT NULL,
+    mac_address TEXT NULL,
+    hostname TEXT NULL,
+    status TEXT NOT NULL DEFAULT 'Unknown',
+    services TEXT NOT NULL DEFAULT '[]',
+    vulnerabilities TEXT NOT NULL DEFAULT '[]',
+    last_scan_duration_ms BIGINT NULL,
+    last_port_scan TEXT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_hosts_last_seen ON hosts(last_seen DESC);
+
+CREATE TABLE IF NOT EXISTS vulnerabilities (
+    id BIGSERIAL PRIMARY KEY,
+    host_ip TEXT NOT NULL REFERENCES hosts(ip) ON DELETE CASCADE,
+    port INTEGER,
+    vulnerability_name TEXT NOT NULL,
+    severity TEXT,
+    description TEXT,
+    discovered_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_vulns_host ON vulnerabilities(host_ip);
+
+CREATE TABLE IF NOT EXISTS config (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS display_status (
+    id INTEGER PRIMARY KEY DEFAULT 1,
+    status TEXT NOT NULL DEFAULT 'idle',
+    last_update TEXT NOT NULL DEFAULT 'never',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (id = 1)
+);
+
+CREATE TABLE IF NOT EXISTS logs (
+    id TEXT PRIMARY KEY NOT NULL,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    severity TEXT NOT NULL DEFAULT 'INFO',
+    service TEXT NOT NULL,
+    module TEXT NULL,
+    job_id TEXT NULL,
+    content TEXT NOT NULL
+);
+
+CREATE INDEX idx_logs_created_at ON logs(created_at);
+CREATE INDEX idx_logs_job_id ON logs(job_id);
+CREATE INDEX idx_logs_level ON logs(severity);
+CREATE INDEX idx_logs_service ON logs(service);
+
+INSERT INTO display_status (id, status, last_update) VALUES (1, 'idle', 'never');
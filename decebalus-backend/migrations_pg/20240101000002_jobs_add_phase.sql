@@ -0,0 +1,2 @@
+-- Current step of a multi-phase job, e.g. "service-detection (2/3)"
+ALTER TABLE jobs ADD COLUMN phase TEXT NULL;
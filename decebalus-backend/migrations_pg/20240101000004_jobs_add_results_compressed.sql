@@ -0,0 +1,2 @@
+-- TRUE when `results` holds base64-encoded gzip instead of plain text
+ALTER TABLE jobs ADD COLUMN results_compressed BOOLEAN NOT NULL DEFAULT FALSE;
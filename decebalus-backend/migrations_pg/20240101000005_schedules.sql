@@ -0,0 +1,12 @@
+-- Named, reusable job recipes fired on a cron schedule.
+-- Timestamps are unix seconds, like jobs.scheduled_at.
+CREATE TABLE IF NOT EXISTS schedules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    job_type TEXT NOT NULL,
+    target TEXT,
+    cron TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at BIGINT NOT NULL,
+    last_run_at BIGINT
+);
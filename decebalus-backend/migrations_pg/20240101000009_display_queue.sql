@@ -0,0 +1,7 @@
+-- Messages the display rotates through, oldest first.
+-- created_at is unix seconds, like schedules.created_at.
+CREATE TABLE IF NOT EXISTS display_queue (
+    id BIGSERIAL PRIMARY KEY,
+    text TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
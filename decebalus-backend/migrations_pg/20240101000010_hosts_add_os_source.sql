@@ -0,0 +1,2 @@
+-- Where hosts.os came from (nmap, banner, ttl, ports); a ttl guess is low confidence
+ALTER TABLE hosts ADD COLUMN os_source TEXT NULL;
@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Query, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::api::error::AppError;
+use crate::state::AppState;
+
+/// Browsers can't set headers on WebSocket or EventSource connections, so the
+/// token may also come as `?access_token=`.
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    access_token: Option<String>,
+}
+
+/// Middleware: reject requests without `Authorization: Bearer <API_TOKEN>`.
+/// Lets everything through when no token is configured.
+pub async fn require_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(expected) = state.api_token.as_deref() else {
+        return Ok(next.run(req).await);
+    };
+
+    let from_header = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let token = from_header.or_else(|| {
+        Query::<TokenQuery>::try_from_uri(req.uri()).ok().and_then(|q| q.0.access_token)
+    });
+
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(next.run(req).await),
+        Some(_) => Err(AppError::Unauthorized("Invalid API token".to_string())),
+        None => Err(AppError::Unauthorized("Missing API token".to_string())),
+    }
+}
+
+/// Compare without bailing out at the first differing byte, so response
+/// timing doesn't reveal how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_compares_whole_tokens() {
+        assert!(constant_time_eq(b"s3cret", b"s3cret"));
+        assert!(!constant_time_eq(b"s3cret", b"s3creT"));
+        assert!(!constant_time_eq(b"s3cret", b"s3cret2"));
+        assert!(!constant_time_eq(b"", b"s3cret"));
+    }
+}
@@ -1,61 +1,116 @@
 use axum::{
     extract::State,
     response::IntoResponse,
-    http::StatusCode,
+    http::header,
     Json,
 };
 use std::sync::Arc;
 use serde_json::{json, Value};
+use crate::api::error::{AppError, FieldError};
+use crate::config_check;
 use crate::state::AppState;
-use crate::db::repository;
+use crate::models::{Config, WsEvent};
 
 
 /// Get current configuration
 /// GET /api/config
-pub async fn get_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-   match repository::get_config(&state.db).await {
-        Ok(config) => Json(json!({
-            "status": "success",
-            "config": config
-        })).into_response(),
-
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": e.to_string()
-            }))
-        ).into_response(),
-    }
+pub async fn get_config(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    let config = state.repo.get_config()
+        .await
+        .map_err(|e| AppError::internal("Failed to load config", e))?;
+    Ok(Json(json!({
+        "status": "success",
+        "config": config
+    })))
 }
 
 /// Update configuration
 /// POST /api/config
 /// Body: { "key": "value", ... } (any JSON object)
+/// Oversized configs are rejected with 413, overlong or deeply nested values with 400.
+/// Known sections such as `scan_config` must match their schema, otherwise 400
+/// lists the offending fields; unknown keys are stored unchecked.
 pub async fn update_config(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<Value>,
-) -> impl IntoResponse {
-    let mut config = match repository::get_config(&state.db).await {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::error!("Failed to load config: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "status": "error", "message": e.to_string() })),
-            ).into_response();
-        }
-    };
-
-    config.settings = payload;
-
-    if let Err(e) = repository::update_config(&state.db, &config).await {
-        tracing::error!("Failed to update config: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "status": "error", "message": e.to_string() })),
-        ).into_response();
+) -> Result<Json<Value>, AppError> {
+    replace_config(&state, Config { settings: payload }, "Configuration updated successfully").await
+}
+
+/// Download the full configuration as a JSON file
+/// GET /api/config/export
+pub async fn export_config(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+    let config = state.repo.get_config()
+        .await
+        .map_err(|e| AppError::internal("Failed to export config", e))?;
+    Ok((
+        [(header::CONTENT_DISPOSITION, "attachment; filename=\"decebalus-config.json\"")],
+        Json(config),
+    ))
+}
+
+/// Replace the configuration with a previously exported one
+/// POST /api/config/import
+/// Body: the JSON produced by `GET /api/config/export`
+/// Validated like `POST /api/config`; `settings` must also be a JSON object.
+pub async fn import_config(
+    State(state): State<Arc<AppState>>,
+    Json(imported): Json<Config>,
+) -> Result<Json<Value>, AppError> {
+    if !imported.settings.is_object() {
+        return Err(AppError::BadRequest("Imported settings must be a JSON object".to_string()));
     }
 
-    Json(json!({ "status": "success", "message": "Configuration updated successfully" })).into_response()
-}
\ No newline at end of file
+    replace_config(&state, imported, "Configuration imported successfully").await
+}
+
+/// Validate `candidate`, store it in place of the current config and
+/// broadcast the changed keys.
+async fn replace_config(state: &AppState, candidate: Config, success: &str) -> Result<Json<Value>, AppError> {
+    if let Err(e) = candidate.check_limits() {
+        tracing::warn!("Rejected config update: {}", e);
+        return Err(if e.is_too_large() {
+            AppError::PayloadTooLarge(e.to_string())
+        } else {
+            AppError::BadRequest(e.to_string())
+        });
+    }
+
+    // Known sections must have the right shape; unknown keys are kept as they are
+    let problems = config_check::check_config(&candidate);
+    if !problems.is_empty() {
+        tracing::warn!("Rejected config update: {}", problems.join("; "));
+        return Err(AppError::Invalid {
+            message: format!("Config has {} invalid field(s)", problems.len()),
+            fields: problems.into_iter().map(field_error).collect(),
+        });
+    }
+
+    let mut config = state.repo.get_config()
+        .await
+        .map_err(|e| AppError::internal("Failed to load config", e))?;
+
+    let previous = config.clone();
+    config.settings = candidate.settings;
+
+    state.repo.update_config(&config)
+        .await
+        .map_err(|e| AppError::internal("Failed to update config", e))?;
+    state.config.set(config.clone()).await;
+
+    // Let connected dashboards know which keys to refresh
+    let changed = previous.changed_keys(&config);
+    if !changed.is_empty() {
+        let _ = state.broadcaster.send(WsEvent::ConfigChanged { keys: changed });
+    }
+
+    Ok(Json(json!({ "status": "success", "message": success })))
+}
+
+/// `check_config` words each problem as `field: message`.
+fn field_error(problem: String) -> FieldError {
+    match problem.split_once(": ") {
+        Some((field, message)) => FieldError { field: field.to_string(), message: message.to_string() },
+        None => FieldError { field: "$".to_string(), message: problem },
+    }
+}
@@ -1,56 +1,112 @@
 use axum::{
     extract::State,
-    response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::IntoResponse,
     Json,
 };
-use chrono::Utc;
+use std::path::Path;
 use std::sync::Arc;
 use serde_json::{json, Value};
-use crate::models::DisplayStatus;
+use crate::api::error::AppError;
+use crate::models::{DisplayMessage, DisplayStatus, PushDisplayMessageRequest};
+use crate::services::display::{self, PanelImage};
 use crate::state::AppState;
-use crate::db::repository;
 
 /// Get e-paper display status
 /// GET /api/display/status
-pub async fn get_display_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match repository::get_display_status(&state.db).await {
-        Ok(status) => Json(status).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": format!("Failed to get display status: {}", e) })),
-        ).into_response(),
-    }
+pub async fn get_display_status(State(state): State<Arc<AppState>>) -> Result<Json<DisplayStatus>, AppError> {
+    state.repo.get_display_status()
+        .await
+        .map(Json)
+        .map_err(|e| AppError::internal("Failed to get display status", e))
 }
 
 /// Update e-paper display
 /// POST /api/display/update
 /// Body: { "text": "Status message", "image": "optional_base64_image" }
+///
+/// `image` is a base64 PNG or BMP sized for the panel (`display.width` x
+/// `display.height`, either way round); anything else is a 400. With only
+/// `text`, the text is rendered into the panel image instead. The text is
+/// shown straight away and also pushed onto the display queue.
 pub async fn update_display(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<Value>,
-) -> Response {
-    let text = payload
-        .get("text")
-        .and_then(|v| v.as_str())
-        .unwrap_or("No text provided");
-
-    // Create a new DisplayStatus instance
-    let new_status = DisplayStatus {
-        status: text.to_string(),
-        last_update: Utc::now().to_rfc3339(),
+) -> Result<Json<Value>, AppError> {
+    let given_text = payload.get("text").and_then(|v| v.as_str());
+    let text = given_text.unwrap_or("No text provided");
+
+    let panel = state.config.get(state.repo.as_ref()).await.map(|c| c.display_config()).unwrap_or_default();
+    let image = match (payload.get("image"), given_text) {
+        (None | Some(Value::Null), None) => None,
+        (None | Some(Value::Null), Some(text)) => Some(
+            PanelImage::from_text(text, &panel).map_err(|e| AppError::internal("Failed to render display text", e))?,
+        ),
+        (Some(Value::String(encoded)), _) => Some(PanelImage::from_base64(encoded, &panel).map_err(AppError::BadRequest)?),
+        (Some(_), _) => return Err(AppError::BadRequest("image must be a base64 string".to_string())),
     };
 
-    if let Err(e) = repository::update_display_status(&state.db, &new_status).await {
-        tracing::error!("Failed to update display status: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Failed to update display status" })),
-        ).into_response();
+    if let Some(text) = given_text {
+        state.repo.push_display_message(text, panel.queue_size)
+            .await
+            .map_err(|e| AppError::internal("Failed to queue display message", e))?;
     }
+    display::show(&state, text, image.as_ref())
+        .await
+        .map_err(|e| AppError::internal("Failed to update display", e))?;
 
-    let _ = state.broadcaster.send(format!("display_updated:{}", text));
+    Ok(Json(json!({ "status": "success", "message": format!("Display updated: {}", text) })))
+}
 
-    Json(json!({ "status": "success", "message": format!("Display updated: {}", text) })).into_response()
+/// Add a message to the display queue; the display rotates through the
+/// queue every `display.rotate_interval_secs`
+/// POST /api/display/queue
+/// Body: { "text": "Status message" }
+pub async fn push_display_message(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<PushDisplayMessageRequest>,
+) -> Result<(StatusCode, Json<DisplayMessage>), AppError> {
+    if payload.text.trim().is_empty() {
+        return Err(AppError::BadRequest("text must not be empty".to_string()));
+    }
+
+    let panel = state.config.get(state.repo.as_ref()).await.map(|c| c.display_config()).unwrap_or_default();
+    let message = state.repo.push_display_message(&payload.text, panel.queue_size)
+        .await
+        .map_err(|e| AppError::internal("Failed to queue display message", e))?;
+    Ok((StatusCode::CREATED, Json(message)))
 }
 
+/// Queued display messages, in rotation order
+/// GET /api/display/queue
+pub async fn list_display_messages(State(state): State<Arc<AppState>>) -> Result<Json<Vec<DisplayMessage>>, AppError> {
+    state.repo.list_display_messages()
+        .await
+        .map(Json)
+        .map_err(|e| AppError::internal("Failed to list display messages", e))
+}
+
+/// Empty the display queue; the panel keeps showing its current message
+/// DELETE /api/display/queue
+pub async fn clear_display_messages(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    let cleared = state.repo.clear_display_messages()
+        .await
+        .map_err(|e| AppError::internal("Failed to clear display queue", e))?;
+    Ok(Json(json!({ "cleared": cleared })))
+}
+
+/// The image currently on the e-paper display
+/// GET /api/display/image
+pub async fn get_display_image(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+    let status = state.repo.get_display_status()
+        .await
+        .map_err(|e| AppError::internal("Failed to get display status", e))?;
+    let Some(path) = status.image else {
+        return Err(AppError::NotFound("No image has been sent to the display".to_string()));
+    };
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| AppError::internal("Failed to read display image", e))?;
+    Ok(([(header::CONTENT_TYPE, display::content_type(Path::new(&path)))], bytes))
+}
@@ -0,0 +1,157 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::json;
+use crate::error::Error;
+
+/// One invalid field in a request body, e.g. `scan_config.ports`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Error returned by API handlers.
+///
+/// Every variant renders as `{"error": {"code": ..., "message": ...}}` with a
+/// matching status, so clients can rely on one shape for all failures.
+/// `Invalid` adds `"fields": [{"field": ..., "message": ...}]`.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    /// Missing or wrong API token.
+    #[error("{0}")]
+    Unauthorized(String),
+
+    /// The request clashes with the resource's current state.
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    PayloadTooLarge(String),
+
+    /// A 400 naming each field that failed validation.
+    #[error("{message}")]
+    Invalid { message: String, fields: Vec<FieldError> },
+
+    /// Server-side failure. The message is sent to the client, so it should
+    /// say what failed without internal details; see [`AppError::internal`].
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Log `e` and return an `Internal` error that only carries `message`.
+    pub fn internal(message: &str, e: impl std::fmt::Display) -> Self {
+        tracing::error!("{}: {}", message, e);
+        Self::Internal(message.to_string())
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::BadRequest(_) | Self::Invalid { .. } => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Machine-readable `error.code` in the response body.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Conflict(_) => "conflict",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::Invalid { .. } => "invalid",
+            Self::Internal(_) => "internal",
+        }
+    }
+}
+
+impl From<Error> for AppError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::NotFound(msg) => Self::NotFound(msg),
+            Error::BadRequest(msg) | Error::Config(msg) => Self::BadRequest(msg),
+            // Internal details go to the log, not to the client
+            Error::Database(_) => Self::internal("Database error", e),
+            Error::Serialization(_) => Self::internal("Serialization error", e),
+            Error::Scan(msg) => Self::Internal(msg),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        Error::from(e).into()
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let mut body = json!({ "error": { "code": self.code(), "message": self.to_string() } });
+        if let Self::Invalid { fields, .. } = &self {
+            body["error"]["fields"] = json!(fields);
+        }
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(err: AppError) -> (StatusCode, serde_json::Value) {
+        let response = err.into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn renders_the_error_envelope() {
+        let (status, body) = body(AppError::Conflict("Job is running".into())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body, json!({ "error": { "code": "conflict", "message": "Job is running" } }));
+    }
+
+    #[tokio::test]
+    async fn invalid_lists_its_fields() {
+        let field = FieldError { field: "scan_config.ports".into(), message: "expected a sequence".into() };
+        let (status, body) = body(AppError::Invalid { message: "Invalid config".into(), fields: vec![field] }).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "invalid");
+        assert_eq!(body["error"]["fields"], json!([{ "field": "scan_config.ports", "message": "expected a sequence" }]));
+    }
+
+    #[tokio::test]
+    async fn crate_errors_keep_their_status() {
+        let cases = [
+            (Error::NotFound("gone".into()), StatusCode::NOT_FOUND, "not_found"),
+            (Error::Config("bad value".into()), StatusCode::BAD_REQUEST, "bad_request"),
+            (Error::Database(sqlx::Error::PoolTimedOut), StatusCode::INTERNAL_SERVER_ERROR, "internal"),
+        ];
+        for (err, status, code) in cases {
+            let (actual, body) = body(err.into()).await;
+            assert_eq!(actual, status);
+            assert_eq!(body["error"]["code"], code);
+        }
+    }
+
+    #[test]
+    fn missing_rows_are_not_found() {
+        assert!(matches!(AppError::from(sqlx::Error::RowNotFound), AppError::NotFound(_)));
+    }
+}
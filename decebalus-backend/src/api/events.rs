@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use crate::api::websocket::{event_json, Subscription};
+use crate::state::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SseQuery {
+    /// Only replay buffered events numbered after this
+    pub since: Option<u64>,
+    /// Comma-separated topics, same as a WebSocket subscribe (default: all)
+    pub topics: Option<String>,
+}
+
+/// Server-sent events, for clients that can't speak WebSocket
+/// GET /api/events?since=<seq>&topics=job:abc123,hosts
+///
+/// Each event is a `data:` line with the same JSON the WebSocket sends and
+/// its `seq` as the SSE `id`. Buffered events come first, as on the WebSocket.
+pub async fn event_stream(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SseQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let topics = query
+        .topics
+        .map(|t| t.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect())
+        .unwrap_or_default();
+    let subscription = Subscription::new(topics);
+
+    // Subscribe now rather than on first poll, so nothing sent after the request is missed
+    let replay = state.broadcaster.subscribe_since(query.since);
+    let live = stream::unfold((replay.rx, replay.next_seq), |(mut rx, mut next_seq)| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let seq = next_seq;
+                    next_seq += 1;
+                    return Some(((seq, event), (rx, next_seq)));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("SSE client lagged, skipped {} events", skipped);
+                    next_seq += skipped;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    // When the client disconnects axum drops the stream, which ends the subscription
+    let events = stream::iter(replay.events)
+        .chain(live)
+        .filter(move |(_, event)| std::future::ready(subscription.matches(event)))
+        .filter_map(|(seq, event)| async move {
+            let json = event_json(seq, &event)?;
+            Some(Ok(Event::default().id(seq.to_string()).data(json)))
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,35 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::state::AppState;
+
+/// How long the database ping may take before the DB counts as down.
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness/readiness check, reachable without an API token
+/// GET /health
+///
+/// 200 when the database answers, 503 when it doesn't. Both report how many
+/// job workers are busy.
+pub async fn health(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let ping = state.repo.ping();
+    let db_up = matches!(tokio::time::timeout(DB_PING_TIMEOUT, ping).await, Ok(Ok(_)));
+    if !db_up {
+        tracing::warn!("Health check: database unreachable");
+    }
+
+    let running_jobs = state.max_threads.saturating_sub(state.semaphore.available_permits());
+    let (status, body_status, db) = if db_up {
+        (StatusCode::OK, "ok", "up")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable", "down")
+    };
+
+    (status, Json(json!({
+        "status": body_status,
+        "db": db,
+        "running_jobs": running_jobs,
+        "max_threads": state.max_threads,
+    })))
+}
@@ -1,44 +1,91 @@
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::StatusCode,
     Json,
 };
 use std::sync::Arc;
-use serde_json::json;
+use chrono::Utc;
+use serde::Deserialize;
+use crate::api::error::AppError;
+use crate::models::{Host, HostGraph, WsEvent};
 use crate::state::AppState;
-use crate::db::repository;
 
-/// List all discovered hosts
-pub async fn list_hosts(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match repository::list_hosts(&state.db).await {
-        Ok(hosts) => Json(hosts).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to list hosts: {}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to list hosts"})),
-            ).into_response()
-        }
+#[derive(Debug, Default, Deserialize)]
+pub struct ListHostsQuery {
+    /// `ip` (default), `scan_time` (slowest last scan first) or
+    /// `data_quality` (most trustworthy first)
+    pub sort: Option<String>,
+    /// Only hosts with this port open
+    pub port: Option<u16>,
+    /// Only hosts whose IP or hostname contains this (case-insensitive)
+    pub q: Option<String>,
+}
+
+enum HostSort {
+    Ip,
+    ScanTime,
+    DataQuality,
+}
+
+/// List discovered hosts
+/// GET /api/hosts?port=22&q=192.168&sort=scan_time
+pub async fn list_hosts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListHostsQuery>,
+) -> Result<Json<Vec<Host>>, AppError> {
+    let sort = match query.sort.as_deref() {
+        None | Some("ip") => HostSort::Ip,
+        Some("scan_time") => HostSort::ScanTime,
+        Some("data_quality") => HostSort::DataQuality,
+        Some(other) => return Err(AppError::BadRequest(format!("Unknown sort: {}", other))),
+    };
+
+    let now = Utc::now();
+    let q = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let mut hosts: Vec<Host> = state.repo.list_hosts()
+        .await?
+        .into_iter()
+        .filter(|h| query.port.is_none_or(|port| h.has_open_port(port)))
+        .filter(|h| q.is_none_or(|q| h.matches_search(q)))
+        .map(|h| h.with_data_quality(now))
+        .collect();
+    match sort {
+        HostSort::Ip => {}
+        // Slowest first; hosts never scanned go last
+        HostSort::ScanTime => hosts.sort_by_key(|h| std::cmp::Reverse(h.last_scan_duration_ms)),
+        HostSort::DataQuality => hosts.sort_by_key(|h| std::cmp::Reverse(h.data_quality)),
     }
+    Ok(Json(hosts))
+}
+
+/// Network topology of all hosts: subnet, host and service nodes with the edges between them
+/// GET /api/hosts/graph
+pub async fn host_graph(State(state): State<Arc<AppState>>) -> Result<Json<HostGraph>, AppError> {
+    let hosts = state.repo.list_hosts().await?;
+    Ok(Json(HostGraph::from_hosts(&hosts)))
 }
 
 /// Get details for a specific host by IP
 pub async fn get_host(
     State(state): State<Arc<AppState>>,
     Path(ip): Path<String>,
-) -> impl IntoResponse {
-    match repository::get_host(&state.db, &ip).await {
-        Ok(Some(host)) => (axum::http::StatusCode::OK, Json(host)).into_response(),
-        Ok(None) => (
-            axum::http::StatusCode::NOT_FOUND,
-            Json(json!({"error": format!("Host with IP {} not found", ip)})),
-        ).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to get host: {}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get host"})),
-            ).into_response()
-        }
+) -> Result<Json<Host>, AppError> {
+    state.repo.get_host(&ip)
+        .await?
+        .map(|h| Json(h.with_data_quality(Utc::now())))
+        .ok_or_else(|| AppError::NotFound(format!("Host with IP {} not found", ip)))
+}
+
+/// Remove a host from the inventory, e.g. one that has left the network for good.
+/// It comes back if a later discovery finds it again.
+/// DELETE /api/hosts/{ip}
+pub async fn delete_host(
+    State(state): State<Arc<AppState>>,
+    Path(ip): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if !state.repo.delete_host(&ip).await? {
+        return Err(AppError::NotFound(format!("Host with IP {} not found", ip)));
     }
-}
\ No newline at end of file
+    let _ = state.broadcaster.send(WsEvent::HostRemoved { ip });
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,42 +1,64 @@
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
     Json,
 };
 use chrono::Utc;
 use std::sync::Arc;
 use axum::http::StatusCode;
-use axum::response::Response;
 use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
-use crate::models::{CreateJobRequest, Job};
+use crate::api::error::AppError;
+use crate::models::{CreateJobRequest, Job, JobStatus, RetryFailedRequest, Schedule, UpdateJobRequest, WsEvent};
 use crate::state::AppState;
 use crate::services::JobExecutor;
-use crate::db::{repository, DbPool};
+use crate::services::export::ExportFormat;
+use crate::db;
+use crate::db::repository_trait::Repository;
+
+/// Page size when `limit` isn't given.
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// Largest page `GET /api/jobs` returns; bigger limits are capped to this.
+const MAX_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListJobsQuery {
+    /// Page size, 1 to 200 (default 50)
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Only jobs with this status, e.g. `completed`; an unknown status is a 400
+    pub status: Option<JobStatus>,
+}
+
+/// One page of jobs, newest first, with the number of jobs matching the filter.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobPage {
+    pub items: Vec<Job>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+}
 
 /// Create a new job
 pub async fn create_job(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<CreateJobRequest>,
-) -> impl IntoResponse {
+) -> Result<(StatusCode, Json<Job>), AppError> {
+    // A start time or cron schedule makes this a scheduled job, same as POST /api/jobs/schedule
+    if payload.scheduled_at.is_some() || payload.cron.is_some() {
+        return schedule_job(State(state), Json(payload)).await;
+    }
 
-    let job = match parse_job_from_request(&payload) {
-        Ok(job) => job,
-        Err(resp) => return resp
-    };
+    let default_target = configured_target(&state).await?;
+    let job = parse_job_from_request(&payload, default_target.as_deref())?;
 
     // Save to database
-    if let Err(e) = repository::create_job(&state.db, &job).await {
-        tracing::error!("Failed to create job in database: {}", e);
-        return (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to create job"})),
-        ).into_response();
-    }
+    persist_job(state.repo.as_ref(), &job).await?;
 
-    let _ = state
-        .broadcaster
-        .send(format!("job_queued:{}:{}", job.id, job.job_type));
+    let _ = state.broadcaster.send(WsEvent::JobQueued {
+        job_id: job.id.clone(),
+        job_type: job.job_type.clone(),
+    });
 
     // Spawn job execution in background
     let state_clone = state.clone();
@@ -44,207 +66,308 @@ pub async fn create_job(
         JobExecutor::run_queue(&state_clone).await;
     });
 
-    (axum::http::StatusCode::CREATED, Json(job)).into_response()
+    Ok((StatusCode::CREATED, Json(job)))
 }
 
 pub async fn schedule_job(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<CreateJobRequest>,
-) -> impl IntoResponse {
+) -> Result<(StatusCode, Json<Job>), AppError> {
 
-    if payload.scheduled_at.is_none() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "scheduled_at is required for scheduled jobs" })),
-        ).into_response();
+    if payload.scheduled_at.is_none() && payload.cron.is_none() {
+        return Err(AppError::BadRequest("scheduled_at or cron is required for scheduled jobs".to_string()));
     }
 
-    let mut job = match parse_job_from_request(&payload) {
-        Ok(job) => job,
-        Err(resp) => return resp
-    };
-    job.status = "scheduled".to_string();
+    let default_target = configured_target(&state).await?;
+    let mut job = parse_job_from_request(&payload, default_target.as_deref())?;
+    job.status = JobStatus::Scheduled;
 
-    if let Err(resp) = persist_job(&state.db, &job).await {
-        return resp;
-    }
+    persist_job(state.repo.as_ref(), &job).await?;
 
-    let _ = state
-        .broadcaster
-        .send(format!("job_scheduled:{}:{}:{}", job.id, job.job_type, job.scheduled_at.unwrap_or(0)));
+    let _ = state.broadcaster.send(WsEvent::JobScheduled {
+        job_id: job.id.clone(),
+        job_type: job.job_type.clone(),
+        scheduled_at: job.scheduled_at.unwrap_or(0),
+    });
     tracing::info!("job_scheduled:{}:{}:{}", job.id, job.job_type, job.scheduled_at.unwrap_or(0));
 
-    (axum::http::StatusCode::CREATED, Json(job)).into_response()
+    Ok((StatusCode::CREATED, Json(job)))
 }
 
-/// List all jobs
-pub async fn list_jobs(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match repository::list_jobs(&state.db).await {
-        Ok(jobs) => Json(jobs).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to list jobs: {}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to list jobs"})),
-            ).into_response()
-        }
-    }
+/// List jobs, newest first
+/// GET /api/jobs?limit=50&offset=0&status=completed
+pub async fn list_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListJobsQuery>,
+) -> Result<Json<JobPage>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+
+    let (items, total) = state.repo.list_jobs_paged(query.status, limit, offset)
+        .await
+        .map_err(|e| AppError::internal("Failed to list jobs", e))?;
+
+    Ok(Json(JobPage { items, total, limit, offset }))
 }
 
 /// Get a specific job by ID
 pub async fn get_job(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-    match repository::get_job(&state.db, &id).await {
-        Ok(Some(job)) => (axum::http::StatusCode::OK, Json(job)).into_response(),
-        Ok(None) => (
-            axum::http::StatusCode::NOT_FOUND,
-            Json(json!({"error": format!("Job with ID {} not found", id)})),
-        ).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to get job: {}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get job"})),
-            ).into_response()
+) -> Result<Json<Job>, AppError> {
+    find_job(state.repo.as_ref(), &id).await.map(Json)
+}
+
+/// Change the priority of a job that hasn't started yet
+/// PATCH /api/jobs/{id}
+/// Body: { "priority": "CRITICAL" }
+///
+/// Only queued jobs can be reprioritised (409 otherwise); the next queue pass
+/// picks jobs in the new order.
+pub async fn update_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateJobRequest>,
+) -> Result<Json<Job>, AppError> {
+    let job = find_job(state.repo.as_ref(), &id).await?;
+    if !job.is_queued() {
+        return Err(AppError::Conflict(format!("Job is {}; only queued jobs can change priority", job.status)));
+    }
+
+    let updated = state.repo.update_job_priority(&id, payload.priority)
+        .await
+        .map_err(|e| AppError::internal("Failed to update job priority", e))?;
+    match updated {
+        Some(job) => {
+            tracing::info!("Job {} priority set to {:?}", id, job.priority);
+            Ok(Json(job))
         }
+        // Claimed by a worker or cancelled since the lookup above
+        None => Err(AppError::Conflict(format!("Job {} is no longer queued", id))),
+    }
+}
+
+/// Requeue failed jobs in bulk
+/// POST /api/jobs/retry-failed
+/// Body (optional): { "job_type": "port-scan", "since": 1700000000, "until": 1700003600 }
+pub async fn retry_failed_jobs(
+    State(state): State<Arc<AppState>>,
+    payload: Option<Json<RetryFailedRequest>>,
+) -> Result<Json<Value>, AppError> {
+    let filter = payload.map(|Json(p)| p).unwrap_or_default();
+
+    let requeued = state.repo.requeue_failed_jobs(
+        filter.job_type.as_deref(),
+        filter.since,
+        filter.until,
+    )
+        .await
+        .map_err(|e| AppError::internal("Failed to requeue failed jobs", e))?;
+
+    if requeued > 0 {
+        let _ = state.broadcaster.send(WsEvent::JobsRequeued { count: requeued });
+
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            JobExecutor::run_queue(&state_clone).await;
+        });
+    }
+
+    Ok(Json(json!({ "requeued": requeued })))
+}
+
+/// Re-run a single failed or cancelled job
+/// POST /api/jobs/{id}/retry
+pub async fn retry_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let job = find_job(state.repo.as_ref(), &id).await?;
+
+    if !job.is_failed() && !job.is_cancelled() {
+        return Err(AppError::Conflict(format!(
+            "Job is {}; only failed or cancelled jobs can be retried",
+            job.status
+        )));
     }
+
+    let requeued = async {
+        state.repo.update_job_results(&id, None).await?;
+        state.repo.update_job_status(&id, JobStatus::Queued).await
+    };
+    requeued.await.map_err(|e| AppError::internal("Failed to requeue job", e))?;
+
+    let _ = state.broadcaster.send(WsEvent::JobRetried { job_id: id.clone() });
+
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        JobExecutor::run_queue(&state_clone).await;
+    });
+
+    Ok(Json(json!({ "message": format!("Job {} requeued", id) })))
+}
+
+/// List jobs spawned by a job (e.g. the port-scan queued after a discovery)
+pub async fn get_job_children(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Job>>, AppError> {
+    find_job(state.repo.as_ref(), &id).await?;
+
+    state.repo.get_child_jobs(&id)
+        .await
+        .map(Json)
+        .map_err(|e| AppError::internal("Failed to list child jobs", e))
 }
 
 /// Cancel a running job
 pub async fn cancel_job(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-
-    let job = match repository::get_job(&state.db, &id).await {
-        Ok(Some(job)) => job,
-        Ok(None) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({ "error": format!("Job with ID {} not found", id) })),
-            )
-                .into_response();
-        }
-        Err(e) => {
-            tracing::error!("Failed to get job: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Failed to get job" })),
-            )
-                .into_response();
-        }
-    };
+) -> Result<Json<Value>, AppError> {
+
+    let job = find_job(state.repo.as_ref(), &id).await?;
 
     if !job.is_queued() && !job.is_running() && !job.is_scheduled() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "Job cannot be cancelled" })),
-        )
-            .into_response();
+        return Err(AppError::BadRequest("Job cannot be cancelled".to_string()));
     }
 
-    if let Err(e) = repository::update_job_status(&state.db, &id, "cancelled").await {
-        tracing::error!("Failed to cancel job: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Failed to cancel job" })),
-        )
-            .into_response();
+    state.repo.update_job_status(&id, JobStatus::Cancelled)
+        .await
+        .map_err(|e| AppError::internal("Failed to cancel job", e))?;
+
+    let _ = state.broadcaster.send(WsEvent::JobCancelled { job_id: id.clone() });
+
+    Ok(Json(json!({
+        "message": format!("Cancelling job with {} ID", id)
+    })))
+}
+
+/// Delete a finished, failed, cancelled or waiting job together with its logs
+/// DELETE /api/jobs/{id}
+///
+/// Running jobs can't be deleted (409); cancel them first.
+pub async fn delete_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let job = find_job(state.repo.as_ref(), &id).await?;
+    if job.is_running() {
+        return Err(AppError::Conflict(format!("Job {} is running; cancel it before deleting", id)));
     }
 
-    let _ = state.broadcaster.send(format!("job_cancelled:{}", id));
+    let logs_deleted = state.repo.delete_job(&id)
+        .await
+        .map_err(|e| AppError::internal("Failed to delete job", e))?;
+    match logs_deleted {
+        Some(logs_deleted) => Ok(Json(json!({ "deleted": id, "logs_deleted": logs_deleted }))),
+        // Claimed by a worker, or deleted, since the lookup above
+        None => Err(AppError::Conflict(format!("Job {} changed while deleting; try again", id))),
+    }
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "message": format!("Cancelling job with {} ID", id)
-        })),
-    )
-        .into_response()
+/// Look up a job, as a 404 if it doesn't exist.
+async fn find_job(repo: &dyn Repository, id: &str) -> Result<Job, AppError> {
+    repo.get_job(id)
+        .await
+        .map_err(|e| AppError::internal("Failed to get job", e))?
+        .ok_or_else(|| AppError::NotFound(format!("Job with ID {} not found", id)))
 }
 
-fn parse_job_from_request(payload: &CreateJobRequest) -> Result<Job, Response>  {
+/// `scan_config.target`, what a discovery job without a target of its own runs against.
+pub(crate) async fn configured_target(state: &AppState) -> Result<Option<String>, sqlx::Error> {
+    Ok(state.config.get(state.repo.as_ref()).await?.scan_config().target)
+}
+
+fn parse_job_from_request(payload: &CreateJobRequest, default_target: Option<&str>) -> Result<Job, AppError>  {
     let job_type = payload.job_type.clone();
 
     let mut job = Job::new(job_type.clone());
 
     let mut config = Map::new();
 
-    if job_type == "discovery" {
-        let target = payload.target.clone().ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "error": "target is required for discovery jobs"
-                })),
-            )
-                .into_response()
-        })?;
-
-        if target != "self" {
-            validate_cidr(&target).map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": e })),
-                )
-                    .into_response()
-            })?;
-        }
-
+    let target = validate_target(&job_type, payload.target.clone(), default_target).map_err(AppError::BadRequest)?;
+    if let Some(target) = target {
         config.insert("target".to_string(), Value::String(target));
     }
-
-    if job_type == "port-scan" {
-        if let Some(target) = payload.target.clone() {
-            target.parse::<std::net::IpAddr>().map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": format!("Invalid IP address: {}", target) })),
-                )
-                    .into_response()
-            })?;
-            config.insert("target".to_string(), Value::String(target));
-        }
-        // No target = scan all discovered hosts
+    if job_type == "discovery" && payload.auto_port_scan {
+        config.insert("auto_port_scan".to_string(), Value::Bool(true));
     }
-
-    if job_type == "nmap-scan" {
-        if let Some(target) = payload.target.clone() {
-            target.parse::<std::net::IpAddr>().map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": format!("Invalid IP address: {}", target) })),
-                )
-                    .into_response()
-            })?;
-            config.insert("target".to_string(), Value::String(target));
-        }
-        // No target = scan all discovered hosts
+    if let Some(ports) = &payload.ports {
+        let ports = validate_ports(&job_type, ports).map_err(AppError::BadRequest)?;
+        config.insert("ports".to_string(), json!(ports));
     }
+    if job_type == "export"
+        && let Some(format) = &payload.format
+    {
+        ExportFormat::parse(format).map_err(AppError::BadRequest)?;
+        config.insert("format".to_string(), Value::String(format.to_ascii_lowercase()));
+    }
+
+    job.max_retries = payload.max_retries;
 
-    if !payload.scheduled_at.is_none() {
+    if payload.scheduled_at.is_some() {
         job.scheduled_at = Some(payload.scheduled_at.unwrap_or(Utc::now().timestamp()));
     }
 
+    if let Some(cron) = &payload.cron {
+        Schedule::parse_cron(cron).map_err(AppError::BadRequest)?;
+        job.schedule = Some(cron.clone());
+        if job.scheduled_at.is_none() {
+            let first = job.next_run_after(Utc::now())
+                .ok_or_else(|| AppError::BadRequest(format!("Cron expression '{}' never fires", cron)))?;
+            job.scheduled_at = Some(first);
+        }
+    }
+
     job.config = Value::Object(config);
     Ok(job)
 }
 
+/// Check `target` for a job of `job_type` and return what goes in the job config.
+/// Shared with schedules, which store the same target for the jobs they spawn.
+/// A discovery job needs a target, its own or `default_target` (`scan_config.target`).
+pub(crate) fn validate_target(job_type: &str, target: Option<String>, default_target: Option<&str>) -> Result<Option<String>, String> {
+    match job_type {
+        // No target = `scan_config.target`, which the job reads when it runs
+        "discovery" => match target {
+            Some(target) => {
+                // Discovery accepts one or more comma-separated networks
+                if target != "self" {
+                    for cidr in target.split(',').map(str::trim) {
+                        validate_cidr(cidr)?;
+                    }
+                }
+                Ok(Some(target))
+            }
+            None if default_target.is_some() => Ok(None),
+            None => Err("target is required for discovery jobs".to_string()),
+        },
+        // port-scan / nmap-scan / vuln-scan / attack: no target = scan all discovered hosts
+        "port-scan" | "nmap-scan" | "vuln-scan" | "attack" => match target {
+            Some(target) => {
+                target
+                    .parse::<std::net::IpAddr>()
+                    .map_err(|_| format!("Invalid IP address: {}", target))?;
+                Ok(Some(target))
+            }
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
 async fn persist_job(
-    db: &DbPool,
+    repo: &dyn Repository,
     job: &Job,
-) -> Result<(), Response> {
-    if let Err(e) = repository::create_job(db, job).await {
-        tracing::error!("Failed to create job in database: {}", e);
-
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to create job"
-            })),
-        ).into_response());
+) -> Result<(), AppError> {
+    if let Err(e) = repo.create_job(job).await {
+        if db::is_unique_violation(&e) {
+            tracing::warn!("Job {} already exists", job.id);
+            return Err(AppError::Conflict(format!("Job with ID {} already exists", job.id)));
+        }
+
+        return Err(AppError::internal("Failed to create job", e));
     }
 
     Ok(())
@@ -254,4 +377,49 @@ async fn persist_job(
 fn validate_cidr(cidr: &str) -> Result<IpNet, String> {
     cidr.parse::<IpNet>()
         .map_err(|_| format!("Invalid CIDR notation: {}", cidr))
-}
\ No newline at end of file
+}
+
+/// Check a job's own port list, returning it sorted and without duplicates.
+fn validate_ports(job_type: &str, ports: &[u16]) -> Result<Vec<u16>, String> {
+    if job_type != "port-scan" && job_type != "discovery" {
+        return Err(format!("ports can't be set for {} jobs", job_type));
+    }
+    if ports.is_empty() {
+        return Err("ports must list at least one port".to_string());
+    }
+    if ports.contains(&0) {
+        return Err("Invalid port: 0".to_string());
+    }
+    let mut ports = ports.to_vec();
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(ports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::db_repository::DbRepository;
+
+    async fn test_repo() -> DbRepository {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        DbRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn persist_job_returns_conflict_for_duplicate_id() {
+        let repo = test_repo().await;
+        let mut job = Job::new("discovery".into());
+        job.id = "dup".into();
+
+        assert!(persist_job(&repo, &job).await.is_ok());
+
+        let err = persist_job(&repo, &job).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+    }
+}
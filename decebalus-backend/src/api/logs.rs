@@ -1,24 +1,55 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
     Json,
 };
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use serde_json::json;
+use crate::api::error::AppError;
+use crate::models::{Log, LogFilter};
 use crate::state::AppState;
-use crate::db::repository;
 
-pub async fn get_all_logs(state: State<Arc<AppState>>) -> impl IntoResponse {
-    match repository::get_logs(&state.db).await {
-        Ok(logs) => Json(logs).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to list logs: {}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to list logs"})),
-            ).into_response()
-        }
-    }
+#[derive(Debug, Default, Deserialize)]
+pub struct LogsQuery {
+    /// `ERROR`, `WARN`, `INFO`, … (case-insensitive)
+    pub severity: Option<String>,
+    pub service: Option<String>,
+    /// RFC 3339, e.g. `2024-05-01T10:00:00Z`
+    pub since: Option<String>,
+    /// RFC 3339
+    pub until: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// List logs, newest first, optionally filtered
+/// GET /api/logs?severity=ERROR&service=port_scanner&since=2024-05-01T10:00:00Z&until=…&limit=100
+pub async fn get_all_logs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<Vec<Log>>, AppError> {
+    let time = |name: &str, value: Option<String>| {
+        value
+            .map(|v| {
+                DateTime::parse_from_rfc3339(&v)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|_| AppError::BadRequest(format!("{}: expected an RFC 3339 timestamp, got '{}'", name, v)))
+            })
+            .transpose()
+    };
+    let filter = LogFilter {
+        severity: query.severity,
+        service: query.service,
+        since: time("since", query.since)?,
+        until: time("until", query.until)?,
+        limit: query.limit,
+    };
+
+    state.repo.query_logs(&filter)
+        .await
+        .map(Json)
+        .map_err(|e| AppError::internal("Failed to list logs", e))
 }
 
 pub async fn get_logs_by_job_id(
@@ -26,7 +57,7 @@ pub async fn get_logs_by_job_id(
     Path(job_id): Path<String>,
 ) -> impl IntoResponse {
 
-    match repository::get_logs_by_job_id(&state.db, job_id).await {
+    match state.repo.get_logs_by_job_id(job_id).await {
         Ok(logs) => Json(logs).into_response(),
         Err(e) => {
             tracing::error!("Failed to get logs for job: {}", e);
@@ -38,3 +69,15 @@ pub async fn get_logs_by_job_id(
         }
     }
 }
+
+/// A single log entry by its own id
+/// GET /api/logs/id/{id}
+pub async fn get_log_by_id(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Log>, AppError> {
+    state.repo.get_log(id.clone())
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Log with ID {} not found", id)))
+}
@@ -1,6 +1,82 @@
+pub mod auth;
+pub mod error;
 pub mod jobs;
 pub mod hosts;
+pub mod schedules;
+pub mod services;
+pub mod summary;
 pub mod display;
 pub mod config;
 pub mod websocket;
-pub mod logs;
\ No newline at end of file
+pub mod logs;
+pub mod events;
+pub mod health;
+pub mod queue;
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use crate::state::AppState;
+
+/// All HTTP and WebSocket routes, bound to `state`. Everything but `/health`
+/// needs the API token when one is configured.
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        // Job routes
+        .route("/api/jobs", post(jobs::create_job).get(jobs::list_jobs))
+        .route("/api/jobs/schedule", post(jobs::schedule_job).get(jobs::list_jobs))
+        .route("/api/jobs/retry-failed", post(jobs::retry_failed_jobs))
+        .route("/api/jobs/{id}", get(jobs::get_job).patch(jobs::update_job).delete(jobs::delete_job))
+        .route("/api/jobs/{id}/cancel", post(jobs::cancel_job))
+        .route("/api/jobs/{id}/retry", post(jobs::retry_job))
+        .route("/api/jobs/{id}/children", get(jobs::get_job_children))
+        // Queue control
+        .route("/api/queue/pause", post(queue::pause_queue))
+        .route("/api/queue/resume", post(queue::resume_queue))
+        .route("/api/queue/status", get(queue::queue_status))
+        // Schedule routes
+        .route("/api/schedules", post(schedules::create_schedule).get(schedules::list_schedules))
+        .route(
+            "/api/schedules/{id}",
+            get(schedules::get_schedule)
+                .patch(schedules::update_schedule)
+                .delete(schedules::delete_schedule),
+        )
+        // Host routes
+        .route("/api/hosts", get(hosts::list_hosts))
+        .route("/api/hosts/graph", get(hosts::host_graph))
+        .route("/api/hosts/{ip}", get(hosts::get_host).delete(hosts::delete_host))
+        // Service routes
+        .route("/api/services/catalog", get(services::service_catalog))
+        // Dashboard overview
+        .route("/api/summary", get(summary::get_summary))
+        // Display routes
+        .route("/api/display/status", get(display::get_display_status))
+        .route("/api/display/update", post(display::update_display))
+        .route("/api/display/image", get(display::get_display_image))
+        .route(
+            "/api/display/queue",
+            get(display::list_display_messages)
+                .post(display::push_display_message)
+                .delete(display::clear_display_messages),
+        )
+        // Config routes
+        .route("/api/config", get(config::get_config).post(config::update_config))
+        .route("/api/config/export", get(config::export_config))
+        .route("/api/config/import", post(config::import_config))
+        // Logs routes
+        .route("/api/logs", get(logs::get_all_logs))
+        .route("/api/logs/{job_id}", get(logs::get_logs_by_job_id))
+        .route("/api/logs/id/{id}", get(logs::get_log_by_id))
+        // Live events
+        .route("/api/events", get(events::event_stream))
+        // WebSocket route
+        .route("/ws", get(websocket::ws_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_token))
+        // Left open for load balancers and orchestrators
+        .route("/health", get(health::health))
+        .with_state(state)
+}
@@ -0,0 +1,55 @@
+use axum::{extract::State, Json};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use crate::api::error::AppError;
+use crate::models::{JobStatus, WsEvent};
+use crate::services::JobExecutor;
+use crate::state::AppState;
+
+const THIS_SERVICE: &str = "queue";
+
+/// Stop queued and scheduled jobs from starting; running jobs carry on
+/// POST /api/queue/pause
+pub async fn pause_queue(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    set_paused(&state, true).await;
+    queue_status(State(state)).await
+}
+
+/// Start jobs again, beginning with whatever queued up while paused
+/// POST /api/queue/resume
+pub async fn resume_queue(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    set_paused(&state, false).await;
+
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        JobExecutor::run_queue(&state_clone).await;
+    });
+
+    queue_status(State(state)).await
+}
+
+/// Whether the queue is paused, with queued and running job counts
+/// GET /api/queue/status
+pub async fn queue_status(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    let (_, queued) = state.repo.list_jobs_paged(Some(JobStatus::Queued), 1, 0)
+        .await
+        .map_err(|e| AppError::internal("Failed to count queued jobs", e))?;
+
+    Ok(Json(json!({
+        "paused": state.queue_paused.load(Ordering::SeqCst),
+        "queued_jobs": queued,
+        "running_jobs": state.max_threads.saturating_sub(state.semaphore.available_permits()),
+    })))
+}
+
+async fn set_paused(state: &Arc<AppState>, paused: bool) {
+    if state.queue_paused.swap(paused, Ordering::SeqCst) == paused {
+        return;
+    }
+
+    let msg = if paused { "Job queue paused" } else { "Job queue resumed" };
+    tracing::info!("{}", msg);
+    let _ = state.repo.add_log("INFO", THIS_SERVICE, None, None, msg).await;
+    let _ = state.broadcaster.send(WsEvent::QueuePaused { paused });
+}
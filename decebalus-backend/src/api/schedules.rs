@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use crate::api::jobs::{configured_target, validate_target};
+use crate::error::{Error, Result};
+use crate::models::{CreateScheduleRequest, Schedule, UpdateScheduleRequest};
+use crate::state::AppState;
+
+/// Job types a schedule can run.
+const SCHEDULABLE_JOB_TYPES: &[&str] = &["discovery", "port-scan", "nmap-scan", "vuln-scan", "export"];
+
+/// Create a named schedule
+/// POST /api/schedules
+pub async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateScheduleRequest>,
+) -> Result<(StatusCode, Json<Schedule>)> {
+    if !SCHEDULABLE_JOB_TYPES.contains(&payload.job_type.as_str()) {
+        return Err(Error::BadRequest(format!(
+            "Unknown job_type: {} (expected one of {})",
+            payload.job_type,
+            SCHEDULABLE_JOB_TYPES.join(", ")
+        )));
+    }
+
+    let mut schedule = Schedule::new(payload.name, payload.job_type, payload.target, payload.cron);
+    schedule.enabled = payload.enabled;
+    validate(&state, &mut schedule).await?;
+
+    state.repo.create_schedule(&schedule).await?;
+    tracing::info!("Created schedule {} ({})", schedule.id, schedule.name);
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// List all schedules
+/// GET /api/schedules
+pub async fn list_schedules(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Schedule>>> {
+    Ok(Json(state.repo.list_schedules().await?))
+}
+
+/// GET /api/schedules/{id}
+pub async fn get_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Schedule>> {
+    Ok(Json(find(&state, &id).await?))
+}
+
+/// Change name, target, cron or enabled; fields left out keep their value
+/// PATCH /api/schedules/{id}
+pub async fn update_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateScheduleRequest>,
+) -> Result<Json<Schedule>> {
+    let mut schedule = find(&state, &id).await?;
+    if let Some(name) = payload.name {
+        schedule.name = name;
+    }
+    if let Some(target) = payload.target {
+        schedule.target = Some(target);
+    }
+    if let Some(cron) = payload.cron {
+        schedule.cron = cron;
+    }
+    if let Some(enabled) = payload.enabled {
+        schedule.enabled = enabled;
+    }
+    validate(&state, &mut schedule).await?;
+
+    state.repo.update_schedule(&schedule).await?;
+    Ok(Json(schedule))
+}
+
+/// Delete a schedule; jobs it already spawned are kept
+/// DELETE /api/schedules/{id}
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode> {
+    if state.repo.delete_schedule(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(&id))
+    }
+}
+
+async fn find(state: &AppState, id: &str) -> Result<Schedule> {
+    state.repo.get_schedule(id)
+        .await?
+        .ok_or_else(|| not_found(id))
+}
+
+fn not_found(id: &str) -> Error {
+    Error::NotFound(format!("Schedule with ID {} not found", id))
+}
+
+/// Reject an empty name, a bad cron expression or a target its job type can't
+/// use, normalizing the target the same way job creation does.
+async fn validate(state: &AppState, schedule: &mut Schedule) -> Result<()> {
+    if schedule.name.trim().is_empty() {
+        return Err(Error::BadRequest("name is required".to_string()));
+    }
+    Schedule::parse_cron(&schedule.cron).map_err(Error::BadRequest)?;
+    let default_target = configured_target(state).await?;
+    schedule.target = validate_target(&schedule.job_type, schedule.target.take(), default_target.as_deref()).map_err(Error::BadRequest)?;
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use axum::{extract::State, Json};
+use std::sync::Arc;
+use crate::error::Result;
+use crate::models::CatalogEntry;
+use crate::state::AppState;
+
+/// Every service/version ever fingerprinted, with first and last sighting
+/// GET /api/services/catalog
+pub async fn service_catalog(State(state): State<Arc<AppState>>) -> Result<Json<Vec<CatalogEntry>>> {
+    Ok(Json(state.repo.list_service_catalog().await?))
+}
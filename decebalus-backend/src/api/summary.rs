@@ -0,0 +1,12 @@
+use axum::{extract::State, Json};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use crate::error::Result;
+use crate::models::Summary;
+use crate::state::AppState;
+
+/// Host, job and port counts for the dashboard overview
+/// GET /api/summary
+pub async fn get_summary(State(state): State<Arc<AppState>>) -> Result<Json<Summary>> {
+    Ok(Json(state.repo.get_summary(Utc::now() - Duration::hours(24)).await?))
+}
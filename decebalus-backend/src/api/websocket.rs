@@ -1,54 +1,159 @@
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Query, State},
     response::IntoResponse,
 };
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::info;
+use crate::models::WsEvent;
 use crate::state::AppState;
 
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Only replay buffered events numbered after this
+    pub since: Option<u64>,
+}
+
+/// Frame a client sends to pick which events it gets, e.g.
+/// `{"subscribe":["job:abc123","hosts"]}`.
+#[derive(Debug, Deserialize)]
+struct SubscribeRequest {
+    subscribe: Vec<String>,
+}
+
+/// Topics one connection is subscribed to. `None` means every event, which is
+/// what a client gets until it subscribes (or after subscribing to `all`).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Subscription(Option<HashSet<String>>);
+
+impl Subscription {
+    pub fn new(topics: Vec<String>) -> Self {
+        if topics.is_empty() || topics.iter().any(|t| t == "all") {
+            return Self(None);
+        }
+        Self(Some(topics.into_iter().collect()))
+    }
+
+    /// Whether `event` is on one of the subscribed topics: its broad topic
+    /// (`jobs`, `hosts`, …) or `job:<id>` for the job it is about.
+    pub fn matches(&self, event: &WsEvent) -> bool {
+        let Some(topics) = &self.0 else { return true };
+        topics.contains(event.topic())
+            || event.job_id().is_some_and(|id| topics.contains(&format!("job:{}", id)))
+    }
+}
+
+/// Event as sent to clients: its JSON with the sequence number added, e.g.
+/// `{"seq":42,"type":"job_completed","job_id":"abc"}`.
+pub fn event_json(seq: u64, event: &WsEvent) -> Option<String> {
+    let mut value = serde_json::to_value(event).ok()?;
+    value.as_object_mut()?.insert("seq".to_string(), seq.into());
+    Some(value.to_string())
+}
+
 /// WebSocket endpoint for real-time updates
-/// GET /ws
+/// GET /ws?since=<seq>
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    Query(query): Query<EventsQuery>,
 ) -> impl IntoResponse {
     info!("Connected to WS!");
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, query.since))
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
-    let (mut sender, mut receiver) = socket.split();
-    let mut rx = state.broadcaster.subscribe();
-
-    // Spawn task to forward broadcast messages to client
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if sender.send(Message::Text(msg.into())).await.is_err() {
-                break;
-            }
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, since: Option<u64>) {
+    let (sender, receiver) = socket.split();
+    serve(sender, receiver, state, since).await;
+    info!("WebSocket connection closed");
+}
+
+/// Send one client the buffered events after `since` (all of them if `None`),
+/// then forward live ones, filtered by the topics it subscribed to. Every
+/// event carries its `seq` so a reconnecting client can pass the last one it
+/// saw as `since`. Subscriptions are answered with
+/// `{"type":"subscribed","topics":[...]}`. Returns once the client goes away.
+pub async fn serve<S, R, E>(mut sender: S, mut receiver: R, state: Arc<AppState>, since: Option<u64>)
+where
+    S: Sink<Message> + Unpin,
+    R: Stream<Item = Result<Message, E>> + Unpin,
+{
+    let replay = state.broadcaster.subscribe_since(since);
+    let mut rx = replay.rx;
+    let mut next_seq = replay.next_seq;
+    let mut subscription = Subscription::default();
+
+    for (seq, event) in &replay.events {
+        let Some(json) = event_json(*seq, event) else { continue };
+        if sender.send(Message::Text(json.into())).await.is_err() {
+            return;
         }
-    });
-
-    // Spawn task to handle incoming messages from client
-    let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            match msg {
-                Message::Text(t) => {
-                    info!("Received message from client: {}", t);
+    }
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("WebSocket client lagged, skipped {} events", skipped);
+                        next_seq += skipped;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                // Live events arrive in sequence order, see `EventBus::send`
+                let seq = next_seq;
+                next_seq += 1;
+                if !subscription.matches(&event) {
+                    continue;
+                }
+                let Some(json) = event_json(seq, &event) else { continue };
+                if sender.send(Message::Text(json.into())).await.is_err() {
+                    break;
                 }
-                Message::Close(_) => break,
-                _ => {}
             }
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Text(t))) => {
+                    let Ok(request) = serde_json::from_str::<SubscribeRequest>(&t) else {
+                        info!("Received message from client: {}", t);
+                        continue;
+                    };
+                    let ack = serde_json::json!({ "type": "subscribed", "topics": request.subscribe });
+                    subscription = Subscription::new(request.subscribe);
+                    if sender.send(Message::Text(ack.to_string().into())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
         }
-    });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Wait for either task to finish
-    tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
+    #[test]
+    fn no_topics_or_all_means_every_event() {
+        let event = WsEvent::log("hello");
+        assert!(Subscription::default().matches(&event));
+        assert!(Subscription::new(vec![]).matches(&event));
+        assert!(Subscription::new(vec!["all".into(), "hosts".into()]).matches(&event));
     }
 
-    info!("WebSocket connection closed");
-}
\ No newline at end of file
+    #[test]
+    fn matches_broad_topics_and_single_jobs() {
+        let sub = Subscription::new(vec!["job:abc".into(), "hosts".into()]);
+        assert!(sub.matches(&WsEvent::JobCompleted { job_id: "abc".into() }));
+        assert!(sub.matches(&WsEvent::NewHost { ip: "10.0.0.5".into() }));
+        assert!(!sub.matches(&WsEvent::JobCompleted { job_id: "def".into() }));
+        assert!(!sub.matches(&WsEvent::log("hello")));
+    }
+}
@@ -0,0 +1,219 @@
+//! `--check-config` / `CHECK_CONFIG=1`: validate the environment and the stored
+//! config, report every problem and exit, without starting the server or workers.
+
+use serde::de::DeserializeOwned;
+use crate::db::repository_trait::Repository;
+use crate::models::{AttacksConfig, Config, DisplayConfig, HostsConfig, JobsConfig, LogsConfig, ScanConfig, SmtpConfig, WebhooksConfig, parse_exclusion};
+use crate::server;
+use crate::services::fingerprint::BANNER_PARSERS;
+
+/// Notifiers registered at startup, valid in `integrations.notifiers`.
+pub const KNOWN_NOTIFIERS: &[&str] = &["email", "webhook", "noop"];
+
+/// Numeric env vars read at startup, all of which must be positive integers when set.
+const NUMERIC_ENV: &[&str] = &[
+    "MAX_THREADS",
+    "MAX_SCAN_CONCURRENCY",
+    "MAX_DISCOVER_THREADS",
+    "LOG_RETENTION_DAYS",
+    "RESULTS_COMPRESS_THRESHOLD",
+    "SCHEDULER_INTERVAL_SECS",
+];
+
+/// Problems found by a config check. Empty means the config is usable.
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub problems: Vec<String>,
+}
+
+impl ConfigReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// 0 when the config is valid, 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        if self.is_ok() { 0 } else { 1 }
+    }
+}
+
+/// Whether check mode was asked for, by `--check-config` or `CHECK_CONFIG=1`/`true`.
+pub fn requested() -> bool {
+    std::env::args().any(|a| a == "--check-config")
+        || std::env::var("CHECK_CONFIG").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// Check the environment (through `env`) and the config stored in `repo`.
+pub async fn run<F>(repo: &dyn Repository, env: F) -> ConfigReport
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut problems = check_env(env);
+    match repo.get_config().await {
+        Ok(config) => problems.extend(check_config(&config)),
+        Err(e) => problems.push(format!("cannot load config from database: {}", e)),
+    }
+    ConfigReport { problems }
+}
+
+/// Problems with the environment variables the server reads at startup.
+pub fn check_env<F>(env: F) -> Vec<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut problems: Vec<String> = NUMERIC_ENV
+        .iter()
+        .filter_map(|&name| {
+            let value = env(name)?;
+            match value.parse::<usize>() {
+                Ok(n) if n > 0 => None,
+                _ => Some(format!("{}: expected a positive integer, got '{}'", name, value)),
+            }
+        })
+        .collect();
+    if let Err(e) = server::bind_addr(&env) {
+        problems.push(e);
+    }
+    if let Err(e) = server::tls_paths(&env) {
+        problems.push(e);
+    }
+    if let Err(e) = server::cors_layer(&env) {
+        problems.push(e);
+    }
+    problems
+}
+
+/// Problems with the stored config. The typed getters on `Config` silently fall
+/// back to defaults for malformed sections; this reports them instead.
+pub fn check_config(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if !config.settings.is_object() {
+        problems.push("config must be a JSON object".to_string());
+        return problems;
+    }
+    if let Err(e) = config.check_limits() {
+        problems.push(e.to_string());
+    }
+
+    if let Some(scan) = section::<ScanConfig>(config, &["scan_config"], &mut problems) {
+        check_scan_config(&scan, &mut problems);
+    }
+    section::<HostsConfig>(config, &["hosts"], &mut problems);
+    section::<JobsConfig>(config, &["jobs"], &mut problems);
+    section::<LogsConfig>(config, &["logs"], &mut problems);
+    section::<AttacksConfig>(config, &["attacks"], &mut problems);
+    if let Some(display) = section::<DisplayConfig>(config, &["display"], &mut problems) {
+        if display.width == 0 || display.height == 0 {
+            problems.push(format!("display: width and height must be above 0, got {}x{}", display.width, display.height));
+        }
+        if display.queue_size == 0 {
+            problems.push("display.queue_size: must be above 0".to_string());
+        }
+    }
+    if let Some(webhooks) = section::<WebhooksConfig>(config, &["webhooks"], &mut problems) {
+        for url in &webhooks.urls {
+            match reqwest::Url::parse(url) {
+                Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+                _ => problems.push(format!("webhooks.urls: '{}' is not an http(s) URL", url)),
+            }
+        }
+    }
+    if let Some(smtp) = section::<SmtpConfig>(config, &["integrations", "smtp"], &mut problems)
+        && (smtp.host.is_empty() || smtp.to.is_empty())
+    {
+        problems.push("integrations.smtp: host and at least one 'to' address are required".to_string());
+    }
+    if let Some(notifiers) = section::<Vec<String>>(config, &["integrations", "notifiers"], &mut problems) {
+        for name in notifiers.iter().filter(|n| !KNOWN_NOTIFIERS.contains(&n.as_str())) {
+            problems.push(format!(
+                "integrations.notifiers: unknown notifier '{}' (known: {})",
+                name,
+                KNOWN_NOTIFIERS.join(", ")
+            ));
+        }
+    }
+
+    problems
+}
+
+fn check_scan_config(scan: &ScanConfig, problems: &mut Vec<String>) {
+    let autopilot = &scan.autopilot;
+    for (name, value) in [
+        ("error_threshold", autopilot.error_threshold),
+        ("recovery_threshold", autopilot.recovery_threshold),
+    ] {
+        if !(0.0..=1.0).contains(&value) {
+            problems.push(format!("scan_config.autopilot.{}: must be between 0 and 1, got {}", name, value));
+        }
+    }
+    if !(autopilot.backoff_factor > 0.0 && autopilot.backoff_factor < 1.0) {
+        problems.push(format!(
+            "scan_config.autopilot.backoff_factor: must be between 0 and 1 (exclusive), got {}",
+            autopilot.backoff_factor
+        ));
+    }
+    if !(0.0..=100.0).contains(&scan.ramp_down.tail_percent) {
+        problems.push(format!(
+            "scan_config.ramp_down.tail_percent: must be between 0 and 100, got {}",
+            scan.ramp_down.tail_percent
+        ));
+    }
+    for parser in scan.banner_parsers.iter().flatten() {
+        if !BANNER_PARSERS.iter().any(|(name, _)| name == parser) {
+            problems.push(format!("scan_config.banner_parsers: unknown parser '{}'", parser));
+        }
+    }
+    let timeout = &scan.adaptive_timeout;
+    if timeout.multiplier < 1.0 || timeout.min_ms > timeout.max_ms {
+        problems.push(format!(
+            "scan_config.adaptive_timeout: needs multiplier >= 1 and min_ms <= max_ms, got {} and {}..{}",
+            timeout.multiplier, timeout.min_ms, timeout.max_ms
+        ));
+    }
+    if let Some(rate) = scan.max_connections_per_second
+        && rate < 0.0
+    {
+        problems.push(format!("scan_config.max_connections_per_second: must not be negative, got {}", rate));
+    }
+    if scan.ports.as_ref().is_some_and(|ports| ports.is_empty() || ports.contains(&0)) {
+        problems.push("scan_config.ports: must list ports between 1 and 65535".to_string());
+    }
+    for entry in scan.exclusions.iter().filter(|e| parse_exclusion(e).is_none()) {
+        problems.push(format!("scan_config.exclusions: '{}' is not an IP or CIDR", entry));
+    }
+}
+
+/// Strictly parse the section at `path`, recording a problem if it's malformed.
+/// Returns `None` when the section is absent or invalid.
+fn section<T: DeserializeOwned>(config: &Config, path: &[&str], problems: &mut Vec<String>) -> Option<T> {
+    let value = path.iter().try_fold(&config.settings, |v, key| v.get(key))?;
+    match serde_json::from_value(value.clone()) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            let path = path.join(".");
+            let fields = field_problems::<T>(&path, value);
+            if fields.is_empty() {
+                problems.push(format!("{}: {}", path, e));
+            } else {
+                problems.extend(fields);
+            }
+            None
+        }
+    }
+}
+
+/// Name the keys of an object section that don't parse. Sections default
+/// every missing field, so each key can be tried on its own.
+fn field_problems<T: DeserializeOwned>(path: &str, value: &serde_json::Value) -> Vec<String> {
+    let Some(fields) = value.as_object() else { return Vec::new() };
+    fields
+        .iter()
+        .filter_map(|(key, field)| {
+            let alone = serde_json::Map::from_iter([(key.clone(), field.clone())]);
+            serde_json::from_value::<T>(alone.into())
+                .err()
+                .map(|e| format!("{}.{}: {}", path, key, e))
+        })
+        .collect()
+}
@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use sqlx::SqlitePool;
 use crate::db::repository_trait::Repository;
-use crate::models::{Job, Host, Config, DisplayStatus, Log};
+use crate::models::{Job, JobPriority, JobResult, JobStatus, Host, CatalogEntry, Config, DisplayMessage, DisplayStatus, Log, LogFilter, Schedule, Summary, Vulnerability};
 use chrono::DateTime;
 use chrono::Utc;
 
@@ -33,7 +33,11 @@ impl Repository for DbRepository {
         crate::db::repository::list_jobs(&self.pool).await
     }
 
-    async fn update_job_status(&self, id: &str, status: &str) -> Result<(), sqlx::Error> {
+    async fn list_jobs_paged(&self, status: Option<JobStatus>, limit: u32, offset: u32) -> Result<(Vec<Job>, u64), sqlx::Error> {
+        crate::db::repository::list_jobs_paged(&self.pool, status, limit, offset).await
+    }
+
+    async fn update_job_status(&self, id: &str, status: JobStatus) -> Result<(), sqlx::Error> {
         crate::db::repository::update_job_status(&self.pool, id, status).await
     }
 
@@ -41,6 +45,14 @@ impl Repository for DbRepository {
         crate::db::repository::get_running_jobs(&self.pool).await
     }
 
+    async fn get_stale_running_jobs(&self, before: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        crate::db::repository::get_stale_running_jobs(&self.pool, before).await
+    }
+
+    async fn touch_running_job(&self, id: &str) -> Result<(), sqlx::Error> {
+        crate::db::repository::touch_running_job(&self.pool, id).await
+    }
+
     async fn get_queued_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
         crate::db::repository::get_queued_jobs(&self.pool).await
     }
@@ -49,15 +61,55 @@ impl Repository for DbRepository {
         crate::db::repository::get_scheduled_jobs_due(&self.pool, now).await
     }
 
-    async fn update_job_results(&self, id: &str, results: Option<String>) -> Result<(), sqlx::Error> {
+    async fn get_child_jobs(&self, parent_id: &str) -> Result<Vec<Job>, sqlx::Error> {
+        crate::db::repository::get_child_jobs(&self.pool, parent_id).await
+    }
+
+    async fn claim_next_job(&self) -> Result<Option<Job>, sqlx::Error> {
+        crate::db::repository::claim_next_job(&self.pool).await
+    }
+
+    async fn claim_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error> {
+        crate::db::repository::claim_job(&self.pool, id).await
+    }
+
+    async fn update_job_priority(&self, id: &str, priority: JobPriority) -> Result<Option<Job>, sqlx::Error> {
+        crate::db::repository::update_job_priority(&self.pool, id, priority).await
+    }
+
+    async fn requeue_failed_jobs(&self, job_type: Option<&str>, since: Option<i64>, until: Option<i64>) -> Result<u64, sqlx::Error> {
+        crate::db::repository::requeue_failed_jobs(&self.pool, job_type, since, until).await
+    }
+
+    async fn delete_job(&self, id: &str) -> Result<Option<u64>, sqlx::Error> {
+        crate::db::repository::delete_job(&self.pool, id).await
+    }
+
+    async fn update_job_results(&self, id: &str, results: Option<JobResult>) -> Result<(), sqlx::Error> {
         crate::db::repository::update_job_results(&self.pool, id, results).await
     }
 
+    async fn update_job_phase(&self, id: &str, phase: Option<&str>) -> Result<(), sqlx::Error> {
+        crate::db::repository::update_job_phase(&self.pool, id, phase).await
+    }
+
+    async fn schedule_job_retry(&self, id: &str, retries: u32, run_at: i64) -> Result<(), sqlx::Error> {
+        crate::db::repository::schedule_job_retry(&self.pool, id, retries, run_at).await
+    }
+
     // ================= HOSTS =================
     async fn upsert_host(&self, host: &Host) -> Result<(), sqlx::Error> {
         crate::db::repository::upsert_host(&self.pool, host).await
     }
 
+    async fn upsert_hosts(&self, hosts: &[Host]) -> Result<(), sqlx::Error> {
+        crate::db::repository::upsert_hosts(&self.pool, hosts).await
+    }
+
+    async fn set_host_vulnerabilities(&self, ip: &str, vulnerabilities: &[Vulnerability]) -> Result<(), sqlx::Error> {
+        crate::db::repository::set_host_vulnerabilities(&self.pool, ip, vulnerabilities).await
+    }
+
     async fn get_host(&self, ip: &str) -> Result<Option<Host>, sqlx::Error> {
         crate::db::repository::get_host(&self.pool, ip).await
     }
@@ -66,6 +118,49 @@ impl Repository for DbRepository {
         crate::db::repository::list_hosts(&self.pool).await
     }
 
+    async fn delete_host(&self, ip: &str) -> Result<bool, sqlx::Error> {
+        crate::db::repository::delete_host(&self.pool, ip).await
+    }
+
+    // ================= SERVICE CATALOG =================
+    async fn record_service_seen(&self, name: &str, version: Option<&str>, seen_at: &str) -> Result<(), sqlx::Error> {
+        crate::db::repository::record_service_seen(&self.pool, name, version, seen_at).await
+    }
+
+    async fn list_service_catalog(&self) -> Result<Vec<CatalogEntry>, sqlx::Error> {
+        crate::db::repository::list_service_catalog(&self.pool).await
+    }
+
+    // ================= SUMMARY =================
+    async fn get_summary(&self, seen_since: DateTime<Utc>) -> Result<Summary, sqlx::Error> {
+        crate::db::repository::get_summary(&self.pool, seen_since).await
+    }
+
+    // ================= SCHEDULES =================
+    async fn create_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        crate::db::repository::create_schedule(&self.pool, schedule).await
+    }
+
+    async fn get_schedule(&self, id: &str) -> Result<Option<Schedule>, sqlx::Error> {
+        crate::db::repository::get_schedule(&self.pool, id).await
+    }
+
+    async fn list_schedules(&self) -> Result<Vec<Schedule>, sqlx::Error> {
+        crate::db::repository::list_schedules(&self.pool).await
+    }
+
+    async fn update_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        crate::db::repository::update_schedule(&self.pool, schedule).await
+    }
+
+    async fn delete_schedule(&self, id: &str) -> Result<bool, sqlx::Error> {
+        crate::db::repository::delete_schedule(&self.pool, id).await
+    }
+
+    async fn mark_schedule_run(&self, id: &str, at: i64) -> Result<(), sqlx::Error> {
+        crate::db::repository::mark_schedule_run(&self.pool, id, at).await
+    }
+
     // ================= CONFIG =================
     async fn get_config(&self) -> Result<Config, sqlx::Error> {
         crate::db::repository::get_config(&self.pool).await
@@ -84,6 +179,19 @@ impl Repository for DbRepository {
         crate::db::repository::update_display_status(&self.pool, status).await
     }
 
+    // ================= DISPLAY QUEUE =================
+    async fn push_display_message(&self, text: &str, keep: u32) -> Result<DisplayMessage, sqlx::Error> {
+        crate::db::repository::push_display_message(&self.pool, text, keep).await
+    }
+
+    async fn list_display_messages(&self) -> Result<Vec<DisplayMessage>, sqlx::Error> {
+        crate::db::repository::list_display_messages(&self.pool).await
+    }
+
+    async fn clear_display_messages(&self) -> Result<u64, sqlx::Error> {
+        crate::db::repository::clear_display_messages(&self.pool).await
+    }
+
     // ================= LOGS =================
     async fn add_log(
         &self,
@@ -100,6 +208,10 @@ impl Repository for DbRepository {
         crate::db::repository::get_logs(&self.pool).await
     }
 
+    async fn query_logs(&self, filter: &LogFilter) -> Result<Vec<Log>, sqlx::Error> {
+        crate::db::repository::query_logs(&self.pool, filter).await
+    }
+
     async fn get_log(&self, id: String) -> Result<Option<Log>, sqlx::Error> {
         crate::db::repository::get_log(&self.pool, id).await
     }
@@ -111,4 +223,8 @@ impl Repository for DbRepository {
     async fn cleanup_old_logs(&self, days: i64) -> Result<u64, sqlx::Error> {
         crate::db::repository::cleanup_old_logs(&self.pool, days).await
     }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        crate::db::repository::ping(&self.pool).await
+    }
 }
@@ -1,18 +1,24 @@
 // src/db/inmemory_repository.rs
 
 use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use chrono::{DateTime, Utc};
 use crate::db::repository_trait::Repository;
-use crate::models::{Job, Host, Config, DisplayStatus, Log};
+use crate::models::{Job, JobPriority, JobResult, JobStatus, Host, CatalogEntry, Config, DisplayMessage, DisplayStatus, Log, LogFilter, PortCount, Schedule, Summary, TOP_PORTS, Vulnerability};
 
 #[derive(Clone, Default)]
 pub struct InMemoryRepository {
     jobs: Arc<Mutex<Vec<Job>>>,
     hosts: Arc<Mutex<Vec<Host>>>,
+    service_catalog: Arc<Mutex<Vec<CatalogEntry>>>,
+    schedules: Arc<Mutex<Vec<Schedule>>>,
     logs: Arc<Mutex<Vec<Log>>>,
     config: Arc<Mutex<Config>>,
     display_status: Arc<Mutex<DisplayStatus>>,
+    display_queue: Arc<Mutex<Vec<DisplayMessage>>>,
+    /// Unix seconds of each job's last write, standing in for the `updated_at` column.
+    job_updated_at: Arc<Mutex<HashMap<String, i64>>>,
 }
 
 impl InMemoryRepository {
@@ -20,14 +26,37 @@ impl InMemoryRepository {
         Self {
             jobs: Arc::new(Mutex::new(Vec::new())),
             hosts: Arc::new(Mutex::new(Vec::new())),
+            service_catalog: Arc::new(Mutex::new(Vec::new())),
+            schedules: Arc::new(Mutex::new(Vec::new())),
             logs: Arc::new(Mutex::new(Vec::new())),
             config: Arc::new(Mutex::new(Config { settings: serde_json::Value::Object(Default::default()) })),
             display_status: Arc::new(Mutex::new(DisplayStatus {
                 status: "ok".to_string(),
                 last_update: Utc::now().to_rfc3339(),
+                image: None,
             })),
+            display_queue: Arc::new(Mutex::new(Vec::new())),
+            job_updated_at: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    /// Backdate a job's last update, e.g. to seed old failures.
+    pub fn set_job_updated_at(&self, id: &str, at: i64) {
+        self.job_updated_at.lock().unwrap().insert(id.to_string(), at);
+    }
+
+    fn touch_job(&self, id: &str) {
+        self.set_job_updated_at(id, Utc::now().timestamp());
+    }
+
+    fn job_updated_at(&self, id: &str) -> i64 {
+        self.job_updated_at.lock().unwrap().get(id).copied().unwrap_or_else(|| Utc::now().timestamp())
+    }
+
+    /// Store `log` as-is, timestamp included, e.g. to seed old entries.
+    pub fn insert_log(&self, log: Log) {
+        self.logs.lock().unwrap().push(log);
+    }
 }
 
 #[async_trait]
@@ -36,57 +65,210 @@ impl Repository for InMemoryRepository {
     async fn create_job(&self, job: &Job) -> Result<(), sqlx::Error> {
         let mut jobs = self.jobs.lock().unwrap();
         jobs.push(job.clone());
+        drop(jobs);
+        self.touch_job(&job.id);
         Ok(())
     }
 
     async fn get_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error> {
         let jobs = self.jobs.lock().unwrap();
-        Ok(jobs.iter().cloned().find(|j| j.id == id))
+        Ok(jobs.iter().find(|j| j.id == id).cloned())
     }
 
     async fn list_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
         let jobs = self.jobs.lock().unwrap();
-        Ok(jobs.clone())
+        // Newest first, like the database-backed repositories
+        Ok(jobs.iter().rev().cloned().collect())
     }
 
-    async fn update_job_status(&self, id: &str, status: &str) -> Result<(), sqlx::Error> {
+    async fn list_jobs_paged(&self, status: Option<JobStatus>, limit: u32, offset: u32) -> Result<(Vec<Job>, u64), sqlx::Error> {
+        let jobs = self.jobs.lock().unwrap();
+        // Newest first, like the database-backed repositories
+        let matching: Vec<&Job> = jobs.iter()
+            .rev()
+            .filter(|j| status.is_none_or(|s| j.status == s))
+            .collect();
+        let page = matching.iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|j| (*j).clone())
+            .collect();
+        Ok((page, matching.len() as u64))
+    }
+
+    async fn update_job_status(&self, id: &str, status: JobStatus) -> Result<(), sqlx::Error> {
         let mut jobs = self.jobs.lock().unwrap();
         for job in jobs.iter_mut() {
             if job.id == id {
-                job.status = status.to_string();
+                job.status = status;
             }
         }
+        drop(jobs);
+        self.touch_job(id);
         Ok(())
     }
 
     async fn get_running_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
         let jobs = self.jobs.lock().unwrap();
-        Ok(jobs.iter().cloned().filter(|j| j.status == "running").collect())
+        Ok(jobs.iter().filter(|j| j.status == JobStatus::Running).cloned().collect())
+    }
+
+    async fn get_stale_running_jobs(&self, before: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        let jobs = self.jobs.lock().unwrap();
+        Ok(jobs.iter()
+            .filter(|j| j.status == JobStatus::Running && self.job_updated_at(&j.id) < before.timestamp())
+            .cloned()
+            .collect())
+    }
+
+    async fn touch_running_job(&self, id: &str) -> Result<(), sqlx::Error> {
+        let running = self.jobs.lock().unwrap().iter().any(|j| j.id == id && j.status == JobStatus::Running);
+        if running {
+            self.touch_job(id);
+        }
+        Ok(())
     }
 
     async fn get_queued_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
         let jobs = self.jobs.lock().unwrap();
-        Ok(jobs.iter().cloned().filter(|j| j.status == "queued").collect())
+        Ok(jobs.iter().filter(|j| j.status == JobStatus::Queued).cloned().collect())
     }
 
     async fn get_scheduled_jobs_due(&self, now: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
         let jobs = self.jobs.lock().unwrap();
-        Ok(jobs.iter().cloned()
-            .filter(|j| j.status == "scheduled")
+        Ok(jobs.iter()
+            .filter(|j| j.status == JobStatus::Scheduled)
             .filter(|j| {
                 j.scheduled_at
-                    .map_or(false, |ts| ts < now.timestamp())
+                    .is_some_and(|ts| ts < now.timestamp())
             })
+            .cloned()
+            .collect())
+    }
+
+    async fn get_child_jobs(&self, parent_id: &str) -> Result<Vec<Job>, sqlx::Error> {
+        let jobs = self.jobs.lock().unwrap();
+        Ok(jobs.iter()
+            .filter(|j| j.parent_job_id.as_deref() == Some(parent_id))
+            .cloned()
             .collect())
     }
 
-    async fn update_job_results(&self, id: &str, results: Option<String>) -> Result<(), sqlx::Error> {
+    async fn claim_next_job(&self) -> Result<Option<Job>, sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        // max_by_key keeps the last max; iterate in reverse so ties go to the oldest job
+        let next = jobs.iter_mut()
+            .rev()
+            .filter(|j| j.status == JobStatus::Queued)
+            .max_by_key(|j| j.priority);
+        let claimed = next.map(|job| {
+            job.status = JobStatus::Running;
+            job.clone()
+        });
+        drop(jobs);
+        if let Some(job) = &claimed {
+            self.touch_job(&job.id);
+        }
+        Ok(claimed)
+    }
+
+    async fn claim_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let claimed = jobs.iter_mut()
+            .find(|j| j.id == id && (j.status == JobStatus::Queued || j.status == JobStatus::Scheduled))
+            .map(|job| {
+                job.status = JobStatus::Running;
+                job.clone()
+            });
+        drop(jobs);
+        if claimed.is_some() {
+            self.touch_job(id);
+        }
+        Ok(claimed)
+    }
+
+    async fn update_job_priority(&self, id: &str, priority: JobPriority) -> Result<Option<Job>, sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let updated = jobs.iter_mut()
+            .find(|j| j.id == id && j.status == JobStatus::Queued)
+            .map(|job| {
+                job.priority = priority;
+                job.clone()
+            });
+        drop(jobs);
+        if updated.is_some() {
+            self.touch_job(id);
+        }
+        Ok(updated)
+    }
+
+    async fn requeue_failed_jobs(&self, job_type: Option<&str>, since: Option<i64>, until: Option<i64>) -> Result<u64, sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut requeued = Vec::new();
+        for job in jobs.iter_mut() {
+            if job.status != JobStatus::Failed || job_type.is_some_and(|t| job.job_type != t) {
+                continue;
+            }
+            let updated_at = self.job_updated_at(&job.id);
+            if since.is_some_and(|s| updated_at < s) || until.is_some_and(|u| updated_at > u) {
+                continue;
+            }
+            job.status = JobStatus::Queued;
+            job.results = None;
+            requeued.push(job.id.clone());
+        }
+        drop(jobs);
+        for id in &requeued {
+            self.touch_job(id);
+        }
+        Ok(requeued.len() as u64)
+    }
+
+    async fn delete_job(&self, id: &str) -> Result<Option<u64>, sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(pos) = jobs.iter().position(|j| j.id == id && !j.is_running()) else {
+            return Ok(None);
+        };
+        jobs.remove(pos);
+        self.job_updated_at.lock().unwrap().remove(id);
+
+        let mut logs = self.logs.lock().unwrap();
+        let before = logs.len();
+        logs.retain(|l| l.job_id.as_deref() != Some(id));
+        Ok(Some((before - logs.len()) as u64))
+    }
+
+    async fn update_job_results(&self, id: &str, results: Option<JobResult>) -> Result<(), sqlx::Error> {
         let mut jobs = self.jobs.lock().unwrap();
         for job in jobs.iter_mut() {
             if job.id == id {
                 job.results = results.clone();
             }
         }
+        drop(jobs);
+        self.touch_job(id);
+        Ok(())
+    }
+
+    async fn update_job_phase(&self, id: &str, phase: Option<&str>) -> Result<(), sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+            job.phase = phase.map(str::to_string);
+        }
+        drop(jobs);
+        self.touch_job(id);
+        Ok(())
+    }
+
+    async fn schedule_job_retry(&self, id: &str, retries: u32, run_at: i64) -> Result<(), sqlx::Error> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+            job.status = JobStatus::Scheduled;
+            job.retries = retries;
+            job.scheduled_at = Some(run_at);
+        }
+        drop(jobs);
+        self.touch_job(id);
         Ok(())
     }
 
@@ -101,9 +283,36 @@ impl Repository for InMemoryRepository {
         Ok(())
     }
 
+    async fn upsert_hosts(&self, batch: &[Host]) -> Result<(), sqlx::Error> {
+        let mut hosts = self.hosts.lock().unwrap();
+        for host in batch {
+            if let Some(existing) = hosts.iter_mut().find(|h| h.ip == host.ip) {
+                existing.status = host.status;
+                existing.last_seen = host.last_seen.clone();
+                if host.mac_address.is_some() {
+                    existing.mac_address = host.mac_address.clone();
+                }
+                if host.hostname.is_some() {
+                    existing.hostname = host.hostname.clone();
+                }
+            } else {
+                hosts.push(host.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_host_vulnerabilities(&self, ip: &str, vulnerabilities: &[Vulnerability]) -> Result<(), sqlx::Error> {
+        let mut hosts = self.hosts.lock().unwrap();
+        if let Some(host) = hosts.iter_mut().find(|h| h.ip == ip) {
+            host.vulnerabilities = vulnerabilities.to_vec();
+        }
+        Ok(())
+    }
+
     async fn get_host(&self, ip: &str) -> Result<Option<Host>, sqlx::Error> {
         let hosts = self.hosts.lock().unwrap();
-        Ok(hosts.iter().cloned().find(|h| h.ip == ip))
+        Ok(hosts.iter().find(|h| h.ip == ip).cloned())
     }
 
     async fn list_hosts(&self) -> Result<Vec<Host>, sqlx::Error> {
@@ -111,6 +320,114 @@ impl Repository for InMemoryRepository {
         Ok(hosts.clone())
     }
 
+    async fn delete_host(&self, ip: &str) -> Result<bool, sqlx::Error> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let before = hosts.len();
+        hosts.retain(|h| h.ip != ip);
+        Ok(hosts.len() < before)
+    }
+
+    // ================= SERVICE CATALOG =================
+    async fn record_service_seen(&self, name: &str, version: Option<&str>, seen_at: &str) -> Result<(), sqlx::Error> {
+        let mut catalog = self.service_catalog.lock().unwrap();
+        let version = version.filter(|v| !v.is_empty());
+        if let Some(entry) = catalog.iter_mut().find(|e| e.name == name && e.version.as_deref() == version) {
+            if seen_at > entry.last_seen.as_str() {
+                entry.last_seen = seen_at.to_string();
+            }
+            entry.sightings += 1;
+        } else {
+            catalog.push(CatalogEntry {
+                name: name.to_string(),
+                version: version.map(str::to_string),
+                first_seen: seen_at.to_string(),
+                last_seen: seen_at.to_string(),
+                sightings: 1,
+            });
+        }
+        Ok(())
+    }
+
+    async fn list_service_catalog(&self) -> Result<Vec<CatalogEntry>, sqlx::Error> {
+        let mut catalog = self.service_catalog.lock().unwrap().clone();
+        catalog.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Ok(catalog)
+    }
+
+    // ================= SUMMARY =================
+    async fn get_summary(&self, seen_since: DateTime<Utc>) -> Result<Summary, sqlx::Error> {
+        let hosts = self.hosts.lock().unwrap();
+        let since = seen_since.to_rfc3339();
+
+        let mut port_hosts: HashMap<(u16, String), HashSet<&str>> = HashMap::new();
+        for host in hosts.iter() {
+            for port in host.ports.iter().filter(|p| p.status == "open") {
+                port_hosts.entry((port.number, port.protocol.clone())).or_default().insert(&host.ip);
+            }
+        }
+        let mut top_ports: Vec<PortCount> = port_hosts
+            .into_iter()
+            .map(|((port, protocol), ips)| PortCount { port, protocol, hosts: ips.len() as i64 })
+            .collect();
+        top_ports.sort_by(|a, b| b.hosts.cmp(&a.hosts).then(a.port.cmp(&b.port)).then(a.protocol.cmp(&b.protocol)));
+        top_ports.truncate(TOP_PORTS);
+
+        let jobs = self.jobs.lock().unwrap();
+        Ok(Summary {
+            total_hosts: hosts.len() as i64,
+            hosts_seen_24h: hosts.iter().filter(|h| h.last_seen >= since).count() as i64,
+            jobs_by_status: Summary::job_counts(jobs.iter().map(|j| (j.status, 1))),
+            top_ports,
+            last_scan_at: hosts
+                .iter()
+                .flat_map(|h| std::iter::once(h.last_seen.clone()).chain(h.last_port_scan.clone()))
+                .max(),
+        })
+    }
+
+    // ================= SCHEDULES =================
+    async fn create_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        self.schedules.lock().unwrap().push(schedule.clone());
+        Ok(())
+    }
+
+    async fn get_schedule(&self, id: &str) -> Result<Option<Schedule>, sqlx::Error> {
+        let schedules = self.schedules.lock().unwrap();
+        Ok(schedules.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn list_schedules(&self) -> Result<Vec<Schedule>, sqlx::Error> {
+        let mut schedules = self.schedules.lock().unwrap().clone();
+        schedules.sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+        Ok(schedules)
+    }
+
+    async fn update_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        let mut schedules = self.schedules.lock().unwrap();
+        if let Some(s) = schedules.iter_mut().find(|s| s.id == schedule.id) {
+            s.name = schedule.name.clone();
+            s.target = schedule.target.clone();
+            s.cron = schedule.cron.clone();
+            s.enabled = schedule.enabled;
+        }
+        Ok(())
+    }
+
+    async fn delete_schedule(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let before = schedules.len();
+        schedules.retain(|s| s.id != id);
+        Ok(schedules.len() < before)
+    }
+
+    async fn mark_schedule_run(&self, id: &str, at: i64) -> Result<(), sqlx::Error> {
+        let mut schedules = self.schedules.lock().unwrap();
+        if let Some(s) = schedules.iter_mut().find(|s| s.id == id) {
+            s.last_run_at = Some(at);
+        }
+        Ok(())
+    }
+
     // ================= CONFIG =================
     async fn get_config(&self) -> Result<Config, sqlx::Error> {
         let config = self.config.lock().unwrap();
@@ -135,6 +452,32 @@ impl Repository for InMemoryRepository {
         Ok(())
     }
 
+    // ================= DISPLAY QUEUE =================
+    async fn push_display_message(&self, text: &str, keep: u32) -> Result<DisplayMessage, sqlx::Error> {
+        let mut queue = self.display_queue.lock().unwrap();
+        let message = DisplayMessage {
+            id: queue.last().map_or(1, |m| m.id + 1),
+            text: text.to_string(),
+            created_at: Utc::now().timestamp(),
+        };
+        queue.retain(|m| m.text != text);
+        queue.push(message.clone());
+        let excess = queue.len().saturating_sub(keep as usize);
+        queue.drain(..excess);
+        Ok(message)
+    }
+
+    async fn list_display_messages(&self) -> Result<Vec<DisplayMessage>, sqlx::Error> {
+        Ok(self.display_queue.lock().unwrap().clone())
+    }
+
+    async fn clear_display_messages(&self) -> Result<u64, sqlx::Error> {
+        let mut queue = self.display_queue.lock().unwrap();
+        let cleared = queue.len() as u64;
+        queue.clear();
+        Ok(cleared)
+    }
+
     // ================= LOGS =================
     async fn add_log(
         &self,
@@ -162,15 +505,27 @@ impl Repository for InMemoryRepository {
         Ok(logs.clone())
     }
 
+    async fn query_logs(&self, filter: &LogFilter) -> Result<Vec<Log>, sqlx::Error> {
+        let logs = self.logs.lock().unwrap();
+        // Stored oldest first
+        Ok(logs.iter()
+            .rev()
+            .filter(|l| filter.matches(l))
+            .take(filter.limit.map_or(usize::MAX, |n| n as usize))
+            .cloned()
+            .collect())
+    }
+
     async fn get_log(&self, id: String) -> Result<Option<Log>, sqlx::Error> {
         let logs = self.logs.lock().unwrap();
-        Ok(logs.iter().cloned().find(|l| l.id == id))
+        Ok(logs.iter().find(|l| l.id == id).cloned())
     }
 
     async fn get_logs_by_job_id(&self, job_id: String) -> Result<Vec<Log>, sqlx::Error> {
         let logs = self.logs.lock().unwrap();
-        Ok(logs.iter().cloned()
+        Ok(logs.iter()
             .filter(|l| l.job_id.as_ref() == Some(&job_id))
+            .cloned()
             .collect())
     }
 
@@ -185,4 +540,8 @@ impl Repository for InMemoryRepository {
         });
         Ok((original_len - logs.len()) as u64)
     }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
 }
@@ -1,32 +1,313 @@
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::{SqlitePool, sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous}};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
+use db_repository::DbRepository;
+use repository_trait::Repository;
+use crate::models::Config;
 
 // Repositories
 pub mod repository;           // real DB implementation
 pub mod repository_trait;     // Repository trait
 pub mod db_repository;        // trait impl for real DB
 pub mod inmemory_repository;  // trait impl for in-memory testing
+#[cfg(feature = "postgres")]
+pub mod pg_repository;        // trait impl for Postgres
+pub mod results_codec;        // gzip for large job results
 
 pub type DbPool = sqlx::SqlitePool; // <- must be pub
 
-/// Initialize database connection pool
-pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
+/// Database engine, picked from the `DATABASE_URL` scheme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    /// `postgres://` / `postgresql://` select Postgres; anything else is SQLite.
+    pub fn from_url(database_url: &str) -> Self {
+        if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            Self::Postgres
+        } else {
+            Self::Sqlite
+        }
+    }
+}
+
+/// An open database: the repository everything goes through, and the SQLite
+/// pool behind it when the backend is SQLite.
+pub struct Database {
+    pub repo: Arc<dyn Repository>,
+    pub sqlite: Option<DbPool>,
+}
+
+/// Connect to the database behind `database_url`, with the repository picked
+/// from the URL scheme. Postgres needs the `postgres` feature.
+pub async fn connect_repository(database_url: &str) -> Result<Database, InitError> {
+    match Backend::from_url(database_url) {
+        Backend::Sqlite => {
+            let pool = init_pool(database_url).await?;
+            Ok(Database { repo: Arc::new(DbRepository::new(pool.clone())), sqlite: Some(pool) })
+        }
+        #[cfg(feature = "postgres")]
+        Backend::Postgres => match pg_repository::PgRepository::connect(database_url).await {
+            Ok(repo) => Ok(Database { repo: Arc::new(repo), sqlite: None }),
+            Err(sqlx::Error::Migrate(e)) => Err(InitError::Migration(*e)),
+            Err(e) => Err(InitError::Connect(e)),
+        },
+        #[cfg(not(feature = "postgres"))]
+        Backend::Postgres => Err(InitError::Connect(sqlx::Error::Configuration(
+            "Postgres DATABASE_URL given, but this build lacks the `postgres` feature".into(),
+        ))),
+    }
+}
+
+/// Why the database could not be opened at startup.
+/// Connection and migration problems need different fixes, so they exit with different codes.
+#[derive(Debug, thiserror::Error)]
+pub enum InitError {
+    #[error("cannot connect to database: {0}")]
+    Connect(#[source] sqlx::Error),
+
+    #[error("database migration failed: {0}")]
+    Migration(#[source] MigrateError),
+}
+
+impl InitError {
+    /// Process exit code for this failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Connect(_) => 2,
+            Self::Migration(_) => 3,
+        }
+    }
+
+    /// What the operator can do about it.
+    pub fn hint(&self) -> String {
+        match self {
+            Self::Connect(_) => {
+                "check DATABASE_URL and that the database file's directory exists and is writable".to_string()
+            }
+            Self::Migration(e) => match e {
+                MigrateError::VersionMissing(v) => format!(
+                    "database schema is newer than this binary (migration {} is unknown); \
+                     upgrade decebalus-backend or restore a backup made with this version",
+                    v
+                ),
+                MigrateError::VersionMismatch(v) => format!(
+                    "migration {} was edited after it was applied; restore the original migration file",
+                    v
+                ),
+                MigrateError::ExecuteMigration(_, v) => format!(
+                    "migration {} could not be applied; the database was left at the previous version, \
+                     fix the cause above (disk full, locked file, manual schema edits) and restart",
+                    v
+                ),
+                MigrateError::Dirty(v) => format!(
+                    "migration {} is partially applied; repair the schema by hand, \
+                     delete its row from _sqlx_migrations and restart",
+                    v
+                ),
+                _ => "restore a database backup or recreate the database".to_string(),
+            },
+        }
+    }
+}
+
+/// True if the error is a UNIQUE / PRIMARY KEY constraint violation,
+/// e.g. inserting a job whose id already exists.
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|e| e.is_unique_violation())
+}
+
+/// Store `Config::defaults()` when the config table is empty, so a new
+/// database shows the settings in use through the API. Returns whether it did.
+pub async fn seed_default_config(repo: &dyn Repository) -> Result<bool, sqlx::Error> {
+    let current = repo.get_config().await?;
+    if current.settings.as_object().is_some_and(|o| !o.is_empty()) {
+        return Ok(false);
+    }
+    repo.update_config(&Config::defaults()).await?;
+    Ok(true)
+}
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Bring the schema up to date, naming the migration that failed if any.
+pub async fn run_migrations(pool: &SqlitePool, migrator: &Migrator) -> Result<(), InitError> {
+    tracing::info!("Running database migrations...");
+    migrator.run(pool).await.map_err(|e| {
+        tracing::error!("Database migration failed: {}", e);
+        InitError::Migration(e)
+    })
+}
+
+/// Initialize database connection pool.
+/// WAL lets readers run alongside a writer, and writers wait up to 5s for the
+/// lock instead of failing with "database is locked" while jobs run in parallel.
+pub async fn init_pool(database_url: &str) -> Result<SqlitePool, InitError> {
     tracing::info!("Connecting to database: {}", database_url);
 
+    let options = SqliteConnectOptions::from_str(database_url)
+        .map_err(InitError::Connect)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(Duration::from_secs(5));
+
     let pool = SqlitePoolOptions::new()
-        .max_connections(5)
+        .max_connections(10)
         .acquire_timeout(Duration::from_secs(3))
-        .connect(database_url)
-        .await?;
+        .connect_with(options)
+        .await
+        .map_err(InitError::Connect)?;
 
-    tracing::info!("Running database migrations...");
-
-    // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await?;
+    run_migrations(&pool, &MIGRATOR).await?;
 
     tracing::info!("Database initialized successfully");
 
     Ok(pool)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn failing_migration_is_reported_as_migration_error() {
+        let dir = std::env::temp_dir().join(format!("decebalus-bad-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("20990101000001_broken.sql"), "ALTER TABLE no_such_table ADD COLUMN x TEXT;").unwrap();
+        let migrator = Migrator::new(Path::new(&dir)).await.unwrap();
+
+        let err = run_migrations(&memory_pool().await, &migrator).await.unwrap_err();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(matches!(err, InitError::Migration(MigrateError::ExecuteMigration(_, 20990101000001))));
+        assert_eq!(err.exit_code(), 3);
+        assert!(err.hint().contains("20990101000001"));
+    }
+
+    #[tokio::test]
+    async fn schema_newer_than_binary_gets_a_hint() {
+        let pool = memory_pool().await;
+        run_migrations(&pool, &MIGRATOR).await.unwrap();
+        // Pretend a newer build applied a migration this one doesn't know about
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+             VALUES (20990101000000, 'future', TRUE, X'00', 0)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = run_migrations(&pool, &MIGRATOR).await.unwrap_err();
+        assert!(matches!(err, InitError::Migration(MigrateError::VersionMissing(20990101000000))));
+        assert!(err.hint().contains("newer than this binary"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_database_is_a_connect_error() {
+        let err = init_pool("sqlite:/nonexistent-dir/decebalus.db").await.unwrap_err();
+        assert!(matches!(err, InitError::Connect(_)));
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_do_not_hit_lock_errors() {
+        let path = std::env::temp_dir().join(format!("decebalus-wal-{}.db", uuid::Uuid::new_v4()));
+        let pool = init_pool(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&pool).await.unwrap();
+        assert_eq!(mode, "wal");
+
+        let mut writers = tokio::task::JoinSet::new();
+        for i in 0..200 {
+            let pool = pool.clone();
+            writers.spawn(async move {
+                repository::add_log(&pool, "INFO", "test", None, None, &format!("write {}", i)).await
+            });
+        }
+        while let Some(result) = writers.join_next().await {
+            result.unwrap().expect("write failed under contention");
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM logs WHERE service = 'test'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        assert_eq!(count, 200);
+    }
+
+    #[tokio::test]
+    async fn hot_queries_use_indexes() {
+        let pool = memory_pool().await;
+        run_migrations(&pool, &MIGRATOR).await.unwrap();
+
+        let plan = |sql: &'static str| {
+            let pool = pool.clone();
+            async move {
+                let rows: Vec<(i64, i64, i64, String)> = sqlx::query_as(&format!("EXPLAIN QUERY PLAN {}", sql))
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+                rows.into_iter().map(|(_, _, _, detail)| detail).collect::<Vec<_>>().join("; ")
+            }
+        };
+
+        let status = plan("SELECT id FROM jobs WHERE status = 'queued'").await;
+        assert!(status.contains("USING INDEX idx_jobs_status"), "{}", status);
+        let job_logs = plan("SELECT id FROM logs WHERE job_id = 'abc'").await;
+        assert!(job_logs.contains("USING INDEX idx_logs_job_id"), "{}", job_logs);
+        let old_logs = plan("SELECT id FROM logs WHERE created_at < '2024-01-01'").await;
+        assert!(old_logs.contains("USING INDEX idx_logs_created_at"), "{}", old_logs);
+    }
+
+    #[test]
+    fn backend_is_picked_from_url_scheme() {
+        assert_eq!(Backend::from_url("sqlite:data/decebalus.db"), Backend::Sqlite);
+        assert_eq!(Backend::from_url("sqlite::memory:"), Backend::Sqlite);
+        assert_eq!(Backend::from_url("postgres://user@db/decebalus"), Backend::Postgres);
+        assert_eq!(Backend::from_url("postgresql://user@db/decebalus"), Backend::Postgres);
+    }
+
+    #[tokio::test]
+    async fn sqlite_url_opens_the_sqlite_repository() {
+        let path = std::env::temp_dir().join(format!("decebalus-connect-{}.db", uuid::Uuid::new_v4()));
+        let database = connect_repository(&format!("sqlite://{}?mode=rwc", path.display())).await.ok().unwrap();
+        let pool = database.sqlite.expect("SQLite backend keeps its pool");
+        database.repo.ping().await.unwrap();
+
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    #[tokio::test]
+    async fn postgres_url_needs_the_postgres_feature() {
+        let err = connect_repository("postgres://decebalus@127.0.0.1:1/decebalus").await.err().unwrap();
+        assert!(err.to_string().contains("`postgres` feature"), "{}", err);
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_url_is_opened_with_the_postgres_driver() {
+        // Nothing listens on port 1: the Postgres pool gives up waiting for a
+        // connection, where SQLite would have rejected the URL itself
+        let err = connect_repository("postgres://decebalus@127.0.0.1:1/decebalus").await.err().unwrap();
+        assert!(matches!(err, InitError::Connect(sqlx::Error::PoolTimedOut | sqlx::Error::Io(_))), "{}", err);
+    }
+}
@@ -0,0 +1,777 @@
+// src/db/pg_repository.rs
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::postgres::{PgExecutor, PgPool, PgPoolOptions, PgRow};
+use sqlx::Row;
+use crate::db::repository_trait::Repository;
+use crate::db::{repository, results_codec};
+use crate::models::{CatalogEntry, Config, DisplayMessage, DisplayStatus, Host, HostStatus, Job, JobPriority, JobResult, JobStatus, Log, LogFilter, PortCount, Schedule, Summary, TOP_PORTS, Vulnerability};
+
+const JOB_COLUMNS: &str = "id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule";
+const HOST_COLUMNS: &str = "ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities, last_scan_duration_ms, last_port_scan, os_source";
+const SCHEDULE_COLUMNS: &str = "id, name, job_type, target, cron, enabled, created_at, last_run_at";
+const LOG_COLUMNS: &str = "id, created_at, severity, service, module, job_id, content";
+
+/// Postgres-backed repository, for deployments where several instances share one database.
+/// Schema lives in `migrations_pg/`; semantics match `DbRepository`.
+pub struct PgRepository {
+    pub pool: PgPool,
+}
+
+impl PgRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Connect and bring the schema up to date.
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .acquire_timeout(std::time::Duration::from_secs(3))
+            .connect(database_url)
+            .await?;
+
+        tracing::info!("Running Postgres migrations...");
+        sqlx::migrate!("./migrations_pg").run(&pool).await?;
+
+        Ok(Self::new(pool))
+    }
+}
+
+fn priority_to_int(priority: JobPriority) -> i32 {
+    match priority {
+        JobPriority::LOW => 0,
+        JobPriority::NORMAL => 1,
+        JobPriority::HIGH => 2,
+        JobPriority::CRITICAL => 3,
+    }
+}
+
+fn job_from_row(row: &PgRow) -> Job {
+    let priority = match row.get::<i32, _>("priority") {
+        0 => JobPriority::LOW,
+        2 => JobPriority::HIGH,
+        3 => JobPriority::CRITICAL,
+        _ => JobPriority::NORMAL,
+    };
+
+    Job {
+        id: row.get("id"),
+        job_type: row.get("job_type"),
+        status: repository::parse_status(&row.get::<String, _>("status")),
+        priority,
+        results: results_codec::decode(row.get("results"), row.get("results_compressed")).map(|text| JobResult::from_stored(&text)),
+        created_at: row.get("created_at"),
+        scheduled_at: row.get("scheduled_at"),
+        config: serde_json::from_str(&row.get::<String, _>("config")).unwrap_or_default(),
+        parent_job_id: row.get("parent_job_id"),
+        run_id: row.get("run_id"),
+        phase: row.get("phase"),
+        retries: row.get::<i32, _>("retries").max(0) as u32,
+        max_retries: row.get::<Option<i32>, _>("max_retries").map(|n| n.max(0) as u32),
+        schedule: row.get("schedule"),
+    }
+}
+
+fn json_column<T: serde::de::DeserializeOwned + Default>(row: &PgRow, column: &str) -> T {
+    row.try_get::<String, _>(column)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn host_from_row(r: &PgRow) -> Host {
+    let status = match r.try_get::<String, _>("status").as_deref() {
+        Ok("Up") => HostStatus::Up,
+        Ok("Down") => HostStatus::Down,
+        _ => HostStatus::Unknown,
+    };
+
+    Host {
+        ip: r.get("ip"),
+        ports: json_column(r, "ports"),
+        banners: json_column(r, "banners"),
+        last_seen: r.get("last_seen"),
+        first_seen: r.get("first_seen"),
+        os: r.get("os"),
+        os_version: r.get("os_version"),
+        os_source: r.get("os_source"),
+        device_type: r.get("device_type"),
+        mac_address: r.get("mac_address"),
+        hostname: r.get("hostname"),
+        status,
+        services: json_column(r, "services"),
+        vulnerabilities: json_column(r, "vulnerabilities"),
+        last_scan_duration_ms: r.get("last_scan_duration_ms"),
+        last_port_scan: r.get("last_port_scan"),
+        data_quality: None,
+    }
+}
+
+fn schedule_from_row(r: &PgRow) -> Schedule {
+    Schedule {
+        id: r.get("id"),
+        name: r.get("name"),
+        job_type: r.get("job_type"),
+        target: r.get("target"),
+        cron: r.get("cron"),
+        enabled: r.get("enabled"),
+        created_at: r.get("created_at"),
+        last_run_at: r.get("last_run_at"),
+    }
+}
+
+fn display_message_from_row(r: &PgRow) -> DisplayMessage {
+    DisplayMessage {
+        id: r.get("id"),
+        text: r.get("text"),
+        created_at: r.get("created_at"),
+    }
+}
+
+fn log_from_row(r: &PgRow) -> Log {
+    Log {
+        id: r.get("id"),
+        created_at: r.get("created_at"),
+        severity: r.get("severity"),
+        service: r.get("service"),
+        module: r.get("module"),
+        job_id: r.get("job_id"),
+        content: r.get("content"),
+    }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+    // ================= JOBS =================
+    async fn create_job(&self, job: &Job) -> Result<(), sqlx::Error> {
+        let results = job.results.as_ref().map(JobResult::to_stored);
+        let (results, compressed) = results_codec::encode(results.as_deref(), results_codec::threshold());
+        sqlx::query(
+            "INSERT INTO jobs (id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, retries, max_retries, schedule) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)"
+        )
+        .bind(&job.id)
+        .bind(&job.job_type)
+        .bind(job.status.as_str())
+        .bind(priority_to_int(job.priority))
+        .bind(results)
+        .bind(compressed)
+        .bind(&job.created_at)
+        .bind(job.scheduled_at)
+        .bind(job.config.to_string())
+        .bind(&job.parent_job_id)
+        .bind(&job.run_id)
+        .bind(job.retries as i32)
+        .bind(job.max_retries.map(|n| n as i32))
+        .bind(&job.schedule)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|r| job_from_row(&r)))
+    }
+
+    async fn list_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM jobs ORDER BY created_at DESC, seq DESC", JOB_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    async fn list_jobs_paged(&self, status: Option<JobStatus>, limit: u32, offset: u32) -> Result<(Vec<Job>, u64), sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM jobs WHERE ($1::text IS NULL OR status = $1) ORDER BY created_at DESC, seq DESC LIMIT $2 OFFSET $3",
+            JOB_COLUMNS
+        ))
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE ($1::text IS NULL OR status = $1)")
+            .bind(status.map(|s| s.as_str()))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((rows.iter().map(job_from_row).collect(), total as u64))
+    }
+
+    async fn update_job_status(&self, id: &str, status: JobStatus) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET status = $1, updated_at = now() WHERE id = $2")
+            .bind(status.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_job_results(&self, id: &str, results: Option<JobResult>) -> Result<(), sqlx::Error> {
+        let results = results.as_ref().map(JobResult::to_stored);
+        let (results, compressed) = results_codec::encode(results.as_deref(), results_codec::threshold());
+        sqlx::query("UPDATE jobs SET results = $1, results_compressed = $2, updated_at = now() WHERE id = $3")
+            .bind(results)
+            .bind(compressed)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_job_phase(&self, id: &str, phase: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET phase = $1, updated_at = now() WHERE id = $2")
+            .bind(phase)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn schedule_job_retry(&self, id: &str, retries: u32, run_at: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET status = 'scheduled', retries = $1, scheduled_at = $2, updated_at = now() WHERE id = $3")
+            .bind(retries as i32)
+            .bind(run_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_running_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM jobs WHERE status = 'running'", JOB_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    async fn get_stale_running_jobs(&self, before: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM jobs WHERE status = 'running' AND EXTRACT(EPOCH FROM updated_at) < $1", JOB_COLUMNS))
+            .bind(before.timestamp())
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    async fn touch_running_job(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET updated_at = now() WHERE id = $1 AND status = 'running'")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_queued_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM jobs WHERE status = 'queued'", JOB_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    async fn get_scheduled_jobs_due(&self, now: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM jobs WHERE status = 'scheduled' AND scheduled_at < $1",
+            JOB_COLUMNS
+        ))
+        .bind(now.timestamp())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    async fn get_child_jobs(&self, parent_id: &str) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM jobs WHERE parent_job_id = $1 ORDER BY created_at ASC, seq ASC",
+            JOB_COLUMNS
+        ))
+        .bind(parent_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(job_from_row).collect())
+    }
+
+    /// `SKIP LOCKED` lets several instances claim from the same queue without blocking each other.
+    async fn claim_next_job(&self) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            "UPDATE jobs SET status = 'running', updated_at = now()
+             WHERE id = (
+                 SELECT id FROM jobs WHERE status = 'queued'
+                 ORDER BY priority DESC, created_at ASC, seq ASC
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING {}",
+            JOB_COLUMNS
+        ))
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| job_from_row(&r)))
+    }
+
+    async fn claim_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            "UPDATE jobs SET status = 'running', updated_at = now()
+             WHERE id = $1 AND status IN ('queued', 'scheduled')
+             RETURNING {}",
+            JOB_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| job_from_row(&r)))
+    }
+
+    async fn update_job_priority(&self, id: &str, priority: JobPriority) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            "UPDATE jobs SET priority = $2, updated_at = now()
+             WHERE id = $1 AND status = 'queued'
+             RETURNING {}",
+            JOB_COLUMNS
+        ))
+        .bind(id)
+        .bind(priority_to_int(priority))
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| job_from_row(&r)))
+    }
+
+    async fn requeue_failed_jobs(&self, job_type: Option<&str>, since: Option<i64>, until: Option<i64>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE jobs SET status = 'queued', results = NULL, results_compressed = FALSE, updated_at = now()
+             WHERE status = 'failed'
+             AND ($1::TEXT IS NULL OR job_type = $1)
+             AND ($2::BIGINT IS NULL OR EXTRACT(EPOCH FROM updated_at) >= $2)
+             AND ($3::BIGINT IS NULL OR EXTRACT(EPOCH FROM updated_at) <= $3)"
+        )
+        .bind(job_type)
+        .bind(since)
+        .bind(until)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_job(&self, id: &str) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let deleted = sqlx::query("DELETE FROM jobs WHERE id = $1 AND status != 'running'")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let logs = sqlx::query("DELETE FROM logs WHERE job_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(logs.rows_affected()))
+    }
+
+    // ================= HOSTS =================
+    async fn upsert_host(&self, host: &Host) -> Result<(), sqlx::Error> {
+        upsert_host_with(&self.pool, host, HOST_UPDATE_ALL).await
+    }
+
+    async fn upsert_hosts(&self, hosts: &[Host]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for host in hosts {
+            upsert_host_with(&mut *tx, host, HOST_UPDATE_DISCOVERED).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_host(&self, ip: &str) -> Result<Option<Host>, sqlx::Error> {
+        let row = sqlx::query(&format!("SELECT {} FROM hosts WHERE ip = $1", HOST_COLUMNS))
+            .bind(ip)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|r| host_from_row(&r)))
+    }
+
+    async fn set_host_vulnerabilities(&self, ip: &str, vulnerabilities: &[Vulnerability]) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE hosts SET vulnerabilities = $1, updated_at = now() WHERE ip = $2")
+            .bind(serde_json::to_string(vulnerabilities).unwrap_or_else(|_| "[]".to_string()))
+            .bind(ip)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_hosts(&self) -> Result<Vec<Host>, sqlx::Error> {
+        // inet ordering sorts addresses numerically, like the SQLite octet sort
+        let rows = sqlx::query(&format!("SELECT {} FROM hosts ORDER BY ip::inet", HOST_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(host_from_row).collect())
+    }
+
+    async fn delete_host(&self, ip: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM hosts WHERE ip = $1")
+            .bind(ip)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ================= SERVICE CATALOG =================
+    async fn record_service_seen(&self, name: &str, version: Option<&str>, seen_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO service_catalog (name, version, first_seen, last_seen)
+             VALUES ($1, $2, $3, $3)
+             ON CONFLICT (name, version) DO UPDATE SET
+                 last_seen = GREATEST(service_catalog.last_seen, EXCLUDED.last_seen),
+                 sightings = service_catalog.sightings + 1",
+        )
+        .bind(name)
+        .bind(version.unwrap_or(""))
+        .bind(seen_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_service_catalog(&self) -> Result<Vec<CatalogEntry>, sqlx::Error> {
+        let rows = sqlx::query("SELECT name, version, first_seen, last_seen, sightings FROM service_catalog ORDER BY name, version")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|r| CatalogEntry {
+                name: r.get("name"),
+                version: Some(r.get::<String, _>("version")).filter(|v| !v.is_empty()),
+                first_seen: r.get("first_seen"),
+                last_seen: r.get("last_seen"),
+                sightings: r.get("sightings"),
+            })
+            .collect())
+    }
+
+    async fn get_summary(&self, seen_since: DateTime<Utc>) -> Result<Summary, sqlx::Error> {
+        let (total_hosts, hosts_seen_24h, last_seen, last_port_scan): (i64, i64, Option<String>, Option<String>) = sqlx::query_as(
+            "SELECT COUNT(*), COUNT(*) FILTER (WHERE last_seen >= $1), MAX(last_seen), MAX(last_port_scan) FROM hosts"
+        )
+        .bind(seen_since.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        let status_rows: Vec<(String, i64)> = sqlx::query_as("SELECT status, COUNT(*) FROM jobs GROUP BY status")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let port_rows: Vec<(i32, String, i64)> = sqlx::query_as(
+            "SELECT (p->>'number')::INTEGER AS port, p->>'protocol' AS protocol, COUNT(DISTINCT h.ip) AS hosts
+             FROM hosts h, jsonb_array_elements(h.ports::jsonb) p
+             WHERE p->>'status' = 'open'
+             GROUP BY 1, 2
+             ORDER BY hosts DESC, port ASC
+             LIMIT $1"
+        )
+        .bind(TOP_PORTS as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Summary {
+            total_hosts,
+            hosts_seen_24h,
+            jobs_by_status: Summary::job_counts(status_rows.iter().map(|(status, count)| (repository::parse_status(status), *count))),
+            top_ports: port_rows
+                .into_iter()
+                .map(|(port, protocol, hosts)| PortCount { port: port as u16, protocol, hosts })
+                .collect(),
+            last_scan_at: last_seen.max(last_port_scan),
+        })
+    }
+
+    // ================= SCHEDULES =================
+    async fn create_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!("INSERT INTO schedules ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)", SCHEDULE_COLUMNS))
+            .bind(&schedule.id)
+            .bind(&schedule.name)
+            .bind(&schedule.job_type)
+            .bind(&schedule.target)
+            .bind(&schedule.cron)
+            .bind(schedule.enabled)
+            .bind(schedule.created_at)
+            .bind(schedule.last_run_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_schedule(&self, id: &str) -> Result<Option<Schedule>, sqlx::Error> {
+        let row = sqlx::query(&format!("SELECT {} FROM schedules WHERE id = $1", SCHEDULE_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(schedule_from_row))
+    }
+
+    async fn list_schedules(&self) -> Result<Vec<Schedule>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM schedules ORDER BY name, id", SCHEDULE_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(schedule_from_row).collect())
+    }
+
+    async fn update_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE schedules SET name = $1, target = $2, cron = $3, enabled = $4 WHERE id = $5")
+            .bind(&schedule.name)
+            .bind(&schedule.target)
+            .bind(&schedule.cron)
+            .bind(schedule.enabled)
+            .bind(&schedule.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_schedule(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM schedules WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn mark_schedule_run(&self, id: &str, at: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE schedules SET last_run_at = $1 WHERE id = $2")
+            .bind(at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // ================= CONFIG =================
+    async fn get_config(&self) -> Result<Config, sqlx::Error> {
+        let rows = sqlx::query("SELECT key, value FROM config")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut settings = serde_json::Map::new();
+        for row in rows {
+            let key: String = row.get("key");
+            let value: String = row.get("value");
+            if let Ok(json_value) = serde_json::from_str(&value) {
+                settings.insert(key, json_value);
+            }
+        }
+        Ok(Config { settings: serde_json::Value::Object(settings) })
+    }
+
+    async fn update_config(&self, config: &Config) -> Result<(), sqlx::Error> {
+        // One transaction, so other instances never see a half-written config
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM config").execute(&mut *tx).await?;
+        if let Some(obj) = config.settings.as_object() {
+            for (key, value) in obj {
+                sqlx::query("INSERT INTO config (key, value) VALUES ($1, $2)")
+                    .bind(key)
+                    .bind(value.to_string())
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await
+    }
+
+    // ================= DISPLAY =================
+    async fn get_display_status(&self) -> Result<DisplayStatus, sqlx::Error> {
+        let row = sqlx::query("SELECT status, last_update, image_path FROM display_status WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(DisplayStatus {
+            status: row.get("status"),
+            last_update: row.get("last_update"),
+            image: row.get("image_path"),
+        })
+    }
+
+    async fn update_display_status(&self, status: &DisplayStatus) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE display_status SET status = $1, last_update = $2, image_path = $3, updated_at = now() WHERE id = 1")
+            .bind(&status.status)
+            .bind(&status.last_update)
+            .bind(&status.image)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // ================= DISPLAY QUEUE =================
+    async fn push_display_message(&self, text: &str, keep: u32) -> Result<DisplayMessage, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM display_queue WHERE text = $1")
+            .bind(text)
+            .execute(&mut *tx)
+            .await?;
+        let row = sqlx::query("INSERT INTO display_queue (text, created_at) VALUES ($1, $2) RETURNING id, text, created_at")
+            .bind(text)
+            .bind(Utc::now().timestamp())
+            .fetch_one(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM display_queue WHERE id NOT IN (SELECT id FROM display_queue ORDER BY id DESC LIMIT $1)")
+            .bind(keep as i64)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(display_message_from_row(&row))
+    }
+
+    async fn list_display_messages(&self) -> Result<Vec<DisplayMessage>, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, text, created_at FROM display_queue ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(display_message_from_row).collect())
+    }
+
+    async fn clear_display_messages(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM display_queue").execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    // ================= LOGS =================
+    async fn add_log(&self, severity: &str, service: &str, module: Option<&str>, job_id: Option<&str>, content: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO logs (id, severity, service, module, job_id, content) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(severity)
+            .bind(service)
+            .bind(module)
+            .bind(job_id)
+            .bind(content)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_logs(&self) -> Result<Vec<Log>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM logs ORDER BY created_at DESC", LOG_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(log_from_row).collect())
+    }
+
+    async fn query_logs(&self, filter: &LogFilter) -> Result<Vec<Log>, sqlx::Error> {
+        let (since, until) = filter.time_bounds();
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM logs
+             WHERE ($1::TEXT IS NULL OR UPPER(severity) = UPPER($1))
+             AND ($2::TEXT IS NULL OR service = $2)
+             AND ($3::TEXT IS NULL OR created_at >= $3)
+             AND ($4::TEXT IS NULL OR created_at <= $4)
+             ORDER BY created_at DESC
+             LIMIT $5",
+            LOG_COLUMNS
+        ))
+        .bind(filter.severity.as_deref())
+        .bind(filter.service.as_deref())
+        .bind(since)
+        .bind(until)
+        // LIMIT NULL means no limit
+        .bind(filter.limit.map(i64::from))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(log_from_row).collect())
+    }
+
+    async fn get_log(&self, id: String) -> Result<Option<Log>, sqlx::Error> {
+        let row = sqlx::query(&format!("SELECT {} FROM logs WHERE id = $1", LOG_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|r| log_from_row(&r)))
+    }
+
+    async fn get_logs_by_job_id(&self, job_id: String) -> Result<Vec<Log>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT {} FROM logs WHERE job_id = $1 ORDER BY created_at ASC", LOG_COLUMNS))
+            .bind(job_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(log_from_row).collect())
+    }
+
+    async fn cleanup_old_logs(&self, days: i64) -> Result<u64, sqlx::Error> {
+        let cutoff = (Utc::now() - Duration::days(days)).format("%Y-%m-%d %H:%M:%S").to_string();
+        let result = sqlx::query("DELETE FROM logs WHERE created_at < $1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        let deleted = result.rows_affected();
+        tracing::info!("🧹 Deleted {} old logs (older than {} days)", deleted, days);
+        Ok(deleted)
+    }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+/// `ON CONFLICT` update replacing the whole row.
+const HOST_UPDATE_ALL: &str = "
+    ports = EXCLUDED.ports,
+    banners = EXCLUDED.banners,
+    last_seen = EXCLUDED.last_seen,
+    os = EXCLUDED.os,
+    os_version = EXCLUDED.os_version,
+    device_type = EXCLUDED.device_type,
+    mac_address = EXCLUDED.mac_address,
+    hostname = EXCLUDED.hostname,
+    status = EXCLUDED.status,
+    services = EXCLUDED.services,
+    vulnerabilities = EXCLUDED.vulnerabilities,
+    last_scan_duration_ms = EXCLUDED.last_scan_duration_ms,
+    last_port_scan = EXCLUDED.last_port_scan,
+    os_source = EXCLUDED.os_source,";
+
+/// `ON CONFLICT` update touching only what discovery finds out.
+const HOST_UPDATE_DISCOVERED: &str = "
+    last_seen = EXCLUDED.last_seen,
+    mac_address = COALESCE(EXCLUDED.mac_address, hosts.mac_address),
+    hostname = COALESCE(EXCLUDED.hostname, hosts.hostname),
+    status = EXCLUDED.status,";
+
+/// Shared by `upsert_host` and `upsert_hosts`; `update` is the
+/// `ON CONFLICT` column list.
+async fn upsert_host_with<'e, E: PgExecutor<'e>>(executor: E, host: &Host, update: &str) -> Result<(), sqlx::Error> {
+    let to_json = |v: serde_json::Result<String>| v.unwrap_or_else(|_| "[]".to_string());
+    let status_str = serde_json::to_string(&host.status)
+        .unwrap_or_else(|_| "\"Unknown\"".to_string())
+        .trim_matches('"')
+        .to_string();
+
+    sqlx::query(&format!(
+        "INSERT INTO hosts ({})
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+         ON CONFLICT (ip) DO UPDATE SET {}
+             updated_at = now()",
+        HOST_COLUMNS, update
+    ))
+    .bind(&host.ip)
+    .bind(to_json(serde_json::to_string(&host.ports)))
+    .bind(to_json(serde_json::to_string(&host.banners)))
+    .bind(&host.last_seen)
+    .bind(&host.first_seen)
+    .bind(&host.os)
+    .bind(&host.os_version)
+    .bind(&host.device_type)
+    .bind(&host.mac_address)
+    .bind(&host.hostname)
+    .bind(status_str)
+    .bind(to_json(serde_json::to_string(&host.services)))
+    .bind(to_json(serde_json::to_string(&host.vulnerabilities)))
+    .bind(host.last_scan_duration_ms)
+    .bind(&host.last_port_scan)
+    .bind(&host.os_source)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -1,28 +1,34 @@
 use chrono::{DateTime, Duration, Utc};
-use sqlx::{Row, SqlitePool, sqlite::SqliteRow};
-use crate::models::{Config, DisplayStatus, Host, Job, JobPriority, Log};
+use sqlx::{Row, SqliteExecutor, SqlitePool, sqlite::SqliteRow};
+use crate::db::results_codec;
+use crate::models::{CatalogEntry, Config, DisplayMessage, DisplayStatus, Host, Job, JobPriority, JobResult, JobStatus, Log, LogFilter, PortCount, Schedule, Summary, TOP_PORTS, Vulnerability};
 
 // ==================== JOB REPOSITORY ====================
 
 /// Create a new job in the database
 pub async fn create_job(pool: &SqlitePool, job: &Job) -> Result<(), sqlx::Error> {
-    let priority_int = match job.priority {
-        JobPriority::LOW => 0,
-        JobPriority::NORMAL => 1,
-        JobPriority::HIGH => 2,
-        JobPriority::CRITICAL => 3,
-    };
+    let priority_int = priority_to_int(job.priority);
+
+    let results = job.results.as_ref().map(JobResult::to_stored);
+    let (results, compressed) = results_codec::encode(results.as_deref(), results_codec::threshold());
 
     sqlx::query(
-        "INSERT INTO jobs (id, job_type, status, priority, results, scheduled_at, config) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+        "INSERT INTO jobs (id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, retries, max_retries, schedule) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
     )
     .bind(&job.id)
     .bind(&job.job_type)
-    .bind(&job.status)
+    .bind(job.status.as_str())
     .bind(priority_int)
-    .bind(&job.results)
-    .bind(&job.scheduled_at)
+    .bind(results)
+    .bind(compressed)
+    .bind(&job.created_at)
+    .bind(job.scheduled_at)
     .bind(&job.config)
+    .bind(&job.parent_job_id)
+    .bind(&job.run_id)
+    .bind(job.retries)
+    .bind(job.max_retries)
+    .bind(&job.schedule)
     .execute(pool)
     .await?;
     
@@ -32,7 +38,7 @@ pub async fn create_job(pool: &SqlitePool, job: &Job) -> Result<(), sqlx::Error>
 /// Get a job by ID
 pub async fn get_job(pool: &SqlitePool, id: &str) -> Result<Option<Job>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT id, job_type, status, priority, results, created_at, scheduled_at, config FROM jobs WHERE id = ?1"
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs WHERE id = ?1"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -44,46 +50,51 @@ pub async fn get_job(pool: &SqlitePool, id: &str) -> Result<Option<Job>, sqlx::E
 /// List all jobs
 pub async fn list_jobs(pool: &SqlitePool) -> Result<Vec<Job>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT id, job_type, status, priority, results, created_at, scheduled_at, config FROM jobs ORDER BY created_at DESC"
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs ORDER BY created_at DESC, rowid DESC"
     )
     .fetch_all(pool)
     .await?;
     
-    let jobs = rows.into_iter().map(|r| {
-        let priority_int = r.get::<i32, _>("priority");
-        let priority = match priority_int {
-            0 => JobPriority::LOW,
-            1 => JobPriority::NORMAL,
-            2 => JobPriority::HIGH,
-            3 => JobPriority::CRITICAL,
-            _ => JobPriority::NORMAL,
-        };
-        
-        Job {
-        id: r.get("id"),
-        job_type: r.get("job_type"),
-        status: r.get("status"),
-        priority: priority,
-        results: r.get("results"),
-        created_at: r.get("created_at"),
-        scheduled_at: r.get("scheduled_at"),
-        config: r.get("config"),
-        }
-    }).collect();
+    let jobs = rows.into_iter().map(|r| self::from_row(&r)).collect();
     
     Ok(jobs)
 }
 
+/// One page of jobs, newest first, optionally only those with `status`.
+/// Also returns how many jobs match in total.
+pub async fn list_jobs_paged(
+    pool: &SqlitePool,
+    status: Option<JobStatus>,
+    limit: u32,
+    offset: u32,
+) -> Result<(Vec<Job>, u64), sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs WHERE (?1 IS NULL OR status = ?1) ORDER BY created_at DESC, rowid DESC LIMIT ?2 OFFSET ?3"
+    )
+    .bind(status.map(|s| s.as_str()))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE (?1 IS NULL OR status = ?1)")
+        .bind(status.map(|s| s.as_str()))
+        .fetch_one(pool)
+        .await?;
+
+    Ok((rows.into_iter().map(|r| self::from_row(&r)).collect(), total as u64))
+}
+
 /// Update job status
 pub async fn update_job_status(
     pool: &SqlitePool,
     id: &str,
-    status: &str,
+    status: JobStatus,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         "UPDATE jobs SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2"
     )
-    .bind(status)
+    .bind(status.as_str())
     .bind(id)
     .execute(pool)
     .await?;
@@ -91,16 +102,59 @@ pub async fn update_job_status(
     Ok(())
 }
 
+/// Put a failed job back on the schedule for another attempt at `run_at`
+/// (unix seconds), recording it as retry number `retries`.
+pub async fn schedule_job_retry(
+    pool: &SqlitePool,
+    id: &str,
+    retries: u32,
+    run_at: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE jobs SET status = 'scheduled', retries = ?1, scheduled_at = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3"
+    )
+    .bind(retries)
+    .bind(run_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn get_running_jobs(pool: &SqlitePool) -> Result<Vec<Job>, sqlx::Error> {
-    let rows = sqlx::query("SELECT id, job_type, status, priority, results, created_at, scheduled_at, config FROM jobs WHERE status = 'running'")
+    let rows = sqlx::query("SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs WHERE status = 'running'")
         .fetch_all(pool)
         .await?;
     
     Ok(rows.into_iter().map(|r| self::from_row(&r)).collect())
 }
 
+/// Running jobs whose `updated_at` is older than `before`
+pub async fn get_stale_running_jobs(pool: &SqlitePool, before: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs
+         WHERE status = 'running'
+         AND CAST(strftime('%s', updated_at) AS INTEGER) < ?1"
+    )
+    .bind(before.timestamp())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| self::from_row(&r)).collect())
+}
+
+/// Bump `updated_at` of a running job, so the watchdog sees it progressing.
+pub async fn touch_running_job(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE jobs SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 pub async fn get_queued_jobs(pool: &SqlitePool) -> Result<Vec<Job>, sqlx::Error> {
-    let rows = sqlx::query("SELECT id, job_type, status, priority, results, created_at, scheduled_at, config FROM jobs WHERE status = 'queued'")
+    let rows = sqlx::query("SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs WHERE status = 'queued'")
         .fetch_all(pool)
         .await?;
     
@@ -112,7 +166,7 @@ pub async fn get_scheduled_jobs_due(
     now: DateTime<Utc>,
 ) -> Result<Vec<Job>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT id, job_type, status, priority, results, created_at, scheduled_at, config FROM jobs
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs
          WHERE status = 'scheduled' 
          AND scheduled_at < ?1"
     )
@@ -123,16 +177,144 @@ pub async fn get_scheduled_jobs_due(
     Ok(rows.into_iter().map(|r| self::from_row(&r)).collect())
 }
 
-/// Update job results
+/// Atomically take the highest-priority queued job and mark it running.
+/// The select and update happen in a single statement, so concurrent callers
+/// can never claim the same job.
+pub async fn claim_next_job(pool: &SqlitePool) -> Result<Option<Job>, sqlx::Error> {
+    let row = sqlx::query(
+        "UPDATE jobs SET status = 'running', updated_at = CURRENT_TIMESTAMP
+         WHERE status = 'queued'
+         AND id = (SELECT id FROM jobs WHERE status = 'queued' ORDER BY priority DESC, created_at ASC, rowid ASC LIMIT 1)
+         RETURNING id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule"
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| self::from_row(&r)))
+}
+
+/// Atomically move a specific queued or scheduled job to running.
+/// Returns `None` if the job is gone or was already picked up.
+pub async fn claim_job(pool: &SqlitePool, id: &str) -> Result<Option<Job>, sqlx::Error> {
+    let row = sqlx::query(
+        "UPDATE jobs SET status = 'running', updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND status IN ('queued', 'scheduled')
+         RETURNING id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| self::from_row(&r)))
+}
+
+/// Change the priority of a job that is still queued, so the next claim
+/// takes it in its new place. Returns `None` if the job is gone or no
+/// longer queued.
+pub async fn update_job_priority(pool: &SqlitePool, id: &str, priority: JobPriority) -> Result<Option<Job>, sqlx::Error> {
+    let row = sqlx::query(
+        "UPDATE jobs SET priority = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND status = 'queued'
+         RETURNING id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule"
+    )
+    .bind(id)
+    .bind(priority_to_int(priority))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| self::from_row(&r)))
+}
+
+/// Put failed jobs back in the queue, optionally only those of `job_type`
+/// and/or those that failed within `[since, until]` (unix seconds).
+/// Returns how many jobs were requeued.
+pub async fn requeue_failed_jobs(
+    pool: &SqlitePool,
+    job_type: Option<&str>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE jobs SET status = 'queued', results = NULL, results_compressed = 0, updated_at = CURRENT_TIMESTAMP
+         WHERE status = 'failed'
+         AND (?1 IS NULL OR job_type = ?1)
+         AND (?2 IS NULL OR CAST(strftime('%s', updated_at) AS INTEGER) >= ?2)
+         AND (?3 IS NULL OR CAST(strftime('%s', updated_at) AS INTEGER) <= ?3)"
+    )
+    .bind(job_type)
+    .bind(since)
+    .bind(until)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete a job and its logs in one transaction. Running jobs are left alone.
+/// Returns the number of logs deleted, or `None` if there was no such job or it is running.
+pub async fn delete_job(pool: &SqlitePool, id: &str) -> Result<Option<u64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query("DELETE FROM jobs WHERE id = ?1 AND status != 'running'")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let logs = sqlx::query("DELETE FROM logs WHERE job_id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(Some(logs.rows_affected()))
+}
+
+/// Jobs spawned by `parent_id`, oldest first
+pub async fn get_child_jobs(pool: &SqlitePool, parent_id: &str) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, job_type, status, priority, results, results_compressed, created_at, scheduled_at, config, parent_job_id, run_id, phase, retries, max_retries, schedule FROM jobs WHERE parent_job_id = ?1 ORDER BY created_at ASC, rowid ASC"
+    )
+    .bind(parent_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| self::from_row(&r)).collect())
+}
+
+/// Record the step a multi-phase job is in
+pub async fn update_job_phase(
+    pool: &SqlitePool,
+    id: &str,
+    phase: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE jobs SET phase = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2"
+    )
+    .bind(phase)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Update job results. Large results are stored compressed (see `results_codec`).
 pub async fn update_job_results(
     pool: &SqlitePool,
     id: &str,
-    results: Option<String>,
+    results: Option<JobResult>,
 ) -> Result<(), sqlx::Error> {
+    let results = results.as_ref().map(JobResult::to_stored);
+    let (results, compressed) = results_codec::encode(results.as_deref(), results_codec::threshold());
+
     sqlx::query(
-        "UPDATE jobs SET results = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2"
+        "UPDATE jobs SET results = ?1, results_compressed = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3"
     )
     .bind(results)
+    .bind(compressed)
     .bind(id)
     .execute(pool)
     .await?;
@@ -140,6 +322,24 @@ pub async fn update_job_results(
     Ok(())
 }
 
+/// Job status from the `status` column. A value no release writes is
+/// reported and read as `failed`, so the job is never picked up again.
+pub(crate) fn parse_status(text: &str) -> JobStatus {
+    text.parse().unwrap_or_else(|e| {
+        tracing::warn!("{}; treating the job as failed", e);
+        JobStatus::Failed
+    })
+}
+
+fn priority_to_int(priority: JobPriority) -> i32 {
+    match priority {
+        JobPriority::LOW => 0,
+        JobPriority::NORMAL => 1,
+        JobPriority::HIGH => 2,
+        JobPriority::CRITICAL => 3,
+    }
+}
+
 pub fn from_row(row: &SqliteRow) -> Job {
     let priority_int = row.get::<i32, _>("priority");
     let priority = match priority_int {
@@ -153,12 +353,19 @@ pub fn from_row(row: &SqliteRow) -> Job {
     Job {
         id: row.get("id"),
         job_type: row.get("job_type"),
-        status: row.get("status"),
+        status: parse_status(&row.get::<String, _>("status")),
         priority,
-        results: row.get("results"),
+        results: results_codec::decode(row.get("results"), row.try_get("results_compressed").unwrap_or(false))
+            .map(|text| JobResult::from_stored(&text)),
         created_at: row.get("created_at"),
         scheduled_at: row.get("scheduled_at"),
-        config: row.get("config")
+        config: row.get("config"),
+        parent_job_id: row.get("parent_job_id"),
+        run_id: row.get("run_id"),
+        phase: row.try_get("phase").ok().flatten(),
+        retries: row.try_get("retries").unwrap_or(0),
+        max_retries: row.try_get("max_retries").ok().flatten(),
+        schedule: row.try_get("schedule").ok().flatten(),
     }
 }
 
@@ -167,6 +374,49 @@ pub fn from_row(row: &SqliteRow) -> Job {
 
 /// Create or update a host
 pub async fn upsert_host(pool: &SqlitePool, host: &Host) -> Result<(), sqlx::Error> {
+    upsert_host_with(pool, host, HOST_UPDATE_ALL).await
+}
+
+/// Save hosts found by discovery in one transaction: either all of them are
+/// saved or none are. New hosts are inserted whole; known hosts only get the
+/// columns discovery owns, so port-scan results written meanwhile survive.
+pub async fn upsert_hosts(pool: &SqlitePool, hosts: &[Host]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for host in hosts {
+        upsert_host_with(&mut *tx, host, HOST_UPDATE_DISCOVERED).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// `ON CONFLICT` update replacing the whole row.
+const HOST_UPDATE_ALL: &str = "
+    ports = ?2,
+    banners = ?3,
+    last_seen = ?4,
+    os = ?6,
+    os_version = ?7,
+    device_type = ?8,
+    mac_address = ?9,
+    hostname = ?10,
+    status = ?11,
+    services = ?12,
+    vulnerabilities = ?13,
+    last_scan_duration_ms = ?14,
+    last_port_scan = ?15,
+    os_source = ?16,";
+
+/// `ON CONFLICT` update touching only what discovery finds out. A MAC or
+/// hostname it couldn't resolve keeps the stored one.
+const HOST_UPDATE_DISCOVERED: &str = "
+    last_seen = ?4,
+    mac_address = COALESCE(?9, mac_address),
+    hostname = COALESCE(?10, hostname),
+    status = ?11,";
+
+/// Shared by `upsert_host` and `upsert_hosts`; `update` is the
+/// `ON CONFLICT` column list.
+async fn upsert_host_with<'e, E: SqliteExecutor<'e>>(executor: E, host: &Host, update: &str) -> Result<(), sqlx::Error> {
     let ports_json = serde_json::to_string(&host.ports).unwrap_or_else(|_| "[]".to_string());
     let banners_json = serde_json::to_string(&host.banners).unwrap_or_else(|_| "[]".to_string());
     let services_json = serde_json::to_string(&host.services).unwrap_or_else(|_| "[]".to_string());
@@ -176,25 +426,15 @@ pub async fn upsert_host(pool: &SqlitePool, host: &Host) -> Result<(), sqlx::Err
         .trim_matches('"')
         .to_string();
 
-    sqlx::query(
+    sqlx::query(&format!(
         r#"
-        INSERT INTO hosts (ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-        ON CONFLICT(ip) DO UPDATE SET
-            ports = ?2,
-            banners = ?3,
-            last_seen = ?4,
-            os = ?6,
-            os_version = ?7,
-            device_type = ?8,
-            mac_address = ?9,
-            hostname = ?10,
-            status = ?11,
-            services = ?12,
-            vulnerabilities = ?13,
+        INSERT INTO hosts (ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities, last_scan_duration_ms, last_port_scan, os_source)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+        ON CONFLICT(ip) DO UPDATE SET {}
             updated_at = CURRENT_TIMESTAMP
-        "#
-    )
+        "#,
+        update
+    ))
     .bind(&host.ip)
     .bind(ports_json)
     .bind(banners_json)
@@ -208,16 +448,42 @@ pub async fn upsert_host(pool: &SqlitePool, host: &Host) -> Result<(), sqlx::Err
     .bind(status_str)
     .bind(services_json)
     .bind(vulns_json)
-    .execute(pool)
+    .bind(host.last_scan_duration_ms)
+    .bind(&host.last_port_scan)
+    .bind(&host.os_source)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
+/// Replace only the vulnerabilities of a host, so jobs that only look for
+/// vulnerabilities don't write back a stale copy of the rest of it.
+pub async fn set_host_vulnerabilities(pool: &SqlitePool, ip: &str, vulnerabilities: &[Vulnerability]) -> Result<(), sqlx::Error> {
+    let vulns_json = serde_json::to_string(vulnerabilities).unwrap_or_else(|_| "[]".to_string());
+    sqlx::query("UPDATE hosts SET vulnerabilities = ?1, updated_at = CURRENT_TIMESTAMP WHERE ip = ?2")
+        .bind(vulns_json)
+        .bind(ip)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete a host. Returns false if it didn't exist.
+pub async fn delete_host(pool: &SqlitePool, ip: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM hosts WHERE ip = ?1")
+        .bind(ip)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 /// Get a host by IP
 pub async fn get_host(pool: &SqlitePool, ip: &str) -> Result<Option<Host>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities FROM hosts WHERE ip = ?1"
+        "SELECT ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities, last_scan_duration_ms, last_port_scan, os_source FROM hosts WHERE ip = ?1"
     )
     .bind(ip)
     .fetch_optional(pool)
@@ -229,7 +495,7 @@ pub async fn get_host(pool: &SqlitePool, ip: &str) -> Result<Option<Host>, sqlx:
 /// List all hosts
 pub async fn list_hosts(pool: &SqlitePool) -> Result<Vec<Host>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities FROM hosts ORDER BY \
+        "SELECT ip, ports, banners, last_seen, first_seen, os, os_version, device_type, mac_address, hostname, status, services, vulnerabilities, last_scan_duration_ms, last_port_scan, os_source FROM hosts ORDER BY \
          CAST(SUBSTR(ip, 1, INSTR(ip, '.')-1) AS INTEGER), \
          CAST(SUBSTR(ip, INSTR(ip, '.')+1, INSTR(SUBSTR(ip, INSTR(ip, '.')+1), '.')-1) AS INTEGER), \
          CAST(SUBSTR(ip, INSTR(ip, '.')+INSTR(SUBSTR(ip, INSTR(ip, '.')+1), '.')+1, INSTR(SUBSTR(ip, INSTR(ip, '.')+INSTR(SUBSTR(ip, INSTR(ip, '.')+1), '.')+1), '.')-1) AS INTEGER), \
@@ -276,12 +542,199 @@ fn host_from_row(r: &SqliteRow) -> Host {
         first_seen: r.try_get("first_seen").unwrap_or_else(|_| r.get("last_seen")),
         os: r.try_get("os").ok().flatten(),
         os_version: r.try_get("os_version").ok().flatten(),
+        os_source: r.try_get("os_source").ok().flatten(),
         device_type: r.try_get("device_type").ok().flatten(),
         mac_address: r.try_get("mac_address").ok().flatten(),
         hostname: r.try_get("hostname").ok().flatten(),
         status,
         services,
         vulnerabilities,
+        last_scan_duration_ms: r.try_get("last_scan_duration_ms").ok().flatten(),
+        last_port_scan: r.try_get("last_port_scan").ok().flatten(),
+        data_quality: None,
+    }
+}
+
+// ==================== SERVICE CATALOG ====================
+
+/// Record a sighting of `name`/`version` at `seen_at` (RFC 3339).
+/// The first sighting sets `first_seen`; later ones only move `last_seen` forward.
+pub async fn record_service_seen(
+    pool: &SqlitePool,
+    name: &str,
+    version: Option<&str>,
+    seen_at: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO service_catalog (name, version, first_seen, last_seen)
+        VALUES (?1, ?2, ?3, ?3)
+        ON CONFLICT(name, version) DO UPDATE SET
+            last_seen = MAX(last_seen, excluded.last_seen),
+            sightings = sightings + 1
+        "#
+    )
+    .bind(name)
+    .bind(version.unwrap_or(""))
+    .bind(seen_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Every service/version ever seen, by name then version
+pub async fn list_service_catalog(pool: &SqlitePool) -> Result<Vec<CatalogEntry>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT name, version, first_seen, last_seen, sightings FROM service_catalog ORDER BY name, version"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(catalog_entry_from_row).collect())
+}
+
+// ==================== SUMMARY ====================
+
+/// Dashboard overview, counted in SQL. Ports are read out of each host's
+/// `ports` JSON; rows with malformed JSON are skipped.
+pub async fn get_summary(pool: &SqlitePool, seen_since: DateTime<Utc>) -> Result<Summary, sqlx::Error> {
+    let (total_hosts, hosts_seen_24h, last_seen, last_port_scan): (i64, i64, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(last_seen >= ?1), 0), MAX(last_seen), MAX(last_port_scan) FROM hosts"
+    )
+    .bind(seen_since.to_rfc3339())
+    .fetch_one(pool)
+    .await?;
+
+    let status_rows: Vec<(String, i64)> = sqlx::query_as("SELECT status, COUNT(*) FROM jobs GROUP BY status")
+        .fetch_all(pool)
+        .await?;
+
+    let port_rows: Vec<(i64, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT json_extract(p.value, '$.number') AS port,
+               json_extract(p.value, '$.protocol') AS protocol,
+               COUNT(DISTINCT h.ip) AS hosts
+        FROM (SELECT ip, ports FROM hosts WHERE json_valid(ports)) h, json_each(h.ports) p
+        WHERE json_extract(p.value, '$.status') = 'open'
+        GROUP BY port, protocol
+        ORDER BY hosts DESC, port ASC
+        LIMIT ?1
+        "#
+    )
+    .bind(TOP_PORTS as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Summary {
+        total_hosts,
+        hosts_seen_24h,
+        jobs_by_status: Summary::job_counts(status_rows.iter().map(|(status, count)| (parse_status(status), *count))),
+        top_ports: port_rows
+            .into_iter()
+            .map(|(port, protocol, hosts)| PortCount { port: port as u16, protocol, hosts })
+            .collect(),
+        last_scan_at: last_seen.max(last_port_scan),
+    })
+}
+
+fn catalog_entry_from_row(r: &SqliteRow) -> CatalogEntry {
+    CatalogEntry {
+        name: r.get("name"),
+        version: Some(r.get::<String, _>("version")).filter(|v| !v.is_empty()),
+        first_seen: r.get("first_seen"),
+        last_seen: r.get("last_seen"),
+        sightings: r.get("sightings"),
+    }
+}
+
+// ==================== SCHEDULES ====================
+
+pub async fn create_schedule(pool: &SqlitePool, schedule: &Schedule) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO schedules (id, name, job_type, target, cron, enabled, created_at, last_run_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+    )
+    .bind(&schedule.id)
+    .bind(&schedule.name)
+    .bind(&schedule.job_type)
+    .bind(&schedule.target)
+    .bind(&schedule.cron)
+    .bind(schedule.enabled)
+    .bind(schedule.created_at)
+    .bind(schedule.last_run_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_schedule(pool: &SqlitePool, id: &str) -> Result<Option<Schedule>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT id, name, job_type, target, cron, enabled, created_at, last_run_at FROM schedules WHERE id = ?1"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(schedule_from_row))
+}
+
+/// All schedules, by name
+pub async fn list_schedules(pool: &SqlitePool) -> Result<Vec<Schedule>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, name, job_type, target, cron, enabled, created_at, last_run_at FROM schedules ORDER BY name, id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(schedule_from_row).collect())
+}
+
+/// Save the editable fields of a schedule (name, target, cron, enabled)
+pub async fn update_schedule(pool: &SqlitePool, schedule: &Schedule) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE schedules SET name = ?1, target = ?2, cron = ?3, enabled = ?4 WHERE id = ?5")
+        .bind(&schedule.name)
+        .bind(&schedule.target)
+        .bind(&schedule.cron)
+        .bind(schedule.enabled)
+        .bind(&schedule.id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete a schedule. Jobs it already spawned are kept. Returns false if it didn't exist.
+pub async fn delete_schedule(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM schedules WHERE id = ?1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record that the schedule spawned a job at `at` (unix seconds)
+pub async fn mark_schedule_run(pool: &SqlitePool, id: &str, at: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE schedules SET last_run_at = ?1 WHERE id = ?2")
+        .bind(at)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+fn schedule_from_row(r: &SqliteRow) -> Schedule {
+    Schedule {
+        id: r.get("id"),
+        name: r.get("name"),
+        job_type: r.get("job_type"),
+        target: r.get("target"),
+        cron: r.get("cron"),
+        enabled: r.get("enabled"),
+        created_at: r.get("created_at"),
+        last_run_at: r.get("last_run_at"),
     }
 }
 
@@ -337,7 +790,7 @@ pub async fn update_config(pool: &SqlitePool, config: &Config) -> Result<(), sql
 /// Get display status
 pub async fn get_display_status(pool: &SqlitePool) -> Result<DisplayStatus, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT status, last_update FROM display_status WHERE id = 1"
+        "SELECT status, last_update, image_path FROM display_status WHERE id = 1"
     )
     .fetch_one(pool)
     .await?;
@@ -345,6 +798,7 @@ pub async fn get_display_status(pool: &SqlitePool) -> Result<DisplayStatus, sqlx
     Ok(DisplayStatus {
         status: row.get("status"),
         last_update: row.get("last_update"),
+        image: row.get("image_path"),
     })
 }
 
@@ -354,16 +808,66 @@ pub async fn update_display_status(
     status: &DisplayStatus,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "UPDATE display_status SET status = ?1, last_update = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = 1"
+        "UPDATE display_status SET status = ?1, last_update = ?2, image_path = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = 1"
     )
     .bind(&status.status)
     .bind(&status.last_update)
+    .bind(&status.image)
     .execute(pool)
     .await?;
     
     Ok(())
 }
 
+// ==================== DISPLAY QUEUE ====================
+
+/// Append `text` to the display queue (moving it to the end if it is already
+/// there) and trim the queue to the newest `keep` messages
+pub async fn push_display_message(pool: &SqlitePool, text: &str, keep: u32) -> Result<DisplayMessage, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM display_queue WHERE text = ?1")
+        .bind(text)
+        .execute(&mut *tx)
+        .await?;
+    let row = sqlx::query(
+        "INSERT INTO display_queue (text, created_at) VALUES (?1, ?2) RETURNING id, text, created_at"
+    )
+    .bind(text)
+    .bind(Utc::now().timestamp())
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM display_queue WHERE id NOT IN (SELECT id FROM display_queue ORDER BY id DESC LIMIT ?1)")
+        .bind(keep as i64)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(display_message_from_row(&row))
+}
+
+/// Queued display messages, oldest first
+pub async fn list_display_messages(pool: &SqlitePool) -> Result<Vec<DisplayMessage>, sqlx::Error> {
+    let rows = sqlx::query("SELECT id, text, created_at FROM display_queue ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(display_message_from_row).collect())
+}
+
+/// Remove every queued display message
+pub async fn clear_display_messages(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM display_queue").execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
+fn display_message_from_row(r: &SqliteRow) -> DisplayMessage {
+    DisplayMessage {
+        id: r.get("id"),
+        text: r.get("text"),
+        created_at: r.get("created_at"),
+    }
+}
 
 // ==================== LOGS ====================
 
@@ -419,6 +923,39 @@ pub async fn get_logs(pool: &SqlitePool) -> Result<Vec<Log>, sqlx::Error> {
     Ok(logs)
 }
 
+/// Logs matching `filter`, newest first.
+pub async fn query_logs(pool: &SqlitePool, filter: &LogFilter) -> Result<Vec<Log>, sqlx::Error> {
+    let (since, until) = filter.time_bounds();
+    let rows = sqlx::query(
+        "SELECT id, created_at, severity, service, module, job_id, content
+         FROM logs
+         WHERE (?1 IS NULL OR UPPER(severity) = UPPER(?1))
+         AND (?2 IS NULL OR service = ?2)
+         AND (?3 IS NULL OR created_at >= ?3)
+         AND (?4 IS NULL OR created_at <= ?4)
+         ORDER BY created_at DESC
+         LIMIT ?5"
+    )
+    .bind(filter.severity.as_deref())
+    .bind(filter.service.as_deref())
+    .bind(since)
+    .bind(until)
+    // A negative LIMIT means no limit
+    .bind(filter.limit.map_or(-1, i64::from))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| Log {
+        id: row.get("id"),
+        created_at: row.get("created_at"),
+        severity: row.get("severity"),
+        service: row.get("service"),
+        module: row.try_get("module").ok().flatten(),
+        job_id: row.try_get("job_id").ok().flatten(),
+        content: row.get("content"),
+    }).collect())
+}
+
 pub async fn get_log(pool: &SqlitePool, id: String) -> Result<Option<Log>, sqlx::Error> {
     let row = sqlx::query(
         "SELECT id, created_at, severity, service, module, job_id, content FROM logs WHERE id = ?1"
@@ -462,6 +999,12 @@ pub async fn get_logs_by_job_id(pool: &SqlitePool, job_id: String) -> Result<Vec
     Ok(logs)
 }
 
+/// `SELECT 1`, to tell whether the database answers.
+pub async fn ping(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
 pub async fn cleanup_old_logs(pool: &SqlitePool, days: i64) -> Result<u64, sqlx::Error> {
     // Calculate the cutoff timestamp
     let cutoff_date = (Utc::now() - Duration::days(days)).to_rfc3339();
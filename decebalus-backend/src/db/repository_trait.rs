@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use crate::models::{Job, Host, Config, Log, DisplayStatus};
+use crate::models::{Job, JobPriority, JobResult, JobStatus, Host, CatalogEntry, Config, Log, LogFilter, DisplayMessage, DisplayStatus, Schedule, Summary, Vulnerability};
 use chrono::{DateTime, Utc};
 
 #[async_trait]
@@ -8,16 +8,50 @@ pub trait Repository: Send + Sync {
     async fn create_job(&self, job: &Job) -> Result<(), sqlx::Error>;
     async fn get_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error>;
     async fn list_jobs(&self) -> Result<Vec<Job>, sqlx::Error>;
-    async fn update_job_status(&self, id: &str, status: &str) -> Result<(), sqlx::Error>;
-    async fn update_job_results(&self, id: &str, results: Option<String>) -> Result<(), sqlx::Error>;
+    async fn list_jobs_paged(&self, status: Option<JobStatus>, limit: u32, offset: u32) -> Result<(Vec<Job>, u64), sqlx::Error>;
+    async fn update_job_status(&self, id: &str, status: JobStatus) -> Result<(), sqlx::Error>;
+    async fn update_job_results(&self, id: &str, results: Option<JobResult>) -> Result<(), sqlx::Error>;
+    async fn update_job_phase(&self, id: &str, phase: Option<&str>) -> Result<(), sqlx::Error>;
+    async fn schedule_job_retry(&self, id: &str, retries: u32, run_at: i64) -> Result<(), sqlx::Error>;
     async fn get_running_jobs(&self) -> Result<Vec<Job>, sqlx::Error>;
+    async fn get_stale_running_jobs(&self, before: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error>;
+    /// Record progress: bump `updated_at` of a job that is still running.
+    async fn touch_running_job(&self, id: &str) -> Result<(), sqlx::Error>;
     async fn get_queued_jobs(&self) -> Result<Vec<Job>, sqlx::Error>;
     async fn get_scheduled_jobs_due(&self, now: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error>;
+    async fn get_child_jobs(&self, parent_id: &str) -> Result<Vec<Job>, sqlx::Error>;
+    async fn claim_next_job(&self) -> Result<Option<Job>, sqlx::Error>;
+    async fn claim_job(&self, id: &str) -> Result<Option<Job>, sqlx::Error>;
+    async fn update_job_priority(&self, id: &str, priority: JobPriority) -> Result<Option<Job>, sqlx::Error>;
+    async fn requeue_failed_jobs(&self, job_type: Option<&str>, since: Option<i64>, until: Option<i64>) -> Result<u64, sqlx::Error>;
+    async fn delete_job(&self, id: &str) -> Result<Option<u64>, sqlx::Error>;
 
     // HOSTS
     async fn upsert_host(&self, host: &Host) -> Result<(), sqlx::Error>;
+    /// Save hosts found by discovery at once; all or nothing. Known hosts only
+    /// get status, last_seen and (when found) MAC and hostname updated.
+    async fn upsert_hosts(&self, hosts: &[Host]) -> Result<(), sqlx::Error>;
+    /// Replace only the vulnerabilities of `ip`; the rest of the host is left as stored.
+    async fn set_host_vulnerabilities(&self, ip: &str, vulnerabilities: &[Vulnerability]) -> Result<(), sqlx::Error>;
     async fn get_host(&self, ip: &str) -> Result<Option<Host>, sqlx::Error>;
     async fn list_hosts(&self) -> Result<Vec<Host>, sqlx::Error>;
+    async fn delete_host(&self, ip: &str) -> Result<bool, sqlx::Error>;
+
+    // SERVICE CATALOG
+    async fn record_service_seen(&self, name: &str, version: Option<&str>, seen_at: &str) -> Result<(), sqlx::Error>;
+    async fn list_service_catalog(&self) -> Result<Vec<CatalogEntry>, sqlx::Error>;
+
+    // SUMMARY
+    /// Dashboard overview; `seen_since` bounds `hosts_seen_24h`.
+    async fn get_summary(&self, seen_since: DateTime<Utc>) -> Result<Summary, sqlx::Error>;
+
+    // SCHEDULES
+    async fn create_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error>;
+    async fn get_schedule(&self, id: &str) -> Result<Option<Schedule>, sqlx::Error>;
+    async fn list_schedules(&self) -> Result<Vec<Schedule>, sqlx::Error>;
+    async fn update_schedule(&self, schedule: &Schedule) -> Result<(), sqlx::Error>;
+    async fn delete_schedule(&self, id: &str) -> Result<bool, sqlx::Error>;
+    async fn mark_schedule_run(&self, id: &str, at: i64) -> Result<(), sqlx::Error>;
 
     // CONFIG
     async fn get_config(&self) -> Result<Config, sqlx::Error>;
@@ -27,10 +61,24 @@ pub trait Repository: Send + Sync {
     async fn get_display_status(&self) -> Result<DisplayStatus, sqlx::Error>;
     async fn update_display_status(&self, status: &DisplayStatus) -> Result<(), sqlx::Error>;
 
+    // DISPLAY QUEUE
+    /// Add `text` to the end of the display queue, moving it there if it is
+    /// already queued, and drop the oldest messages beyond `keep`.
+    async fn push_display_message(&self, text: &str, keep: u32) -> Result<DisplayMessage, sqlx::Error>;
+    /// Queued messages, oldest first.
+    async fn list_display_messages(&self) -> Result<Vec<DisplayMessage>, sqlx::Error>;
+    /// Empty the display queue, returning how many messages were removed.
+    async fn clear_display_messages(&self) -> Result<u64, sqlx::Error>;
+
     // LOGS
     async fn add_log(&self, severity: &str, service: &str, module: Option<&str>, job_id: Option<&str>, content: &str) -> Result<(), sqlx::Error>;
     async fn get_logs(&self) -> Result<Vec<Log>, sqlx::Error>;
+    async fn query_logs(&self, filter: &LogFilter) -> Result<Vec<Log>, sqlx::Error>;
     async fn get_log(&self, id: String) -> Result<Option<Log>, sqlx::Error>;
     async fn get_logs_by_job_id(&self, job_id: String) -> Result<Vec<Log>, sqlx::Error>;
     async fn cleanup_old_logs(&self, days: i64) -> Result<u64, sqlx::Error>;
+
+    // HEALTH
+    /// Cheapest round trip to the storage, to tell whether it answers.
+    async fn ping(&self) -> Result<(), sqlx::Error>;
 }
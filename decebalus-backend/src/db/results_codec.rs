@@ -0,0 +1,93 @@
+//! Transparent gzip compression of large job results.
+//!
+//! Results longer than `RESULTS_COMPRESS_THRESHOLD` bytes (default 64 KiB) are
+//! stored gzip-compressed and base64-encoded in the `results` TEXT column, with
+//! `results_compressed` set. Reads undo it, so callers only ever see plain text.
+
+use std::io::{Read, Write};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+const DEFAULT_THRESHOLD: usize = 64 * 1024;
+
+/// Size above which results are compressed (`RESULTS_COMPRESS_THRESHOLD`).
+pub fn threshold() -> usize {
+    std::env::var("RESULTS_COMPRESS_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD)
+}
+
+/// Column values for `results`: the stored text and whether it is compressed.
+pub fn encode(results: Option<&str>, threshold: usize) -> (Option<String>, bool) {
+    match results {
+        Some(text) if text.len() > threshold => match compress(text) {
+            Ok(packed) => (Some(packed), true),
+            Err(e) => {
+                tracing::warn!("Failed to compress job results, storing as plain text: {}", e);
+                (Some(text.to_string()), false)
+            }
+        },
+        other => (other.map(str::to_string), false),
+    }
+}
+
+/// Plain results from the stored column values.
+/// Undecodable data is returned as stored rather than lost.
+pub fn decode(stored: Option<String>, compressed: bool) -> Option<String> {
+    let stored = stored?;
+    if !compressed {
+        return Some(stored);
+    }
+    match decompress(&stored) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            tracing::error!("Failed to decompress job results: {}", e);
+            Some(stored)
+        }
+    }
+}
+
+fn compress(text: &str) -> std::io::Result<String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    Ok(STANDARD.encode(encoder.finish()?))
+}
+
+fn decompress(packed: &str) -> std::io::Result<String> {
+    let bytes = STANDARD
+        .decode(packed)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let mut text = String::new();
+    GzDecoder::new(bytes.as_slice()).read_to_string(&mut text)?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_results_are_stored_as_is() {
+        let (stored, compressed) = encode(Some("{\"hosts_found\":3}"), 1024);
+        assert_eq!(stored.as_deref(), Some("{\"hosts_found\":3}"));
+        assert!(!compressed);
+        assert_eq!(encode(None, 0), (None, false));
+    }
+
+    #[test]
+    fn large_results_round_trip() {
+        let results = serde_json::json!({
+            "hosts": (0..2000).map(|i| format!("10.0.{}.{} open 22/tcp ssh OpenSSH 9.6", i / 256, i % 256)).collect::<Vec<_>>()
+        })
+        .to_string();
+
+        let (stored, compressed) = encode(Some(&results), 1024);
+        assert!(compressed);
+        assert!(stored.as_ref().unwrap().len() < results.len() / 4);
+        assert_eq!(decode(stored, compressed).as_deref(), Some(results.as_str()));
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use crate::api::error::AppError;
+
+/// Crate-wide error type.
+///
+/// New code should return `error::Result` and let `?` do the conversions;
+/// handlers can return it directly since it renders like [`AppError`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(sqlx::Error),
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("scan error: {0}")]
+    Scan(String),
+
+    #[error("invalid config: {0}")]
+    Config(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    /// Malformed request parameters.
+    #[error("{0}")]
+    BadRequest(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => Self::NotFound("Record not found".to_string()),
+            other => Self::Database(other),
+        }
+    }
+}
+
+impl Error {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Config(_) | Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Database(_) | Self::Serialization(_) | Self::Scan(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    /// Rendered through [`AppError`] so every handler shares one error shape.
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlx_row_not_found_becomes_not_found() {
+        let err: Error = sqlx::Error::RowNotFound.into();
+        assert!(matches!(err, Error::NotFound(_)));
+    }
+
+    #[test]
+    fn other_sqlx_errors_become_database() {
+        let err: Error = sqlx::Error::PoolTimedOut.into();
+        assert!(matches!(err, Error::Database(sqlx::Error::PoolTimedOut)));
+    }
+
+    #[test]
+    fn serde_json_errors_become_serialization() {
+        let err: Error = serde_json::from_str::<serde_json::Value>("{").unwrap_err().into();
+        assert!(matches!(err, Error::Serialization(_)));
+    }
+
+    #[test]
+    fn into_response_maps_status_codes() {
+        let cases = [
+            (Error::NotFound("Host with IP 10.0.0.1 not found".into()), StatusCode::NOT_FOUND),
+            (Error::Config("bad value".into()), StatusCode::BAD_REQUEST),
+            (Error::BadRequest("Unknown sort: foo".into()), StatusCode::BAD_REQUEST),
+            (Error::Scan("nmap exited".into()), StatusCode::INTERNAL_SERVER_ERROR),
+            (Error::Database(sqlx::Error::PoolTimedOut), StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(err.into_response().status(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn into_response_hides_database_details() {
+        let response = Error::Database(sqlx::Error::PoolTimedOut).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["message"], "Database error");
+    }
+}
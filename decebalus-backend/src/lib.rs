@@ -1,5 +1,10 @@
 pub mod api;
+pub mod config_check;
 pub mod db;
+pub mod error;
 pub mod models;
+pub mod server;
 pub mod services;
 pub mod state;
+
+pub use state::AppState;
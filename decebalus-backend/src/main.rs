@@ -1,19 +1,6 @@
-mod api;
-mod db;
-mod models;
-mod services;
-mod state;
+use std::sync::Arc;
 
-use axum::{
-    routing::{get, post},
-    Router,
-};
-use std::{net::SocketAddr, sync::Arc};
-use tracing_subscriber;
-
-pub use state::AppState;
-
-use crate::{db::repository, services::JobExecutor};
+use decebalus_backend::{api, config_check, db, server, services::{JobExecutor, email_notifier::EmailNotifier, notifier::{NoopNotifier, NotificationHub, WebhookNotifier}, rescan_scheduler::RescanScheduler, job_watchdog::JobWatchdog, log_cleanup::LogCleanup, display_rotator::DisplayRotator}, AppState};
 
 async fn shutdown_signal() {
     tokio::signal::ctrl_c()
@@ -33,63 +20,119 @@ async fn main() {
 
     std::fs::create_dir_all("data").expect("Failed to create data directory");
     
-    let db_pool = db::init_pool(&database_url)
-        .await
-        .expect("Failed to initialize database");
+    // sqlite: URLs use SQLite, postgres:// ones Postgres (with the `postgres` feature)
+    let database = match db::connect_repository(&database_url).await {
+        Ok(database) => database,
+        Err(e) => {
+            tracing::error!("{}", e);
+            tracing::error!("Hint: {}", e.hint());
+            std::process::exit(e.exit_code());
+        }
+    };
+
+    // Validate env + stored config and exit, for CI/deploy pipelines
+    if config_check::requested() {
+        let report = config_check::run(database.repo.as_ref(), |name| std::env::var(name).ok()).await;
+        for problem in &report.problems {
+            eprintln!("config error: {}", problem);
+        }
+        if report.is_ok() {
+            println!("Configuration OK");
+        }
+        std::process::exit(report.exit_code());
+    }
+
+    let state = Arc::new(AppState::new(database.sqlite, database.repo));
+    if state.api_token.is_none() {
+        tracing::warn!("API_TOKEN is not set; the API and WebSocket are open to anyone who can reach this host");
+    }
+    // A new database starts with the default scan and job settings, visible through /api/config
+    match db::seed_default_config(state.repo.as_ref()).await {
+        Ok(true) => tracing::info!("Stored default config in the empty config table"),
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Failed to seed default config: {}", e),
+    }
+    if let Err(e) = state.config.reload(state.repo.as_ref()).await {
+        tracing::warn!("Failed to load config: {}", e);
+    }
+
+    // Integrations; integrations.notifiers picks which ones run (default: email)
+    state.notifiers.register(Arc::new(EmailNotifier::new(state.repo.clone())));
+    state.notifiers.register(Arc::new(WebhookNotifier::new(state.repo.clone())));
+    state.notifiers.register(Arc::new(NoopNotifier));
+    NotificationHub::spawn(state.clone());
+
+    // Re-port-scan hosts whose last scan is older than hosts.rescan_after_hours
+    RescanScheduler::spawn(state.clone());
+
+    // Fail running jobs that stopped making progress (jobs.stuck_after_secs)
+    JobWatchdog::spawn(state.clone());
+
+    // Delete old logs now and then every logs.cleanup_interval_secs (retention: logs.retention_days)
+    LogCleanup::spawn(state.clone());
+
+    // Cycle the display through its message queue every display.rotate_interval_secs
+    DisplayRotator::spawn(state.clone());
 
-    let state = Arc::new(AppState::new(db_pool));
 
-    //Run Scheduled jobs that haven't been run yet
+    // Handle unfinished jobs in case of previously closed app without finalising all jobs:
+    JobExecutor::resume_incomplete_jobs(state.clone()).await;
+    // Start jobs left queued, including those re-queued by the last shutdown
+    JobExecutor::run_queue(&state).await;
+
+    // Run scheduled jobs and schedules as they come due (every SCHEDULER_INTERVAL_SECS)
     let scheduler_state = Arc::clone(&state);
-     tokio::spawn(async move {
+    tokio::spawn(async move {
         JobExecutor::check_and_run_scheduled_jobs(scheduler_state).await;
     });
 
-    // On startup check and cleanup logs older than X amount of days, in case of not set in .env, make it 30 days
-    let retention_days: i64 = std::env::var("LOG_RETENTION_DAYS")
-        .unwrap_or_else(|_| "30".to_string()) // Default to 30 days if not set
-        .parse()
-        .unwrap_or(30);
-
-    let _ = repository::cleanup_old_logs(&state.db, retention_days).await;
-
-
-    // Handle unfinished jobs in case of previously closed app without finalising all jobs:
-    JobExecutor::resume_incomplete_jobs(state.clone()).await;
-
-    let app = Router::new()
-        // Job routes
-        .route("/api/jobs", post(api::jobs::create_job).get(api::jobs::list_jobs))
-        .route("/api/jobs/schedule", post(api::jobs::schedule_job).get(api::jobs::list_jobs))
-        .route("/api/jobs/{id}", get(api::jobs::get_job))
-        .route("/api/jobs/{id}/cancel", post(api::jobs::cancel_job))
-        // Host routes
-        .route("/api/hosts", get(api::hosts::list_hosts))
-        .route("/api/hosts/{ip}", get(api::hosts::get_host))
-        // Display routes
-        .route("/api/display/status", get(api::display::get_display_status))
-        .route("/api/display/update", post(api::display::update_display))
-        // Config routes
-        .route("/api/config", get(api::config::get_config).post(api::config::update_config))
-        // Logs routes
-        .route("/api/logs", get(api::logs::get_all_logs))
-        .route("/api/logs/{id}", get(api::logs::get_logs_by_job_id))
-        // WebSocket route
-        .route("/ws", get(api::websocket::ws_handler))
-        .with_state(state);
-
-    // Bind to address
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    tracing::info!("🚀 Server listening on {}", addr);
+    let mut app = api::router(state.clone());
+
+    // Cross-origin access for a frontend served elsewhere (CORS_ALLOWED_ORIGINS / DEV_CORS)
+    match server::cors_layer(|name| std::env::var(name).ok()) {
+        Ok(Some(cors)) => app = app.layer(cors),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Invalid CORS settings: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Bind to BIND_ADDR:PORT (default 0.0.0.0:8080)
+    let addr = match server::bind_addr(|name| std::env::var(name).ok()) {
+        Ok(addr) => addr,
+        Err(e) => {
+            tracing::error!("Invalid listen address: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // HTTPS when TLS_CERT_PATH and TLS_KEY_PATH are set
+    let tls = match server::tls_paths(|name| std::env::var(name).ok()) {
+        Ok(Some((cert, key))) => match server::load_tls(&cert, &key).await {
+            Ok(tls) => Some(tls),
+            Err(e) => {
+                tracing::error!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("Invalid TLS settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    tracing::info!("🚀 Server listening on {}://{}", scheme, addr);
 
     // Create TCP listener
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
 
     // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    server::run(listener, app, tls, shutdown_signal()).await.unwrap();
+
+    // Let running jobs finish (SHUTDOWN_TIMEOUT_SECS) and re-queue the rest
+    JobExecutor::shutdown(&state, JobExecutor::shutdown_timeout()).await;
 
     tracing::info!("✅ Server has shut down gracefully");
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Active checks, read from the `attacks` section of the config table.
+/// Everything here logs in to or probes other machines, so it is off by default.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AttacksConfig {
+    /// Allow `attack` jobs to run.
+    pub enabled: bool,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// One distinct service/version in the `service_catalog`, with when it was
+/// first and most recently fingerprinted anywhere on the network.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub version: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Number of times it was fingerprinted (once per host per scan).
+    pub sightings: i64,
+}
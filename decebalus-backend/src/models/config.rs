@@ -1,4 +1,39 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::models::{AttacksConfig, DisplayConfig, HostsConfig, JobsConfig, LogsConfig, ScanConfig, SmtpConfig, WebhooksConfig, max_discovery_threads};
+
+/// Largest serialized config accepted by `update_config`. The whole table is
+/// read on every scan, so it is kept small.
+pub const MAX_CONFIG_BYTES: usize = 64 * 1024;
+/// Longest string allowed for any single value (or key).
+pub const MAX_VALUE_LEN: usize = 4096;
+/// Deepest nesting of objects/arrays allowed in a config.
+pub const MAX_CONFIG_DEPTH: usize = 8;
+
+/// Why a config payload was rejected by `Config::check_limits`.
+#[derive(Debug, PartialEq)]
+pub enum ConfigLimitError {
+    TooLarge { size: usize },
+    ValueTooLong { path: String, len: usize },
+    TooDeep { path: String },
+}
+
+impl ConfigLimitError {
+    /// Oversized payloads map to 413; everything else is a plain 400.
+    pub fn is_too_large(&self) -> bool {
+        matches!(self, Self::TooLarge { .. })
+    }
+}
+
+impl fmt::Display for ConfigLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { size } => write!(f, "Config is {} bytes, maximum is {}", size, MAX_CONFIG_BYTES),
+            Self::ValueTooLong { path, len } => write!(f, "Value at '{}' is {} characters, maximum is {}", path, len, MAX_VALUE_LEN),
+            Self::TooDeep { path } => write!(f, "Value at '{}' is nested deeper than {} levels", path, MAX_CONFIG_DEPTH),
+        }
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Config {
@@ -12,6 +47,24 @@ impl Config {
         }
     }
     
+    /// What a fresh install stores: the `scan_config` and `jobs` sections
+    /// spelled out with the values scans would otherwise fall back to, and
+    /// discovery aimed at the host's own networks (`self`).
+    pub fn defaults() -> Self {
+        let scan_config = ScanConfig {
+            target: Some("self".to_string()),
+            ports: Some((1..=u16::MAX).collect()),
+            per_network_concurrency: Some(max_discovery_threads()),
+            banner_concurrency: Some(ScanConfig::default().banner_concurrency()),
+            max_connections_per_second: Some(0.0),
+            ..ScanConfig::default()
+        };
+        let mut config = Self::new();
+        config.set("scan_config".to_string(), serde_json::to_value(scan_config).unwrap_or_default());
+        config.set("jobs".to_string(), serde_json::to_value(JobsConfig::default()).unwrap_or_default());
+        config
+    }
+
     pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
         self.settings.get(key)
     }
@@ -21,6 +74,120 @@ impl Config {
             obj.insert(key, value);
         }
     }
+
+    /// Top-level keys whose values differ between `self` and `other`,
+    /// including keys present on only one side. Sorted for stable output.
+    pub fn changed_keys(&self, other: &Config) -> Vec<String> {
+        let empty = serde_json::Map::new();
+        let old = self.settings.as_object().unwrap_or(&empty);
+        let new = other.settings.as_object().unwrap_or(&empty);
+
+        let mut keys: Vec<String> = old.keys()
+            .chain(new.keys())
+            .filter(|k| old.get(*k) != new.get(*k))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Reject configs that are too big, too deeply nested or hold oversized strings.
+    pub fn check_limits(&self) -> Result<(), ConfigLimitError> {
+        let size = self.settings.to_string().len();
+        if size > MAX_CONFIG_BYTES {
+            return Err(ConfigLimitError::TooLarge { size });
+        }
+        Self::check_value("$", &self.settings, 0)
+    }
+
+    fn check_value(path: &str, value: &serde_json::Value, depth: usize) -> Result<(), ConfigLimitError> {
+        if depth > MAX_CONFIG_DEPTH {
+            return Err(ConfigLimitError::TooDeep { path: path.to_string() });
+        }
+        match value {
+            serde_json::Value::String(s) if s.chars().count() > MAX_VALUE_LEN => {
+                Err(ConfigLimitError::ValueTooLong { path: path.to_string(), len: s.chars().count() })
+            }
+            serde_json::Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, v)| Self::check_value(&format!("{}[{}]", path, i), v, depth + 1)),
+            serde_json::Value::Object(map) => map.iter().try_for_each(|(k, v)| {
+                let child = format!("{}.{}", path, k);
+                if k.chars().count() > MAX_VALUE_LEN {
+                    return Err(ConfigLimitError::ValueTooLong { path: child, len: k.chars().count() });
+                }
+                Self::check_value(&child, v, depth + 1)
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Typed `integrations.smtp` section. Falls back to defaults if missing or malformed.
+    pub fn smtp_config(&self) -> SmtpConfig {
+        self.get("integrations")
+            .and_then(|i| i.get("smtp"))
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Names of the notifiers to drive (`integrations.notifiers`). Defaults to email only.
+    pub fn enabled_notifiers(&self) -> Vec<String> {
+        self.get("integrations")
+            .and_then(|i| i.get("notifiers"))
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_else(|| vec!["email".to_string()])
+    }
+
+    /// Typed `webhooks` section. Falls back to defaults if missing or malformed.
+    pub fn webhooks_config(&self) -> WebhooksConfig {
+        self.get("webhooks")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `hosts` section. Falls back to defaults if missing or malformed.
+    pub fn hosts_config(&self) -> HostsConfig {
+        self.get("hosts")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `jobs` section. Falls back to defaults if missing or malformed.
+    pub fn jobs_config(&self) -> JobsConfig {
+        self.get("jobs")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `logs` section. Falls back to defaults if missing or malformed.
+    pub fn logs_config(&self) -> LogsConfig {
+        self.get("logs")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `attacks` section. Falls back to defaults (disabled) if missing or malformed.
+    pub fn attacks_config(&self) -> AttacksConfig {
+        self.get("attacks")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `display` section. Falls back to defaults if missing or malformed.
+    pub fn display_config(&self) -> DisplayConfig {
+        self.get("display")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Typed `scan_config` section. Falls back to defaults if missing or malformed.
+    pub fn scan_config(&self) -> ScanConfig {
+        self.get("scan_config")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
 }
 
 impl Default for Config {
@@ -47,6 +214,17 @@ mod tests {
         assert!(cfg.settings.as_object().unwrap().is_empty());
     }
 
+    #[test]
+    fn defaults_read_back_as_the_typed_defaults() {
+        let cfg = Config::defaults();
+        assert_eq!(cfg.scan_config().target.as_deref(), Some("self"));
+        assert_eq!(cfg.scan_config().adaptive_timeout, ScanConfig::default().adaptive_timeout);
+        assert!(cfg.scan_config().scans_all_ports());
+        assert_eq!(cfg.scan_config().ports_label(), "1-65535");
+        assert_eq!(cfg.jobs_config(), JobsConfig::default());
+        assert!(cfg.check_limits().is_ok());
+    }
+
     #[test]
     fn test_set_and_get_value() {
         let mut cfg = Config::new();
@@ -84,4 +262,72 @@ mod tests {
 
         assert_eq!(cfg.get("theme"), Some(&json!("dark")));
     }
+
+    #[test]
+    fn test_changed_keys_reports_added_removed_and_modified() {
+        let mut old = Config::new();
+        old.set("theme".to_string(), json!("light"));
+        old.set("user".to_string(), json!("shadowmonk"));
+        old.set("legacy".to_string(), json!(true));
+
+        let mut new = Config::new();
+        new.set("theme".to_string(), json!("dark"));
+        new.set("user".to_string(), json!("shadowmonk"));
+        new.set("device_name".to_string(), json!("decebalus-01"));
+
+        assert_eq!(old.changed_keys(&new), vec!["device_name", "legacy", "theme"]);
+        assert!(new.changed_keys(&new).is_empty());
+    }
+
+    #[test]
+    fn test_smtp_config_reads_nested_section() {
+        let mut cfg = Config::new();
+        assert!(!cfg.smtp_config().is_configured());
+
+        cfg.set("integrations".to_string(), json!({
+            "smtp": { "host": "localhost", "from": "a@b.c", "to": ["d@e.f"], "digest": true }
+        }));
+        let smtp = cfg.smtp_config();
+        assert!(smtp.is_configured());
+        assert!(smtp.digest);
+    }
+
+    #[test]
+    fn test_check_limits() {
+        let mut cfg = Config::new();
+        cfg.set("device_name".to_string(), json!("decebalus-01"));
+        cfg.set("scan_config".to_string(), json!({ "autopilot": { "enabled": true } }));
+        assert!(cfg.check_limits().is_ok());
+
+        let mut long = cfg.clone();
+        long.set("motd".to_string(), json!("x".repeat(MAX_VALUE_LEN + 1)));
+        assert!(matches!(
+            long.check_limits(),
+            Err(ConfigLimitError::ValueTooLong { ref path, .. }) if path == "$.motd"
+        ));
+
+        let mut deep = json!("leaf");
+        for _ in 0..MAX_CONFIG_DEPTH {
+            deep = json!([deep]);
+        }
+        let mut nested = Config::new();
+        nested.set("nested".to_string(), deep);
+        assert!(matches!(nested.check_limits(), Err(ConfigLimitError::TooDeep { .. })));
+
+        let mut big = Config::new();
+        for i in 0..32 {
+            big.set(format!("key{}", i), json!("x".repeat(MAX_VALUE_LEN)));
+        }
+        let err = big.check_limits().unwrap_err();
+        assert!(err.is_too_large());
+    }
+
+    #[test]
+    fn test_scan_config_reads_section() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.scan_config(), ScanConfig::default());
+
+        cfg.set("scan_config".to_string(), json!({ "per_network_concurrency": 4 }));
+        assert_eq!(cfg.scan_config().per_network_concurrency, Some(4));
+    }
 }
@@ -1,15 +1,52 @@
 use serde::Deserialize;
+use crate::models::JobPriority;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateJobRequest {
     #[serde(default = "default_job_type")]
     pub job_type: String,
 
-    // Discovery-specific (optional for now)
+    /// Discovery: CIDR(s) to sweep, defaulting to `scan_config.target`.
+    /// Port-scan: a single host IP instead of every discovered host.
     pub target: Option<String>,
-    pub scheduled_at: Option<i64>
+
+    /// Port-scan, or a discovery's auto port-scan: probe only these TCP ports
+    /// instead of `scan_config.ports`
+    pub ports: Option<Vec<u16>>,
+
+    pub scheduled_at: Option<i64>,
+
+    /// Run on this cron schedule (`sec min hour day-of-month month day-of-week`)
+    /// instead of once. The first run is the next fire time unless `scheduled_at` is set.
+    pub cron: Option<String>,
+
+    /// Discovery only: queue a port-scan of the found hosts once discovery completes
+    #[serde(default)]
+    pub auto_port_scan: bool,
+
+    /// Export only: `json` (default), `csv` or `xml` (nmap format)
+    pub format: Option<String>,
+
+    /// Automatic retries on failure; defaults to `jobs.max_retries` from config
+    pub max_retries: Option<u32>,
 }
 
 fn default_job_type() -> String {
     "discovery".to_string()
 }
+
+/// Body of `POST /api/jobs/retry-failed`. All filters are optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct RetryFailedRequest {
+    pub job_type: Option<String>,
+    /// Only jobs that failed at or after this unix timestamp
+    pub since: Option<i64>,
+    /// Only jobs that failed at or before this unix timestamp
+    pub until: Option<i64>,
+}
+
+/// Body of `PATCH /api/jobs/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct UpdateJobRequest {
+    pub priority: JobPriority,
+}
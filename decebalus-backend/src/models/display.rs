@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 pub struct DisplayStatus {
     pub status: String,
     pub last_update: String,
+    /// File holding the image last pushed to the panel, served by `GET /api/display/image`
+    #[serde(default)]
+    pub image: Option<String>,
 }
 
 impl DisplayStatus {
@@ -11,6 +14,7 @@ impl DisplayStatus {
         Self {
             status: "idle".to_string(),
             last_update: "never".to_string(),
+            image: None,
         }
     }
     
@@ -26,6 +30,22 @@ impl Default for DisplayStatus {
     }
 }
 
+/// A message in the display queue, which the display rotates through.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct DisplayMessage {
+    /// Increases with every push, so it also gives the queue order.
+    pub id: i64,
+    pub text: String,
+    /// Unix timestamp the message was pushed.
+    pub created_at: i64,
+}
+
+/// Body of `POST /api/display/queue`.
+#[derive(Debug, Deserialize)]
+pub struct PushDisplayMessageRequest {
+    pub text: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// E-paper panel settings, read from the `display` section of the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// Panel width in pixels. Images may also be sent rotated (height × width).
+    pub width: u32,
+    /// Panel height in pixels.
+    pub height: u32,
+    /// Seconds each queued message stays on the panel before the next one.
+    pub rotate_interval_secs: u64,
+    /// Most messages kept in the display queue; pushing more drops the oldest.
+    pub queue_size: u32,
+}
+
+impl DisplayConfig {
+    /// Whether an image of `width` × `height` fits the panel, either way round.
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        (width, height) == (self.width, self.height) || (height, width) == (self.width, self.height)
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        // Waveshare 2.13" panel in landscape
+        Self { width: 250, height: 122, rotate_interval_secs: 10, queue_size: 20 }
+    }
+}
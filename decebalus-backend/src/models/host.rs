@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::models::{HostStatus, Port, Service, Vulnerability};
 
@@ -8,6 +8,11 @@ pub struct Host {
     pub ports: Vec<Port>,
     pub os: Option<String>,
     pub os_version: Option<String>,
+    /// Where `os` came from: `nmap`, `banner`, `ttl` or `ports`. A `ttl` guess
+    /// is easily fooled (changed defaults, routers rewriting TTLs), so treat it
+    /// as low confidence.
+    #[serde(default)]
+    pub os_source: Option<String>,
     pub device_type: Option<String>,
     pub mac_address: Option<String>,
     pub hostname: Option<String>,
@@ -18,6 +23,16 @@ pub struct Host {
     pub services: Vec<Service>,
     pub vulnerabilities: Vec<Vulnerability>,
     pub banners: Vec<String>,
+    /// How long the last port scan of this host took, in milliseconds.
+    #[serde(default)]
+    pub last_scan_duration_ms: Option<i64>,
+    /// When the host was last port-scanned (RFC 3339).
+    #[serde(default)]
+    pub last_port_scan: Option<String>,
+    /// Freshness/completeness score (0–100), computed by the API on read and
+    /// never stored. See `Host::data_quality`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_quality: Option<u8>,
 }
 
 fn default_first_seen() -> String {
@@ -32,6 +47,7 @@ impl Host {
             ports: Vec::new(),
             os: None,
             os_version: None,
+            os_source: None,
             device_type: None,
             mac_address: None,
             hostname: None,
@@ -41,10 +57,25 @@ impl Host {
             services: Vec::new(),
             vulnerabilities: Vec::new(),
             banners: Vec::new(),
+            last_scan_duration_ms: None,
+            last_port_scan: None,
+            data_quality: None,
         }
     }
 
     
+    /// Whether `number` is recorded as open, on any protocol.
+    pub fn has_open_port(&self, number: u16) -> bool {
+        self.ports.iter().any(|p| p.number == number && p.status == "open")
+    }
+
+    /// Case-insensitive substring match on the IP or hostname.
+    pub fn matches_search(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.ip.contains(&query)
+            || self.hostname.as_deref().is_some_and(|h| h.to_lowercase().contains(&query))
+    }
+
     pub fn add_port(&mut self, number: u16, protocol: &str, status: &str, service: Option<String>, version: Option<String>, cpe: Option<String>) {
         // Check if the port already exists
         if let Some(existing) = self.ports.iter_mut().find(|p| p.number == number && p.protocol == protocol) {
@@ -82,6 +113,16 @@ impl Host {
         });
     }
 
+    /// After a scan that covered every `protocol` port, mark the ones that were
+    /// open before but not in `open` as closed.
+    pub fn close_unseen_ports(&mut self, protocol: &str, open: &[u16]) {
+        for port in self.ports.iter_mut() {
+            if port.protocol == protocol && port.status == "open" && !open.contains(&port.number) {
+                port.status = "closed".to_string();
+            }
+        }
+    }
+
     
     pub fn add_banner(&mut self, banner: String) {
         if !self.banners.contains(&banner) {
@@ -92,6 +133,54 @@ impl Host {
     pub fn update_last_seen(&mut self) {
         self.last_seen = Utc::now().to_rfc3339();
     }
+
+    /// How far this host's data can be trusted at `now`, from 0 to 100:
+    ///
+    /// ```text
+    /// data_quality = 40 × freshness(last_seen)
+    ///              + 30 × freshness(last_port_scan)
+    ///              + 30 × identified open ports / open ports
+    ///
+    /// freshness(t) = max(0, 1 − age(t) / 7 days)   (0 if never / unparseable)
+    /// ```
+    ///
+    /// A port-scanned host with no open ports counts as fully identified; a host
+    /// never port-scanned gets nothing for identification either.
+    pub fn data_quality(&self, now: DateTime<Utc>) -> u8 {
+        const STALE_AFTER_SECS: f64 = 7.0 * 24.0 * 3600.0;
+        let freshness = |ts: Option<&str>| {
+            ts.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| {
+                    let age = (now - t.with_timezone(&Utc)).num_seconds().max(0) as f64;
+                    (1.0 - age / STALE_AFTER_SECS).max(0.0)
+                })
+                .unwrap_or(0.0)
+        };
+
+        let open: Vec<_> = self.ports.iter().filter(|p| p.status == "open").collect();
+        let identified = if self.last_port_scan.is_none() {
+            0.0
+        } else if open.is_empty() {
+            1.0
+        } else {
+            let known = open
+                .iter()
+                .filter(|p| p.service.as_deref().is_some_and(|s| !s.is_empty() && s != "unknown"))
+                .count();
+            known as f64 / open.len() as f64
+        };
+
+        let score = 40.0 * freshness(Some(&self.last_seen))
+            + 30.0 * freshness(self.last_port_scan.as_deref())
+            + 30.0 * identified;
+        score.round() as u8
+    }
+
+    /// This host with `data_quality` filled in for `now`.
+    pub fn with_data_quality(mut self, now: DateTime<Utc>) -> Self {
+        self.data_quality = Some(self.data_quality(now));
+        self
+    }
 }
 
 impl Default for Host {
@@ -103,7 +192,6 @@ impl Default for Host {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::DateTime;
     use crate::models::HostStatus;
 
     #[test]
@@ -158,6 +246,41 @@ mod tests {
         assert_eq!(ordered, vec![22, 80, 443]);
     }
 
+    #[test]
+    fn close_unseen_ports_only_touches_the_scanned_protocol() {
+        let mut h = Host::new("10.0.0.1".into());
+        h.add_port(22, "tcp", "open", None, None, None);
+        h.add_port(80, "tcp", "open", None, None, None);
+        h.add_port(161, "udp", "open", None, None, None);
+
+        h.close_unseen_ports("tcp", &[22]);
+
+        assert!(h.has_open_port(22));
+        assert!(!h.has_open_port(80));
+        assert!(h.has_open_port(161));
+    }
+
+    #[test]
+    fn has_open_port_ignores_closed_ports() {
+        let mut h = Host::new("10.0.0.1".into());
+        h.add_port(22, "tcp", "open", None, None, None);
+        h.add_port(80, "tcp", "closed", None, None, None);
+
+        assert!(h.has_open_port(22));
+        assert!(!h.has_open_port(80));
+        assert!(!h.has_open_port(443));
+    }
+
+    #[test]
+    fn matches_search_checks_ip_and_hostname() {
+        let mut h = Host::new("192.168.1.20".into());
+        h.hostname = Some("NAS.local".into());
+
+        assert!(h.matches_search("192.168"));
+        assert!(h.matches_search("nas"));
+        assert!(!h.matches_search("10.0"));
+    }
+
     #[test]
     fn add_banner_adds_only_once() {
         let mut h = Host::new("10.0.0.1".into());
@@ -181,6 +304,33 @@ mod tests {
         assert!(h.last_seen > old);
     }
 
+    #[test]
+    fn fresh_complete_host_scores_higher_than_stale_unscanned() {
+        let now = Utc::now();
+
+        let mut fresh = Host::new("10.0.0.1".into());
+        fresh.last_seen = now.to_rfc3339();
+        fresh.last_port_scan = Some(now.to_rfc3339());
+        fresh.ports = vec![Port {
+            number: 22,
+            protocol: "tcp".into(),
+            status: "open".into(),
+            service: Some("ssh".into()),
+            version: None,
+            cpe: None,
+        }];
+
+        let mut stale = Host::new("10.0.0.2".into());
+        stale.last_seen = (now - chrono::Duration::days(30)).to_rfc3339();
+
+        assert_eq!(fresh.data_quality(now), 100);
+        assert_eq!(stale.data_quality(now), 0);
+
+        // Seen just now but never port-scanned: only the last_seen share
+        stale.last_seen = now.to_rfc3339();
+        assert_eq!(stale.data_quality(now), 40);
+    }
+
     #[test]
     fn default_uses_correct_ip() {
         let h = Host::default();
@@ -0,0 +1,94 @@
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use crate::models::Host;
+
+/// Open ports and services that appeared on or went away from a host
+/// between two saves. The basis for drift alerts.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct HostChange {
+    pub added_ports: Vec<u16>,
+    pub removed_ports: Vec<u16>,
+    pub added_services: Vec<String>,
+    pub removed_services: Vec<String>,
+}
+
+impl HostChange {
+    /// What changed from `previous` to `current`, or `None` if their open
+    /// ports and services are the same.
+    pub fn between(previous: &Host, current: &Host) -> Option<Self> {
+        let open_ports = |h: &Host| -> BTreeSet<u16> {
+            h.ports.iter().filter(|p| p.status == "open").map(|p| p.number).collect()
+        };
+        let services = |h: &Host| -> BTreeSet<String> { h.services.iter().map(|s| s.name.clone()).collect() };
+
+        let (before, after) = (open_ports(previous), open_ports(current));
+        let (services_before, services_after) = (services(previous), services(current));
+        let change = Self {
+            added_ports: after.difference(&before).copied().collect(),
+            removed_ports: before.difference(&after).copied().collect(),
+            added_services: services_after.difference(&services_before).cloned().collect(),
+            removed_services: services_before.difference(&services_after).cloned().collect(),
+        };
+
+        (change != Self::default()).then_some(change)
+    }
+
+    /// One-line summary for logs, e.g. `ports +443 -23; services +https`.
+    pub fn describe(&self) -> String {
+        let list = |added: Vec<String>, removed: Vec<String>| {
+            added.into_iter().map(|a| format!("+{}", a))
+                .chain(removed.into_iter().map(|r| format!("-{}", r)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let ports = list(
+            self.added_ports.iter().map(u16::to_string).collect(),
+            self.removed_ports.iter().map(u16::to_string).collect(),
+        );
+        let services = list(self.added_services.clone(), self.removed_services.clone());
+
+        [("ports", ports), ("services", services)]
+            .into_iter()
+            .filter(|(_, list)| !list.is_empty())
+            .map(|(what, list)| format!("{} {}", what, list))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Service;
+
+    #[test]
+    fn reports_opened_and_closed_ports_and_services() {
+        let mut before = Host::new("10.0.0.1".into());
+        before.add_port(22, "tcp", "open", None, None, None);
+        before.add_port(23, "tcp", "open", None, None, None);
+        before.services.push(Service::new("telnet", None, None));
+
+        let mut after = before.clone();
+        after.add_port(23, "tcp", "closed", None, None, None);
+        after.add_port(443, "tcp", "open", None, None, None);
+        after.services = vec![Service::new("https", None, None)];
+
+        let change = HostChange::between(&before, &after).unwrap();
+        assert_eq!(change.added_ports, vec![443]);
+        assert_eq!(change.removed_ports, vec![23]);
+        assert_eq!(change.added_services, vec!["https".to_string()]);
+        assert_eq!(change.removed_services, vec!["telnet".to_string()]);
+        assert_eq!(change.describe(), "ports +443 -23; services +https -telnet");
+    }
+
+    #[test]
+    fn unchanged_host_has_no_change() {
+        let mut host = Host::new("10.0.0.1".into());
+        host.add_port(22, "tcp", "open", None, None, None);
+        let mut rescanned = host.clone();
+        rescanned.update_last_seen();
+        rescanned.os = Some("Linux".into());
+
+        assert_eq!(HostChange::between(&host, &rescanned), None);
+    }
+}
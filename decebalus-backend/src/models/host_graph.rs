@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use serde::{Deserialize, Serialize};
+use crate::models::{Host, HostStatus};
+
+/// Network topology view of the known hosts, shaped for a graph renderer.
+///
+/// Three kinds of nodes: `subnet` (/24 for IPv4, /64 for IPv6), `host` and
+/// `service`. Every host has a `member_of` edge to its subnet and a `runs`
+/// edge to each service it exposes, so hosts sharing a service meet at the
+/// same service node.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct HostGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GraphNode {
+    Subnet {
+        id: String,
+        cidr: String,
+        host_count: usize,
+    },
+    Host {
+        id: String,
+        ip: String,
+        hostname: Option<String>,
+        os: Option<String>,
+        status: HostStatus,
+        open_ports: usize,
+        services: Vec<String>,
+    },
+    Service {
+        id: String,
+        name: String,
+        host_count: usize,
+    },
+}
+
+impl GraphNode {
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Subnet { id, .. } | Self::Host { id, .. } | Self::Service { id, .. } => id,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// `member_of` (host → subnet) or `runs` (host → service)
+    pub relation: String,
+}
+
+impl HostGraph {
+    /// Build the graph from a host list. Node order is stable: subnets, then
+    /// hosts, then services. Subnets and hosts are sorted by address
+    /// (10.0.0.2 before 10.0.0.10), services by name.
+    pub fn from_hosts(hosts: &[Host]) -> Self {
+        let mut subnets: BTreeMap<(Option<IpAddr>, String), usize> = BTreeMap::new();
+        let mut services: BTreeMap<String, usize> = BTreeMap::new();
+        let mut host_nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        let mut hosts: Vec<&Host> = hosts.iter().collect();
+        // Unparseable addresses go first, by their text
+        hosts.sort_by(|a, b| {
+            (a.ip.parse::<IpAddr>().ok(), &a.ip).cmp(&(b.ip.parse::<IpAddr>().ok(), &b.ip))
+        });
+
+        for host in hosts {
+            let host_id = format!("host:{}", host.ip);
+
+            let (network, cidr) = subnet_of(&host.ip);
+            *subnets.entry((network, cidr.clone())).or_default() += 1;
+            edges.push(GraphEdge {
+                source: host_id.clone(),
+                target: format!("subnet:{}", cidr),
+                relation: "member_of".to_string(),
+            });
+
+            let mut names: Vec<String> = host.services.iter().map(|s| s.name.clone()).collect();
+            names.sort();
+            names.dedup();
+            for name in &names {
+                *services.entry(name.clone()).or_default() += 1;
+                edges.push(GraphEdge {
+                    source: host_id.clone(),
+                    target: format!("service:{}", name),
+                    relation: "runs".to_string(),
+                });
+            }
+
+            host_nodes.push(GraphNode::Host {
+                id: host_id,
+                ip: host.ip.clone(),
+                hostname: host.hostname.clone(),
+                os: host.os.clone(),
+                status: host.status,
+                open_ports: host.ports.iter().filter(|p| p.status == "open").count(),
+                services: names,
+            });
+        }
+
+        let mut nodes: Vec<GraphNode> = subnets
+            .into_iter()
+            .map(|((_, cidr), host_count)| GraphNode::Subnet { id: format!("subnet:{}", cidr), cidr, host_count })
+            .collect();
+        nodes.extend(host_nodes);
+        nodes.extend(
+            services
+                .into_iter()
+                .map(|(name, host_count)| GraphNode::Service { id: format!("service:{}", name), name, host_count }),
+        );
+
+        Self { nodes, edges }
+    }
+}
+
+/// Network address and CIDR of the subnet a host is grouped under.
+/// Unparseable addresses get a group of their own, with no network address.
+fn subnet_of(ip: &str) -> (Option<IpAddr>, String) {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            let [a, b, c, _] = v4.octets();
+            (Some(Ipv4Addr::new(a, b, c, 0).into()), format!("{}.{}.{}.0/24", a, b, c))
+        }
+        Ok(IpAddr::V6(v6)) => {
+            let s = v6.segments();
+            let network = Ipv6Addr::new(s[0], s[1], s[2], s[3], 0, 0, 0, 0);
+            (Some(network.into()), format!("{:x}:{:x}:{:x}:{:x}::/64", s[0], s[1], s[2], s[3]))
+        }
+        Err(_) => (None, "unknown".to_string()),
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-host settings, read from the `hosts` section of the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct HostsConfig {
+    /// Re-port-scan hosts whose last port scan is older than this.
+    /// `None` disables automatic rescans.
+    pub rescan_after_hours: Option<u64>,
+    /// Most rescan jobs queued per scheduler pass, so the queue isn't flooded.
+    pub rescan_batch_size: usize,
+    /// Seconds between scheduler passes.
+    pub rescan_check_interval_secs: u64,
+}
+
+impl Default for HostsConfig {
+    fn default() -> Self {
+        Self {
+            rescan_after_hours: None,
+            rescan_batch_size: 5,
+            rescan_check_interval_secs: 300,
+        }
+    }
+}
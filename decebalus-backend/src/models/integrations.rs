@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// Outbound email settings, read from `integrations.smtp` in the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Use STARTTLS. Disable only for local relays.
+    pub tls: bool,
+    /// Collect events and send one summary email per `digest_interval_secs`.
+    pub digest: bool,
+    pub digest_interval_secs: u64,
+    /// Vulnerabilities below this severity (`CRITICAL`, `HIGH`, `MEDIUM`, `LOW`) aren't emailed.
+    pub min_severity: String,
+}
+
+impl SmtpConfig {
+    /// Email is only sent once a relay and at least one recipient are set.
+    pub fn is_configured(&self) -> bool {
+        !self.host.is_empty() && !self.from.is_empty() && !self.to.is_empty()
+    }
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 587,
+            username: None,
+            password: None,
+            from: String::new(),
+            to: Vec::new(),
+            tls: true,
+            digest: false,
+            digest_interval_secs: 3600,
+            min_severity: "HIGH".to_string(),
+        }
+    }
+}
+
+/// Outbound webhooks, read from the `webhooks` section of the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Job and host events are POSTed as JSON to each of these URLs.
+    pub urls: Vec<String>,
+    pub timeout_secs: u64,
+    /// Extra attempts after a failed POST, each one second later than the last.
+    pub retries: u32,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            timeout_secs: 5,
+            retries: 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn defaults_are_not_configured() {
+        let cfg = SmtpConfig::default();
+        assert!(!cfg.is_configured());
+        assert_eq!(cfg.port, 587);
+        assert!(cfg.tls);
+    }
+
+    #[test]
+    fn partial_config_fills_defaults() {
+        let cfg: SmtpConfig = serde_json::from_value(json!({
+            "host": "smtp.example.com",
+            "from": "decebalus@example.com",
+            "to": ["ops@example.com"]
+        })).unwrap();
+
+        assert!(cfg.is_configured());
+        assert_eq!(cfg.port, 587);
+        assert!(!cfg.digest);
+        assert_eq!(cfg.min_severity, "HIGH");
+    }
+}
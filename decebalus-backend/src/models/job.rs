@@ -1,19 +1,38 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::models::JobPriority;
+use crate::models::{JobPriority, JobResult, JobStatus, Schedule};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Job {
     pub id: String,
     pub job_type: String,
     pub priority: JobPriority,
-    pub status: String,
+    pub status: JobStatus,
     pub config: serde_json::Value,
-    pub results: Option<String>,
+    pub results: Option<JobResult>,
     pub created_at: String,
     pub scheduled_at: Option<i64>,
+    /// Job that spawned this one (e.g. the discovery behind an auto port-scan).
+    #[serde(default)]
+    pub parent_job_id: Option<String>,
+    /// Pipeline run shared by a root job and everything it spawns.
+    #[serde(default)]
+    pub run_id: Option<String>,
+    /// Current step of a multi-phase job, e.g. `"service-detection (2/3)"`.
+    #[serde(default)]
+    pub phase: Option<String>,
+    /// Automatic retries used so far.
+    #[serde(default)]
+    pub retries: u32,
+    /// Retry limit for this job; `None` uses `jobs.max_retries` from config.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Cron expression (with seconds) for a recurring job: after each run it
+    /// goes back to `scheduled` for the next fire time. `None` runs once.
+    #[serde(default)]
+    pub schedule: Option<String>,
 }
 
 impl Job {
@@ -21,33 +40,59 @@ impl Job {
         Self {
             id: Uuid::new_v4().to_string(),
             job_type,
-            status: "queued".to_string(),
+            status: JobStatus::Queued,
             priority: JobPriority::NORMAL,
             results: None,
-            created_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            created_at: Utc::now().to_rfc3339(),
             scheduled_at: None,
             config: Default::default(),
+            parent_job_id: None,
+            run_id: None,
+            phase: None,
+            retries: 0,
+            max_retries: None,
+            schedule: None,
         }
     }
+
+    /// New queued job spawned by `parent`, in the same run as the parent.
+    pub fn child_of(parent: &Job, job_type: String) -> Self {
+        let mut job = Self::new(job_type);
+        job.priority = parent.priority;
+        job.parent_job_id = Some(parent.id.clone());
+        job.run_id = Some(parent.run_id.clone().unwrap_or_else(|| parent.id.clone()));
+        job
+    }
     
     pub fn is_running(&self) -> bool {
-        self.status == "running"
+        self.status == JobStatus::Running
     }
     
     pub fn is_completed(&self) -> bool {
-        self.status == "completed"
+        self.status == JobStatus::Completed
     }
     
     pub fn is_cancelled(&self) -> bool {
-        self.status == "cancelled"
+        self.status == JobStatus::Cancelled
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.status == JobStatus::Failed
     }
 
     pub fn is_queued(&self) -> bool {
-        self.status == "queued"
+        self.status == JobStatus::Queued
     }
 
     pub fn is_scheduled(&self) -> bool {
-        self.status == "scheduled"
+        self.status == JobStatus::Scheduled
+    }
+
+    /// Next fire time of a recurring job after `after`, as a unix timestamp.
+    /// `None` for one-shot jobs or a cron expression with no runs left.
+    pub fn next_run_after(&self, after: DateTime<Utc>) -> Option<i64> {
+        let schedule = Schedule::parse_cron(self.schedule.as_deref()?).ok()?;
+        schedule.after(&after).next().map(|t| t.timestamp())
     }
 
     pub fn target(&self) -> Result<String, String> {
@@ -57,6 +102,13 @@ impl Job {
             .map(|s| s.to_string())
             .ok_or_else(|| "Job config missing 'target' field".to_string())
     }
+
+    /// Port list given when the job was created, overriding `scan_config.ports`.
+    pub fn ports(&self) -> Option<Vec<u16>> {
+        self.config
+            .get("ports")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
 }
 
 #[cfg(test)]
@@ -69,10 +121,10 @@ mod tests {
         let job = Job::new("scan".into());
 
         assert_eq!(job.job_type, "scan");
-        assert_eq!(job.status, "queued");
+        assert_eq!(job.status, JobStatus::Queued);
         assert_eq!(job.priority, JobPriority::NORMAL);
         assert!(job.results.is_none());
-        assert!(!job.created_at.is_empty());
+        assert!(chrono::DateTime::parse_from_rfc3339(&job.created_at).is_ok(), "{}", job.created_at);
         assert!(job.scheduled_at.is_none());
 
         // ID should not be empty
@@ -99,28 +151,52 @@ mod tests {
         assert!(!job.is_scheduled());
 
         // Running
-        job.status = "running".into();
+        job.status = JobStatus::Running;
         assert!(job.is_running());
 
         // Completed
-        job.status = "completed".into();
+        job.status = JobStatus::Completed;
         assert!(job.is_completed());
 
         // Cancelled
-        job.status = "cancelled".into();
+        job.status = JobStatus::Cancelled;
         assert!(job.is_cancelled());
 
         // Scheduled
-        job.status = "scheduled".into();
+        job.status = JobStatus::Scheduled;
         assert!(job.is_scheduled());
     }
 
     #[test]
     fn results_can_be_stored() {
         let mut job = Job::new("scan".into());
-        job.results = Some("OK".to_string());
+        job.results = Some(JobResult::Success(serde_json::json!({ "ok": true })));
+
+        assert_eq!(job.results.unwrap().success().unwrap()["ok"], true);
+    }
 
-        assert_eq!(job.results.unwrap(), "OK");
+    #[test]
+    fn recurring_job_fires_on_its_cron_schedule() {
+        let mut job = Job::new("discovery".into());
+        let noon = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(job.next_run_after(noon), None);
+
+        job.schedule = Some("0 0 2 * * *".into());
+        let next = DateTime::parse_from_rfc3339("2024-01-02T02:00:00Z").unwrap().timestamp();
+        assert_eq!(job.next_run_after(noon), Some(next));
+    }
+
+    #[test]
+    fn child_inherits_run_from_parent() {
+        let root = Job::new("discovery".into());
+        let child = Job::child_of(&root, "port-scan".into());
+        let grandchild = Job::child_of(&child, "nmap-scan".into());
+
+        assert_eq!(child.parent_job_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(child.run_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(grandchild.parent_job_id.as_deref(), Some(child.id.as_str()));
+        assert_eq!(grandchild.run_id.as_deref(), Some(root.id.as_str()));
+        assert!(child.is_queued());
     }
 
 
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Outcome of a job run, stored in the `results` column.
+///
+/// Serialized as `{"success": {...}}` or `{"error": "..."}`, so clients can
+/// tell a payload from a failure message without guessing.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobResult {
+    /// What the job produced, e.g. `{"hosts_found": 3, ...}`.
+    Success(Value),
+    /// Why the job failed.
+    Error(String),
+}
+
+impl JobResult {
+    /// Text for the `results` column.
+    pub fn to_stored(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Read back the `results` column. Rows written before results were typed
+    /// hold either a bare JSON payload or plain error text.
+    pub fn from_stored(text: &str) -> Self {
+        if let Ok(result) = serde_json::from_str::<JobResult>(text) {
+            return result;
+        }
+        match serde_json::from_str::<Value>(text) {
+            Ok(value) => JobResult::Success(value),
+            Err(_) => JobResult::Error(text.to_string()),
+        }
+    }
+
+    /// The payload of a successful run.
+    pub fn success(&self) -> Option<&Value> {
+        match self {
+            JobResult::Success(value) => Some(value),
+            JobResult::Error(_) => None,
+        }
+    }
+
+    /// The message of a failed run.
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            JobResult::Success(_) => None,
+            JobResult::Error(message) => Some(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_through_storage() {
+        let ok = JobResult::Success(json!({ "hosts_found": 3 }));
+        assert_eq!(ok.to_stored(), r#"{"success":{"hosts_found":3}}"#);
+        assert_eq!(JobResult::from_stored(&ok.to_stored()), ok);
+
+        let failed = JobResult::Error("Scan timed out".into());
+        assert_eq!(failed.to_stored(), r#"{"error":"Scan timed out"}"#);
+        assert_eq!(JobResult::from_stored(&failed.to_stored()), failed);
+    }
+
+    #[test]
+    fn reads_untyped_results() {
+        assert_eq!(
+            JobResult::from_stored(r#"{"job_id":"abc","hosts_found":2}"#),
+            JobResult::Success(json!({ "job_id": "abc", "hosts_found": 2 }))
+        );
+        assert_eq!(
+            JobResult::from_stored("Invalid target: not-a-network"),
+            JobResult::Error("Invalid target: not-a-network".into())
+        );
+    }
+}
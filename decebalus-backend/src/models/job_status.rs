@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// Where a job is in its lifecycle. Stored as lowercase text (`"queued"`, ...)
+/// in the `status` column and serialized the same way in the API.
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a free worker slot.
+    Queued,
+    /// Waiting for `scheduled_at` (a delayed, retried or recurring job).
+    Scheduled,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub const ALL: [JobStatus; 6] = [
+        JobStatus::Queued,
+        JobStatus::Scheduled,
+        JobStatus::Running,
+        JobStatus::Completed,
+        JobStatus::Failed,
+        JobStatus::Cancelled,
+    ];
+
+    /// Text stored in the database, e.g. `"running"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Scheduled => "scheduled",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JobStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("Unknown job status '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text_and_json() {
+        for status in JobStatus::ALL {
+            assert_eq!(status.to_string().parse::<JobStatus>(), Ok(status));
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", status));
+            assert_eq!(serde_json::from_str::<JobStatus>(&json).unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        assert_eq!("done".parse::<JobStatus>(), Err("Unknown job status 'done'".to_string()));
+        // Stored text is lowercase; anything else is not a status
+        assert!("Running".parse::<JobStatus>().is_err());
+        assert!("".parse::<JobStatus>().is_err());
+        assert!(serde_json::from_str::<JobStatus>("\"paused\"").is_err());
+    }
+}
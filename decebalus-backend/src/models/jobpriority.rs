@@ -1,9 +1,22 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+/// Declaration order is dispatch order: LOW < NORMAL < HIGH < CRITICAL.
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum JobPriority {
     LOW,
     NORMAL,
     HIGH,
     CRITICAL
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities_order_from_low_to_critical() {
+        let mut priorities = vec![JobPriority::HIGH, JobPriority::LOW, JobPriority::CRITICAL, JobPriority::NORMAL];
+        priorities.sort_by(|a, b| b.cmp(a));
+        assert_eq!(priorities, [JobPriority::CRITICAL, JobPriority::HIGH, JobPriority::NORMAL, JobPriority::LOW]);
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// Job execution settings, read from the `jobs` section of the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct JobsConfig {
+    /// Fail running jobs whose `updated_at` hasn't moved for this long.
+    /// Running jobs bump it as they make progress (a new phase, a progress
+    /// message, a host done). `None` disables the stuck-job watchdog.
+    pub stuck_after_secs: Option<u64>,
+    /// Seconds between watchdog passes.
+    pub watchdog_interval_secs: u64,
+    /// Fail a job that runs longer than this and free its worker slot.
+    /// `null` lets jobs run indefinitely.
+    pub timeout_secs: Option<u64>,
+    /// Automatic retries for a failed job, unless the job sets its own limit.
+    pub max_retries: u32,
+    /// Delay before the first retry; each further retry waits twice as long.
+    pub retry_backoff_secs: u64,
+}
+
+impl JobsConfig {
+    /// Time a single job may run for, if limited.
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_secs.map(std::time::Duration::from_secs)
+    }
+
+    /// Delay before retry number `attempt` (1-based), doubling each time.
+    pub fn retry_delay(&self, attempt: u32) -> chrono::Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        chrono::Duration::seconds(self.retry_backoff_secs.saturating_mul(factor).min(i64::MAX as u64) as i64)
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            stuck_after_secs: None,
+            watchdog_interval_secs: 60,
+            // Generous enough for a full nmap scan of a busy host
+            timeout_secs: Some(3600),
+            max_retries: 0,
+            retry_backoff_secs: 30,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        let cfg = JobsConfig { retry_backoff_secs: 10, ..Default::default() };
+        assert_eq!(cfg.retry_delay(1).num_seconds(), 10);
+        assert_eq!(cfg.retry_delay(2).num_seconds(), 20);
+        assert_eq!(cfg.retry_delay(3).num_seconds(), 40);
+        // Large attempt counts don't overflow
+        assert!(cfg.retry_delay(u32::MAX).num_seconds() > 0);
+    }
+}
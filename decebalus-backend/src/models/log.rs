@@ -1,5 +1,9 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// How the database stores `created_at` (UTC). Sorts the same as it compares.
+pub const LOG_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Log {
     pub id: String,
@@ -25,10 +29,55 @@ impl Log {
     }
 }
 
+/// Which logs `query_logs` returns, newest first. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// `ERROR`, `WARN`, `INFO`, … (case-insensitive)
+    pub severity: Option<String>,
+    pub service: Option<String>,
+    /// Only logs written at or after this
+    pub since: Option<DateTime<Utc>>,
+    /// Only logs written at or before this
+    pub until: Option<DateTime<Utc>>,
+    /// At most this many logs
+    pub limit: Option<u32>,
+}
+
+impl LogFilter {
+    /// `since`/`until` in the database's `created_at` format, for SQL comparisons.
+    pub fn time_bounds(&self) -> (Option<String>, Option<String>) {
+        let fmt = |t: &DateTime<Utc>| t.format(LOG_TIME_FORMAT).to_string();
+        (self.since.as_ref().map(fmt), self.until.as_ref().map(fmt))
+    }
+
+    /// Whether `log` passes every condition but `limit`.
+    pub fn matches(&self, log: &Log) -> bool {
+        if self.severity.as_ref().is_some_and(|s| !s.eq_ignore_ascii_case(&log.severity))
+            || self.service.as_ref().is_some_and(|s| *s != log.service)
+        {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Some(at) = log.created_at() else { return false };
+        self.since.is_none_or(|since| at >= since) && self.until.is_none_or(|until| at <= until)
+    }
+}
+
+impl Log {
+    /// `created_at` as a time: the database's UTC format, or RFC 3339.
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.created_at)
+            .map(|t| t.with_timezone(&Utc))
+            .ok()
+            .or_else(|| NaiveDateTime::parse_from_str(&self.created_at, LOG_TIME_FORMAT).ok().map(|t| t.and_utc()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json;
 
     #[test]
     fn new_initializes_correctly() {
@@ -89,5 +138,23 @@ mod tests {
         assert!(deserialized.job_id.is_none());
     }
 
+    #[test]
+    fn filter_matches_severity_service_and_time() {
+        let log = Log::new("1".into(), "2025-11-20 12:00:00".into(), "WARN".into(), "scanner".into(), None, None, "x".into());
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert!(LogFilter::default().matches(&log));
+        assert!(LogFilter { severity: Some("warn".into()), ..Default::default() }.matches(&log));
+        assert!(!LogFilter { severity: Some("ERROR".into()), ..Default::default() }.matches(&log));
+        assert!(!LogFilter { service: Some("api".into()), ..Default::default() }.matches(&log));
 
+        let window = LogFilter { since: Some(at("2025-11-20T11:00:00Z")), until: Some(at("2025-11-20T12:00:00Z")), ..Default::default() };
+        assert!(window.matches(&log));
+        assert!(!LogFilter { since: Some(at("2025-11-20T12:00:01Z")), ..Default::default() }.matches(&log));
+
+        // In-memory logs carry RFC 3339 timestamps
+        let rfc = Log { created_at: "2025-11-20T13:00:00+01:00".into(), ..log };
+        assert!(window.matches(&rfc));
+        assert_eq!(window.time_bounds(), (Some("2025-11-20 11:00:00".into()), Some("2025-11-20 12:00:00".into())));
+    }
 }
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Log retention settings, read from the `logs` section of the config table.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct LogsConfig {
+    /// Delete logs older than this many days. `None` falls back to
+    /// `LOG_RETENTION_DAYS`, then 30.
+    pub retention_days: Option<i64>,
+    /// Seconds between cleanup passes.
+    pub cleanup_interval_secs: u64,
+}
+
+impl LogsConfig {
+    /// Retention in days, with `default_days` used when the config doesn't set one.
+    pub fn retention_days_or(&self, default_days: i64) -> i64 {
+        self.retention_days.unwrap_or(default_days)
+    }
+}
+
+impl Default for LogsConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: None,
+            cleanup_interval_secs: 24 * 60 * 60,
+        }
+    }
+}
@@ -1,5 +1,10 @@
 mod job;
+mod job_result;
+mod job_status;
 mod host;
+mod host_change;
+mod host_graph;
+mod catalog_entry;
 mod display;
 mod config;
 mod status;
@@ -9,15 +14,40 @@ mod vulnerability;
 mod jobpriority;
 mod log;
 mod create_job_request;
+mod scan_config;
+mod integrations;
+mod hosts_config;
+mod jobs_config;
+mod logs_config;
+mod attacks_config;
+mod display_config;
+mod schedule;
+mod summary;
+mod ws_event;
 
 pub use job::Job;
+pub use job_result::JobResult;
+pub use job_status::JobStatus;
 pub use host::Host;
-pub use display::DisplayStatus;
-pub use config::Config;
+pub use host_change::HostChange;
+pub use host_graph::{GraphEdge, GraphNode, HostGraph};
+pub use display::{DisplayMessage, DisplayStatus, PushDisplayMessageRequest};
+pub use config::{Config, ConfigLimitError};
 pub use status::HostStatus;
 pub use port::Port;
 pub use service::Service;
-pub use vulnerability::Vulnerability;
+pub use catalog_entry::CatalogEntry;
+pub use vulnerability::{severity_rank, Vulnerability};
 pub use jobpriority::JobPriority;
-pub use log::Log;
-pub use create_job_request::CreateJobRequest;
\ No newline at end of file
+pub use log::{Log, LogFilter};
+pub use create_job_request::{CreateJobRequest, RetryFailedRequest, UpdateJobRequest};
+pub use scan_config::{AdaptiveTimeoutConfig, AutopilotConfig, DiscoveryMethod, RampDownConfig, ScanConfig, max_discovery_threads, parse_exclusion};
+pub use integrations::{SmtpConfig, WebhooksConfig};
+pub use hosts_config::HostsConfig;
+pub use jobs_config::JobsConfig;
+pub use logs_config::LogsConfig;
+pub use attacks_config::AttacksConfig;
+pub use display_config::DisplayConfig;
+pub use schedule::{CreateScheduleRequest, Schedule, UpdateScheduleRequest};
+pub use summary::{PortCount, Summary, TOP_PORTS};
+pub use ws_event::WsEvent;
\ No newline at end of file
@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Typed view of the `scan_config` entry in the config table.
+/// Missing fields fall back to their defaults so a partial config is always usable.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ScanConfig {
+    /// Max concurrent probes against a single target network during discovery.
+    /// `None` means only the global discovery limit applies.
+    pub per_network_concurrency: Option<usize>,
+    /// Adaptive back-off for port scans when the network starts erroring.
+    pub autopilot: AutopilotConfig,
+    /// Protocols whose banner parser is used in the banner fallback
+    /// (`ssh`, `http`, `smtp`, `ftp`). `None` enables all of them.
+    pub banner_parsers: Option<Vec<String>>,
+    /// Decoy-resistant discovery: number of distinct ports that must accept a
+    /// connection before a host counts as up. Tarpits and honeypots that accept
+    /// a single probe are then ignored. With ICMP discovery an echo reply counts
+    /// as one of them. `None` means one port (or echo reply) is enough.
+    pub alive_confirmations: Option<usize>,
+    /// Max concurrent banner grabs against one host, independent of the
+    /// port-check concurrency. Defaults to 1, one grab at a time.
+    pub banner_concurrency: Option<usize>,
+    /// Tapering of port-scan concurrency over the last stretch of a scan.
+    pub ramp_down: RampDownConfig,
+    /// Connect timeouts sized from the round-trip times seen during a scan.
+    pub adaptive_timeout: AdaptiveTimeoutConfig,
+    /// Most TCP connection attempts per second a port scan makes against one
+    /// host, whatever the concurrency. `None` or 0 means no limit; set it low
+    /// for fragile devices such as printers.
+    pub max_connections_per_second: Option<f64>,
+    /// With `auto_port_scan`, queue a port-scan for each host as soon as
+    /// discovery finds it instead of one for all hosts once discovery is done.
+    pub stream_port_scan: bool,
+    /// How TCP-probed addresses are checked for liveness during discovery.
+    pub discovery_method: DiscoveryMethod,
+    /// Reverse-DNS each host discovery finds to fill in its hostname.
+    /// Defaults to on; turn off to speed up discovery on networks without PTR records.
+    pub reverse_dns: Option<bool>,
+    /// IPs and CIDRs discovery never probes, e.g. the gateway or the scanner itself.
+    pub exclusions: Vec<String>,
+    /// Discovery target (`self` or comma-separated CIDRs) for jobs that don't name one.
+    pub target: Option<String>,
+    /// TCP ports a port scan checks when the job doesn't list its own, as
+    /// port numbers and `"first-last"` ranges, e.g. `[22, "8000-8100"]`.
+    /// `None` scans all of 1-65535.
+    #[serde(with = "port_list")]
+    pub ports: Option<Vec<u16>>,
+}
+
+/// Liveness check used by discovery for addresses ARP didn't answer for.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryMethod {
+    /// Connect to a handful of common ports.
+    #[default]
+    Tcp,
+    /// ICMP echo first, then the TCP check for hosts that don't answer it.
+    /// Needs raw socket access (CAP_NET_RAW); discovery falls back to `tcp` without it.
+    Icmp,
+}
+
+impl DiscoveryMethod {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Icmp => "ICMP",
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Ports that must respond in the TCP alive check (at least 1).
+    pub fn alive_confirmations(&self) -> usize {
+        self.alive_confirmations.unwrap_or(1).max(1)
+    }
+
+    /// Concurrent banner grabs per host (at least 1).
+    pub fn banner_concurrency(&self) -> usize {
+        self.banner_concurrency.unwrap_or(1).max(1)
+    }
+
+    pub fn reverse_dns(&self) -> bool {
+        self.reverse_dns.unwrap_or(true)
+    }
+
+    /// Whether a port scan covers every TCP port, so ports it doesn't find
+    /// open can be marked closed.
+    pub fn scans_all_ports(&self) -> bool {
+        match &self.ports {
+            Some(ports) => ports.iter().filter(|p| **p != 0).collect::<HashSet<_>>().len() == usize::from(u16::MAX),
+            None => true,
+        }
+    }
+
+    /// Ports the TCP connect scan probes, in ascending order.
+    pub fn tcp_ports(&self) -> Vec<u16> {
+        match &self.ports {
+            Some(ports) => ports.clone(),
+            None => (1..=65535).collect(),
+        }
+    }
+
+    /// Port range for scan logs, e.g. `1-65535` or `22,80,443`.
+    pub fn ports_label(&self) -> String {
+        match &self.ports {
+            Some(ports) => port_runs(ports)
+                .into_iter()
+                .map(|(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+                .collect::<Vec<_>>()
+                .join(","),
+            None => "1-65535".to_string(),
+        }
+    }
+
+    /// `exclusions` as networks, a bare IP being a single-address network.
+    /// Entries that are neither are skipped; `--check-config` reports them.
+    pub fn excluded_networks(&self) -> Vec<IpNet> {
+        self.exclusions.iter().filter_map(|e| parse_exclusion(e)).collect()
+    }
+}
+
+/// Largest concurrency discovery runs at, the `MAX_DISCOVER_THREADS` env var
+/// (default 256). A per-network cap above it has no effect.
+pub fn max_discovery_threads() -> usize {
+    std::env::var("MAX_DISCOVER_THREADS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(256)
+}
+
+/// Sorted, deduplicated `ports` as runs of consecutive ports.
+fn port_runs(ports: &[u16]) -> Vec<(u16, u16)> {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+    for port in sorted {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == port => *last = port,
+            _ => runs.push((port, port)),
+        }
+    }
+    runs
+}
+
+/// `scan_config.ports` as JSON: numbers for single ports and `"first-last"`
+/// strings for ranges, so all 65 535 ports fit in `["1-65535"]`.
+mod port_list {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Port(u16),
+        Range(String),
+    }
+
+    pub fn serialize<S: Serializer>(ports: &Option<Vec<u16>>, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = ports.as_ref().map(|ports| {
+            port_runs(ports)
+                .into_iter()
+                .map(|(first, last)| if first == last { Entry::Port(first) } else { Entry::Range(format!("{}-{}", first, last)) })
+                .collect::<Vec<_>>()
+        });
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u16>>, D::Error> {
+        let Some(entries) = Option::<Vec<Entry>>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let mut ports = Vec::new();
+        for entry in entries {
+            match entry {
+                Entry::Port(port) => ports.push(port),
+                Entry::Range(range) => {
+                    let bounds = range
+                        .split_once('-')
+                        .and_then(|(first, last)| Some((first.trim().parse::<u16>().ok()?, last.trim().parse::<u16>().ok()?)))
+                        .filter(|(first, last)| first <= last);
+                    let Some((first, last)) = bounds else {
+                        return Err(serde::de::Error::custom(format!("'{}' is not a port range like 8000-8100", range)));
+                    };
+                    ports.extend(first..=last);
+                }
+            }
+        }
+        Ok(Some(ports))
+    }
+}
+
+/// An exclusion entry: a CIDR (`10.0.0.0/28`) or a single IP (`10.0.0.1`).
+pub fn parse_exclusion(entry: &str) -> Option<IpNet> {
+    let entry = entry.trim();
+    entry.parse::<IpNet>().ok().or_else(|| entry.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// Bounds for the scan aggressiveness autopilot, which only steers scans once
+/// `enabled` is switched on.
+/// The controller never goes below `min_concurrency` or above the scan's configured concurrency.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct AutopilotConfig {
+    pub enabled: bool,
+    /// Lowest concurrency the controller may back off to.
+    pub min_concurrency: usize,
+    /// Number of connection attempts evaluated per adjustment.
+    pub window: usize,
+    /// Error rate (0.0–1.0) in a window that triggers a back-off.
+    pub error_threshold: f64,
+    /// Error rate at or below which concurrency is stepped back up.
+    pub recovery_threshold: f64,
+    /// Multiplier applied to concurrency on back-off.
+    pub backoff_factor: f64,
+    /// Largest delay inserted before each connection attempt while backed off.
+    pub max_delay_ms: u64,
+}
+
+impl Default for AutopilotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_concurrency: 16,
+            window: 200,
+            error_threshold: 0.2,
+            recovery_threshold: 0.02,
+            backoff_factor: 0.5,
+            max_delay_ms: 50,
+        }
+    }
+}
+
+/// Concurrency taper near the end of a port scan, disabled by default.
+/// Once fewer than `tail_percent` of the ports are left to probe, concurrency
+/// shrinks in proportion to the remaining work, down to `min_concurrency`.
+/// This avoids a final burst of simultaneous timeouts on filtered ports.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct RampDownConfig {
+    pub enabled: bool,
+    /// Share of the scan (0–100) over which concurrency is tapered.
+    pub tail_percent: f64,
+    /// Concurrency the taper ends at.
+    pub min_concurrency: usize,
+}
+
+impl Default for RampDownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tail_percent: 5.0,
+            min_concurrency: 8,
+        }
+    }
+}
+
+/// Bounds for connect timeouts adapted to the observed round-trip time.
+/// Discovery starts at 500 ms and port scans at 200 ms until enough connects have answered.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct AdaptiveTimeoutConfig {
+    pub enabled: bool,
+    /// Timeout as a multiple of the median round-trip time.
+    pub multiplier: f64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Default for AdaptiveTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            multiplier: 3.0,
+            min_ms: 50,
+            max_ms: 3000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let cfg: ScanConfig = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(cfg, ScanConfig::default());
+        assert!(cfg.per_network_concurrency.is_none());
+        assert!(!cfg.autopilot.enabled);
+        assert!(!cfg.ramp_down.enabled);
+    }
+
+    #[test]
+    fn ports_take_numbers_and_ranges() {
+        let cfg: ScanConfig = serde_json::from_value(json!({ "ports": [22, "8000-8002", 443] })).unwrap();
+        assert_eq!(cfg.ports, Some(vec![22, 8000, 8001, 8002, 443]));
+        assert_eq!(cfg.ports_label(), "22,443,8000-8002");
+        assert!(!cfg.scans_all_ports());
+        assert_eq!(serde_json::to_value(&cfg).unwrap()["ports"], json!([22, 443, "8000-8002"]));
+
+        assert!(serde_json::from_value::<ScanConfig>(json!({ "ports": ["8100-8000"] })).is_err());
+        assert!(serde_json::from_value::<ScanConfig>(json!({ "ports": ["http"] })).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let cfg: ScanConfig = serde_json::from_value(json!({
+            "per_network_concurrency": 8,
+            "something_else": true
+        })).unwrap();
+        assert_eq!(cfg.per_network_concurrency, Some(8));
+    }
+
+    #[test]
+    fn discovery_method_parses_lowercase() {
+        let cfg: ScanConfig = serde_json::from_value(json!({"discovery_method": "icmp"})).unwrap();
+        assert_eq!(cfg.discovery_method, DiscoveryMethod::Icmp);
+        assert_eq!(ScanConfig::default().discovery_method, DiscoveryMethod::Tcp);
+    }
+
+    #[test]
+    fn exclusions_accept_ips_and_cidrs() {
+        let cfg: ScanConfig = serde_json::from_value(json!({
+            "exclusions": ["192.168.1.1", "10.0.0.0/28", "nope"]
+        })).unwrap();
+        let nets: Vec<String> = cfg.excluded_networks().iter().map(|n| n.to_string()).collect();
+        assert_eq!(nets, ["192.168.1.1/32", "10.0.0.0/28"]);
+    }
+}
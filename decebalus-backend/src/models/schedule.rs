@@ -0,0 +1,140 @@
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::Job;
+
+/// A named, reusable recipe for a job that runs on a cron schedule,
+/// e.g. "Nightly discovery of 192.168.1.0/24".
+///
+/// The scheduler turns each due, enabled schedule into a fresh queued job.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub job_type: String,
+    pub target: Option<String>,
+    /// Cron expression with a leading seconds field:
+    /// `sec min hour day-of-month month day-of-week [year]`, e.g. `0 0 2 * * *`.
+    pub cron: String,
+    pub enabled: bool,
+    /// Unix timestamp the schedule was created.
+    pub created_at: i64,
+    /// Unix timestamp of the last job it spawned.
+    pub last_run_at: Option<i64>,
+}
+
+impl Schedule {
+    pub fn new(name: String, job_type: String, target: Option<String>, cron: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            job_type,
+            target,
+            cron,
+            enabled: true,
+            created_at: Utc::now().timestamp(),
+            last_run_at: None,
+        }
+    }
+
+    /// Parse a cron expression, with the error worded for API clients.
+    pub fn parse_cron(expr: &str) -> Result<cron::Schedule, String> {
+        cron::Schedule::from_str(expr).map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+    }
+
+    /// First fire time after the last run (or creation, if it never ran).
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        let since = DateTime::from_timestamp(self.last_run_at.unwrap_or(self.created_at), 0)?;
+        Self::parse_cron(&self.cron).ok()?.after(&since).next()
+    }
+
+    /// Enabled and with a fire time at or before `now`.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.next_run().is_some_and(|next| next <= now)
+    }
+
+    /// A new queued job for one run of this schedule.
+    pub fn to_job(&self) -> Job {
+        let mut job = Job::new(self.job_type.clone());
+        let mut config = serde_json::Map::new();
+        if let Some(target) = &self.target {
+            config.insert("target".to_string(), serde_json::Value::String(target.clone()));
+        }
+        config.insert("schedule_id".to_string(), serde_json::Value::String(self.id.clone()));
+        job.config = serde_json::Value::Object(config);
+        job
+    }
+}
+
+/// Body of `POST /api/schedules`.
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    pub name: String,
+    pub job_type: String,
+    pub target: Option<String>,
+    pub cron: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Body of `PATCH /api/schedules/{id}`. Only the fields present are changed.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateScheduleRequest {
+    pub name: Option<String>,
+    pub target: Option<String>,
+    pub cron: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::JobStatus;
+
+    fn nightly() -> Schedule {
+        let mut s = Schedule::new("Nightly".into(), "discovery".into(), Some("10.0.0.0/24".into()), "0 0 2 * * *".into());
+        s.created_at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z").unwrap().timestamp();
+        s
+    }
+
+    #[test]
+    fn due_once_the_next_fire_time_has_passed() {
+        let s = nightly();
+        let before = DateTime::parse_from_rfc3339("2024-01-02T01:59:59Z").unwrap().with_timezone(&Utc);
+        let after = DateTime::parse_from_rfc3339("2024-01-02T02:00:00Z").unwrap().with_timezone(&Utc);
+
+        assert!(!s.is_due(before));
+        assert!(s.is_due(after));
+    }
+
+    #[test]
+    fn last_run_moves_the_next_fire_time() {
+        let mut s = nightly();
+        s.last_run_at = Some(DateTime::parse_from_rfc3339("2024-01-02T02:00:05Z").unwrap().timestamp());
+
+        assert_eq!(s.next_run().unwrap().to_rfc3339(), "2024-01-03T02:00:00+00:00");
+    }
+
+    #[test]
+    fn job_carries_target_and_schedule_id() {
+        let s = nightly();
+        let job = s.to_job();
+
+        assert_eq!(job.job_type, "discovery");
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.config["target"], "10.0.0.0/24");
+        assert_eq!(job.config["schedule_id"], s.id.as_str());
+    }
+
+    #[test]
+    fn invalid_cron_is_rejected() {
+        assert!(Schedule::parse_cron("every night").is_err());
+        assert!(Schedule::parse_cron("0 0 2 * * *").is_ok());
+    }
+}
@@ -5,4 +5,18 @@ pub struct Service {
     pub name: String,
     pub version: Option<String>,
     pub description: Option<String>,
-}
\ No newline at end of file
+    /// Protocol-specific fields parsed from the banner (HTTP status/server, SSH version, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl Service {
+    pub fn new(name: &str, version: Option<String>, description: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+            description,
+            details: None,
+        }
+    }
+}
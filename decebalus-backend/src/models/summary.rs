@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::models::JobStatus;
+
+/// How many ports `Summary::top_ports` lists.
+pub const TOP_PORTS: usize = 10;
+
+/// Dashboard overview, served by `GET /api/summary` so the frontend can
+/// render it with one call.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Summary {
+    pub total_hosts: i64,
+    /// Hosts whose `last_seen` falls within the last 24 hours.
+    pub hosts_seen_24h: i64,
+    /// Number of jobs per status; every status is listed, with 0 if unused.
+    pub jobs_by_status: BTreeMap<String, i64>,
+    /// Ports open on the most hosts, most common first.
+    pub top_ports: Vec<PortCount>,
+    /// Latest `last_seen` or `last_port_scan` of any host.
+    pub last_scan_at: Option<String>,
+}
+
+/// One entry of `Summary::top_ports`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PortCount {
+    pub port: u16,
+    pub protocol: String,
+    /// Hosts this port is open on.
+    pub hosts: i64,
+}
+
+impl Summary {
+    /// `jobs_by_status` from per-status counts, filling in the missing statuses.
+    pub fn job_counts(counts: impl IntoIterator<Item = (JobStatus, i64)>) -> BTreeMap<String, i64> {
+        let mut by_status: BTreeMap<String, i64> =
+            JobStatus::ALL.iter().map(|s| (s.to_string(), 0)).collect();
+        for (status, count) in counts {
+            *by_status.entry(status.to_string()).or_default() += count;
+        }
+        by_status
+    }
+}
@@ -5,4 +5,21 @@ pub struct Vulnerability {
     pub id: String,
     pub description: String,
     pub severity: String,
-}
\ No newline at end of file
+    /// CVSS base score, when the CVE has been scored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_score: Option<f32>,
+    /// Software the CVE was matched on, e.g. `OpenSSH 7.2p2`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+}
+
+/// Order of the severity labels, lowest first. Unknown labels rank below `LOW`.
+pub fn severity_rank(severity: &str) -> u8 {
+    match severity.to_ascii_uppercase().as_str() {
+        "CRITICAL" => 4,
+        "HIGH" => 3,
+        "MEDIUM" => 2,
+        "LOW" => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use crate::models::{HostChange, Vulnerability};
+
+/// Event carried by the app broadcaster and sent to WebSocket clients as
+/// tagged JSON, e.g. `{"type":"job_completed","job_id":"abc"}`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsEvent {
+    JobQueued { job_id: String, job_type: String },
+    /// A job stored to run at `scheduled_at` (unix seconds).
+    JobScheduled { job_id: String, job_type: String, scheduled_at: i64 },
+    JobRunning { job_id: String },
+    /// A multi-phase job moved on to `phase`, e.g. `tcp-scan (1/3)`.
+    JobPhase { job_id: String, phase: String },
+    JobCompleted { job_id: String },
+    /// `error` is the failure reason, or `timeout`.
+    JobFailed { job_id: String, error: String },
+    JobCancelled { job_id: String },
+    /// A failed or cancelled job was requeued by hand.
+    JobRetried { job_id: String },
+    /// A failed run was put back on the schedule; `attempt` counts from 1.
+    JobRetryScheduled { job_id: String, attempt: u32 },
+    /// Bulk requeue of failed jobs.
+    JobsRequeued { count: u64 },
+    /// The job queue was paused or resumed.
+    QueuePaused { paused: bool },
+    /// Discovery saw `ip` alive, whether or not it was known before.
+    HostFound { ip: String },
+    /// Discovery saw `ip` for the first time.
+    NewHost { ip: String },
+    /// `ip` was deleted from the inventory.
+    HostRemoved { ip: String },
+    /// A rescan of `ip` found open ports or services that weren't there before, or lost some.
+    HostChanged {
+        ip: String,
+        added_ports: Vec<u16>,
+        removed_ports: Vec<u16>,
+        added_services: Vec<String>,
+        removed_services: Vec<String>,
+    },
+    /// A vuln-scan or attack job found a vulnerability `ip` didn't have before.
+    VulnerabilityFound { ip: String, id: String, severity: String, description: String },
+    /// Human-readable progress of a running scan.
+    ScanProgress { job_id: String, message: String },
+    /// A port-scan job finished one of its hosts.
+    HostScanned { job_id: String, ip: String, open_ports: usize },
+    Log { message: String },
+    DisplayUpdated { text: String },
+    /// Top-level config keys that changed.
+    ConfigChanged { keys: Vec<String> },
+}
+
+impl WsEvent {
+    /// The job this event is about, if any.
+    pub fn job_id(&self) -> Option<&str> {
+        match self {
+            Self::JobQueued { job_id, .. }
+            | Self::JobScheduled { job_id, .. }
+            | Self::JobRunning { job_id }
+            | Self::JobPhase { job_id, .. }
+            | Self::JobCompleted { job_id }
+            | Self::JobFailed { job_id, .. }
+            | Self::JobCancelled { job_id }
+            | Self::JobRetried { job_id }
+            | Self::JobRetryScheduled { job_id, .. }
+            | Self::ScanProgress { job_id, .. }
+            | Self::HostScanned { job_id, .. } => Some(job_id),
+            _ => None,
+        }
+    }
+
+    /// Broad topic a WebSocket client can subscribe to: `jobs`, `hosts`,
+    /// `logs`, `display` or `config`. Job events can also be picked out by
+    /// `job:<id>`, see [`WsEvent::job_id`].
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Self::HostFound { .. }
+            | Self::NewHost { .. }
+            | Self::HostRemoved { .. }
+            | Self::HostChanged { .. }
+            | Self::VulnerabilityFound { .. } => "hosts",
+            Self::Log { .. } => "logs",
+            Self::DisplayUpdated { .. } => "display",
+            Self::ConfigChanged { .. } => "config",
+            _ => "jobs",
+        }
+    }
+
+    pub fn scan_progress(job_id: &str, message: impl Into<String>) -> Self {
+        Self::ScanProgress { job_id: job_id.to_string(), message: message.into() }
+    }
+
+    pub fn log(message: impl Into<String>) -> Self {
+        Self::Log { message: message.into() }
+    }
+
+    pub fn host_changed(ip: &str, change: HostChange) -> Self {
+        Self::HostChanged {
+            ip: ip.to_string(),
+            added_ports: change.added_ports,
+            removed_ports: change.removed_ports,
+            added_services: change.added_services,
+            removed_services: change.removed_services,
+        }
+    }
+
+    pub fn vulnerability_found(ip: &str, vuln: &Vulnerability) -> Self {
+        Self::VulnerabilityFound {
+            ip: ip.to_string(),
+            id: vuln.id.clone(),
+            severity: vuln.severity.clone(),
+            description: vuln.description.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_tagged_json() {
+        let event = WsEvent::JobFailed { job_id: "abc".into(), error: "timeout".into() };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "job_failed", "job_id": "abc", "error": "timeout" })
+        );
+    }
+
+    #[test]
+    fn scan_progress_round_trips() {
+        let event = WsEvent::scan_progress("abc", "TCP scan done — 2 open port(s) on 10.0.0.5: [22, 80]");
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"scan_progress""#));
+        assert_eq!(serde_json::from_str::<WsEvent>(&json).unwrap(), event);
+    }
+
+    #[test]
+    fn job_id_is_exposed_for_job_events_only() {
+        assert_eq!(WsEvent::JobRunning { job_id: "abc".into() }.job_id(), Some("abc"));
+        assert_eq!(WsEvent::NewHost { ip: "10.0.0.5".into() }.job_id(), None);
+    }
+
+    #[test]
+    fn events_are_grouped_into_topics() {
+        assert_eq!(WsEvent::JobsRequeued { count: 2 }.topic(), "jobs");
+        assert_eq!(WsEvent::scan_progress("abc", "tcp").topic(), "jobs");
+        assert_eq!(WsEvent::NewHost { ip: "10.0.0.5".into() }.topic(), "hosts");
+        assert_eq!(WsEvent::log("hello").topic(), "logs");
+    }
+}
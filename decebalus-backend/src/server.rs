@@ -0,0 +1,207 @@
+//! Where and how the HTTP server listens.
+
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use axum::http::{HeaderName, HeaderValue, Method};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use tower_http::cors::CorsLayer;
+
+pub const DEFAULT_BIND_ADDR: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// How long in-flight HTTPS connections get to finish on shutdown.
+const TLS_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// CORS defaults when only `CORS_ALLOWED_ORIGINS` is set.
+const DEFAULT_CORS_METHODS: &str = "GET,POST,PATCH,DELETE";
+const DEFAULT_CORS_HEADERS: &str = "authorization,content-type";
+
+/// Listen address from `BIND_ADDR` (an IPv4 or IPv6 address, default
+/// `0.0.0.0`) and `PORT` (default 8080), read through `env`.
+pub fn bind_addr<F>(env: F) -> Result<SocketAddr, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let ip = match env("BIND_ADDR") {
+        Some(value) => value
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|_| format!("BIND_ADDR: expected an IP address, got '{}'", value))?,
+        None => DEFAULT_BIND_ADDR,
+    };
+    let port = match env("PORT") {
+        Some(value) => value
+            .trim()
+            .parse::<u16>()
+            .map_err(|_| format!("PORT: expected a port number (0-65535), got '{}'", value))?,
+        None => DEFAULT_PORT,
+    };
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Certificate and key paths from `TLS_CERT_PATH` and `TLS_KEY_PATH`, read
+/// through `env`. `None` (plain HTTP) when neither is set; setting only one
+/// is an error.
+pub fn tls_paths<F>(env: F) -> Result<Option<(PathBuf, PathBuf)>, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    match (env("TLS_CERT_PATH"), env("TLS_KEY_PATH")) {
+        (Some(cert), Some(key)) => Ok(Some((cert.into(), key.into()))),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err("TLS_CERT_PATH is set but TLS_KEY_PATH is not".to_string()),
+        (None, Some(_)) => Err("TLS_KEY_PATH is set but TLS_CERT_PATH is not".to_string()),
+    }
+}
+
+/// Cross-origin access, read through `env`:
+///
+/// - `CORS_ALLOWED_ORIGINS`: comma-separated origins, e.g. `https://dash.lan:5173`.
+///   `CORS_ALLOWED_METHODS` / `CORS_ALLOWED_HEADERS` override the defaults
+///   (`GET,POST,PATCH,DELETE` and `authorization,content-type`).
+/// - `DEV_CORS=1`: allow any origin, method and header, for local development.
+///
+/// Credentials are allowed in both modes, so browsers can send cookies or auth
+/// with requests and WebSocket upgrades. `None` (same-origin only) when neither
+/// is set.
+pub fn cors_layer<F>(env: F) -> Result<Option<CorsLayer>, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if env("DEV_CORS").is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true")) {
+        return Ok(Some(CorsLayer::very_permissive()));
+    }
+    let Some(origins) = env("CORS_ALLOWED_ORIGINS") else { return Ok(None) };
+
+    let origins = parse_list("CORS_ALLOWED_ORIGINS", &origins, |o| HeaderValue::from_str(o).ok())?;
+    let methods = env("CORS_ALLOWED_METHODS").unwrap_or_else(|| DEFAULT_CORS_METHODS.to_string());
+    let methods = parse_list("CORS_ALLOWED_METHODS", &methods, |m| {
+        Method::from_bytes(m.to_ascii_uppercase().as_bytes()).ok()
+    })?;
+    let headers = env("CORS_ALLOWED_HEADERS").unwrap_or_else(|| DEFAULT_CORS_HEADERS.to_string());
+    let headers = parse_list("CORS_ALLOWED_HEADERS", &headers, |h| HeaderName::from_bytes(h.as_bytes()).ok())?;
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .allow_credentials(true),
+    ))
+}
+
+/// Parse a comma-separated env value, naming the first bad entry on failure.
+fn parse_list<T>(name: &str, value: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, String> {
+    let items: Vec<&str> = value.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+    if items.is_empty() {
+        return Err(format!("{}: expected a comma-separated list", name));
+    }
+    items
+        .into_iter()
+        .map(|item| parse(item).ok_or_else(|| format!("{}: invalid entry '{}'", name, item)))
+        .collect()
+}
+
+/// Load the certificate chain and private key for HTTPS.
+///
+/// Both files are PEM. `cert` holds one or more `BEGIN CERTIFICATE` blocks,
+/// the server certificate first and then any intermediates. `key` holds one
+/// unencrypted private key for it: PKCS#8 (`BEGIN PRIVATE KEY`), PKCS#1
+/// (`BEGIN RSA PRIVATE KEY`) or SEC1 (`BEGIN EC PRIVATE KEY`).
+pub async fn load_tls(cert: &Path, key: &Path) -> io::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(cert, key).await.map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("cannot load TLS cert {} / key {}: {}", cert.display(), key.display(), e),
+        )
+    })
+}
+
+/// Serve `app` on `listener` until `shutdown` resolves, over HTTPS when `tls`
+/// is given and plain HTTP otherwise. Either way in-flight requests are let
+/// finish before returning.
+pub async fn run<S>(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+    shutdown: S,
+) -> io::Result<()>
+where
+    S: Future<Output = ()> + Send + 'static,
+{
+    let Some(tls) = tls else {
+        return axum::serve(listener, app).with_graceful_shutdown(shutdown).await;
+    };
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(Some(TLS_SHUTDOWN_GRACE));
+    });
+
+    axum_server::from_tcp_rustls(listener.into_std()?, tls)
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn defaults_to_all_interfaces_on_8080() {
+        assert_eq!(bind_addr(env(&[])).unwrap(), "0.0.0.0:8080".parse().unwrap());
+    }
+
+    #[test]
+    fn reads_address_and_port() {
+        let addr = bind_addr(env(&[("BIND_ADDR", "127.0.0.1"), ("PORT", "9090")])).unwrap();
+        assert_eq!(addr, "127.0.0.1:9090".parse().unwrap());
+
+        let addr = bind_addr(env(&[("BIND_ADDR", "::1")])).unwrap();
+        assert_eq!(addr, "[::1]:8080".parse().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let err = bind_addr(env(&[("BIND_ADDR", "localhost")])).unwrap_err();
+        assert_eq!(err, "BIND_ADDR: expected an IP address, got 'localhost'");
+        assert!(bind_addr(env(&[("PORT", "70000")])).unwrap_err().starts_with("PORT:"));
+    }
+
+    #[test]
+    fn tls_needs_both_paths() {
+        assert_eq!(tls_paths(env(&[])).unwrap(), None);
+        let paths = tls_paths(env(&[("TLS_CERT_PATH", "cert.pem"), ("TLS_KEY_PATH", "key.pem")])).unwrap();
+        assert_eq!(paths, Some(("cert.pem".into(), "key.pem".into())));
+        assert!(tls_paths(env(&[("TLS_CERT_PATH", "cert.pem")])).is_err());
+    }
+
+    #[test]
+    fn cors_is_off_unless_configured() {
+        assert!(cors_layer(env(&[])).unwrap().is_none());
+        assert!(cors_layer(env(&[("DEV_CORS", "1")])).unwrap().is_some());
+        assert!(cors_layer(env(&[("CORS_ALLOWED_ORIGINS", "http://dash.lan")])).unwrap().is_some());
+    }
+
+    #[test]
+    fn cors_rejects_bad_entries() {
+        let err = cors_layer(env(&[("CORS_ALLOWED_ORIGINS", "http://ok.lan, bad\norigin")])).err().unwrap();
+        assert!(err.starts_with("CORS_ALLOWED_ORIGINS: invalid entry"), "{}", err);
+        let err = cors_layer(env(&[("CORS_ALLOWED_ORIGINS", "http://ok.lan"), ("CORS_ALLOWED_METHODS", "GET,GE T")]))
+            .err()
+            .unwrap();
+        assert_eq!(err, "CORS_ALLOWED_METHODS: invalid entry 'GE T'");
+        assert!(cors_layer(env(&[("CORS_ALLOWED_ORIGINS", " , ")])).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! The kernel's ARP cache, for MAC addresses of local-subnet hosts found
+//! without raw socket access (TCP probe discovery). Connecting to a host on
+//! the local subnet leaves an entry behind; hosts behind a router never get one.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+/// Kernel ARP cache on Linux.
+const PROC_NET_ARP: &str = "/proc/net/arp";
+
+/// `ATF_COM`: the entry holds a resolved hardware address.
+const ATF_COM: u32 = 0x2;
+
+/// MAC address of `ip` from the system ARP cache, if it has a complete entry.
+/// Always `None` where `/proc/net/arp` doesn't exist.
+pub async fn lookup(ip: Ipv4Addr) -> Option<String> {
+    let table = tokio::fs::read_to_string(PROC_NET_ARP).await.ok()?;
+    parse(&table).remove(&ip)
+}
+
+/// Complete entries of a `/proc/net/arp` dump, as lowercase `aa:bb:cc:dd:ee:ff`.
+pub fn parse(table: &str) -> HashMap<Ipv4Addr, String> {
+    table
+        .lines()
+        .skip(1) // header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [ip, _hw_type, flags, mac, ..] = fields[..] else { return None };
+            let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+            if flags & ATF_COM == 0 || mac == "00:00:00:00:00:00" {
+                return None;
+            }
+            Some((ip.parse().ok()?, mac.to_ascii_lowercase()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         a4:91:b1:0c:3e:71     *        wlan0
+192.168.1.23     0x1         0x2         DC:A6:32:1F:90:0B     *        wlan0
+192.168.1.40     0x1         0x0         00:00:00:00:00:00     *        wlan0
+10.0.0.8         0x1         0x6         02:42:ac:11:00:02     *        docker0
+";
+
+    #[test]
+    fn parses_complete_entries_only() {
+        let table = parse(SAMPLE);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table[&Ipv4Addr::new(192, 168, 1, 1)], "a4:91:b1:0c:3e:71");
+        // Normalised to lowercase, like the MACs the raw ARP scan records
+        assert_eq!(table[&Ipv4Addr::new(192, 168, 1, 23)], "dc:a6:32:1f:90:0b");
+        // Permanent entries (ATF_PERM | ATF_COM) count too
+        assert_eq!(table[&Ipv4Addr::new(10, 0, 0, 8)], "02:42:ac:11:00:02");
+        // Incomplete: the host didn't answer ARP
+        assert!(!table.contains_key(&Ipv4Addr::new(192, 168, 1, 40)));
+    }
+
+    #[test]
+    fn ignores_header_and_malformed_lines() {
+        assert!(parse("IP address HW type Flags HW address Mask Device\n").is_empty());
+        assert!(parse("header\nnot an entry\n192.168.1.1 0x1 zz aa:bb:cc:dd:ee:ff * eth0\n").is_empty());
+        assert!(parse("").is_empty());
+    }
+}
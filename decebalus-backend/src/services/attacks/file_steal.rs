@@ -1,9 +1,8 @@
 /// File Exfiltration Module
-
 pub struct FileSteal;
 
 impl FileSteal {
-    pub async fn steal_via_ssh(target: &str, username: &str, password: &str, remote_path: &str) -> Result<Vec<u8>, String> {
+    pub async fn steal_via_ssh(_target: &str, _username: &str, _password: &str, _remote_path: &str) -> Result<Vec<u8>, String> {
         // TODO: Implement using SSH/SFTP
         tracing::warn!("File stealing not yet implemented");
         Err("Not implemented".to_string())
@@ -1,9 +1,8 @@
 /// FTP Brute Force Attack Module
-
 pub struct FtpBruteForce;
 
 impl FtpBruteForce {
-    pub async fn attack(target: &str, username: &str, wordlist_path: &str) -> Result<Option<String>, String> {
+    pub async fn attack(_target: &str, _username: &str, _wordlist_path: &str) -> Result<Option<String>, String> {
         // TODO: Implement
         tracing::warn!("FTP brute force not yet implemented");
         Err("Not implemented".to_string())
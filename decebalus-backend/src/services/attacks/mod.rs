@@ -1,8 +1,12 @@
-// Attack modules
-// TODO: Implement these modules
+// Attack modules. Everything here is opt-in: `attack` jobs only run with
+// `attacks.enabled` set in the config.
+// TODO: Implement ssh_brute, ftp_brute and file_steal
 
 pub mod ssh_brute;
 pub mod ftp_brute;
 pub mod file_steal;
+pub mod ssh_default_creds;
+pub mod weak_ciphers;
 
-// Placeholder for now
\ No newline at end of file
+pub use ssh_default_creds::ssh_default_creds;
+pub use weak_ciphers::weak_ciphers;
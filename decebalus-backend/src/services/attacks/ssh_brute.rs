@@ -7,11 +7,10 @@
 /// - Connection attempts
 /// - Success/failure tracking
 /// - Rate limiting
-
 pub struct SshBruteForce;
 
 impl SshBruteForce {
-    pub async fn attack(target: &str, username: &str, wordlist_path: &str) -> Result<Option<String>, String> {
+    pub async fn attack(_target: &str, _username: &str, _wordlist_path: &str) -> Result<Option<String>, String> {
         // TODO: Implement
         tracing::warn!("SSH brute force not yet implemented");
         Err("Not implemented".to_string())
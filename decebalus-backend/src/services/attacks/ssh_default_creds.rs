@@ -0,0 +1,122 @@
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+use ssh2::Session;
+use crate::models::Vulnerability;
+
+/// Vulnerability id recorded for an SSH login that accepts default credentials.
+pub const SSH_DEFAULT_CREDS_ID: &str = "DEFAULT-CREDS-SSH";
+
+/// Username/password pairs shipped as defaults by common distros, boards and appliances.
+pub const SSH_DEFAULT_CREDENTIALS: &[(&str, &str)] = &[
+    ("root", "root"),
+    ("root", "toor"),
+    ("root", "admin"),
+    ("admin", "admin"),
+    ("admin", "password"),
+    ("pi", "raspberry"),
+    ("ubuntu", "ubuntu"),
+    ("user", "user"),
+    ("kali", "kali"),
+    ("guest", "guest"),
+];
+
+/// Connect, handshake and read/write timeout of each login attempt.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Try every pair in `SSH_DEFAULT_CREDENTIALS` against the SSH server at
+/// `ip:port`, one connection per attempt. Returns a `Vulnerability` listing the
+/// pairs that logged in, `None` if none did, or an error if the port doesn't
+/// speak SSH at all.
+pub async fn ssh_default_creds(ip: &str, port: u16) -> Result<Option<Vulnerability>, String> {
+    let addr: SocketAddr = format!("{}:{}", ip, port)
+        .parse()
+        .map_err(|_| format!("Invalid SSH target {}:{}", ip, port))?;
+
+    let accepted = tokio::task::spawn_blocking(move || {
+        let mut accepted = Vec::new();
+        for &(user, password) in SSH_DEFAULT_CREDENTIALS {
+            if try_login(addr, user, password)? {
+                accepted.push((user, password));
+            }
+        }
+        Ok::<_, String>(accepted)
+    })
+    .await
+    .map_err(|e| format!("SSH credential check on {} failed: {}", addr, e))??;
+
+    Ok(finding(port, &accepted))
+}
+
+/// One password login. `Ok(false)` is a rejected login; `Err` means no SSH session could be set up.
+fn try_login(addr: SocketAddr, user: &str, password: &str) -> Result<bool, String> {
+    let tcp = TcpStream::connect_timeout(&addr, ATTEMPT_TIMEOUT)
+        .map_err(|e| format!("Can't connect to {}: {}", addr, e))?;
+    let mut session = Session::new().map_err(|e| e.to_string())?;
+    session.set_timeout(ATTEMPT_TIMEOUT.as_millis() as u32);
+    session.set_tcp_stream(tcp);
+    session
+        .handshake()
+        .map_err(|e| format!("SSH handshake with {} failed: {}", addr, e))?;
+
+    // A wrong password is an error from libssh2, not a failed session
+    let _ = session.userauth_password(user, password);
+    Ok(session.authenticated())
+}
+
+/// The vulnerability for `accepted` logins on `port`, if there are any.
+fn finding(port: u16, accepted: &[(&str, &str)]) -> Option<Vulnerability> {
+    if accepted.is_empty() {
+        return None;
+    }
+    let pairs = accepted
+        .iter()
+        .map(|(user, password)| format!("{}/{}", user, if password.is_empty() { "<empty>" } else { password }))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(Vulnerability {
+        id: SSH_DEFAULT_CREDS_ID.to_string(),
+        description: format!("SSH on port {} accepts default credentials: {}", port, pairs),
+        severity: "CRITICAL".to_string(),
+        cvss_score: None,
+        service: Some(format!("ssh/{}", port)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn credential_list_has_no_duplicates() {
+        let unique: HashSet<_> = SSH_DEFAULT_CREDENTIALS.iter().collect();
+        assert_eq!(unique.len(), SSH_DEFAULT_CREDENTIALS.len());
+        assert!(SSH_DEFAULT_CREDENTIALS.contains(&("pi", "raspberry")));
+    }
+
+    #[test]
+    fn accepted_logins_become_a_critical_finding() {
+        assert!(finding(22, &[]).is_none());
+
+        let vuln = finding(2222, &[("root", "root"), ("pi", "raspberry")]).unwrap();
+        assert_eq!(vuln.id, SSH_DEFAULT_CREDS_ID);
+        assert_eq!(vuln.severity, "CRITICAL");
+        assert_eq!(vuln.description, "SSH on port 2222 accepts default credentials: root/root, pi/raspberry");
+    }
+
+    #[tokio::test]
+    async fn port_that_is_not_ssh_is_an_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Answers with garbage and hangs up, like a non-SSH service would
+        std::thread::spawn(move || {
+            use std::io::Write;
+            if let Ok((mut socket, _)) = listener.accept() {
+                let _ = socket.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+            }
+        });
+
+        let result = ssh_default_creds("127.0.0.1", port).await;
+        assert!(result.is_err(), "{:?}", result);
+    }
+}
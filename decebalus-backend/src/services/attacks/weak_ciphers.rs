@@ -0,0 +1,366 @@
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::models::{Port, Vulnerability};
+
+/// Prefix of the vulnerability ids recorded by this check, e.g. `WEAK-TLS-RC4`.
+pub const WEAK_TLS_ID_PREFIX: &str = "WEAK-TLS-";
+
+/// Connect, write and read timeout of each handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Protocol versions that can be offered in a ClientHello.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsVersion {
+    Ssl3,
+    Tls10,
+    Tls11,
+    Tls12,
+}
+
+impl TlsVersion {
+    pub const ALL: [TlsVersion; 4] = [Self::Ssl3, Self::Tls10, Self::Tls11, Self::Tls12];
+
+    fn wire(self) -> u16 {
+        match self {
+            Self::Ssl3 => 0x0300,
+            Self::Tls10 => 0x0301,
+            Self::Tls11 => 0x0302,
+            Self::Tls12 => 0x0303,
+        }
+    }
+
+    fn from_wire(v: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.wire() == v)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ssl3 => "SSLv3",
+            Self::Tls10 => "TLS 1.0",
+            Self::Tls11 => "TLS 1.1",
+            Self::Tls12 => "TLS 1.2",
+        }
+    }
+}
+
+/// Why a cipher suite counts as weak.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Weakness {
+    Null,
+    Export,
+    Rc4,
+    Des,
+}
+
+/// Cipher suites offered while probing: `(id, name, weakness)`. The strong ones
+/// are there so servers that also speak old protocol versions have something to pick.
+const CIPHER_SUITES: &[(u16, &str, Option<Weakness>)] = &[
+    (0x0001, "TLS_RSA_WITH_NULL_MD5", Some(Weakness::Null)),
+    (0x0002, "TLS_RSA_WITH_NULL_SHA", Some(Weakness::Null)),
+    (0x003B, "TLS_RSA_WITH_NULL_SHA256", Some(Weakness::Null)),
+    (0x0003, "TLS_RSA_EXPORT_WITH_RC4_40_MD5", Some(Weakness::Export)),
+    (0x0006, "TLS_RSA_EXPORT_WITH_RC2_CBC_40_MD5", Some(Weakness::Export)),
+    (0x0008, "TLS_RSA_EXPORT_WITH_DES40_CBC_SHA", Some(Weakness::Export)),
+    (0x0014, "TLS_DHE_RSA_EXPORT_WITH_DES40_CBC_SHA", Some(Weakness::Export)),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5", Some(Weakness::Rc4)),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA", Some(Weakness::Rc4)),
+    (0xC007, "TLS_ECDHE_ECDSA_WITH_RC4_128_SHA", Some(Weakness::Rc4)),
+    (0xC011, "TLS_ECDHE_RSA_WITH_RC4_128_SHA", Some(Weakness::Rc4)),
+    (0x0009, "TLS_RSA_WITH_DES_CBC_SHA", Some(Weakness::Des)),
+    (0x000A, "TLS_RSA_WITH_3DES_EDE_CBC_SHA", Some(Weakness::Des)),
+    (0x0016, "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA", Some(Weakness::Des)),
+    (0xC012, "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA", Some(Weakness::Des)),
+    (0x002F, "TLS_RSA_WITH_AES_128_CBC_SHA", None),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA", None),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA", None),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA", None),
+    (0xC009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA", None),
+    (0xC00A, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA", None),
+    (0xC013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA", None),
+    (0xC014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA", None),
+    (0x009C, "TLS_RSA_WITH_AES_128_GCM_SHA256", None),
+    (0x009D, "TLS_RSA_WITH_AES_256_GCM_SHA384", None),
+    (0xC02B, "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256", None),
+    (0xC02C, "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384", None),
+    (0xC02F, "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256", None),
+    (0xC030, "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384", None),
+    (0xCCA8, "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256", None),
+    (0xCCA9, "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256", None),
+];
+
+/// Service names that speak TLS from the first byte.
+const TLS_SERVICES: &[&str] = &["https", "imaps", "pop3s", "smtps", "ldaps", "ftps", "ircs", "mqtts", "ssl"];
+
+/// Ports that usually speak TLS even when service detection didn't name them.
+const TLS_PORTS: &[u16] = &[443, 465, 636, 853, 993, 995, 8443, 8883];
+
+/// Whether `port` is an open TCP port worth a TLS handshake.
+pub fn is_tls_port(port: &Port) -> bool {
+    port.status == "open"
+        && port.protocol == "tcp"
+        && (TLS_PORTS.contains(&port.number)
+            || port.service.as_deref().is_some_and(|s| TLS_SERVICES.contains(&s) || s.starts_with("ssl/")))
+}
+
+/// Enumerate the protocol versions and cipher suites the TLS server at
+/// `ip:port` accepts, by offering raw ClientHellos and reading the ServerHello,
+/// and return the deprecated ones as vulnerabilities. Nothing past the
+/// ServerHello is exchanged. Errors only if the port can't be reached.
+pub async fn weak_ciphers(ip: &str, port: u16) -> Result<Vec<Vulnerability>, String> {
+    let addr = format!("{}:{}", ip, port);
+    let mut accepted = Vec::new();
+
+    for version in TlsVersion::ALL {
+        let mut offered: Vec<u16> = CIPHER_SUITES.iter().map(|(id, _, _)| *id).collect();
+        // Each answer names one suite; take it out and ask again until the server refuses
+        while !offered.is_empty() {
+            let Some((negotiated, cipher)) = server_hello(&addr, version, &offered).await? else { break };
+            if negotiated != version || !offered.contains(&cipher) {
+                break;
+            }
+            accepted.push((version, cipher));
+            offered.retain(|c| *c != cipher);
+        }
+    }
+
+    Ok(classify(port, &accepted))
+}
+
+/// One handshake attempt: the server's chosen version and cipher suite, or
+/// `None` if it refused or hung up.
+async fn server_hello(addr: &str, version: TlsVersion, ciphers: &[u16]) -> Result<Option<(TlsVersion, u16)>, String> {
+    let mut stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .map_err(|_| format!("Connecting to {} timed out", addr))?
+        .map_err(|e| format!("Can't connect to {}: {}", addr, e))?;
+
+    let exchange = async {
+        stream.write_all(&client_hello(version, ciphers)).await.ok()?;
+        read_server_hello(&mut stream).await
+    };
+    Ok(tokio::time::timeout(HANDSHAKE_TIMEOUT, exchange).await.ok().flatten())
+}
+
+/// A ClientHello record offering `version` and `ciphers`.
+pub(crate) fn client_hello(version: TlsVersion, ciphers: &[u16]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend(version.wire().to_be_bytes());
+    body.extend((0..32u8).map(|i| i.wrapping_mul(37)));
+    body.push(0); // no session id
+    body.extend(((ciphers.len() * 2) as u16).to_be_bytes());
+    for c in ciphers {
+        body.extend(c.to_be_bytes());
+    }
+    body.extend([1, 0]); // null compression only
+
+    // SSLv3 servers may choke on extensions
+    if version != TlsVersion::Ssl3 {
+        let mut ext = Vec::new();
+        // supported_groups: secp256r1, secp384r1, x25519
+        ext.extend([0x00, 0x0a, 0x00, 0x08, 0x00, 0x06, 0x00, 0x17, 0x00, 0x18, 0x00, 0x1d]);
+        // ec_point_formats: uncompressed
+        ext.extend([0x00, 0x0b, 0x00, 0x02, 0x01, 0x00]);
+        // signature_algorithms: RSA PKCS#1 / ECDSA / RSA-PSS with SHA-256..512, plus SHA-1
+        let sig_algs: [u16; 11] = [0x0401, 0x0501, 0x0601, 0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0201, 0x0203];
+        ext.extend(0x000du16.to_be_bytes());
+        ext.extend(((sig_algs.len() * 2 + 2) as u16).to_be_bytes());
+        ext.extend(((sig_algs.len() * 2) as u16).to_be_bytes());
+        for alg in sig_algs {
+            ext.extend(alg.to_be_bytes());
+        }
+        body.extend((ext.len() as u16).to_be_bytes());
+        body.extend(ext);
+    }
+
+    let mut handshake = vec![1]; // client_hello
+    handshake.extend(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(body);
+
+    // Record layer version stays at TLS 1.0 (SSLv3 for SSLv3) for compatibility
+    let record_version = version.wire().min(TlsVersion::Tls10.wire());
+    let mut record = vec![22];
+    record.extend(record_version.to_be_bytes());
+    record.extend((handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+/// Read handshake records until the ServerHello's version and cipher suite are in.
+/// `None` on an alert, anything that isn't a ServerHello, or a closed connection.
+async fn read_server_hello(stream: &mut TcpStream) -> Option<(TlsVersion, u16)> {
+    let mut handshake = Vec::new();
+    loop {
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).await.ok()?;
+        if header[0] != 22 {
+            return None;
+        }
+        let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let mut fragment = vec![0u8; len];
+        stream.read_exact(&mut fragment).await.ok()?;
+        handshake.extend(fragment);
+
+        if let Some(parsed) = parse_server_hello(&handshake) {
+            return parsed;
+        }
+    }
+}
+
+/// `Some(result)` once `handshake` holds enough of a ServerHello to decide,
+/// `None` while more bytes are needed.
+fn parse_server_hello(handshake: &[u8]) -> Option<Option<(TlsVersion, u16)>> {
+    let msg_type = *handshake.first()?;
+    if msg_type != 2 {
+        return Some(None);
+    }
+    // type(1) length(3) version(2) random(32) session_id_len(1)
+    let sid_len = *handshake.get(38)? as usize;
+    let at = 39 + sid_len;
+    let cipher = u16::from_be_bytes([*handshake.get(at)?, *handshake.get(at + 1)?]);
+    let version = u16::from_be_bytes([handshake[4], handshake[5]]);
+    Some(TlsVersion::from_wire(version).map(|v| (v, cipher)))
+}
+
+/// Vulnerabilities for the deprecated parts of what the server on `port` accepted:
+/// one per old protocol version and one per class of weak cipher.
+pub fn classify(port: u16, accepted: &[(TlsVersion, u16)]) -> Vec<Vulnerability> {
+    let finding = |id: &str, severity: &str, description: String| Vulnerability {
+        id: format!("{}{}", WEAK_TLS_ID_PREFIX, id),
+        description,
+        severity: severity.to_string(),
+        cvss_score: None,
+        service: Some(format!("tls/{}", port)),
+    };
+    let mut found = Vec::new();
+
+    for (version, id, severity, why) in [
+        (TlsVersion::Ssl3, "SSLV3", "HIGH", "broken by POODLE"),
+        (TlsVersion::Tls10, "TLS10", "MEDIUM", "deprecated by RFC 8996"),
+        (TlsVersion::Tls11, "TLS11", "MEDIUM", "deprecated by RFC 8996"),
+    ] {
+        if accepted.iter().any(|(v, _)| *v == version) {
+            found.push(finding(id, severity, format!("Port {} accepts {}, {}", port, version.label(), why)));
+        }
+    }
+
+    for (weakness, id, severity, what) in [
+        (Weakness::Null, "NULL", "HIGH", "NULL (unencrypted)"),
+        (Weakness::Export, "EXPORT", "HIGH", "export-grade"),
+        (Weakness::Rc4, "RC4", "HIGH", "RC4"),
+        (Weakness::Des, "DES", "MEDIUM", "DES/3DES"),
+    ] {
+        let mut names: Vec<&str> = accepted
+            .iter()
+            .filter_map(|(_, cipher)| CIPHER_SUITES.iter().find(|(c, _, _)| c == cipher))
+            .filter(|(_, _, w)| *w == Some(weakness))
+            .map(|(_, name, _)| *name)
+            .collect();
+        if !names.is_empty() {
+            names.sort_unstable();
+            names.dedup();
+            found.push(finding(id, severity, format!("Port {} accepts {} cipher suites: {}", port, what, names.join(", "))));
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn ids(vulns: &[Vulnerability]) -> Vec<&str> {
+        vulns.iter().map(|v| v.id.as_str()).collect()
+    }
+
+    #[test]
+    fn modern_server_has_no_findings() {
+        let accepted = [(TlsVersion::Tls12, 0xC02F), (TlsVersion::Tls12, 0xCCA8)];
+        assert!(classify(443, &accepted).is_empty());
+    }
+
+    #[test]
+    fn old_versions_and_weak_ciphers_are_flagged() {
+        let accepted = [
+            (TlsVersion::Ssl3, 0x0005),
+            (TlsVersion::Tls10, 0x0005),
+            (TlsVersion::Tls10, 0x000A),
+            (TlsVersion::Tls12, 0x0003),
+            (TlsVersion::Tls12, 0xC02F),
+        ];
+        let vulns = classify(8443, &accepted);
+
+        assert_eq!(ids(&vulns), ["WEAK-TLS-SSLV3", "WEAK-TLS-TLS10", "WEAK-TLS-EXPORT", "WEAK-TLS-RC4", "WEAK-TLS-DES"]);
+        assert_eq!(vulns[0].severity, "HIGH");
+        assert_eq!(vulns[1].severity, "MEDIUM");
+        assert_eq!(vulns[3].description, "Port 8443 accepts RC4 cipher suites: TLS_RSA_WITH_RC4_128_SHA");
+        assert_eq!(vulns[3].service.as_deref(), Some("tls/8443"));
+    }
+
+    /// Offered version and cipher suites of a ClientHello built by `client_hello`.
+    fn offered(hello: &[u8]) -> (u16, Vec<u16>) {
+        let version = u16::from_be_bytes([hello[9], hello[10]]);
+        let sid_len = hello[43] as usize;
+        let at = 44 + sid_len;
+        let len = u16::from_be_bytes([hello[at], hello[at + 1]]) as usize;
+        let ciphers = hello[at + 2..at + 2 + len].chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        (version, ciphers)
+    }
+
+    /// Server speaking only TLS 1.0 with RC4-SHA, answering a ServerHello or a handshake_failure alert.
+    async fn weak_tls_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let Ok(n) = socket.read(&mut buf).await else { continue };
+                let (version, ciphers) = offered(&buf[..n]);
+                let reply = if version == 0x0301 && ciphers.contains(&0x0005) {
+                    let mut hello = vec![2, 0, 0, 38, 0x03, 0x01];
+                    hello.extend([0u8; 32]);
+                    hello.extend([0, 0x00, 0x05, 0]);
+                    let mut record = vec![22, 0x03, 0x01, 0, hello.len() as u8];
+                    record.extend(hello);
+                    record
+                } else {
+                    vec![21, 0x03, 0x01, 0, 2, 2, 40]
+                };
+                let _ = socket.write_all(&reply).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn enumerates_a_weak_local_server() {
+        let port = weak_tls_server().await;
+
+        let vulns = weak_ciphers("127.0.0.1", port).await.unwrap();
+
+        assert_eq!(ids(&vulns), ["WEAK-TLS-TLS10", "WEAK-TLS-RC4"]);
+    }
+
+    #[tokio::test]
+    async fn unreachable_port_is_an_error() {
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        assert!(weak_ciphers("127.0.0.1", port).await.is_err());
+    }
+
+    #[test]
+    fn tls_ports_are_recognised_by_service_or_number() {
+        let port = |number: u16, service: Option<&str>| Port {
+            number,
+            protocol: "tcp".into(),
+            status: "open".into(),
+            service: service.map(str::to_string),
+            version: None,
+            cpe: None,
+        };
+        assert!(is_tls_port(&port(443, None)));
+        assert!(is_tls_port(&port(4443, Some("https"))));
+        assert!(!is_tls_port(&port(80, Some("http"))));
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::models::AutopilotConfig;
+
+/// Result of a single connection attempt, as seen by the autopilot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Open,
+    /// Connection refused — a closed port, which is normal.
+    Closed,
+    /// No answer in time — usually a filtered port, also normal.
+    TimedOut,
+    /// Local or network-level failure (out of sockets, unreachable, reset…).
+    Error,
+}
+
+impl ProbeOutcome {
+    pub fn from_io_error(err: &std::io::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ConnectionRefused => Self::Closed,
+            ErrorKind::TimedOut => Self::TimedOut,
+            _ => Self::Error,
+        }
+    }
+}
+
+struct Window {
+    samples: usize,
+    errors: usize,
+    /// Current concurrency target.
+    limit: usize,
+    /// Permits that must be forgotten as they come back to reach `limit`.
+    debt: usize,
+    delay_ms: u64,
+}
+
+/// Scan aggressiveness autopilot.
+///
+/// Wraps a semaphore whose size follows the recent error rate: each full window of
+/// attempts is evaluated, concurrency is cut by `backoff_factor` (and a per-attempt
+/// delay added) when errors exceed `error_threshold`, and stepped back up once the
+/// network is healthy again. Bounds are `min_concurrency..=max`.
+pub struct AdaptiveLimiter {
+    sem: Arc<Semaphore>,
+    window: Mutex<Window>,
+    cfg: AutopilotConfig,
+    max: usize,
+}
+
+/// Permit for one connection attempt. Returned to the limiter on drop unless
+/// the limiter has shrunk in the meantime.
+pub struct AutopilotPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+impl Drop for AutopilotPermit {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else { return };
+        let mut window = self.limiter.window.lock().unwrap();
+        if window.debt > 0 {
+            window.debt -= 1;
+            permit.forget();
+        }
+    }
+}
+
+impl AdaptiveLimiter {
+    pub fn new(max: usize, cfg: AutopilotConfig) -> Arc<Self> {
+        let max = max.max(1);
+        Arc::new(Self {
+            sem: Arc::new(Semaphore::new(max)),
+            window: Mutex::new(Window { samples: 0, errors: 0, limit: max, debt: 0, delay_ms: 0 }),
+            cfg,
+            max,
+        })
+    }
+
+    /// Current concurrency target.
+    pub fn limit(&self) -> usize {
+        self.window.lock().unwrap().limit
+    }
+
+    /// Delay to wait before the next connection attempt.
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.window.lock().unwrap().delay_ms)
+    }
+
+    pub async fn acquire(self: &Arc<Self>) -> AutopilotPermit {
+        let permit = self.sem.clone().acquire_owned().await.unwrap();
+        AutopilotPermit { permit: Some(permit), limiter: self.clone() }
+    }
+
+    /// Record an attempt and, at the end of a window, adjust the limit.
+    /// Returns the new limit when it changed.
+    pub fn record(&self, outcome: ProbeOutcome) -> Option<usize> {
+        let mut window = self.window.lock().unwrap();
+        window.samples += 1;
+        if outcome == ProbeOutcome::Error {
+            window.errors += 1;
+        }
+        if window.samples < self.cfg.window.max(1) {
+            return None;
+        }
+
+        let rate = window.errors as f64 / window.samples as f64;
+        window.samples = 0;
+        window.errors = 0;
+
+        let min = self.cfg.min_concurrency.clamp(1, self.max);
+        let current = window.limit;
+        let target = if rate >= self.cfg.error_threshold {
+            window.delay_ms = (window.delay_ms * 2).max(1).min(self.cfg.max_delay_ms);
+            ((current as f64 * self.cfg.backoff_factor) as usize).max(min)
+        } else if rate <= self.cfg.recovery_threshold {
+            window.delay_ms /= 2;
+            // Recover in steps of 10% of the ceiling so a flapping network doesn't oscillate
+            (current + (self.max / 10).max(1)).min(self.max)
+        } else {
+            current
+        };
+
+        if target < current {
+            let shrink = current - target;
+            // Drop idle permits right away; the rest are forgotten as attempts finish
+            let forgotten = self.sem.forget_permits(shrink);
+            window.debt += shrink - forgotten;
+        } else if target > current {
+            let mut grow = target - current;
+            let repaid = grow.min(window.debt);
+            window.debt -= repaid;
+            grow -= repaid;
+            self.sem.add_permits(grow);
+        }
+        window.limit = target;
+
+        if target != current {
+            tracing::info!(
+                "Scan autopilot: error rate {:.0}%, concurrency {} -> {}",
+                rate * 100.0, current, target
+            );
+            Some(target)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> AutopilotConfig {
+        AutopilotConfig {
+            min_concurrency: 4,
+            window: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn error_spike_reduces_concurrency() {
+        let limiter = AdaptiveLimiter::new(100, cfg());
+
+        for _ in 0..10 {
+            limiter.record(ProbeOutcome::Error);
+        }
+        assert_eq!(limiter.limit(), 50);
+        assert_eq!(limiter.sem.available_permits(), 50);
+        assert!(limiter.delay() > Duration::ZERO);
+
+        for _ in 0..40 {
+            limiter.record(ProbeOutcome::Error);
+        }
+        // Never below the configured floor
+        assert_eq!(limiter.limit(), 4);
+    }
+
+    #[test]
+    fn closed_and_filtered_ports_are_not_errors() {
+        let limiter = AdaptiveLimiter::new(100, cfg());
+        for i in 0..50 {
+            let outcome = if i % 2 == 0 { ProbeOutcome::Closed } else { ProbeOutcome::TimedOut };
+            limiter.record(outcome);
+        }
+        assert_eq!(limiter.limit(), 100);
+    }
+
+    #[test]
+    fn recovers_after_errors_stop() {
+        let limiter = AdaptiveLimiter::new(100, cfg());
+        for _ in 0..10 {
+            limiter.record(ProbeOutcome::Error);
+        }
+        assert_eq!(limiter.limit(), 50);
+
+        for _ in 0..100 {
+            limiter.record(ProbeOutcome::Closed);
+        }
+        assert_eq!(limiter.limit(), 100);
+        assert_eq!(limiter.sem.available_permits(), 100);
+        assert_eq!(limiter.delay(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn in_flight_permits_are_forgotten_after_backoff() {
+        let limiter = AdaptiveLimiter::new(10, AutopilotConfig { min_concurrency: 1, window: 1, ..Default::default() });
+        let held: Vec<_> = futures_util::future::join_all((0..10).map(|_| limiter.acquire())).await;
+
+        limiter.record(ProbeOutcome::Error);
+        assert_eq!(limiter.limit(), 5);
+
+        drop(held);
+        assert_eq!(limiter.sem.available_permits(), 5);
+    }
+}
@@ -0,0 +1,61 @@
+use tokio::sync::RwLock;
+use crate::db::repository_trait::Repository;
+use crate::models::Config;
+
+/// Last known `Config`, shared through `AppState` so jobs don't query the
+/// config table every time they start. Loaded on first use (and at startup),
+/// replaced whenever the config API stores a new one.
+#[derive(Default)]
+pub struct ConfigCache {
+    current: RwLock<Option<Config>>,
+}
+
+impl ConfigCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached config, read from `repo` if nothing is cached yet.
+    pub async fn get(&self, repo: &dyn Repository) -> Result<Config, sqlx::Error> {
+        if let Some(config) = self.current.read().await.as_ref() {
+            return Ok(config.clone());
+        }
+        self.reload(repo).await
+    }
+
+    /// Re-read the config from `repo`, replacing the cached copy.
+    pub async fn reload(&self, repo: &dyn Repository) -> Result<Config, sqlx::Error> {
+        let config = repo.get_config().await?;
+        *self.current.write().await = Some(config.clone());
+        Ok(config)
+    }
+
+    /// Cache `config` after it has been stored.
+    pub async fn set(&self, config: Config) {
+        *self.current.write().await = Some(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::db::inmemory_repository::InMemoryRepository;
+
+    #[tokio::test]
+    async fn serves_the_cached_copy_until_replaced() {
+        let repo = InMemoryRepository::new();
+        repo.update_config(&Config { settings: json!({ "device_name": "first" }) }).await.unwrap();
+
+        let cache = ConfigCache::new();
+        assert_eq!(cache.get(&repo).await.unwrap().settings["device_name"], "first");
+
+        // Written behind the cache's back: not seen until a reload
+        repo.update_config(&Config { settings: json!({ "device_name": "second" }) }).await.unwrap();
+        assert_eq!(cache.get(&repo).await.unwrap().settings["device_name"], "first");
+        assert_eq!(cache.reload(&repo).await.unwrap().settings["device_name"], "second");
+
+        cache.set(Config { settings: json!({ "device_name": "third" }) }).await;
+        assert_eq!(cache.get(&repo).await.unwrap().settings["device_name"], "third");
+    }
+}
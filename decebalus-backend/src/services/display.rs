@@ -0,0 +1,283 @@
+//! Images for the e-paper display: checking what clients push, rendering
+//! status text, and keeping the current image on disk under `data/display`.
+
+use std::convert::Infallible;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_6X10, FONT_9X15};
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use image::{GrayImage, ImageFormat, Luma};
+use crate::models::{DisplayConfig, DisplayStatus, WsEvent};
+use crate::services::safe_path::AllowedDirs;
+use crate::state::AppState;
+
+/// Largest decoded image accepted; panel images are a few KB.
+pub const MAX_IMAGE_BYTES: usize = 1024 * 1024;
+
+/// Fonts `render_text` can use, largest first.
+const FONTS: [&MonoFont<'static>; 3] = [&FONT_10X20, &FONT_9X15, &FONT_6X10];
+/// Blank border around rendered text, in pixels.
+const MARGIN: u32 = 4;
+
+/// A decoded image that fits the panel, in its original encoding.
+#[derive(Debug)]
+pub struct PanelImage {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+}
+
+impl PanelImage {
+    /// Decode a base64 PNG or BMP (optionally a `data:` URL) and check it
+    /// matches the panel size in `cfg`.
+    pub fn from_base64(encoded: &str, cfg: &DisplayConfig) -> Result<Self, String> {
+        // Accept `data:image/png;base64,...` as browsers produce it
+        let encoded = encoded.split_once(";base64,").map_or(encoded, |(_, data)| data);
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("Image is not valid base64: {}", e))?;
+        Self::from_bytes(bytes, cfg)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, cfg: &DisplayConfig) -> Result<Self, String> {
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(format!("Image is {} bytes, maximum is {}", bytes.len(), MAX_IMAGE_BYTES));
+        }
+        let format = match image::guess_format(&bytes) {
+            Ok(format @ (ImageFormat::Png | ImageFormat::Bmp)) => format,
+            _ => return Err("Unsupported image format; send a PNG or BMP".to_string()),
+        };
+        let decoded = image::load_from_memory_with_format(&bytes, format)
+            .map_err(|e| format!("Image could not be decoded: {}", e))?;
+        if !cfg.fits(decoded.width(), decoded.height()) {
+            return Err(format!(
+                "Image is {}x{}; the panel is {}x{}",
+                decoded.width(),
+                decoded.height(),
+                cfg.width,
+                cfg.height
+            ));
+        }
+        Ok(Self { bytes, format })
+    }
+
+    /// `text` rendered for the panel in `cfg`.
+    pub fn from_text(text: &str, cfg: &DisplayConfig) -> Result<Self, String> {
+        Ok(Self { bytes: render_text(text, cfg.width, cfg.height)?, format: ImageFormat::Png })
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.format {
+            ImageFormat::Bmp => "bmp",
+            _ => "png",
+        }
+    }
+}
+
+/// Render `text` black on white as a `width` x `height` PNG, word-wrapped in
+/// the largest built-in font it fits in. Lines that don't fit even in the
+/// smallest font are cut off.
+pub fn render_text(text: &str, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let inner_width = width.saturating_sub(2 * MARGIN);
+    let inner_height = height.saturating_sub(2 * MARGIN);
+    let layout = |font: &'static MonoFont<'static>| {
+        let columns = (inner_width / font.character_size.width).max(1) as usize;
+        (font, wrap(text, columns))
+    };
+    let (font, lines) = FONTS
+        .into_iter()
+        .map(layout)
+        .find(|(font, lines)| lines.len() as u32 * font.character_size.height <= inner_height)
+        .unwrap_or_else(|| layout(&FONT_6X10));
+
+    let mut canvas = Canvas(GrayImage::from_pixel(width, height, Luma([255])));
+    let style = MonoTextStyle::new(font, BinaryColor::On);
+    let line_height = font.character_size.height;
+    for (i, line) in lines.iter().enumerate() {
+        let top = MARGIN + i as u32 * line_height;
+        if top + line_height > height.saturating_sub(MARGIN) {
+            break;
+        }
+        let origin = Point::new(MARGIN as i32, top as i32);
+        let Ok(_) = Text::with_baseline(line, origin, style, Baseline::Top).draw(&mut canvas);
+    }
+
+    let mut bytes = Vec::new();
+    canvas.0
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode display image: {}", e))?;
+    Ok(bytes)
+}
+
+/// Split `text` into lines of at most `columns` characters, breaking at
+/// spaces where possible and inside words that are longer than a line.
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let chars: Vec<char> = word.chars().collect();
+            for piece in chars.chunks(columns) {
+                let used = line.chars().count();
+                if used > 0 && used + 1 + piece.len() > columns {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.extend(piece);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Grayscale image that embedded-graphics draws on: on is black, off is white.
+struct Canvas(GrayImage);
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.0.width(), self.0.height())
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y))
+                && x < self.0.width()
+                && y < self.0.height()
+            {
+                self.0.put_pixel(x, y, Luma([if color.is_on() { 0 } else { 255 }]));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `image` to a new file in the display directory and remove
+/// `previous`, the file it replaces. Returns the new file's path.
+pub async fn store_image(image: &PanelImage, previous: Option<&str>) -> Result<PathBuf, String> {
+    let dir = AllowedDirs::from_env().resolve("display")?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(format!("display-{}.{}", uuid::Uuid::new_v4(), image.extension()));
+    tokio::fs::write(&path, &image.bytes)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    if let Some(previous) = previous
+        && let Err(e) = tokio::fs::remove_file(previous).await
+    {
+        tracing::debug!("Could not remove old display image {}: {}", previous, e);
+    }
+    Ok(path)
+}
+
+/// Put `text` on the display, replacing the panel image with `image` if
+/// given, and tell WebSocket clients. Returns the new display status.
+pub async fn show(state: &AppState, text: &str, image: Option<&PanelImage>) -> Result<DisplayStatus, String> {
+    let mut status = state.repo.get_display_status()
+        .await
+        .map_err(|e| format!("Failed to get display status: {}", e))?;
+    if let Some(image) = image {
+        let path = store_image(image, status.image.as_deref()).await?;
+        status.image = Some(path.display().to_string());
+    }
+    status.update(text.to_string());
+
+    state.repo.update_display_status(&status)
+        .await
+        .map_err(|e| format!("Failed to update display status: {}", e))?;
+    let _ = state.broadcaster.send(WsEvent::DisplayUpdated { text: text.to_string() });
+    Ok(status)
+}
+
+/// MIME type of a stored display image, from its extension.
+pub fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("bmp") => "image/bmp",
+        _ => "image/png",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::GrayImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn panel_sized_png_is_accepted_either_way_round() {
+        let cfg = DisplayConfig::default();
+        for (w, h) in [(250, 122), (122, 250)] {
+            let image = PanelImage::from_base64(&STANDARD.encode(png(w, h)), &cfg).unwrap();
+            assert_eq!(image.format, ImageFormat::Png);
+        }
+        let data_url = format!("data:image/png;base64,{}", STANDARD.encode(png(250, 122)));
+        assert!(PanelImage::from_base64(&data_url, &cfg).is_ok());
+    }
+
+    #[test]
+    fn bad_images_are_rejected() {
+        let cfg = DisplayConfig::default();
+        let wrong_size = PanelImage::from_base64(&STANDARD.encode(png(100, 100)), &cfg).unwrap_err();
+        assert!(wrong_size.contains("100x100"), "{}", wrong_size);
+        assert!(PanelImage::from_base64("not base64!", &cfg).unwrap_err().contains("base64"));
+        assert!(PanelImage::from_base64(&STANDARD.encode(b"GIF89a"), &cfg).unwrap_err().contains("format"));
+        // A PNG signature with a truncated body
+        assert!(PanelImage::from_bytes(png(250, 122)[..40].to_vec(), &cfg).unwrap_err().contains("decoded"));
+    }
+
+    #[test]
+    fn rendered_text_fills_the_panel_in_black_and_white() {
+        let bytes = render_text("12 hosts up, 3 new ports since the last scan", 250, 122).unwrap();
+        let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap().to_luma8();
+
+        assert_eq!(image.dimensions(), (250, 122));
+        assert!(image.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+        assert!(image.pixels().any(|p| p.0[0] == 0), "no text was drawn");
+        // Rendered images pass the same checks as uploaded ones
+        assert!(PanelImage::from_bytes(bytes, &DisplayConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn empty_text_renders_a_blank_panel() {
+        let bytes = render_text("", 122, 250).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_luma8();
+        assert_eq!(image.dimensions(), (122, 250));
+        assert!(image.pixels().all(|p| p.0[0] == 255));
+    }
+
+    #[test]
+    fn text_wraps_at_spaces_and_inside_long_words() {
+        assert_eq!(wrap("scan done: 3 hosts", 10), ["scan done:", "3 hosts"]);
+        assert_eq!(wrap("aaaaaaaaaaaa b", 5), ["aaaaa", "aaaaa", "aa b"]);
+        assert_eq!(wrap("one\ntwo", 20), ["one", "two"]);
+    }
+
+    #[test]
+    fn content_type_follows_the_extension() {
+        assert_eq!(content_type(Path::new("data/display/display-1.bmp")), "image/bmp");
+        assert_eq!(content_type(Path::new("data/display/display-1.png")), "image/png");
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::models::{DisplayConfig, DisplayMessage};
+use crate::services::display::{self, PanelImage};
+use crate::state::AppState;
+
+/// Display Rotator
+/// Every `display.rotate_interval_secs`, puts the next message from the
+/// display queue on the panel, so several messages can share one display.
+pub struct DisplayRotator;
+
+impl DisplayRotator {
+    pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+        tokio::spawn(Self::run(state))
+    }
+
+    async fn run(state: Arc<AppState>) {
+        tracing::info!("Display rotator started...");
+
+        loop {
+            // Re-read each pass so config edits apply without a restart
+            let cfg = state.config.get(state.repo.as_ref()).await.map(|c| c.display_config()).unwrap_or_default();
+
+            if let Err(e) = Self::rotate(&state, &cfg).await {
+                tracing::error!("Display rotation failed: {}", e);
+            }
+
+            tokio::time::sleep(Duration::from_secs(cfg.rotate_interval_secs.max(1))).await;
+        }
+    }
+
+    /// Show the queued message after the one on the panel, wrapping around to
+    /// the oldest. Returns the text now on the panel, or `None` if the queue
+    /// is empty and the panel was left alone.
+    pub async fn rotate(state: &AppState, cfg: &DisplayConfig) -> Result<Option<String>, String> {
+        let messages = state.repo.list_display_messages()
+            .await
+            .map_err(|e| format!("Failed to list display messages: {}", e))?;
+        let current = state.repo.get_display_status()
+            .await
+            .map_err(|e| format!("Failed to get display status: {}", e))?
+            .status;
+
+        let Some(next) = next_message(&messages, &current) else {
+            return Ok(None);
+        };
+        // A queue of one doesn't need redrawing every interval
+        if next.text != current {
+            let image = PanelImage::from_text(&next.text, cfg)?;
+            display::show(state, &next.text, Some(&image)).await?;
+        }
+        Ok(Some(next.text.clone()))
+    }
+}
+
+/// The message after the one reading `current`, or the oldest one if
+/// `current` isn't queued. Queued texts are unique, so the match is too.
+fn next_message<'a>(messages: &'a [DisplayMessage], current: &str) -> Option<&'a DisplayMessage> {
+    let next = messages
+        .iter()
+        .position(|m| m.text == current)
+        .map_or(0, |i| (i + 1) % messages.len());
+    messages.get(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(texts: &[&str]) -> Vec<DisplayMessage> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| DisplayMessage { id: i as i64 + 1, text: text.to_string(), created_at: 0 })
+            .collect()
+    }
+
+    #[test]
+    fn next_message_wraps_around_the_queue() {
+        let messages = queue(&["a", "b", "c"]);
+
+        assert_eq!(next_message(&messages, "a").unwrap().text, "b");
+        assert_eq!(next_message(&messages, "c").unwrap().text, "a");
+        // Something pushed outside the queue starts it over
+        assert_eq!(next_message(&messages, "idle").unwrap().text, "a");
+    }
+
+    #[test]
+    fn next_message_of_an_empty_queue_is_none() {
+        assert!(next_message(&[], "idle").is_none());
+        assert_eq!(next_message(&queue(&["only"]), "only").unwrap().text, "only");
+    }
+}
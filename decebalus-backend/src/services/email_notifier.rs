@@ -0,0 +1,178 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Arc;
+use crate::db::repository_trait::Repository;
+use crate::models::{severity_rank, SmtpConfig, WsEvent};
+use crate::services::notifier::Notifier;
+
+const THIS_SERVICE: &str = "email_notifier";
+
+/// Email Notifier
+/// Emails significant events (hosts seen for the first time and new
+/// vulnerabilities of at least `min_severity`) to `integrations.smtp.to`,
+/// either one by one or as a periodic digest.
+pub struct EmailNotifier {
+    repo: Arc<dyn Repository>,
+    pending: Mutex<Vec<String>>,
+    last_flush: Mutex<Instant>,
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn notify(&self, event: &WsEvent) {
+        let Some(line) = Self::describe(event) else { return };
+        let Some(cfg) = self.load_config().await else { return };
+        if !Self::severe_enough(event, &cfg) {
+            return;
+        }
+
+        if cfg.digest {
+            self.pending.lock().unwrap().push(line);
+        } else {
+            self.deliver(&cfg, "Decebalus: new activity", &line).await;
+        }
+    }
+
+    /// Flush the digest once `digest_interval_secs` has passed.
+    async fn tick(&self) {
+        if self.pending.lock().unwrap().is_empty() {
+            return;
+        }
+        let Some(cfg) = self.load_config().await else {
+            self.pending.lock().unwrap().clear();
+            return;
+        };
+        if cfg.digest && self.last_flush.lock().unwrap().elapsed() < Duration::from_secs(cfg.digest_interval_secs) {
+            return;
+        }
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let subject = format!("Decebalus digest: {} new event(s)", pending.len());
+        self.deliver(&cfg, &subject, &pending.join("\n")).await;
+        *self.last_flush.lock().unwrap() = Instant::now();
+    }
+}
+
+impl EmailNotifier {
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        Self {
+            repo,
+            pending: Mutex::new(Vec::new()),
+            last_flush: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Human-readable line for events worth an email; `None` for everything else.
+    fn describe(event: &WsEvent) -> Option<String> {
+        match event {
+            WsEvent::NewHost { ip } => Some(format!("New host discovered: {}", ip)),
+            WsEvent::VulnerabilityFound { ip, id, severity, description } => {
+                Some(format!("New {} vulnerability on {}: {} — {}", severity, ip, id, description))
+            }
+            _ => None,
+        }
+    }
+
+    /// Vulnerabilities below `cfg.min_severity` are dropped; other events always pass.
+    fn severe_enough(event: &WsEvent, cfg: &SmtpConfig) -> bool {
+        match event {
+            WsEvent::VulnerabilityFound { severity, .. } => severity_rank(severity) >= severity_rank(&cfg.min_severity),
+            _ => true,
+        }
+    }
+
+    async fn load_config(&self) -> Option<SmtpConfig> {
+        let cfg = self.repo.get_config().await.ok()?.smtp_config();
+        cfg.is_configured().then_some(cfg)
+    }
+
+    async fn deliver(&self, cfg: &SmtpConfig, subject: &str, body: &str) {
+        if let Err(e) = Self::send(cfg, subject, body).await {
+            let msg = format!("Failed to send notification email: {}", e);
+            tracing::warn!("{}", msg);
+            let _ = self.repo.add_log("WARN", THIS_SERVICE, None, None, &msg).await;
+        }
+    }
+
+    /// Send a single plain-text email through the configured relay.
+    pub async fn send(cfg: &SmtpConfig, subject: &str, body: &str) -> Result<(), String> {
+        let from: Mailbox = cfg.from.parse().map_err(|e| format!("Invalid from address: {}", e))?;
+        let mut builder = Message::builder().from(from).subject(subject);
+        for to in &cfg.to {
+            let mailbox: Mailbox = to.parse().map_err(|e| format!("Invalid recipient {}: {}", to, e))?;
+            builder = builder.to(mailbox);
+        }
+        let email = builder
+            .body(body.to_string())
+            .map_err(|e| format!("Failed to build email: {}", e))?;
+
+        let mut transport = if cfg.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.host)
+                .map_err(|e| format!("Invalid SMTP relay {}: {}", cfg.host, e))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host)
+        }
+        .port(cfg.port)
+        .timeout(Some(Duration::from_secs(10)));
+
+        if let (Some(user), Some(pass)) = (&cfg.username, &cfg.password) {
+            transport = transport.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        transport
+            .build()
+            .send(email)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP delivery failed: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_hosts_and_vulnerabilities_are_significant() {
+        assert_eq!(
+            EmailNotifier::describe(&WsEvent::NewHost { ip: "192.168.1.20".into() }).as_deref(),
+            Some("New host discovered: 192.168.1.20")
+        );
+        assert!(EmailNotifier::describe(&WsEvent::HostFound { ip: "192.168.1.20".into() }).is_none());
+        assert!(EmailNotifier::describe(&WsEvent::JobCompleted { job_id: "abc".into() }).is_none());
+
+        let vuln = WsEvent::VulnerabilityFound {
+            ip: "192.168.1.20".into(),
+            id: "CVE-2016-6210".into(),
+            severity: "HIGH".into(),
+            description: "OpenSSH user enumeration".into(),
+        };
+        assert_eq!(
+            EmailNotifier::describe(&vuln).as_deref(),
+            Some("New HIGH vulnerability on 192.168.1.20: CVE-2016-6210 — OpenSSH user enumeration")
+        );
+    }
+
+    #[test]
+    fn vulnerabilities_below_min_severity_are_dropped() {
+        let vuln = |severity: &str| WsEvent::VulnerabilityFound {
+            ip: "192.168.1.20".into(),
+            id: "CVE-1".into(),
+            severity: severity.into(),
+            description: String::new(),
+        };
+        let cfg = SmtpConfig::default();
+        assert!(EmailNotifier::severe_enough(&vuln("CRITICAL"), &cfg));
+        assert!(EmailNotifier::severe_enough(&vuln("HIGH"), &cfg));
+        assert!(!EmailNotifier::severe_enough(&vuln("MEDIUM"), &cfg));
+        assert!(!EmailNotifier::severe_enough(&vuln("UNKNOWN"), &cfg));
+        assert!(EmailNotifier::severe_enough(&WsEvent::NewHost { ip: "192.168.1.20".into() }, &cfg));
+    }
+}
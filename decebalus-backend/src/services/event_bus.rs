@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::SendError};
+use crate::models::WsEvent;
+
+/// Events kept for clients that reconnect.
+pub const HISTORY_CAPACITY: usize = 500;
+
+/// App-wide event broadcaster that also remembers the last
+/// [`HISTORY_CAPACITY`] events, numbered from 1, so WebSocket and SSE clients
+/// can catch up on what they missed.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<WsEvent>,
+    history: Arc<Mutex<History>>,
+}
+
+#[derive(Default)]
+struct History {
+    events: VecDeque<(u64, WsEvent)>,
+    /// Sequence number the next event gets.
+    next_seq: u64,
+}
+
+/// Buffered events followed by a live subscription, see [`EventBus::subscribe_since`].
+pub struct Replay {
+    /// Buffered events after the requested sequence number, oldest first.
+    pub events: Vec<(u64, WsEvent)>,
+    pub rx: broadcast::Receiver<WsEvent>,
+    /// Sequence number of the first event `rx` will receive.
+    pub next_seq: u64,
+}
+
+impl EventBus {
+    /// Bus whose live channel holds up to `capacity` events per slow subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _rx) = broadcast::channel(capacity);
+        let history = History { events: VecDeque::new(), next_seq: 1 };
+        Self { tx, history: Arc::new(Mutex::new(history)) }
+    }
+
+    /// Record `event` and send it to live subscribers. Like
+    /// `broadcast::Sender::send`, fails only when nobody is subscribed; the
+    /// event is still kept for replay then.
+    pub fn send(&self, event: WsEvent) -> Result<usize, SendError<WsEvent>> {
+        // Sending under the lock keeps the live order the same as the sequence
+        let mut history = self.history.lock().unwrap();
+        let seq = history.next_seq;
+        history.next_seq += 1;
+        if history.events.len() == HISTORY_CAPACITY {
+            history.events.pop_front();
+        }
+        history.events.push_back((seq, event.clone()));
+        self.tx.send(event)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WsEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    /// Buffered events numbered after `since` (all of them if `None`) and a
+    /// subscription that picks up right after the last one, with no gap or
+    /// repeat between the two.
+    pub fn subscribe_since(&self, since: Option<u64>) -> Replay {
+        let history = self.history.lock().unwrap();
+        let since = since.unwrap_or(0);
+        Replay {
+            events: history.events.iter().filter(|(seq, _)| *seq > since).cloned().collect(),
+            rx: self.tx.subscribe(),
+            next_seq: history.next_seq,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(n: u64) -> WsEvent {
+        WsEvent::log(format!("event {}", n))
+    }
+
+    #[test]
+    fn events_are_kept_without_subscribers() {
+        let bus = EventBus::new(8);
+        assert!(bus.send(log(1)).is_err());
+
+        let replay = bus.subscribe_since(None);
+        assert_eq!(replay.events, [(1, log(1))]);
+        assert_eq!(replay.next_seq, 2);
+    }
+
+    #[test]
+    fn history_is_bounded_and_filtered_by_sequence() {
+        let bus = EventBus::new(8);
+        for n in 1..=HISTORY_CAPACITY as u64 + 10 {
+            let _ = bus.send(log(n));
+        }
+
+        let all = bus.subscribe_since(None).events;
+        assert_eq!(all.len(), HISTORY_CAPACITY);
+        assert_eq!(all[0], (11, log(11)));
+
+        let recent = bus.subscribe_since(Some(HISTORY_CAPACITY as u64 + 8)).events;
+        let seqs: Vec<u64> = recent.iter().map(|(seq, _)| *seq).collect();
+        assert_eq!(seqs, [HISTORY_CAPACITY as u64 + 9, HISTORY_CAPACITY as u64 + 10]);
+    }
+
+    #[tokio::test]
+    async fn live_events_continue_the_replayed_sequence() {
+        let bus = EventBus::new(8);
+        let _ = bus.send(log(1));
+
+        let mut replay = bus.subscribe_since(Some(0));
+        let _ = bus.send(log(2));
+
+        assert_eq!(replay.events, [(1, log(1))]);
+        assert_eq!(replay.next_seq, 2);
+        assert_eq!(replay.rx.recv().await.unwrap(), log(2));
+    }
+}
@@ -0,0 +1,277 @@
+//! File formats for the `export` job.
+
+use std::io;
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::Writer;
+use crate::models::{Host, HostStatus};
+use crate::services::fingerprint;
+
+/// Output format of an export, from the job's `format` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// Hosts and jobs, as stored
+    #[default]
+    Json,
+    /// One row per host: `ip, hostname, os, open_ports, last_seen`
+    Csv,
+    /// Hosts and their ports as an nmap `-oX` report
+    Xml,
+}
+
+impl ExportFormat {
+    pub const NAMES: &[&str] = &["json", "csv", "xml"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "xml" | "nmap" => Ok(Self::Xml),
+            other => Err(format!("Unknown export format '{}' (expected one of: {})", other, Self::NAMES.join(", "))),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Xml => "xml",
+        }
+    }
+}
+
+/// Hosts flattened to CSV with a header row. `open_ports` lists
+/// `<number>/<protocol>` entries separated by `;`, e.g. `22/tcp;53/udp`.
+pub fn hosts_csv(hosts: &[Host]) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["ip", "hostname", "os", "open_ports", "last_seen"])?;
+
+    for host in hosts {
+        let open_ports = host
+            .ports
+            .iter()
+            .filter(|p| p.status == "open")
+            .map(|p| format!("{}/{}", p.number, p.protocol))
+            .collect::<Vec<_>>()
+            .join(";");
+        writer.write_record([
+            host.ip.as_str(),
+            host.hostname.as_deref().unwrap_or_default(),
+            host.os.as_deref().unwrap_or_default(),
+            open_ports.as_str(),
+            host.last_seen.as_str(),
+        ])?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8(bytes).expect("CSV built from strings is UTF-8"))
+}
+
+/// Hosts as an nmap XML report (`nmap -oX`), for tools that import nmap
+/// results. Only what we store is filled in: address, MAC, hostname, status,
+/// ports with their service and CPE, and the OS guess. Hosts without ports
+/// get an empty `<ports/>`.
+pub fn hosts_nmap_xml(hosts: &[Host], exported_at: DateTime<Utc>) -> io::Result<String> {
+    let time = exported_at.timestamp().to_string();
+    let timestr = exported_at.to_rfc2822();
+    let up = hosts.iter().filter(|h| matches!(h.status, HostStatus::Up)).count();
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer.write_event(Event::DocType(BytesText::from_escaped("nmaprun")))?;
+    writer
+        .create_element("nmaprun")
+        .with_attributes([
+            ("scanner", "nmap"),
+            ("args", "decebalus export"),
+            ("start", time.as_str()),
+            ("startstr", timestr.as_str()),
+            ("version", env!("CARGO_PKG_VERSION")),
+            ("xmloutputversion", "1.05"),
+        ])
+        .write_inner_content(|w| {
+            for host in hosts {
+                write_host(w, host)?;
+            }
+            w.create_element("runstats").write_inner_content(|w| {
+                w.create_element("finished")
+                    .with_attributes([
+                        ("time", time.as_str()),
+                        ("timestr", timestr.as_str()),
+                        ("elapsed", "0"),
+                        ("exit", "success"),
+                    ])
+                    .write_empty()?;
+                w.create_element("hosts")
+                    .with_attributes([
+                        ("up", up.to_string().as_str()),
+                        ("down", (hosts.len() - up).to_string().as_str()),
+                        ("total", hosts.len().to_string().as_str()),
+                    ])
+                    .write_empty()?;
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+
+    let mut xml = String::from_utf8(writer.into_inner()).expect("XML built from strings is UTF-8");
+    xml.push('\n');
+    Ok(xml)
+}
+
+fn write_host<W: io::Write>(w: &mut Writer<W>, host: &Host) -> io::Result<()> {
+    let state = match host.status {
+        HostStatus::Up => "up",
+        HostStatus::Down => "down",
+        HostStatus::Unknown => "unknown",
+    };
+    let addrtype = if host.ip.contains(':') { "ipv6" } else { "ipv4" };
+
+    w.create_element("host").write_inner_content(|w| {
+        w.create_element("status")
+            .with_attributes([("state", state), ("reason", "unknown"), ("reason_ttl", "0")])
+            .write_empty()?;
+        w.create_element("address")
+            .with_attributes([("addr", host.ip.as_str()), ("addrtype", addrtype)])
+            .write_empty()?;
+        if let Some(mac) = &host.mac_address {
+            // nmap writes MACs in uppercase
+            w.create_element("address")
+                .with_attributes([("addr", mac.to_uppercase().as_str()), ("addrtype", "mac")])
+                .write_empty()?;
+        }
+
+        let hostnames = w.create_element("hostnames");
+        match &host.hostname {
+            Some(name) => hostnames.write_inner_content(|w| {
+                w.create_element("hostname")
+                    .with_attributes([("name", name.as_str()), ("type", "PTR")])
+                    .write_empty()?;
+                Ok(())
+            })?,
+            None => hostnames.write_empty()?,
+        };
+
+        let ports = w.create_element("ports");
+        if host.ports.is_empty() {
+            ports.write_empty()?;
+        } else {
+            ports.write_inner_content(|w| {
+                for port in &host.ports {
+                    write_port(w, port)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        if let Some(os) = &host.os {
+            let name = match &host.os_version {
+                Some(version) => format!("{} {}", os, version),
+                None => os.clone(),
+            };
+            w.create_element("os").write_inner_content(|w| {
+                w.create_element("osmatch")
+                    .with_attributes([("name", name.as_str()), ("accuracy", "100"), ("line", "0")])
+                    .write_empty()?;
+                Ok(())
+            })?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn write_port<W: io::Write>(w: &mut Writer<W>, port: &crate::models::Port) -> io::Result<()> {
+    let portid = port.number.to_string();
+    w.create_element("port")
+        .with_attributes([("protocol", port.protocol.as_str()), ("portid", portid.as_str())])
+        .write_inner_content(|w| {
+            w.create_element("state")
+                .with_attributes([("state", port.status.as_str()), ("reason", "unknown"), ("reason_ttl", "0")])
+                .write_empty()?;
+
+            let Some(name) = &port.service else { return Ok(()) };
+            let mut attrs = vec![("name", name.clone())];
+            match port.version.as_deref().map(|v| (v, fingerprint::split_product_version(v))) {
+                Some((_, Some((product, version)))) => {
+                    attrs.push(("product", product));
+                    attrs.push(("version", version));
+                }
+                Some((text, None)) => attrs.push(("product", text.to_string())),
+                None => {}
+            }
+            attrs.push(("method", "probed".to_string()));
+            attrs.push(("conf", "10".to_string()));
+
+            let service = w
+                .create_element("service")
+                .with_attributes(attrs.iter().map(|(k, v)| (*k, v.as_str())));
+            match &port.cpe {
+                Some(cpe) => service.write_inner_content(|w| {
+                    w.create_element("cpe").write_text_content(BytesText::new(cpe))?;
+                    Ok(())
+                })?,
+                None => service.write_empty()?,
+            };
+            Ok(())
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_format_names() {
+        assert_eq!(ExportFormat::parse("json"), Ok(ExportFormat::Json));
+        assert_eq!(ExportFormat::parse("CSV"), Ok(ExportFormat::Csv));
+        assert_eq!(ExportFormat::parse("nmap"), Ok(ExportFormat::Xml));
+        assert!(ExportFormat::parse("xlsx").unwrap_err().contains("json, csv, xml"));
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_commas() {
+        let mut host = Host::new("10.0.0.1".into());
+        host.os = Some("Linux 4.15 - 5.8, Ubuntu".into());
+        host.add_port(22, "tcp", "open", None, None, None);
+        host.add_port(23, "tcp", "closed", None, None, None);
+        host.add_port(53, "udp", "open", None, None, None);
+
+        let csv = hosts_csv(&[host.clone()]).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("ip,hostname,os,open_ports,last_seen"));
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("10.0.0.1,,\"Linux 4.15 - 5.8, Ubuntu\",22/tcp;53/udp,{}", host.last_seen)
+        );
+    }
+
+    #[test]
+    fn nmap_xml_lists_ports_and_services() {
+        let mut host = Host::new("192.168.1.10".into());
+        host.status = HostStatus::Up;
+        host.hostname = Some("nas.lan".into());
+        host.mac_address = Some("dc:a6:32:1f:90:0b".into());
+        host.os = Some("Linux".into());
+        host.os_version = Some("5.X".into());
+        host.add_port(22, "tcp", "open", Some("ssh".into()), Some("OpenSSH 8.2p1".into()), Some("cpe:/a:openbsd:openssh:8.2p1".into()));
+        host.add_port(80, "tcp", "open", Some("http".into()), Some("lighttpd".into()), None);
+        host.add_port(161, "udp", "open", Some("snmp".into()), None, None);
+
+        let xml = hosts_nmap_xml(&[host, Host::new("192.168.1.11".into())], Utc::now()).unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE nmaprun>\n<nmaprun scanner=\"nmap\""));
+        assert!(xml.contains(r#"<address addr="192.168.1.10" addrtype="ipv4"/>"#));
+        assert!(xml.contains(r#"<address addr="DC:A6:32:1F:90:0B" addrtype="mac"/>"#));
+        assert!(xml.contains(r#"<hostname name="nas.lan" type="PTR"/>"#));
+        assert!(xml.contains(r#"<port protocol="tcp" portid="22">"#));
+        assert!(xml.contains(r#"<service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10">"#));
+        assert!(xml.contains("<cpe>cpe:/a:openbsd:openssh:8.2p1</cpe>"));
+        assert!(xml.contains(r#"<service name="http" product="lighttpd" method="probed" conf="10"/>"#));
+        assert!(xml.contains(r#"<port protocol="udp" portid="161">"#));
+        assert!(xml.contains(r#"<osmatch name="Linux 5.X" accuracy="100" line="0"/>"#));
+        // The second host has no ports
+        assert!(xml.contains("<ports/>"));
+        assert!(xml.contains(r#"<hosts up="1" down="1" total="2"/>"#));
+    }
+}
@@ -0,0 +1,395 @@
+use serde_json::{json, Map, Value};
+use crate::models::Service;
+
+/// Parses a banner for one protocol into a `Service` with structured `details`.
+/// Returns `None` when the banner doesn't look like that protocol after all.
+type BannerParser = fn(u16, &str) -> Option<Service>;
+
+/// Protocols with a dedicated parser, in the order banners are sniffed.
+pub const BANNER_PARSERS: &[(&str, BannerParser)] = &[
+    ("ssh", parse_ssh_banner),
+    ("http", parse_http_banner),
+    ("smtp", parse_smtp_banner),
+    ("ftp", parse_ftp_banner),
+];
+
+/// Fingerprint a service from its banner.
+///
+/// The protocol is inferred from the banner content first and the port second;
+/// if a parser for it is enabled (`enabled = None` means all), it extracts
+/// structured fields. Anything else falls back to a generic description.
+pub fn fingerprint_service(port: u16, banner: &str, enabled: Option<&[String]>) -> Service {
+    let protocol = sniff_protocol(port, banner);
+    let is_enabled = enabled.is_none_or(|list| list.iter().any(|p| p == protocol));
+
+    if is_enabled && let Some(service) = parse_service_from_banner(port, banner) {
+        return service;
+    }
+
+    let first_line = banner.lines().next().map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    match protocol {
+        "pop3" | "imap" => Service::new(protocol, None, first_line),
+        "redis" => Service::new("redis", None, None),
+        // Known protocol whose parser is disabled: keep the generic first-line description
+        "ssh" | "http" | "smtp" | "ftp" => Service::new(&http_name(port, protocol), None, first_line),
+        _ => Service::new(&infer_protocol(port), None, first_line),
+    }
+}
+
+/// Structured `Service` for a banner one of the `BANNER_PARSERS` recognizes,
+/// with the raw banner kept in `details.banner`. `None` for anything else.
+///
+/// `Server: Apache/2.4.41 (Ubuntu)` → http, version `Apache/2.4.41 (Ubuntu)`;
+/// `SSH-2.0-OpenSSH_8.2p1` → ssh, version `OpenSSH 8.2p1`;
+/// `220 mx.example.org ESMTP Postfix` → smtp, version `Postfix`.
+pub fn parse_service_from_banner(port: u16, banner: &str) -> Option<Service> {
+    let protocol = sniff_protocol(port, banner);
+    let (_, parser) = BANNER_PARSERS.iter().find(|(name, _)| *name == protocol)?;
+    let mut service = parser(port, banner)?;
+    if let Some(Value::Object(details)) = service.details.as_mut() {
+        details.insert("banner".into(), json!(banner));
+    }
+    Some(service)
+}
+
+/// Best guess at the protocol behind a banner.
+fn sniff_protocol(port: u16, banner: &str) -> &'static str {
+    if banner.starts_with("SSH-") {
+        return "ssh";
+    }
+    if banner.contains("HTTP/") || banner.to_lowercase().contains("\nserver:") {
+        return "http";
+    }
+    if banner.starts_with("220 ") || banner.starts_with("220-") {
+        return match port {
+            21 | 20 => "ftp",
+            25 | 465 | 587 => "smtp",
+            _ if banner.to_uppercase().contains("SMTP") => "smtp",
+            _ => "ftp",
+        };
+    }
+    if banner.starts_with("+OK") {
+        return "pop3";
+    }
+    if banner.starts_with("* OK") {
+        return "imap";
+    }
+    if banner.starts_with("+PONG") || banner.starts_with("-ERR") {
+        return "redis";
+    }
+    "unknown"
+}
+
+fn http_name(port: u16, protocol: &str) -> String {
+    if protocol == "http" && (port == 443 || port == 8443) {
+        "https".to_string()
+    } else {
+        protocol.to_string()
+    }
+}
+
+/// `SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1`
+/// → `{ protocol_version: "2.0", software: "OpenSSH_8.9p1", comment: "Ubuntu-3ubuntu0.1" }`
+pub fn parse_ssh_banner(_port: u16, banner: &str) -> Option<Service> {
+    let first_line = banner.lines().next()?.trim();
+    let rest = first_line.strip_prefix("SSH-")?;
+    let (protocol_version, ident) = rest.split_once('-')?;
+    let (software, comment) = match ident.split_once(' ') {
+        Some((s, c)) => (s, Some(c.trim()).filter(|c| !c.is_empty())),
+        None => (ident, None),
+    };
+
+    let mut details = Map::new();
+    details.insert("protocol_version".into(), json!(protocol_version));
+    details.insert("software".into(), json!(software));
+    if let Some(comment) = comment {
+        details.insert("comment".into(), json!(comment));
+    }
+
+    let mut service = Service::new("ssh", Some(software.replace('_', " ")), comment.map(str::to_string));
+    service.details = Some(Value::Object(details));
+    Some(service)
+}
+
+/// HTTP response head → `{ http_version, status_code, status, server, powered_by }`
+pub fn parse_http_banner(port: u16, banner: &str) -> Option<Service> {
+    let mut details = Map::new();
+    let mut status = None;
+    let mut server = None;
+
+    for line in banner.lines() {
+        if let Some(rest) = line.strip_prefix("HTTP/")
+            && status.is_none()
+        {
+            let mut parts = rest.splitn(2, ' ');
+            let version = parts.next().unwrap_or_default();
+            let status_line = parts.next().unwrap_or_default().trim().to_string();
+            details.insert("http_version".into(), json!(version));
+            if let Some(code) = status_line.split_whitespace().next().and_then(|c| c.parse::<u16>().ok()) {
+                details.insert("status_code".into(), json!(code));
+            }
+            details.insert("status".into(), json!(status_line));
+            status = Some(status_line);
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "server" if server.is_none() => {
+                details.insert("server".into(), json!(value));
+                // `Apache/2.4.41 (Ubuntu)` → product `Apache`, product_version `2.4.41`
+                let product = value.split_whitespace().next().unwrap_or_default();
+                let (product, product_version) = product.split_once('/').unwrap_or((product, ""));
+                if !product.is_empty() {
+                    details.insert("product".into(), json!(product));
+                }
+                if !product_version.is_empty() {
+                    details.insert("product_version".into(), json!(product_version));
+                }
+                server = Some(value.to_string());
+            }
+            "x-powered-by" => {
+                details.insert("powered_by".into(), json!(value));
+            }
+            _ => {}
+        }
+    }
+
+    if status.is_none() && server.is_none() {
+        return None;
+    }
+
+    let mut service = Service::new(&http_name(port, "http"), server, status);
+    service.details = Some(Value::Object(details));
+    Some(service)
+}
+
+/// `220 mail.example.com ESMTP Postfix (Ubuntu)`
+/// → `{ code: 220, domain: "mail.example.com", greeting: "mail.example.com ESMTP Postfix (Ubuntu)", software: "Postfix" }`
+pub fn parse_smtp_banner(_port: u16, banner: &str) -> Option<Service> {
+    let greeting = greeting_220(banner)?;
+    let domain = greeting.split_whitespace().next().unwrap_or_default();
+
+    let mut details = Map::new();
+    details.insert("code".into(), json!(220));
+    details.insert("domain".into(), json!(domain));
+    details.insert("greeting".into(), json!(greeting));
+    let software = smtp_software(&greeting);
+    if let Some(software) = &software {
+        details.insert("software".into(), json!(software));
+    }
+
+    let mut service = Service::new("smtp", software, Some(greeting));
+    service.details = Some(Value::Object(details));
+    Some(service)
+}
+
+/// `220 (vsFTPd 3.0.3)` → `{ code: 220, greeting: "(vsFTPd 3.0.3)", software: "vsFTPd 3.0.3" }`
+pub fn parse_ftp_banner(_port: u16, banner: &str) -> Option<Service> {
+    let greeting = greeting_220(banner)?;
+
+    let mut details = Map::new();
+    details.insert("code".into(), json!(220));
+    details.insert("greeting".into(), json!(greeting));
+    // Many servers announce themselves in parentheses
+    let software = greeting
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(inner, _)| inner.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(software) = &software {
+        details.insert("software".into(), json!(software));
+    }
+
+    let mut service = Service::new("ftp", software, Some(greeting));
+    service.details = Some(Value::Object(details));
+    Some(service)
+}
+
+/// MTA named after `ESMTP`, with its version when one follows:
+/// `ESMTP Exim 4.94.2 Mon, …` → `Exim 4.94.2`. Shouting words like the
+/// `MAIL` in `Microsoft ESMTP MAIL Service` aren't product names.
+fn smtp_software(greeting: &str) -> Option<String> {
+    let mut words = greeting.split_whitespace().skip_while(|w| *w != "ESMTP").skip(1);
+    let product = words.next().filter(|w| {
+        w.starts_with(|c: char| c.is_ascii_alphabetic()) && w.chars().any(|c| c.is_ascii_lowercase())
+    })?;
+    let version = words
+        .next()
+        .map(|w| w.trim_end_matches([';', ',']))
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_digit()));
+    Some(match version {
+        Some(v) => format!("{} {}", product, v),
+        None => product.to_string(),
+    })
+}
+
+/// Text of all `220` greeting lines, joined.
+fn greeting_220(banner: &str) -> Option<String> {
+    let content: Vec<&str> = banner
+        .lines()
+        .filter(|l| l.starts_with("220"))
+        .map(|l| l.trim_start_matches("220").trim_start_matches('-').trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if content.is_empty() { None } else { Some(content.join(" ")) }
+}
+
+/// Split a detected version string into product and version number.
+/// `None` when there's no version number in it.
+///
+/// `OpenSSH 8.2p1` → (`OpenSSH`, `8.2p1`); `Apache/2.4.41 (Ubuntu)` → (`Apache`, `2.4.41`);
+/// `Apache httpd 2.4.41` → (`Apache httpd`, `2.4.41`).
+pub fn split_product_version(text: &str) -> Option<(String, String)> {
+    let text = text.split(" (").next().unwrap_or(text).trim();
+
+    let (product, version) = match text.rsplit_once(' ') {
+        Some((product, version)) => (product, version.split('/').next().unwrap_or(version)),
+        None => text.split_once('/')?,
+    };
+    let (product, version) = (product.trim(), version.trim());
+    if product.is_empty() || !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((product.to_string(), version.to_string()))
+}
+
+/// Well-known service name for a port.
+pub fn infer_protocol(port: u16) -> String {
+    match port {
+        80 | 8080 | 8000 => "http".to_string(),
+        443 | 8443        => "https".to_string(),
+        22                => "ssh".to_string(),
+        21 | 20           => "ftp".to_string(),
+        25 | 465 | 587    => "smtp".to_string(),
+        110 | 995         => "pop3".to_string(),
+        143 | 993         => "imap".to_string(),
+        3306              => "mysql".to_string(),
+        5432              => "postgresql".to_string(),
+        1433              => "mssql".to_string(),
+        27017             => "mongodb".to_string(),
+        139 | 445 | 135   => "smb".to_string(),
+        3389              => "rdp".to_string(),
+        53                => "dns".to_string(),
+        161               => "snmp".to_string(),
+        1521              => "oracle".to_string(),
+        6379              => "redis".to_string(),
+        9200              => "elasticsearch".to_string(),
+        _                 => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_http_response_into_structured_fields() {
+        // As stored after grab_banner: CRs stripped, lines trimmed
+        let banner = "HTTP/1.1 200 OK\nServer: nginx/1.18.0 (Ubuntu)\nDate: Mon, 01 Jan 2024 00:00:00 GMT\nContent-Type: text/html\nX-Powered-By: PHP/8.1.2";
+        let service = fingerprint_service(80, banner, None);
+
+        assert_eq!(service.name, "http");
+        assert_eq!(service.version.as_deref(), Some("nginx/1.18.0 (Ubuntu)"));
+        assert_eq!(service.description.as_deref(), Some("200 OK"));
+
+        let details = service.details.unwrap();
+        assert_eq!(details["http_version"], "1.1");
+        assert_eq!(details["status_code"], 200);
+        assert_eq!(details["server"], "nginx/1.18.0 (Ubuntu)");
+        assert_eq!(details["powered_by"], "PHP/8.1.2");
+    }
+
+    #[test]
+    fn parses_ssh_banner_into_structured_fields() {
+        let service = fingerprint_service(22, "SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1", None);
+
+        assert_eq!(service.name, "ssh");
+        assert_eq!(service.version.as_deref(), Some("OpenSSH 8.9p1"));
+
+        let details = service.details.unwrap();
+        assert_eq!(details["protocol_version"], "2.0");
+        assert_eq!(details["software"], "OpenSSH_8.9p1");
+        assert_eq!(details["comment"], "Ubuntu-3ubuntu0.1");
+    }
+
+    #[test]
+    fn parses_smtp_greeting_domain() {
+        let service = fingerprint_service(25, "220 mail.example.com ESMTP Postfix (Ubuntu)", None);
+
+        assert_eq!(service.name, "smtp");
+        assert_eq!(service.details.unwrap()["domain"], "mail.example.com");
+    }
+
+    #[test]
+    fn parses_ftp_software() {
+        let service = fingerprint_service(21, "220 (vsFTPd 3.0.3)", None);
+
+        assert_eq!(service.name, "ftp");
+        assert_eq!(service.version.as_deref(), Some("vsFTPd 3.0.3"));
+        assert_eq!(service.details.unwrap()["software"], "vsFTPd 3.0.3");
+    }
+
+    #[test]
+    fn disabled_parser_falls_back_to_plain_description() {
+        let enabled = vec!["ssh".to_string()];
+        let service = fingerprint_service(80, "HTTP/1.1 404 Not Found\nServer: Apache", Some(&enabled));
+
+        assert_eq!(service.name, "http");
+        assert!(service.details.is_none());
+        assert_eq!(service.description.as_deref(), Some("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn parses_real_world_banners() {
+        let apache = parse_service_from_banner(80, "HTTP/1.1 200 OK\nDate: Tue, 14 May 2024 09:12:44 GMT\nServer: Apache/2.4.41 (Ubuntu)\nContent-Type: text/html; charset=UTF-8").unwrap();
+        assert_eq!(apache.name, "http");
+        assert_eq!(apache.version.as_deref(), Some("Apache/2.4.41 (Ubuntu)"));
+        let details = apache.details.unwrap();
+        assert_eq!(details["product"], "Apache");
+        assert_eq!(details["product_version"], "2.4.41");
+
+        let iis = parse_service_from_banner(443, "HTTP/1.1 403 Forbidden\nServer: Microsoft-IIS/10.0").unwrap();
+        assert_eq!(iis.name, "https");
+        assert_eq!(iis.details.unwrap()["product"], "Microsoft-IIS");
+
+        let openssh = parse_service_from_banner(22, "SSH-2.0-OpenSSH_8.2p1 Ubuntu-4ubuntu0.5").unwrap();
+        assert_eq!(openssh.name, "ssh");
+        assert_eq!(openssh.version.as_deref(), Some("OpenSSH 8.2p1"));
+
+        // Banner sniffing beats the port number
+        let dropbear = parse_service_from_banner(2222, "SSH-2.0-dropbear_2020.81").unwrap();
+        assert_eq!(dropbear.name, "ssh");
+        assert_eq!(dropbear.version.as_deref(), Some("dropbear 2020.81"));
+
+        let postfix = parse_service_from_banner(25, "220 mail.example.com ESMTP Postfix (Debian/GNU)").unwrap();
+        assert_eq!(postfix.version.as_deref(), Some("Postfix"));
+
+        let exim = parse_service_from_banner(587, "220 mx.example.org ESMTP Exim 4.94.2 Tue, 14 May 2024 09:12:44 +0000").unwrap();
+        assert_eq!(exim.version.as_deref(), Some("Exim 4.94.2"));
+
+        let sendmail = parse_service_from_banner(25, "220 relay.example.net ESMTP Sendmail 8.15.2/8.15.2; Tue, 14 May 2024 09:12:44 GMT").unwrap();
+        assert_eq!(sendmail.version.as_deref(), Some("Sendmail 8.15.2/8.15.2"));
+
+        let exchange = parse_service_from_banner(25, "220 mail.contoso.com Microsoft ESMTP MAIL Service ready at Tue, 14 May 2024 09:12:44 +0000").unwrap();
+        assert_eq!(exchange.name, "smtp");
+        assert_eq!(exchange.version, None);
+        assert_eq!(exchange.details.unwrap()["domain"], "mail.contoso.com");
+    }
+
+    #[test]
+    fn parsed_service_keeps_the_raw_banner() {
+        let banner = "220-ftp.example.com FTP server ready\n220 (vsFTPd 3.0.5)";
+        let service = parse_service_from_banner(21, banner).unwrap();
+        assert_eq!(service.version.as_deref(), Some("vsFTPd 3.0.5"));
+        assert_eq!(service.details.unwrap()["banner"], banner);
+    }
+
+    #[test]
+    fn unrecognized_banners_parse_to_none() {
+        assert!(parse_service_from_banner(6379, "+PONG").is_none());
+        assert!(parse_service_from_banner(3306, "5.7.33-0ubuntu0.18.04.1").is_none());
+        assert!(parse_service_from_banner(22, "").is_none());
+    }
+}
@@ -0,0 +1,170 @@
+use std::io::Read;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
+use pnet_packet::icmp::echo_reply::EchoReplyPacket;
+use pnet_packet::icmp::echo_request::MutableEchoRequestPacket;
+use pnet_packet::icmp::{checksum, IcmpPacket, IcmpTypes};
+use pnet_packet::ipv4::Ipv4Packet;
+use pnet_packet::Packet;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+
+/// Sequence numbers for outgoing echo requests, so concurrent pings from this
+/// process can tell their replies apart.
+static NEXT_SEQUENCE: AtomicU16 = AtomicU16::new(1);
+
+/// ICMP echo ("ping") used as a discovery liveness check.
+///
+/// Prefers an unprivileged ping socket where the kernel allows one
+/// (`net.ipv4.ping_group_range`), then a raw socket, which needs CAP_NET_RAW.
+pub struct IcmpPinger;
+
+impl IcmpPinger {
+    /// `Ok` if an ICMP socket can be opened, otherwise why not.
+    pub fn check_available() -> Result<(), String> {
+        Self::open().map(|_| ()).map_err(|e| {
+            format!("cannot open an ICMP socket ({}); needs CAP_NET_RAW or net.ipv4.ping_group_range", e)
+        })
+    }
+
+    /// Send one echo request to `ip` and wait up to `timeout` for the reply.
+    /// Any socket error counts as no reply.
+    pub async fn ping(ip: Ipv4Addr, timeout: Duration) -> bool {
+        tokio::task::spawn_blocking(move || Self::ping_blocking(ip, timeout).unwrap_or(false))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Returns the socket and whether received packets start with an IP header
+    /// (raw sockets) or with the ICMP header (ping sockets).
+    fn open() -> std::io::Result<(Socket, bool)> {
+        match Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4)) {
+            Ok(socket) => Ok((socket, false)),
+            Err(_) => Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)).map(|s| (s, true)),
+        }
+    }
+
+    /// TTL of `ip`'s echo reply, for guessing its OS. Only raw sockets see
+    /// the reply's IP header, so without CAP_NET_RAW this is always `None`.
+    pub async fn reply_ttl(ip: Ipv4Addr, timeout: Duration) -> Option<u8> {
+        tokio::task::spawn_blocking(move || {
+            let socket = Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)).ok()?;
+            Self::echo(&socket, true, ip, timeout).ok().flatten().flatten()
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    fn ping_blocking(ip: Ipv4Addr, timeout: Duration) -> std::io::Result<bool> {
+        let (socket, raw) = Self::open()?;
+        Ok(Self::echo(&socket, raw, ip, timeout)?.is_some())
+    }
+
+    /// Send one echo request and wait for the reply. `None` if none came in
+    /// time, otherwise the reply's TTL if the socket shows IP headers.
+    fn echo(socket: &Socket, raw: bool, ip: Ipv4Addr, timeout: Duration) -> std::io::Result<Option<Option<u8>>> {
+        // Connecting filters incoming packets down to replies from `ip`
+        socket.connect(&SockAddr::from(SocketAddrV4::new(ip, 0)))?;
+        let identifier = std::process::id() as u16;
+        let sequence = NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        socket.send(&Self::echo_request(identifier, sequence))?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 1500];
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Ok(None);
+            }
+            socket.set_read_timeout(Some(left))?;
+            let n = match (&*socket).read(&mut buf) {
+                Ok(n) => n,
+                Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            };
+
+            let (icmp, ttl) = if raw {
+                let Some(header) = Ipv4Packet::new(&buf[..n]) else { continue };
+                (&buf[(header.get_header_length() as usize * 4).min(n)..n], Some(header.get_ttl()))
+            } else {
+                (&buf[..n], None)
+            };
+            // Raw sockets also see our own request when pinging a local address,
+            // and every other process's replies from `ip`. Ping sockets get only
+            // their own, with the identifier rewritten by the kernel.
+            let ours = if raw {
+                Self::is_reply_to(icmp, identifier, sequence)
+            } else {
+                IcmpPacket::new(icmp).is_some_and(|p| p.get_icmp_type() == IcmpTypes::EchoReply)
+            };
+            if ours {
+                return Ok(Some(ttl));
+            }
+        }
+    }
+
+    /// Whether `icmp` is the echo reply to our request `identifier`/`sequence`.
+    fn is_reply_to(icmp: &[u8], identifier: u16, sequence: u16) -> bool {
+        EchoReplyPacket::new(icmp).is_some_and(|p| {
+            p.get_icmp_type() == IcmpTypes::EchoReply
+                && p.get_identifier() == identifier
+                && p.get_sequence_number() == sequence
+        })
+    }
+
+    /// 16-byte echo request: 8-byte header plus an 8-byte zero payload.
+    fn echo_request(identifier: u16, sequence: u16) -> [u8; 16] {
+        let mut buf = [0u8; 16];
+        let mut packet = MutableEchoRequestPacket::new(&mut buf).unwrap();
+        packet.set_icmp_type(IcmpTypes::EchoRequest);
+        packet.set_identifier(identifier);
+        packet.set_sequence_number(sequence);
+        let sum = checksum(&IcmpPacket::new(packet.packet()).unwrap());
+        packet.set_checksum(sum);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echo_request_checksum_verifies() {
+        let buf = IcmpPinger::echo_request(0x1234, 7);
+        let packet = IcmpPacket::new(&buf).unwrap();
+        assert_eq!(packet.get_icmp_type(), IcmpTypes::EchoRequest);
+        assert_eq!(checksum(&packet), packet.get_checksum());
+    }
+
+    #[test]
+    fn only_the_matching_echo_reply_is_ours() {
+        let mut reply = IcmpPinger::echo_request(0x1234, 7);
+        reply[0] = IcmpTypes::EchoReply.0;
+        assert!(IcmpPinger::is_reply_to(&reply, 0x1234, 7));
+        assert!(!IcmpPinger::is_reply_to(&reply, 0x4321, 7));
+        assert!(!IcmpPinger::is_reply_to(&reply, 0x1234, 8));
+        // Our own request, seen by a raw socket pinging a local address
+        let request = IcmpPinger::echo_request(0x1234, 7);
+        assert!(!IcmpPinger::is_reply_to(&request, 0x1234, 7));
+    }
+
+    #[tokio::test]
+    async fn loopback_answers_when_icmp_is_permitted() {
+        if IcmpPinger::check_available().is_err() {
+            return;
+        }
+        assert!(IcmpPinger::ping(Ipv4Addr::LOCALHOST, Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test]
+    async fn loopback_reply_carries_a_ttl_when_raw_sockets_are_permitted() {
+        if Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)).is_err() {
+            return;
+        }
+        assert!(IcmpPinger::reply_ttl(Ipv4Addr::LOCALHOST, Duration::from_secs(1)).await.is_some());
+    }
+}
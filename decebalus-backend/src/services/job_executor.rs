@@ -1,11 +1,16 @@
+use std::collections::HashSet;
 use std::sync::Arc;
-use chrono::Utc;
-use tokio::sync::OwnedSemaphorePermit;
+use std::sync::atomic::Ordering;
+use chrono::{DateTime, Utc};
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
 use tokio::time::{Duration, sleep};
-use crate::models::{Job, JobPriority};
+use crate::models::{Job, JobResult, JobStatus, JobsConfig, Vulnerability, WsEvent};
 use crate::state::AppState;
-use crate::services::{scanner, port_scanner};
-use crate::db::repository;
+use crate::services::{export, scanner, port_scanner, subprocess, EventSink, ScanContext, ScanError};
+use crate::services::attacks::{self, ssh_default_creds::SSH_DEFAULT_CREDS_ID, weak_ciphers::{self, WEAK_TLS_ID_PREFIX}};
+use crate::services::export::ExportFormat;
+use crate::services::safe_path::AllowedDirs;
+use crate::db::repository_trait::Repository;
 
 
 /// Job Executor Service
@@ -16,51 +21,75 @@ const THIS_SERVICE: &str = "job_executor";
 impl JobExecutor {
     /// Execute a job based on its type
     /// This runs in a separate tokio task (background worker)
-    pub async fn execute_job(job: Job, state: Arc<AppState>, _permit: OwnedSemaphorePermit) {
-        tracing::info!("Starting job execution: {} (type: {})", &job.id, job.job_type);
-        let _ = repository::add_log(&state.db, "INFO", "scanner", Some("job_executor"), Some(&job.id), "Starting job execution").await;
-        let _ = state.broadcaster.send(format!("Starting job execution: {} (type: {})", &job.id, job.job_type));
-        // Double-check that the job hasn't already been picked up
-        match repository::get_job(&state.db, &job.id).await {
-            Ok(Some(job)) => {
-                if job.is_queued() || job.is_scheduled() {
-                    // Update job status to running
-                    Self::update_job_status(&state, &job.id, "running").await;
-                    // Broadcast that job started
-                    let _ = state.broadcaster.send(format!("job_running:{}", job.id));
-
-                    // Execute based on job type
-                    let result = match job.job_type.as_str() {
-                        "discovery" => Self::run_discovery(&state, &job).await,
-                        "port-scan" => Self::run_port_scan(&state, &job).await,
-                        "nmap-scan" => Self::run_nmap_scan(&state, &job).await,
-                        "export" => Self::run_export(&state, &job).await,
-                        _ => {
-                            tracing::warn!("Unknown job type: {}", job.job_type);
-                            Err(format!("Unknown job type: {}", job.job_type))
-                        }
-                    };
+    pub async fn execute_job(job: Job, state: Arc<AppState>, permit: OwnedSemaphorePermit) {
+        // Claim the job atomically so it can't be picked up twice
+        match state.repo.claim_job(&job.id).await {
+            Ok(Some(job)) => Self::run_claimed_job(job, state, permit).await,
+            Ok(None) => tracing::debug!("Job {} already claimed or no longer runnable", job.id),
+            Err(e) => tracing::error!("Failed to claim job {}: {}", job.id, e),
+        }
+    }
 
-                    // Update job with results
-                    match result {
-                        Ok(results) => {
-                            Self::update_job_status(&state, &job.id, "completed").await;
-                            Self::update_job_results(&state, &job.id, Some(results)).await;
-                            let _ = state.broadcaster.send(format!("job_completed:{}", job.id));
-                            tracing::info!("Job completed successfully: {}", job.id);
-                        }
-                        Err(error) => {
-                            Self::update_job_status(&state, &job.id, "failed").await;
-                            Self::update_job_results(&state, &job.id, Some(error.clone())).await;
-                            let _ = state.broadcaster.send(format!("job_failed:{}:{}", job.id, error));
-                            tracing::error!("Job failed: {} - {}", job.id, error);
-                        }
-                    }
+    /// Run a job that has already been moved to `running` by a claim.
+    async fn run_claimed_job(job: Job, state: Arc<AppState>, _permit: OwnedSemaphorePermit) {
+        tracing::info!("Starting job execution: {} (type: {})", &job.id, job.job_type);
+        let _ = state.repo.add_log("INFO", "scanner", Some("job_executor"), Some(&job.id), "Starting job execution").await;
+        // Broadcast that job started
+        let _ = state.broadcaster.send(WsEvent::JobRunning { job_id: job.id.clone() });
+
+        // Execute based on job type
+        let body = async {
+            match job.job_type.as_str() {
+                "discovery" => Self::run_discovery(&state, &job).await,
+                "port-scan" => Self::run_port_scan(&state, &job).await,
+                "nmap-scan" => Self::run_nmap_scan(&state, &job).await,
+                "vuln-scan" => Self::run_vuln_scan(&state, &job).await,
+                "attack" => Self::run_attack(&state, &job).await,
+                "export" => Self::run_export(&state, &job).await,
+                _ => {
+                    tracing::warn!("Unknown job type: {}", job.job_type);
+                    Err(ScanError::Config(format!("Unknown job type: {}", job.job_type)))
                 }
             }
-            Ok(None) => (),
-            Err(e) => {
-                tracing::error!("Failed to get job: {}", e);
+        };
+
+        // Dropping the body on timeout stops its scans and kills any subprocess
+        let jobs_cfg = state.config.get(state.repo.as_ref()).await.map(|c| c.jobs_config()).unwrap_or_default();
+        let result = match jobs_cfg.timeout() {
+            Some(limit) => tokio::time::timeout(limit, body).await.unwrap_or(Err(ScanError::Timeout(limit))),
+            None => body.await,
+        };
+
+        // A finished job is no longer in any phase, whatever the outcome
+        if let Err(e) = state.repo.update_job_phase(&job.id, None).await {
+            tracing::error!("Failed to clear phase of job {}: {}", job.id, e);
+        }
+
+        // Update job with results
+        match result {
+            Err(ScanError::Cancelled) => {
+                // Status is already `cancelled` (or `failed` by the watchdog); don't overwrite it
+                tracing::info!("Job stopped after cancellation: {}", job.id);
+            }
+            _ if Self::was_stopped(&state, &job.id).await => {
+                // Stopped while finishing, before it checked; keep that status
+                tracing::info!("Job finished after it was stopped: {}", job.id);
+            }
+            Ok(results) => {
+                Self::update_job_status(&state, &job.id, JobStatus::Completed).await;
+                Self::update_job_results(&state, &job.id, Some(JobResult::Success(results))).await;
+                let _ = state.broadcaster.send(WsEvent::JobCompleted { job_id: job.id.clone() });
+                tracing::info!("Job completed successfully: {}", job.id);
+                Self::reschedule_recurring(&state, &job).await;
+            }
+            Err(error) => {
+                tracing::error!("Job failed: {} - {}", job.id, error);
+                if let ScanError::Timeout(_) = error {
+                    let _ = state.repo.add_log("ERROR", THIS_SERVICE, Some("execute_job"), Some(&job.id), &error.to_string()).await;
+                }
+                if !Self::handle_failure(&state, &job, &jobs_cfg, &error).await {
+                    Self::reschedule_recurring(&state, &job).await;
+                }
             }
         }
 
@@ -68,67 +97,246 @@ impl JobExecutor {
         tracing::debug!("Job finished, semaphore slot released: {}", job.id);
     }
 
-    pub async fn run_queue(state: &Arc<AppState>) {
-        let mut jobs = repository::get_queued_jobs(&state.db).await.unwrap_or_default();
+    /// Whether the job left `running` from outside: cancelled, or failed by the watchdog.
+    async fn was_stopped(state: &AppState, job_id: &str) -> bool {
+        matches!(state.repo.get_job(job_id).await, Ok(Some(job)) if job.is_cancelled() || job.is_failed())
+    }
+
+    /// Record a failed run. A retryable error with retries left puts the job back
+    /// on the schedule after an exponential backoff; otherwise it is marked failed
+    /// and `JobFailed` is broadcast. Returns whether a retry was scheduled.
+    async fn handle_failure(state: &Arc<AppState>, job: &Job, cfg: &JobsConfig, error: &ScanError) -> bool {
+        Self::update_job_results(state, &job.id, Some(JobResult::Error(error.to_string()))).await;
+
+        if error.is_retryable() && job.retries < job.max_retries.unwrap_or(cfg.max_retries) {
+            let attempt = job.retries + 1;
+            let run_at = Utc::now() + cfg.retry_delay(attempt);
+            match state.repo.schedule_job_retry(&job.id, attempt, run_at.timestamp()).await {
+                Ok(()) => {
+                    let msg = format!("Retry {} scheduled for {}", attempt, run_at.to_rfc3339());
+                    tracing::info!("{}: {}", msg, job.id);
+                    let _ = state.repo.add_log("WARN", THIS_SERVICE, Some("execute_job"), Some(&job.id), &msg).await;
+                    let _ = state.broadcaster.send(WsEvent::JobRetryScheduled { job_id: job.id.clone(), attempt });
+                    return true;
+                }
+                Err(e) => tracing::error!("Failed to schedule retry for job {}: {}", job.id, e),
+            }
+        }
+
+        let reason = match error {
+            ScanError::Timeout(_) => "timeout".to_string(),
+            e => e.to_string(),
+        };
+        Self::update_job_status(state, &job.id, JobStatus::Failed).await;
+        let _ = state.broadcaster.send(WsEvent::JobFailed { job_id: job.id.clone(), error: reason });
+        false
+    }
 
-        if jobs.is_empty() {
+    /// Put a recurring job back on the schedule for its next cron fire time,
+    /// keeping the results of the run that just finished.
+    async fn reschedule_recurring(state: &Arc<AppState>, job: &Job) {
+        let Some(next) = job.next_run_after(Utc::now()) else { return };
+
+        // Same transition as a retry, with the retry count reset for the next run
+        if let Err(e) = state.repo.schedule_job_retry(&job.id, 0, next).await {
+            tracing::error!("Failed to reschedule recurring job {}: {}", job.id, e);
             return;
         }
+        let msg = format!("Next run scheduled for {} ({})", DateTime::from_timestamp(next, 0).unwrap_or_default().to_rfc3339(), job.schedule.as_deref().unwrap_or_default());
+        tracing::info!("{}: {}", msg, job.id);
+        let _ = state.repo.add_log("INFO", THIS_SERVICE, Some("execute_job"), Some(&job.id), &msg).await;
+        let _ = state.broadcaster.send(WsEvent::JobScheduled { job_id: job.id.clone(), job_type: job.job_type.clone(), scheduled_at: next });
+    }
 
-        jobs.sort_by(|a, b| {
-            fn rank(p: &JobPriority) -> u8 {
-                match p {
-                    JobPriority::LOW => 0,
-                    JobPriority::NORMAL => 1,
-                    JobPriority::HIGH => 2,
-                    JobPriority::CRITICAL => 3,
-                }
+    /// Whether new jobs may start: the queue isn't paused and shutdown hasn't begun.
+    pub fn may_start_jobs(state: &AppState) -> bool {
+        !state.queue_paused.load(Ordering::SeqCst) && state.jobs.is_accepting()
+    }
+
+    /// Dispatch queued jobs, highest priority first, while worker slots are free.
+    /// Each job is claimed atomically, so concurrent passes never start the same job.
+    pub async fn run_queue(state: &Arc<AppState>) {
+        loop {
+            // No new jobs while paused or once shutdown has begun; they stay queued
+            if !Self::may_start_jobs(state) {
+                break;
             }
-            rank(&b.priority).cmp(&rank(&a.priority)) // descending: highest priority first
-        });
 
-        // Spawn jobs up to available permits
-        for job in jobs {
-            let state_clone = state.clone();
-            let job_clone = job.clone();
-            let semaphore = state.semaphore.clone();
+            // Take a slot first so a claimed job always has somewhere to run
+            let Ok(permit) = state.semaphore.clone().try_acquire_owned() else {
+                break;
+            };
 
-            // Try to get a permit — if none available, skip or wait
-            let permit = match semaphore.clone().try_acquire_owned() {
-                Ok(p) => p,
-                Err(_) => {
-                    // No available slot; stop spawning
+            let job = match state.repo.claim_next_job().await {
+                Ok(Some(job)) => job,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("Failed to claim next job: {}", e);
                     break;
                 }
             };
 
-            tokio::spawn(async move {
-                // Run job with a semaphore permit.
-                // Permit is dropped automatically at the end of the async block
-                Self::execute_job(job_clone, state_clone, permit).await;
+            let state_clone = state.clone();
+            state.jobs.spawn(async move {
+                // Permit is dropped automatically when the job finishes
+                Self::run_claimed_job(job, state_clone, permit).await;
             });
         }
-    }    
-    /// Run network discovery
-    async fn run_discovery(state: &Arc<AppState>, job: &Job) -> Result<String, String> {
+    }
+
+    /// Run network discovery. With `auto_port_scan` the job has two phases,
+    /// discovery and queueing the port-scan of what it found.
+    async fn run_discovery(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
         tracing::info!("Running network discovery for job {}", job.id);
-        let target = job.target()?;
+        let mut ctx = ScanContext::from_state(state).await;
+        // The job's own target wins over the configured default
+        let target = job.target()
+            .ok()
+            .or_else(|| ctx.config.target.clone())
+            .ok_or_else(|| ScanError::InvalidTarget("No target in the job and no scan_config.target".to_string()))?;
+
+        let auto_port_scan = job.config.get("auto_port_scan").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if auto_port_scan && ctx.config.stream_port_scan {
+            return Self::run_streaming_discovery(state, job, &target, &mut ctx).await;
+        }
+
+        ctx.set_phase(&job.id, if auto_port_scan { "discovery (1/2)" } else { "discovery (1/1)" }).await;
+        let hosts_found = scanner::NetworkScanner::discover_hosts(&target, &ctx).await?;
 
-        let hosts_found = scanner::NetworkScanner::discover_hosts(&target, state).await?;
+        let port_scan_job = if auto_port_scan && hosts_found > 0 {
+            ctx.set_phase(&job.id, "queueing port-scan (2/2)").await;
+            Self::enqueue_child(state, job, "port-scan", None).await
+        } else {
+            None
+        };
 
         let results = serde_json::json!({
             "job_id": job.id,
             "job_type": "discovery",
             "target_network": target,
             "hosts_found": hosts_found,
+            "port_scan_job_id": port_scan_job,
             "timestamp": chrono::Utc::now().to_rfc3339(),
         });
 
-        Ok(results.to_string())
+        Ok(results)
+    }
+
+    /// Discovery with `scan_config.stream_port_scan`: every `HostFound` event
+    /// queues a single-host port-scan right away, so scanning overlaps discovery.
+    /// If the discovery is cancelled, no more scans are queued and the streamed
+    /// scans that haven't started yet are cancelled with it.
+    async fn run_streaming_discovery(
+        state: &Arc<AppState>,
+        job: &Job,
+        target: &str,
+        ctx: &mut ScanContext,
+    ) -> Result<serde_json::Value, ScanError> {
+        let (found_tx, found_rx) = mpsc::unbounded_channel();
+        let events = ctx.events.clone();
+        ctx.events = EventSink::new(move |event| {
+            if let WsEvent::HostFound { ip } = &event {
+                let _ = found_tx.send(ip.clone());
+            }
+            events.send(event);
+        });
+
+        // Port scans overlap discovery, so there is only the one phase
+        ctx.set_phase(&job.id, "discovery + streamed port-scans (1/1)").await;
+        let streamer = tokio::spawn(Self::stream_port_scans(state.clone(), job.clone(), found_rx));
+        let discovered = scanner::NetworkScanner::discover_hosts(target, ctx).await;
+        // Dropping the sender lets the streamer drain what's left and finish
+        ctx.events = EventSink::noop();
+        let port_scan_jobs = streamer.await.unwrap_or_default();
+
+        if subprocess::is_cancelled(ctx, &job.id).await {
+            for id in &port_scan_jobs {
+                if let Ok(Some(child)) = state.repo.get_job(id).await
+                    && child.is_queued()
+                {
+                    Self::update_job_status(state, id, JobStatus::Cancelled).await;
+                    let _ = state.broadcaster.send(WsEvent::JobCancelled { job_id: id.clone() });
+                }
+            }
+            return Err(ScanError::Cancelled);
+        }
+
+        let results = serde_json::json!({
+            "job_id": job.id,
+            "job_type": "discovery",
+            "target_network": target,
+            "hosts_found": discovered?,
+            "port_scan_job_ids": port_scan_jobs,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+
+        Ok(results)
+    }
+
+    /// Queue one port-scan per discovered IP until `found` closes.
+    /// IPs reported more than once (ARP and TCP overlap) are only scanned once.
+    async fn stream_port_scans(state: Arc<AppState>, parent: Job, mut found: mpsc::UnboundedReceiver<String>) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut queued = Vec::new();
+
+        while let Some(ip) = found.recv().await {
+            if !seen.insert(ip.clone()) {
+                continue;
+            }
+            // Keep draining after a cancel, just stop queueing
+            if Self::was_stopped(&state, &parent.id).await {
+                continue;
+            }
+            if let Some(id) = Self::enqueue_child(&state, &parent, "port-scan", Some(&ip)).await {
+                queued.push(id);
+            }
+        }
+        queued
     }
     
+    /// Queue a follow-up job spawned by `parent` and kick the queue.
+    /// `target` limits the child to a single host.
+    /// Returns the child's id, or `None` if it couldn't be saved.
+    async fn enqueue_child(state: &Arc<AppState>, parent: &Job, job_type: &str, target: Option<&str>) -> Option<String> {
+        let mut child = Job::child_of(parent, job_type.to_string());
+        let mut config = serde_json::Map::new();
+        if let Some(target) = target {
+            config.insert("target".to_string(), target.into());
+        }
+        // A discovery's own port list carries over to the scans it spawns
+        if let Some(ports) = parent.config.get("ports") {
+            config.insert("ports".to_string(), ports.clone());
+        }
+        if !config.is_empty() {
+            child.config = config.into();
+        }
+        if let Err(e) = state.repo.create_job(&child).await {
+            tracing::error!("Failed to queue {} after job {}: {}", job_type, parent.id, e);
+            return None;
+        }
+
+        let msg = format!("Queued {} job {} (spawned by {})", job_type, child.id, parent.id);
+        tracing::info!("{}", msg);
+        let _ = state.repo.add_log("INFO", THIS_SERVICE, None, Some(&parent.id), &msg).await;
+        let _ = state.broadcaster.send(WsEvent::JobQueued { job_id: child.id.clone(), job_type: child.job_type.clone() });
+
+        Self::kick_queue(state.clone());
+
+        Some(child.id)
+    }
+
+    /// Dispatch queued jobs in the background.
+    /// Kept as a plain fn so the spawn isn't part of `execute_job`'s future type,
+    /// which would otherwise be cyclic (run_queue → execute_job → run_queue).
+    fn kick_queue(state: Arc<AppState>) {
+        tokio::spawn(async move {
+            Self::run_queue(&state).await;
+        });
+    }
+
     /// Run port scanning — either a single host (if job.config.target is set) or all hosts.
-    async fn run_port_scan(state: &Arc<AppState>, job: &Job) -> Result<String, String> {
+    async fn run_port_scan(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
         let hosts_to_scan: Vec<String> = match job.target() {
             Ok(ip) => {
                 let msg = format!(
@@ -136,13 +344,11 @@ impl JobExecutor {
                     job.id, ip, state.max_scan_concurrency
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_port_scan"), Some(&job.id), &msg).await;
+                let _ = state.repo.add_log("INFO", "port_scanner", Some("run_port_scan"), Some(&job.id), &msg).await;
                 vec![ip]
             }
             Err(_) => {
-                let hosts = repository::list_hosts(&state.db)
-                    .await
-                    .map_err(|e| format!("Failed to list hosts: {}", e))?;
+                let hosts = state.repo.list_hosts().await?;
                 let ips: Vec<String> = hosts.iter().map(|h| h.ip.clone()).collect();
                 let msg = format!(
                     "[port-scan] Job {} — mode: all hosts | targets: [{}] | concurrency: {}",
@@ -151,24 +357,34 @@ impl JobExecutor {
                     state.max_scan_concurrency
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_port_scan"), Some(&job.id), &msg).await;
+                let _ = state.repo.add_log("INFO", "port_scanner", Some("run_port_scan"), Some(&job.id), &msg).await;
                 ips
             }
         };
 
         if hosts_to_scan.is_empty() {
-            return Err("No hosts to scan. Run discovery first.".to_string());
+            // Nothing to do isn't a failure — complete with an empty result
+            return Ok(Self::no_hosts_result(job, "port-scan"));
         }
 
+        let mut ctx = ScanContext::from_state(state).await;
+        if let Some(ports) = job.ports() {
+            ctx.config.ports = Some(ports);
+        }
         let mut total_ports_found = 0;
 
         for ip in &hosts_to_scan {
-            let open_ports = port_scanner::PortScanner::scan_host(ip, state, &job.id).await?;
+            if subprocess::is_cancelled(&ctx, &job.id).await {
+                return Err(ScanError::Cancelled);
+            }
+            let open_ports = port_scanner::PortScanner::scan_host(ip, &ctx, &job.id).await?;
             total_ports_found += open_ports;
-            let _ = state.broadcaster.send(format!(
-                "scan_progress:{}:{}:{}",
-                job.id, ip, open_ports
-            ));
+            let _ = state.broadcaster.send(WsEvent::HostScanned {
+                job_id: job.id.clone(),
+                ip: ip.clone(),
+                open_ports,
+            });
+            ctx.record_progress(&job.id).await;
         }
 
         let results = serde_json::json!({
@@ -179,20 +395,20 @@ impl JobExecutor {
             "timestamp": chrono::Utc::now().to_rfc3339(),
         });
 
-        Ok(results.to_string())
+        Ok(results)
     }
 
         /// Resume any jobs that were marked as "running" when the app last shut down.
     /// These are treated as interrupted jobs and re-executed.
     pub async fn resume_incomplete_jobs(state: Arc<AppState>) {
         let content = "Checking for unfinished jobs after restart...";
-        if let Err(e) = repository::add_log(&state.db, "INFO", THIS_SERVICE,None, None, content).await {
+        if let Err(e) = state.repo.add_log("INFO", THIS_SERVICE,None, None, content).await {
             tracing::warn!("Failed to persist log: {}", e);
         }
         tracing::info!("{}", content);
 
         // Step 1: fetch jobs that were left in 'running' state
-        let running_jobs = match repository::get_running_jobs(&state.db).await {
+        let running_jobs = match state.repo.get_running_jobs().await {
             Ok(jobs) => jobs,
             Err(e) => {
                 tracing::error!("Failed to load unfinished jobs: {}", e);
@@ -215,19 +431,14 @@ impl JobExecutor {
             // Step 2: acquire a permit before spawning
             match semaphore.clone().try_acquire_owned() {
                 Ok(permit) => {
-                    tokio::spawn(async move {
+                    state.jobs.spawn(async move {
                         tracing::warn!(
                             "Resuming interrupted job: {} (type: {})",
                             job_clone.id,
                             job_clone.job_type
                         );
                         // Mark job back to 'queued' first to ensure clean re-run
-                        if let Err(e) = repository::update_job_status(
-                            &state_clone.db,
-                            &job_clone.id,
-                            "queued",
-                        )
-                        .await
+                        if let Err(e) = state_clone.repo.update_job_status(&job_clone.id, JobStatus::Queued).await
                         {
                             tracing::error!(
                                 "Failed to reset job {} to queued before resuming: {}",
@@ -247,7 +458,7 @@ impl JobExecutor {
                     );
                     // Optional: mark them as queued again, so they'll get picked up later by run_queue()
                     if let Err(e) =
-                        repository::update_job_status(&state.db, &job.id, "queued").await
+                        state.repo.update_job_status(&job.id, JobStatus::Queued).await
                     {
                         tracing::error!(
                             "Failed to mark deferred resumed job {} as queued: {}",
@@ -260,8 +471,21 @@ impl JobExecutor {
         }
     }
     
+    /// Result for a scan job that found no hosts to work on.
+    fn no_hosts_result(job: &Job, job_type: &str) -> serde_json::Value {
+        tracing::info!("Job {} has no hosts to scan", job.id);
+        serde_json::json!({
+            "job_id": job.id,
+            "job_type": job_type,
+            "hosts_scanned": 0,
+            "total_ports_found": 0,
+            "hint": "No hosts to scan. Run discovery first.",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
     /// Run full nmap scan — either a single host or all discovered hosts.
-    async fn run_nmap_scan(state: &Arc<AppState>, job: &Job) -> Result<String, String> {
+    async fn run_nmap_scan(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
         let hosts_to_scan: Vec<String> = match job.target() {
             Ok(ip) => {
                 let msg = format!(
@@ -269,13 +493,11 @@ impl JobExecutor {
                     job.id, ip
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_nmap_scan"), Some(&job.id), &msg).await;
+                let _ = state.repo.add_log("INFO", "port_scanner", Some("run_nmap_scan"), Some(&job.id), &msg).await;
                 vec![ip]
             }
             Err(_) => {
-                let hosts = repository::list_hosts(&state.db)
-                    .await
-                    .map_err(|e| format!("Failed to list hosts: {}", e))?;
+                let hosts = state.repo.list_hosts().await?;
                 let ips: Vec<String> = hosts.iter().map(|h| h.ip.clone()).collect();
                 let msg = format!(
                     "[nmap-scan] Job {} — mode: all hosts | targets: [{}]",
@@ -283,20 +505,23 @@ impl JobExecutor {
                     ips.join(", ")
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_nmap_scan"), Some(&job.id), &msg).await;
+                let _ = state.repo.add_log("INFO", "port_scanner", Some("run_nmap_scan"), Some(&job.id), &msg).await;
                 ips
             }
         };
 
         if hosts_to_scan.is_empty() {
-            return Err("No hosts to scan. Run discovery first.".to_string());
+            // Nothing to do isn't a failure — complete with an empty result
+            return Ok(Self::no_hosts_result(job, "nmap-scan"));
         }
 
+        let ctx = ScanContext::from_state(state).await;
         let mut total_ports_found = 0;
 
         for ip in &hosts_to_scan {
-            let count = port_scanner::PortScanner::full_nmap_scan(ip, state, &job.id).await?;
+            let count = port_scanner::PortScanner::full_nmap_scan(ip, &ctx, &job.id).await?;
             total_ports_found += count;
+            ctx.record_progress(&job.id).await;
         }
 
         let results = serde_json::json!({
@@ -307,82 +532,417 @@ impl JobExecutor {
             "timestamp": chrono::Utc::now().to_rfc3339(),
         });
 
-        Ok(results.to_string())
+        Ok(results)
     }
-    
-    /// Export results to file
-    async fn run_export(state: &Arc<AppState>, _job: &Job) -> Result<String, String> {
+
+    /// Match the detected services of one host (job.config.target) or all hosts
+    /// against known CVEs and store the findings on each host, replacing the
+    /// previous CVE matches. Services without a version are skipped.
+    async fn run_vuln_scan(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
+        let hosts = match job.target() {
+            Ok(ip) => state.repo.get_host(&ip).await?.into_iter().collect(),
+            Err(_) => state.repo.list_hosts().await?,
+        };
+        if hosts.is_empty() {
+            return Ok(Self::no_hosts_result(job, "vuln-scan"));
+        }
+
+        let ctx = ScanContext::from_state(state).await;
+        let hosts_scanned = hosts.len();
+        let mut services_checked = 0;
+        let mut vulnerabilities_found = 0;
+
+        for host in hosts {
+            if subprocess::is_cancelled(&ctx, &job.id).await {
+                return Err(ScanError::Cancelled);
+            }
+
+            let mut found: Vec<Vulnerability> = Vec::new();
+            for service in &host.services {
+                services_checked += 1;
+                // One failed lookup only costs that service's findings
+                let vulns = match state.vulns.for_service(service).await {
+                    Ok(vulns) => vulns,
+                    Err(e) => {
+                        let msg = format!("[vuln-scan] {} — CVE lookup for {} {} failed, skipped: {}", host.ip, service.name, service.version.as_deref().unwrap_or_default(), e);
+                        tracing::warn!("{}", msg);
+                        let _ = state.repo.add_log("WARN", THIS_SERVICE, Some("run_vuln_scan"), Some(&job.id), &msg).await;
+                        continue;
+                    }
+                };
+                for vuln in vulns {
+                    if !found.iter().any(|v| v.id == vuln.id) {
+                        found.push(vuln);
+                    }
+                }
+            }
+
+            let msg = format!("[vuln-scan] {} — {} known CVE(s) across {} service(s)", host.ip, found.len(), host.services.len());
+            tracing::info!("{}", msg);
+            let _ = state.repo.add_log("INFO", THIS_SERVICE, Some("run_vuln_scan"), Some(&job.id), &msg).await;
+
+            vulnerabilities_found += found.len();
+            // Findings of attack jobs stay
+            Self::store_vulnerabilities(state, &host.ip, |v| v.id.starts_with("CVE-"), found).await?;
+            ctx.record_progress(&job.id).await;
+        }
+
+        let results = serde_json::json!({
+            "job_id": job.id,
+            "job_type": "vuln-scan",
+            "hosts_scanned": hosts_scanned,
+            "services_checked": services_checked,
+            "vulnerabilities_found": vulnerabilities_found,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+
+        Ok(results)
+    }
+
+    /// Opt-in active checks against one host (job.config.target) or all hosts,
+    /// refused unless `attacks.enabled` is set. Every open SSH port is tried with
+    /// default credentials and every TLS port is checked for deprecated protocol
+    /// versions and cipher suites. Findings are stored on the host as
+    /// vulnerabilities, replacing the previous results of the same check.
+    async fn run_attack(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
+        let enabled = state.config
+            .get(state.repo.as_ref())
+            .await
+            .map(|c| c.attacks_config().enabled)
+            .unwrap_or(false);
+        if !enabled {
+            return Err(ScanError::Config("Attacks are disabled; set attacks.enabled in the config to allow them".to_string()));
+        }
+
+        let hosts = match job.target() {
+            Ok(ip) => state.repo.get_host(&ip).await?.into_iter().collect(),
+            Err(_) => state.repo.list_hosts().await?,
+        };
+        if hosts.is_empty() {
+            return Ok(Self::no_hosts_result(job, "attack"));
+        }
+
+        let ctx = ScanContext::from_state(state).await;
+        let hosts_scanned = hosts.len();
+        let mut services_checked = 0;
+        let mut vulnerabilities_found = 0;
+
+        for host in hosts {
+            if subprocess::is_cancelled(&ctx, &job.id).await {
+                return Err(ScanError::Cancelled);
+            }
+
+            let ssh_ports: Vec<u16> = host.ports
+                .iter()
+                .filter(|p| p.status == "open" && p.protocol == "tcp" && (p.number == 22 || p.service.as_deref() == Some("ssh")))
+                .map(|p| p.number)
+                .collect();
+            let tls_ports: Vec<u16> = host.ports
+                .iter()
+                .filter(|p| weak_ciphers::is_tls_port(p))
+                .map(|p| p.number)
+                .collect();
+            if ssh_ports.is_empty() && tls_ports.is_empty() {
+                continue;
+            }
+
+            let checks_ssh = !ssh_ports.is_empty();
+            let checks_tls = !tls_ports.is_empty();
+            let mut found = Vec::new();
+            for port in ssh_ports {
+                services_checked += 1;
+                let (severity, msg) = match attacks::ssh_default_creds(&host.ip, port).await {
+                    Ok(Some(vuln)) => {
+                        let msg = format!("[attack] {} — {}", host.ip, vuln.description);
+                        found.push(vuln);
+                        vulnerabilities_found += 1;
+                        ("WARN", msg)
+                    }
+                    Ok(None) => ("INFO", format!("[attack] {}:{} — no default SSH credentials accepted", host.ip, port)),
+                    Err(e) => ("WARN", format!("[attack] {}:{} — SSH credential check failed: {}", host.ip, port, e)),
+                };
+                tracing::info!("{}", msg);
+                let _ = state.repo.add_log(severity, THIS_SERVICE, Some("run_attack"), Some(&job.id), &msg).await;
+            }
+            for port in tls_ports {
+                services_checked += 1;
+                let (severity, msg) = match attacks::weak_ciphers(&host.ip, port).await {
+                    Ok(weak) if weak.is_empty() => ("INFO", format!("[attack] {}:{} — no weak TLS versions or ciphers accepted", host.ip, port)),
+                    Ok(weak) => {
+                        let msg = format!(
+                            "[attack] {}:{} — weak TLS: {}",
+                            host.ip, port, weak.iter().map(|v| v.description.as_str()).collect::<Vec<_>>().join("; ")
+                        );
+                        vulnerabilities_found += weak.len();
+                        found.extend(weak);
+                        ("WARN", msg)
+                    }
+                    Err(e) => ("WARN", format!("[attack] {}:{} — TLS check failed: {}", host.ip, port, e)),
+                };
+                tracing::info!("{}", msg);
+                let _ = state.repo.add_log(severity, THIS_SERVICE, Some("run_attack"), Some(&job.id), &msg).await;
+            }
+            // Only the checks that ran replace their earlier findings
+            let replaces = |v: &Vulnerability| {
+                (checks_ssh && v.id == SSH_DEFAULT_CREDS_ID) || (checks_tls && v.id.starts_with(WEAK_TLS_ID_PREFIX))
+            };
+            Self::store_vulnerabilities(state, &host.ip, replaces, found).await?;
+            ctx.record_progress(&job.id).await;
+        }
+
+        let results = serde_json::json!({
+            "job_id": job.id,
+            "job_type": "attack",
+            "hosts_scanned": hosts_scanned,
+            "services_checked": services_checked,
+            "vulnerabilities_found": vulnerabilities_found,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+
+        Ok(results)
+    }
+
+    /// Replace the vulnerabilities of `ip` that `replaces` matches with `found`
+    /// and announce the new ones. The host is read back first and only its
+    /// vulnerabilities are written, so whatever other jobs saved to it while
+    /// this one ran is kept. A host deleted meanwhile is left deleted.
+    async fn store_vulnerabilities(
+        state: &AppState,
+        ip: &str,
+        replaces: impl Fn(&Vulnerability) -> bool,
+        found: Vec<Vulnerability>,
+    ) -> Result<(), ScanError> {
+        let Some(current) = state.repo.get_host(ip).await? else {
+            return Ok(());
+        };
+        let mut vulnerabilities = current.vulnerabilities.clone();
+        vulnerabilities.retain(|v| !replaces(v));
+        vulnerabilities.extend(found);
+        state.repo.set_host_vulnerabilities(ip, &vulnerabilities).await?;
+        Self::announce_new_vulnerabilities(state, ip, &current.vulnerabilities, &vulnerabilities);
+        Ok(())
+    }
+
+    /// Broadcast a `VulnerabilityFound` for each entry of `after` whose id
+    /// wasn't already among `before`, so repeat scans don't re-announce.
+    fn announce_new_vulnerabilities(state: &AppState, ip: &str, before: &[Vulnerability], after: &[Vulnerability]) {
+        for vuln in after.iter().filter(|v| !before.iter().any(|b| b.id == v.id)) {
+            let _ = state.broadcaster.send(WsEvent::vulnerability_found(ip, vuln));
+        }
+    }
+
+    /// Export to `<output_dir>/export-<job_id>-<rfc3339>.<format>` (`data/exports`
+    /// by default): all hosts and jobs as JSON, or with `job.config.format` one
+    /// CSV row per host (`csv`) or an nmap XML report (`xml`). The job results
+    /// carry the file path.
+    async fn run_export(state: &Arc<AppState>, job: &Job) -> Result<serde_json::Value, ScanError> {
         tracing::info!("Running export");
+        let format = match job.config.get("format").and_then(|f| f.as_str()) {
+            Some(name) => ExportFormat::parse(name).map_err(ScanError::Config)?,
+            None => ExportFormat::default(),
+        };
+
+        // `export.output_dir` comes from user-editable config; keep it inside the allowed dirs
+        let config = state.config.get(state.repo.as_ref()).await?;
+        let requested_dir = config
+            .get("export")
+            .and_then(|e| e.get("output_dir"))
+            .and_then(|d| d.as_str())
+            .unwrap_or("exports")
+            .to_string();
+        let output_dir = AllowedDirs::from_env().resolve(&requested_dir).map_err(ScanError::Config)?;
         
         // Get all data
-        let hosts = repository::list_hosts(&state.db).await
-                .map_err(|e| format!("Failed to list hosts: {}", e))?;
-        let jobs = repository::list_jobs(&state.db).await
-                .map_err(|e| format!("Failed to list jobs: {}", e))?;
+        let hosts = state.repo.list_hosts().await?;
+        let jobs = state.repo.list_jobs().await?;
         
-        let export_data = serde_json::json!({
-            "export_date": chrono::Utc::now().to_rfc3339(),
-            "jobs": jobs,
-            "hosts": hosts,
+        let now = Utc::now();
+        let contents = match format {
+            ExportFormat::Json => serde_json::json!({
+                "export_date": now.to_rfc3339(),
+                "jobs": jobs,
+                "hosts": hosts,
+            })
+            .to_string(),
+            ExportFormat::Csv => export::hosts_csv(&hosts)
+                .map_err(|e| ScanError::io("Failed to build CSV export", e.into()))?,
+            ExportFormat::Xml => export::hosts_nmap_xml(&hosts, now)
+                .map_err(|e| ScanError::io("Failed to build XML export", e))?,
+        };
+        // Only the JSON export carries jobs
+        let jobs_exported = if format == ExportFormat::Json { jobs.len() } else { 0 };
+
+        let path = output_dir.join(format!(
+            "export-{}-{}.{}",
+            job.id,
+            now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            format.extension()
+        ));
+        tokio::fs::create_dir_all(&output_dir)
+            .await
+            .map_err(|e| ScanError::io(format!("Failed to create {}", output_dir.display()), e))?;
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| ScanError::io(format!("Failed to write {}", path.display()), e))?;
+
+        let msg = format!("Exported {} host(s) and {} job(s) to {}", hosts.len(), jobs_exported, path.display());
+        tracing::info!("{}", msg);
+        let _ = state.repo.add_log("INFO", THIS_SERVICE, None, Some(&job.id), &msg).await;
+
+        let results = serde_json::json!({
+            "job_id": job.id,
+            "job_type": "export",
+            "format": format.extension(),
+            "path": path,
+            "hosts_exported": hosts.len(),
+            "jobs_exported": jobs_exported,
+            "timestamp": now.to_rfc3339(),
         });
-        
-        // TODO: Write to file
-        // std::fs::write("data/export.json", export_data.to_string())?;
-        
-        Ok(export_data.to_string())
+
+        Ok(results)
     }
     
-    async fn update_job_status(state: &Arc<AppState>, job_id: &str, status: &str) {
-        if let Err(e) = repository::update_job_status(&state.db, job_id, status).await {
+    async fn update_job_status(state: &Arc<AppState>, job_id: &str, status: JobStatus) {
+        if let Err(e) = state.repo.update_job_status(job_id, status).await {
             tracing::error!("Failed to update job status: {}", e);
         }
     }
 
-    async fn update_job_results(state: &Arc<AppState>, job_id: &str, results: Option<String>) {
-        if let Err(e) = repository::update_job_results(&state.db, job_id, results).await {
+    async fn update_job_results(state: &Arc<AppState>, job_id: &str, results: Option<JobResult>) {
+        if let Err(e) = state.repo.update_job_results(job_id, results).await {
             tracing::error!("Failed to update job results: {}", e);
         }
     }
 
+    /// How long shutdown waits for running jobs: `SHUTDOWN_TIMEOUT_SECS`, default 30.
+    pub fn shutdown_timeout() -> Duration {
+        let secs = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+        Duration::from_secs(secs)
+    }
+
+    /// Stop starting jobs, wait up to `limit` for the running ones, then put
+    /// any job still marked `running` back to `queued` so the next start picks
+    /// it up instead of leaving it stuck.
+    pub async fn shutdown(state: &Arc<AppState>, limit: Duration) {
+        tracing::info!("Waiting up to {:?} for running jobs to finish...", limit);
+        if !state.jobs.shutdown(limit).await {
+            tracing::warn!("Jobs still running after {:?}; stopping them", limit);
+        }
+
+        let running = match state.repo.get_running_jobs().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!("Failed to load running jobs at shutdown: {}", e);
+                return;
+            }
+        };
+        for job in running {
+            match state.repo.update_job_status(&job.id, JobStatus::Queued).await {
+                Ok(()) => {
+                    let msg = format!("Job {} interrupted by shutdown; re-queued", job.id);
+                    tracing::warn!("{}", msg);
+                    let _ = state.repo.add_log("WARN", THIS_SERVICE, Some("shutdown"), Some(&job.id), &msg).await;
+                }
+                Err(e) => tracing::error!("Failed to re-queue job {} at shutdown: {}", job.id, e),
+            }
+        }
+    }
+
+    /// How often the scheduler checks for due jobs: `SCHEDULER_INTERVAL_SECS`, default 30.
+    pub fn scheduler_interval() -> Duration {
+        let secs = std::env::var("SCHEDULER_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&s| s > 0)
+            .unwrap_or(30);
+        Duration::from_secs(secs)
+    }
+
     pub async fn check_and_run_scheduled_jobs(state: Arc<AppState>) {
-        let check_interval = Duration::from_secs(30); // check every 60 seconds
+        let check_interval = Self::scheduler_interval();
         tracing::info!("Scheduler started...");
-
         loop {
-            // Fetch jobs that are scheduled but not yet started and due for execution
-            match repository::get_scheduled_jobs_due(&state.db, Utc::now()).await {
-                Ok(jobs) if !jobs.is_empty() => {
-                    tracing::info!("Found {} scheduled job(s) ready to run", jobs.len());
-
-                    for job in jobs {
-                        let state_clone = Arc::clone(&state);
-
-                        // Acquire a semaphore permit before starting the job
-                        let permit = match state_clone.semaphore.clone().acquire_owned().await {
-                            Ok(p) => p,
-                            Err(e) => {
-                                tracing::error!("Failed to acquire semaphore permit: {}", e);
-                                continue;
-                            }
-                        };
-
-                        // Spawn each job execution in the background
-                        tokio::spawn(async move {
-                            Self::execute_job(job, state_clone, permit).await;
-                        });
-                    }
-                }
-                Ok(_) => {
-                    tracing::debug!("No scheduled jobs ready at this time");
+            Self::run_due_scheduled_jobs(&state).await;
+
+            // Wait before checking again
+            sleep(check_interval).await;
+        }
+    }
+
+    /// One scheduler pass: queue jobs for due schedules and start scheduled
+    /// jobs (one-shot, retries and recurring) whose time has come.
+    pub async fn run_due_scheduled_jobs(state: &Arc<AppState>) {
+        // Turn due schedules into queued jobs
+        match Self::materialize_due_schedules(state.repo.as_ref(), Utc::now()).await {
+            Ok(jobs) if !jobs.is_empty() => {
+                for job in &jobs {
+                    let _ = state.broadcaster.send(WsEvent::JobQueued { job_id: job.id.clone(), job_type: job.job_type.clone() });
                 }
-                Err(e) => {
-                    tracing::error!("Error checking scheduled jobs: {}", e);
+                Self::kick_queue(state.clone());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Error materializing schedules: {}", e),
+        }
+
+        // Due jobs wait, still scheduled, while the queue is paused
+        if !Self::may_start_jobs(state) {
+            return;
+        }
+
+        // Fetch jobs that are scheduled but not yet started and due for execution
+        match state.repo.get_scheduled_jobs_due(Utc::now()).await {
+            Ok(jobs) if !jobs.is_empty() => {
+                tracing::info!("Found {} scheduled job(s) ready to run", jobs.len());
+
+                for job in jobs {
+                    let state_clone = Arc::clone(state);
+
+                    // Acquire a semaphore permit before starting the job
+                    let permit = match state_clone.semaphore.clone().acquire_owned().await {
+                        Ok(p) => p,
+                        Err(e) => {
+                            tracing::error!("Failed to acquire semaphore permit: {}", e);
+                            continue;
+                        }
+                    };
+
+                    // Spawn each job execution in the background
+                    state.jobs.spawn(async move {
+                        Self::execute_job(job, state_clone, permit).await;
+                    });
                 }
             }
+            Ok(_) => {
+                tracing::debug!("No scheduled jobs ready at this time");
+            }
+            Err(e) => {
+                tracing::error!("Error checking scheduled jobs: {}", e);
+            }
+        }
+    }
 
-            // Wait before checking again
-            sleep(check_interval).await;
+    /// Queue one job for every enabled schedule whose next fire time is at or
+    /// before `now`, and record the run. Runs missed while the server was down
+    /// collapse into a single job. Returns the jobs queued.
+    pub async fn materialize_due_schedules(repo: &dyn Repository, now: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        let mut queued = Vec::new();
+        for schedule in repo.list_schedules().await? {
+            if !schedule.is_due(now) {
+                continue;
+            }
+            let job = schedule.to_job();
+            repo.create_job(&job).await?;
+            repo.mark_schedule_run(&schedule.id, now.timestamp()).await?;
+
+            let msg = format!("Queued {} job {} from schedule '{}'", job.job_type, job.id, schedule.name);
+            tracing::info!("{}", msg);
+            let _ = repo.add_log("INFO", THIS_SERVICE, None, Some(&job.id), &msg).await;
+            queued.push(job);
         }
+        Ok(queued)
     }
 
 
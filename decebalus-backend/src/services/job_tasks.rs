@@ -0,0 +1,104 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// In-flight Job Tasks
+/// Every job execution is spawned here rather than with a bare `tokio::spawn`,
+/// so shutdown can stop new jobs from starting and wait for the running ones
+/// instead of dropping them half-way through a DB update.
+pub struct JobTasks {
+    accepting: AtomicBool,
+    set: Mutex<JoinSet<()>>,
+}
+
+impl Default for JobTasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobTasks {
+    pub fn new() -> Self {
+        Self {
+            accepting: AtomicBool::new(true),
+            set: Mutex::new(JoinSet::new()),
+        }
+    }
+
+    /// Whether new jobs may start; false once shutdown has begun.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// Spawn a job execution, reaping tasks that have already finished.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut set = self.set.lock().unwrap();
+        while set.try_join_next().is_some() {}
+        set.spawn(task);
+    }
+
+    /// Number of job tasks that haven't been reaped yet.
+    pub fn len(&self) -> usize {
+        let mut set = self.set.lock().unwrap();
+        while set.try_join_next().is_some() {}
+        set.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stop accepting jobs and wait up to `limit` for the running ones.
+    /// Tasks still going after that are aborted. Returns whether all of them
+    /// finished on their own.
+    pub async fn shutdown(&self, limit: Duration) -> bool {
+        self.accepting.store(false, Ordering::SeqCst);
+        let mut set = std::mem::take(&mut *self.set.lock().unwrap());
+
+        let drained = tokio::time::timeout(limit, async {
+            while set.join_next().await.is_some() {}
+        })
+        .await
+        .is_ok();
+
+        if !drained {
+            set.shutdown().await;
+        }
+        drained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_waits_for_tasks_that_finish_in_time() {
+        let tasks = JobTasks::new();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tasks.spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = tx.send(());
+        });
+
+        assert!(tasks.shutdown(Duration::from_secs(5)).await);
+        assert!(rx.await.is_ok(), "task should have run to completion");
+        assert!(!tasks.is_accepting());
+    }
+
+    #[tokio::test]
+    async fn shutdown_aborts_tasks_past_the_limit() {
+        let tasks = JobTasks::new();
+        tasks.spawn(async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        assert!(!tasks.shutdown(Duration::from_millis(50)).await);
+        assert!(tasks.is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use crate::db::repository_trait::Repository;
+use crate::models::{Job, JobResult, JobStatus, JobsConfig, WsEvent};
+use crate::state::AppState;
+
+const THIS_SERVICE: &str = "job_watchdog";
+
+/// Stuck Job Watchdog
+/// Periodically fails running jobs whose `updated_at` hasn't advanced for
+/// `jobs.stuck_after_secs`, so a hang no timeout caught doesn't leave a job
+/// `running` forever. Running jobs bump `updated_at` only as they make
+/// progress; a failed job's task sees the status like a cancellation and stops.
+pub struct JobWatchdog;
+
+impl JobWatchdog {
+    pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+        tokio::spawn(Self::run(state))
+    }
+
+    async fn run(state: Arc<AppState>) {
+        let repo = state.repo.clone();
+        tracing::info!("Job watchdog started...");
+
+        loop {
+            // Re-read each pass so config edits apply without a restart
+            let cfg = repo.get_config().await.map(|c| c.jobs_config()).unwrap_or_default();
+
+            match Self::fail_stuck(repo.as_ref(), &cfg, Utc::now()).await {
+                Ok(jobs) => {
+                    for (job, reason) in &jobs {
+                        let _ = state.broadcaster.send(WsEvent::JobFailed { job_id: job.id.clone(), error: reason.clone() });
+                    }
+                }
+                Err(e) => tracing::error!("Job watchdog failed: {}", e),
+            }
+
+            tokio::time::sleep(Duration::from_secs(cfg.watchdog_interval_secs.max(1))).await;
+        }
+    }
+
+    /// Mark every running job that hasn't been updated within `stuck_after_secs`
+    /// of `now` as failed. Returns the jobs failed with the reason recorded.
+    pub async fn fail_stuck(repo: &dyn Repository, cfg: &JobsConfig, now: DateTime<Utc>) -> Result<Vec<(Job, String)>, sqlx::Error> {
+        let Some(secs) = cfg.stuck_after_secs else {
+            return Ok(Vec::new());
+        };
+        let cutoff = now - chrono::Duration::seconds(secs as i64);
+
+        let mut failed = Vec::new();
+        for job in repo.get_stale_running_jobs(cutoff).await? {
+            let reason = format!("stuck: no progress for over {}s (watchdog)", secs);
+            repo.update_job_status(&job.id, JobStatus::Failed).await?;
+            repo.update_job_results(&job.id, Some(JobResult::Error(reason.clone()))).await?;
+
+            let msg = format!("Failed stuck {} job {} (phase: {})", job.job_type, job.id, job.phase.as_deref().unwrap_or("-"));
+            tracing::warn!("{}", msg);
+            let _ = repo.add_log("WARN", THIS_SERVICE, None, Some(&job.id), &msg).await;
+            failed.push((job, reason));
+        }
+        Ok(failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::db_repository::DbRepository;
+    use crate::db::inmemory_repository::InMemoryRepository;
+
+    async fn test_repo() -> DbRepository {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        DbRepository::new(pool)
+    }
+
+    async fn running_job(repo: &DbRepository, updated_at: &str) -> Job {
+        let job = Job::new("port-scan".to_string());
+        repo.create_job(&job).await.unwrap();
+        sqlx::query("UPDATE jobs SET status = 'running', updated_at = ?1 WHERE id = ?2")
+            .bind(updated_at)
+            .bind(&job.id)
+            .execute(&repo.pool)
+            .await
+            .unwrap();
+        job
+    }
+
+    #[tokio::test]
+    async fn stale_running_job_is_failed() {
+        let repo = test_repo().await;
+        let now = Utc::now();
+        let fmt = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+        let stuck = running_job(&repo, &fmt(now - chrono::Duration::hours(2))).await;
+        let busy = running_job(&repo, &fmt(now - chrono::Duration::seconds(30))).await;
+        let cfg = JobsConfig { stuck_after_secs: Some(600), ..Default::default() };
+
+        let failed = JobWatchdog::fail_stuck(&repo, &cfg, now).await.unwrap();
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0.id, stuck.id);
+        let stuck = repo.get_job(&stuck.id).await.unwrap().unwrap();
+        assert_eq!(stuck.status, JobStatus::Failed);
+        assert!(stuck.results.unwrap().error().unwrap().contains("watchdog"));
+        assert_eq!(repo.get_job(&busy.id).await.unwrap().unwrap().status, JobStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn in_memory_jobs_go_stale_without_progress() {
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        let mut stuck = Job::new("port-scan".to_string());
+        let mut busy = Job::new("port-scan".to_string());
+        for job in [&mut stuck, &mut busy] {
+            job.status = JobStatus::Running;
+            repo.create_job(job).await.unwrap();
+            repo.set_job_updated_at(&job.id, (now - chrono::Duration::hours(2)).timestamp());
+        }
+        repo.touch_running_job(&busy.id).await.unwrap();
+        let cfg = JobsConfig { stuck_after_secs: Some(600), ..Default::default() };
+
+        let failed = JobWatchdog::fail_stuck(&repo, &cfg, now).await.unwrap();
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0.id, stuck.id);
+        assert_eq!(repo.get_job(&stuck.id).await.unwrap().unwrap().status, JobStatus::Failed);
+        assert_eq!(repo.get_job(&busy.id).await.unwrap().unwrap().status, JobStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn disabled_without_threshold() {
+        let repo = test_repo().await;
+        let job = running_job(&repo, "2000-01-01 00:00:00").await;
+
+        let failed = JobWatchdog::fail_stuck(&repo, &JobsConfig::default(), Utc::now()).await.unwrap();
+
+        assert!(failed.is_empty());
+        assert_eq!(repo.get_job(&job.id).await.unwrap().unwrap().status, JobStatus::Running);
+    }
+}
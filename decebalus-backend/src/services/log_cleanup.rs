@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::db::repository_trait::Repository;
+use crate::models::LogsConfig;
+use crate::state::AppState;
+
+const THIS_SERVICE: &str = "log_cleanup";
+
+/// Retention when neither `logs.retention_days` nor `LOG_RETENTION_DAYS` is set.
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Log Cleanup
+/// Deletes logs older than `logs.retention_days` on startup and then every
+/// `logs.cleanup_interval_secs`, so the logs table doesn't grow forever on a
+/// long-running install.
+pub struct LogCleanup;
+
+impl LogCleanup {
+    pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+        tokio::spawn(Self::run(state))
+    }
+
+    async fn run(state: Arc<AppState>) {
+        let repo = state.repo.clone();
+        let default_days = std::env::var("LOG_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        tracing::info!("Log cleanup started...");
+
+        loop {
+            // Re-read each pass so config edits apply without a restart
+            let cfg = repo.get_config().await.map(|c| c.logs_config()).unwrap_or_default();
+
+            if let Err(e) = Self::prune(repo.as_ref(), &cfg, default_days).await {
+                tracing::error!("Log cleanup failed: {}", e);
+            }
+
+            tokio::time::sleep(Duration::from_secs(cfg.cleanup_interval_secs.max(1))).await;
+        }
+    }
+
+    /// Delete logs older than the configured retention, falling back to
+    /// `default_days`. Returns how many were deleted.
+    pub async fn prune(repo: &dyn Repository, cfg: &LogsConfig, default_days: i64) -> Result<u64, sqlx::Error> {
+        let days = cfg.retention_days_or(default_days);
+        let deleted = repo.cleanup_old_logs(days).await?;
+
+        let msg = format!("Deleted {} log(s) older than {} days", deleted, days);
+        tracing::info!("{}", msg);
+        if deleted > 0 {
+            let _ = repo.add_log("INFO", THIS_SERVICE, None, None, &msg).await;
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::db::inmemory_repository::InMemoryRepository;
+    use crate::models::Log;
+
+    fn log_aged(days: i64, content: &str) -> Log {
+        Log {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: (Utc::now() - chrono::Duration::days(days)).to_rfc3339(),
+            severity: "INFO".into(),
+            service: "test".into(),
+            module: None,
+            job_id: None,
+            content: content.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn prunes_old_logs_and_keeps_recent_ones() {
+        let repo = InMemoryRepository::new();
+        repo.insert_log(log_aged(45, "old"));
+        repo.insert_log(log_aged(31, "just past retention"));
+        repo.insert_log(log_aged(2, "recent"));
+        repo.add_log("INFO", "test", None, None, "now").await.unwrap();
+
+        let deleted = LogCleanup::prune(&repo, &LogsConfig::default(), 30).await.unwrap();
+        assert_eq!(deleted, 2);
+
+        let logs = repo.get_logs().await.unwrap();
+        let contents: Vec<&str> = logs.iter().map(|l| l.content.as_str()).collect();
+        assert!(contents.contains(&"recent"));
+        assert!(contents.contains(&"now"));
+        assert!(!contents.contains(&"old"));
+        assert!(!contents.contains(&"just past retention"));
+        // The run itself is logged
+        assert!(logs.iter().any(|l| l.service == THIS_SERVICE && l.content.starts_with("Deleted 2 log(s)")));
+    }
+
+    #[tokio::test]
+    async fn configured_retention_overrides_the_default() {
+        let repo = InMemoryRepository::new();
+        repo.insert_log(log_aged(10, "ten days"));
+        repo.insert_log(log_aged(3, "three days"));
+
+        let cfg = LogsConfig { retention_days: Some(7), ..Default::default() };
+        assert_eq!(LogCleanup::prune(&repo, &cfg, 30).await.unwrap(), 1);
+
+        let logs = repo.get_logs().await.unwrap();
+        assert!(logs.iter().any(|l| l.content == "three days"));
+        assert!(!logs.iter().any(|l| l.content == "ten days"));
+    }
+
+    #[tokio::test]
+    async fn nothing_to_delete_adds_no_log() {
+        let repo = InMemoryRepository::new();
+        repo.insert_log(log_aged(1, "fresh"));
+
+        assert_eq!(LogCleanup::prune(&repo, &LogsConfig::default(), 30).await.unwrap(), 0);
+        assert_eq!(repo.get_logs().await.unwrap().len(), 1);
+    }
+}
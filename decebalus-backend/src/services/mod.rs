@@ -1,6 +1,32 @@
 pub mod job_executor;
 pub mod scanner;
+pub mod arp_table;
+pub mod icmp;
 pub mod port_scanner;
+pub mod fingerprint;
+pub mod scan_context;
 pub mod attacks;
+pub mod autopilot;
+pub mod ramp_down;
+pub mod rate_limiter;
+pub mod rtt_estimator;
+pub mod email_notifier;
+pub mod notifier;
+pub mod safe_path;
+pub mod display;
+pub mod display_rotator;
+pub mod subprocess;
+pub mod scan_error;
+pub mod rescan_scheduler;
+pub mod job_watchdog;
+pub mod job_tasks;
+pub mod log_cleanup;
+pub mod config_cache;
+pub mod event_bus;
+pub mod vuln_lookup;
+pub mod export;
 
-pub use job_executor::JobExecutor;
\ No newline at end of file
+pub use event_bus::EventBus;
+pub use job_executor::JobExecutor;
+pub use scan_context::{EventSink, ScanContext};
+pub use scan_error::ScanError;
@@ -0,0 +1,182 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use crate::db::repository_trait::Repository;
+use crate::models::{WebhooksConfig, WsEvent};
+use crate::state::AppState;
+
+/// An integration that reacts to broadcaster events (email, webhook, …).
+///
+/// Notifiers are registered once in `AppState::notifiers`; operators pick which
+/// ones run through `integrations.notifiers` in the config.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name used in `integrations.notifiers`.
+    fn name(&self) -> &str;
+
+    async fn notify(&self, event: &WsEvent);
+
+    /// Called periodically so batching notifiers can flush. No-op by default.
+    async fn tick(&self) {}
+}
+
+/// Set of registered notifiers.
+#[derive(Default)]
+pub struct Notifiers {
+    inner: RwLock<Vec<Arc<dyn Notifier>>>,
+}
+
+impl Notifiers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, notifier: Arc<dyn Notifier>) {
+        self.inner.write().unwrap().push(notifier);
+    }
+
+    /// Registered notifiers whose name is in `enabled`.
+    pub fn enabled(&self, enabled: &[String]) -> Vec<Arc<dyn Notifier>> {
+        self.inner
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|n| enabled.iter().any(|e| e == n.name()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Notification Hub
+/// The single broadcaster subscriber that fans events out to the enabled notifiers.
+pub struct NotificationHub;
+
+impl NotificationHub {
+    /// Subscribe to the broadcaster and start dispatching in the background.
+    /// The subscription is taken before returning, so no event sent afterwards is missed.
+    pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+        let rx = state.broadcaster.subscribe();
+        tokio::spawn(Self::run(state, rx))
+    }
+
+    async fn run(state: Arc<AppState>, mut rx: tokio::sync::broadcast::Receiver<WsEvent>) {
+        let mut tick = tokio::time::interval(Duration::from_secs(60));
+
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let event = match msg {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Notification hub lagged, skipped {} events", skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    for notifier in Self::enabled(&state).await {
+                        notifier.notify(&event).await;
+                    }
+                }
+                _ = tick.tick() => {
+                    for notifier in Self::enabled(&state).await {
+                        notifier.tick().await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Enabled notifiers, re-read from config so changes apply without a restart.
+    async fn enabled(state: &Arc<AppState>) -> Vec<Arc<dyn Notifier>> {
+        let enabled = state.repo.get_config()
+            .await
+            .map(|c| c.enabled_notifiers())
+            .unwrap_or_default();
+        state.notifiers.enabled(&enabled)
+    }
+}
+
+/// Notifier that does nothing; handy as a placeholder in config.
+pub struct NoopNotifier;
+
+#[async_trait]
+impl Notifier for NoopNotifier {
+    fn name(&self) -> &str {
+        "noop"
+    }
+
+    async fn notify(&self, _event: &WsEvent) {}
+}
+
+/// POSTs job and host events as JSON to every URL in `webhooks.urls`.
+pub struct WebhookNotifier {
+    repo: Arc<dyn Repository>,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        Self { repo, client: reqwest::Client::new() }
+    }
+
+    /// POST `event` to `url`, retrying up to `cfg.retries` times. Only the last failure is logged.
+    async fn deliver(repo: Arc<dyn Repository>, client: reqwest::Client, url: String, event: WsEvent, cfg: WebhooksConfig) {
+        let mut attempt = 0;
+        loop {
+            let result = client
+                .post(&url)
+                .timeout(Duration::from_secs(cfg.timeout_secs))
+                .json(&event)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+            let Err(e) = result else { return };
+
+            if attempt >= cfg.retries {
+                let msg = format!("Webhook {} failed after {} attempt(s): {}", url, attempt + 1, e);
+                tracing::warn!("{}", msg);
+                let _ = repo.add_log("WARN", "webhook_notifier", None, None, &msg).await;
+                return;
+            }
+            attempt += 1;
+            tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn notify(&self, event: &WsEvent) {
+        // Progress and log chatter would drown the receiving end
+        if matches!(event, WsEvent::ScanProgress { .. } | WsEvent::HostScanned { .. } | WsEvent::Log { .. }) {
+            return;
+        }
+        let Ok(config) = self.repo.get_config().await else { return };
+        let cfg = config.webhooks_config();
+
+        // Deliveries run on their own so a slow or retrying endpoint doesn't hold up the hub
+        for url in &cfg.urls {
+            tokio::spawn(Self::deliver(self.repo.clone(), self.client.clone(), url.clone(), event.clone(), cfg.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_enabled_notifiers_are_returned() {
+        let notifiers = Notifiers::new();
+        notifiers.register(Arc::new(NoopNotifier));
+
+        assert_eq!(notifiers.enabled(&["noop".to_string()]).len(), 1);
+        assert!(notifiers.enabled(&["email".to_string()]).is_empty());
+    }
+}
@@ -1,9 +1,14 @@
-use std::sync::Arc;
-use std::time::Duration;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
 use futures_util::StreamExt;
-use crate::state::AppState;
-use crate::db::repository;
-use crate::models::Service;
+use tokio::sync::Semaphore;
+use crate::services::{fingerprint, subprocess, ScanContext, ScanError};
+use crate::services::autopilot::{AdaptiveLimiter, ProbeOutcome};
+use crate::services::icmp::IcmpPinger;
+use crate::services::ramp_down::RampDown;
+use crate::services::rate_limiter::RateLimiter;
+use crate::services::rtt_estimator::RttEstimator;
+use crate::models::{Service, WsEvent};
 
 /// Intermediate type carrying per-port service info from nmap or banner fallback.
 struct ServiceInfo {
@@ -15,6 +20,7 @@ struct ServiceInfo {
     extra_info: Option<String>,
     tunnel:     Option<String>,    // "ssl" when nmap reports tunnel="ssl"
     cpe:        Option<String>,    // first CPE string for this service
+    details:    Option<serde_json::Value>,  // structured banner fields (fallback only)
 }
 
 struct NmapScanResult {
@@ -38,33 +44,53 @@ struct NmapExtra {
 /// Port Scanner Service
 ///
 /// Scanning pipeline:
-///   1. Fast concurrent TCP connect scan across all 65 535 ports (200 ms timeout).
+///   1. Fast concurrent TCP connect scan across all 65 535 ports, or only those the job or `scan_config.ports` lists (timeout adapted to the observed RTT, starting at 200 ms).
 ///   2. nmap -sV on the confirmed open ports for service/version detection.
 ///   3. If nmap is unavailable, fall back to banner grabbing + heuristic fingerprinting.
+///      Without an OS from nmap, the TTL of an ICMP echo reply hints at one (needs CAP_NET_RAW).
 ///   4. Persist results and update the host record.
 pub struct PortScanner;
 
 impl PortScanner {
     /// Public entry point. Returns the number of open ports found.
-    pub async fn scan_host(ip: &str, state: &Arc<AppState>, job_id: &str) -> Result<usize, String> {
-        let concurrency = state.max_scan_concurrency;
+    /// The wall-clock time of the scan is recorded on the host.
+    pub async fn scan_host(ip: &str, ctx: &ScanContext, job_id: &str) -> Result<usize, ScanError> {
+        let started = Instant::now();
+        let result = Self::run_scan_host(ip, ctx, job_id).await;
+        Self::record_scan_duration(ctx, ip, started.elapsed()).await;
+        result
+    }
+
+    async fn run_scan_host(ip: &str, ctx: &ScanContext, job_id: &str) -> Result<usize, ScanError> {
+        let concurrency = ctx.max_scan_concurrency;
 
         let msg = format!(
-            "[port-scan] Starting scan on {} | ports: 1-65535 | concurrency: {} | method: TCP connect + nmap -sV fallback",
-            ip, concurrency
+            "[port-scan] Starting scan on {} | ports: {} | concurrency: {} | method: TCP connect + nmap -sV fallback",
+            ip, ctx.config.ports_label(), concurrency
         );
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("scan_host"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!("scan_progress:{}:TCP scanning {} (ports 1-65535, {} concurrent)", job_id, ip, concurrency));
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("scan_host"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "TCP scanning {} (ports {}, {} concurrent)",
+            ip, ctx.config.ports_label(), concurrency
+        )).await;
 
         // ── Phase 1: fast TCP connect scan ──────────────────────────────────
-        let open_ports = Self::tcp_scan_concurrent(ip, concurrency).await;
+        ctx.set_phase(job_id, "tcp-scan (1/3)").await;
+        let open_ports = Self::tcp_scan_concurrent(ip, concurrency, ctx).await;
+        // Only a sweep of every port can tell that a port not seen is now closed
+        let full_scan_of = ctx.config.scans_all_ports().then_some("tcp");
 
         if open_ports.is_empty() {
             let msg = format!("[port-scan] {} — TCP scan complete: 0 open ports found", ip);
             tracing::info!("{}", msg);
-            let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("tcp_scan"), Some(job_id), &msg).await;
-            let _ = state.broadcaster.send(format!("scan_progress:{}:TCP scan done — 0 open ports on {}", job_id, ip));
+            let _ = ctx.repo.add_log("INFO", "port_scanner", Some("tcp_scan"), Some(job_id), &msg).await;
+            ctx.report_progress(job_id, format!("TCP scan done — 0 open ports on {}", ip)).await;
+            // Nothing to detect, but a full sweep still closes what was open before
+            if full_scan_of.is_some() {
+                ctx.set_phase(job_id, "saving (3/3)").await;
+                Self::update_host_scan_results(ctx, ip, &[], &[], None, None, None, full_scan_of, None).await;
+            }
             return Ok(0);
         }
 
@@ -82,30 +108,39 @@ impl PortScanner {
             ip, open_ports.len(), ports_display
         );
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("tcp_scan"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!(
-            "scan_progress:{}:TCP scan done — {} open port(s) on {}: [{}]",
-            job_id, open_ports.len(), ip, ports_display
-        ));
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("tcp_scan"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "TCP scan done — {} open port(s) on {}: [{}]",
+            open_ports.len(), ip, ports_display
+        )).await;
 
         // ── Phase 2: service detection ───────────────────────────────────────
-        let (services, os_name, os_version) = Self::detect_services(ip, &open_ports, state, job_id).await;
+        ctx.set_phase(job_id, "service-detection (2/3)").await;
+        // Banner grabs are heavier than port checks, so they get their own, smaller limit
+        let banner_limit = Semaphore::new(ctx.config.banner_concurrency());
+        let (services, os_name, os_version) = Self::detect_services(ip, &open_ports, ctx, job_id, &banner_limit).await;
+        // The host answered on TCP, so it is worth one echo for its TTL
+        let ttl = match (os_name.is_none(), ip.parse::<Ipv4Addr>()) {
+            (true, Ok(addr)) => IcmpPinger::reply_ttl(addr, Self::TTL_PROBE_TIMEOUT).await,
+            _ => None,
+        };
 
         // ── Phase 3: persist ─────────────────────────────────────────────────
-        let _ = state.broadcaster.send(format!("scan_progress:{}:Saving results for {}", job_id, ip));
+        ctx.set_phase(job_id, "saving (3/3)").await;
+        ctx.report_progress(job_id, format!("Saving results for {}", ip)).await;
         let os_override = if os_name.is_some() {
             Some((os_name, os_version))
         } else {
             None
         };
-        Self::update_host_scan_results(state, ip, &open_ports, &services, os_override, None, None).await;
+        Self::update_host_scan_results(ctx, ip, &open_ports, &services, os_override, None, None, full_scan_of, ttl).await;
 
         let msg = format!(
             "[port-scan] {} — scan complete: {} open port(s), {} service(s) identified",
             ip, open_ports.len(), services.len()
         );
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("scan_host"), Some(job_id), &msg).await;
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("scan_host"), Some(job_id), &msg).await;
 
         Ok(open_ports.len())
     }
@@ -120,13 +155,24 @@ impl PortScanner {
     ///   sudo setcap cap_net_raw,cap_net_admin+eip $(which nmap)
     ///
     /// Returns the total number of open TCP + UDP ports found.
-    pub async fn full_nmap_scan(ip: &str, state: &Arc<AppState>, job_id: &str) -> Result<usize, String> {
+    pub async fn full_nmap_scan(ip: &str, ctx: &ScanContext, job_id: &str) -> Result<usize, ScanError> {
+        let started = Instant::now();
+        let result = Self::run_full_nmap_scan(ip, ctx, job_id).await;
+        Self::record_scan_duration(ctx, ip, started.elapsed()).await;
+        result
+    }
+
+    async fn run_full_nmap_scan(ip: &str, ctx: &ScanContext, job_id: &str) -> Result<usize, ScanError> {
         let msg = format!("[nmap-scan] Starting full nmap scan on {}", ip);
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("full_nmap_scan"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!("scan_progress:{}:Full nmap scan starting on {} (TCP all ports + UDP top 200)", job_id, ip));
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("full_nmap_scan"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "Full nmap scan starting on {} (TCP all ports + UDP top 200)",
+            ip
+        )).await;
 
         // ── TCP scan (with OS detection if capabilities allow) ────────────────
+        ctx.set_phase(job_id, "tcp-scan (1/3)").await;
         let NmapScanResult {
             services: tcp_services,
             os_name,
@@ -136,11 +182,12 @@ impl PortScanner {
             hostname,
             scripts,
             os_cpe,
-        } = Self::run_full_nmap(ip, state, job_id).await?;
+        } = Self::run_full_nmap(ip, ctx, job_id).await?;
         let tcp_ports: Vec<u16> = tcp_services.iter().map(|s| s.port).collect();
 
         // ── UDP scan (best-effort, requires CAP_NET_RAW) ──────────────────────
-        let udp_result = Self::run_udp_scan(ip, state, job_id).await;
+        ctx.set_phase(job_id, "udp-scan (2/3)").await;
+        let udp_result = Self::run_udp_scan(ip, ctx, job_id).await;
         let udp_ports: Vec<u16> = udp_result.as_ref()
             .map(|r| r.services.iter().map(|s| s.port).collect())
             .unwrap_or_default();
@@ -153,14 +200,15 @@ impl PortScanner {
             tcp_services.len() + udp_result.as_ref().map(|r| r.services.len()).unwrap_or(0)
         );
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("full_nmap_scan"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!(
-            "scan_progress:{}:nmap done — {} TCP + {} UDP port(s) on {}",
-            job_id, tcp_ports.len(), udp_ports.len(), ip
-        ));
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("full_nmap_scan"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "nmap done — {} TCP + {} UDP port(s) on {}",
+            tcp_ports.len(), udp_ports.len(), ip
+        )).await;
 
         // ── Persist ───────────────────────────────────────────────────────────
-        let _ = state.broadcaster.send(format!("scan_progress:{}:Saving results for {}", job_id, ip));
+        ctx.set_phase(job_id, "saving (3/3)").await;
+        ctx.report_progress(job_id, format!("Saving results for {}", ip)).await;
 
         let os_override = if os_name.is_some() { Some((os_name, os_version)) } else { None };
         let mac_override = mac_address.map(|mac| (mac, mac_vendor));
@@ -169,12 +217,13 @@ impl PortScanner {
         } else {
             None
         };
-        Self::update_host_scan_results(state, ip, &tcp_ports, &tcp_services, os_override, mac_override, nmap_extra).await;
+        // nmap's --host-timeout can cut the scan short, so missing ports aren't closed
+        Self::update_host_scan_results(ctx, ip, &tcp_ports, &tcp_services, os_override, mac_override, nmap_extra, None, None).await;
 
-        if let Some(udp) = udp_result {
-            if !udp_ports.is_empty() {
-                Self::update_host_scan_results(state, ip, &udp_ports, &udp.services, None, None, None).await;
-            }
+        if let Some(udp) = udp_result
+            && !udp_ports.is_empty()
+        {
+            Self::update_host_scan_results(ctx, ip, &udp_ports, &udp.services, None, None, None, None, None).await;
         }
 
         Ok(total)
@@ -184,26 +233,23 @@ impl PortScanner {
     /// Requires root. Invoked via `sudo nmap` — needs NOPASSWD sudoers rule:
     ///   echo "$USER ALL=(root) NOPASSWD: /usr/bin/nmap" | sudo tee /etc/sudoers.d/decebalus-nmap
     /// Returns None gracefully if sudo is not configured or nmap is unavailable.
-    async fn run_udp_scan(ip: &str, state: &Arc<AppState>, job_id: &str) -> Option<NmapScanResult> {
+    async fn run_udp_scan(ip: &str, ctx: &ScanContext, job_id: &str) -> Option<NmapScanResult> {
         let msg = format!("[nmap-scan] {} — running UDP scan via sudo nmap (top 200 ports)", ip);
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!(
-            "scan_progress:{}:Running UDP scan (top 200 ports) on {}",
-            job_id, ip
-        ));
-
-        let output = tokio::process::Command::new("sudo")
-            .args(["/usr/bin/nmap", "-sU", "--top-ports", "200", "--open",
-                   "--max-retries", "1", "--host-timeout", "120s", "-oX", "-", ip])
-            .output()
-            .await;
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!("Running UDP scan (top 200 ports) on {}", ip)).await;
+
+        let mut cmd = tokio::process::Command::new("sudo");
+        cmd.args(["/usr/bin/nmap", "-sU", "--top-ports", "200", "--open",
+                  "--max-retries", "1", "--host-timeout", "120s", "-oX", "-", ip]);
+        let output = subprocess::run_cancellable(cmd, ctx, job_id).await;
 
         match output {
+            Err(ScanError::Cancelled) => None,
             Err(e) => {
                 let msg = format!("[nmap-scan] {} — UDP scan failed to start: {}", ip, e);
                 tracing::warn!("{}", msg);
-                let _ = repository::add_log(&state.db, "WARN", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
+                let _ = ctx.repo.add_log("WARN", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
                 None
             }
             Ok(out) => {
@@ -215,11 +261,11 @@ impl PortScanner {
                         ip
                     );
                     tracing::warn!("{}", msg);
-                    let _ = repository::add_log(&state.db, "WARN", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
-                    let _ = state.broadcaster.send(format!(
-                        "scan_progress:{}:UDP scan unavailable on {} (sudo not configured)",
-                        job_id, ip
-                    ));
+                    let _ = ctx.repo.add_log("WARN", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
+                    ctx.report_progress(job_id, format!(
+                        "UDP scan unavailable on {} (sudo not configured)",
+                        ip
+                    )).await;
                     return None;
                 }
                 if !stderr.trim().is_empty() {
@@ -231,11 +277,11 @@ impl PortScanner {
                     ip, result.services.len()
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
-                let _ = state.broadcaster.send(format!(
-                    "scan_progress:{}:UDP done — {} open port(s) on {}",
-                    job_id, result.services.len(), ip
-                ));
+                let _ = ctx.repo.add_log("INFO", "port_scanner", Some("run_udp_scan"), Some(job_id), &msg).await;
+                ctx.report_progress(job_id, format!(
+                    "UDP done — {} open port(s) on {}",
+                    result.services.len(), ip
+                )).await;
                 Some(result)
             }
         }
@@ -243,15 +289,51 @@ impl PortScanner {
 
     // ── Phase 1 ──────────────────────────────────────────────────────────────
 
-    /// Scan all 65 535 TCP ports concurrently, respecting `max_concurrent`.
-    async fn tcp_scan_concurrent(ip: &str, max_concurrent: usize) -> Vec<u16> {
+    /// Scan `scan_config.ports` (all 65 535 TCP ports by default) concurrently, respecting `max_concurrent`.
+    /// With the autopilot enabled, concurrency backs off while connections are erroring;
+    /// with the ramp-down enabled, it tapers off over the last few percent of ports.
+    /// `scan_config.max_connections_per_second` caps the attempt rate on top of all that.
+    async fn tcp_scan_concurrent(ip: &str, max_concurrent: usize, ctx: &ScanContext) -> Vec<u16> {
         let ip = ip.to_string();
-
-        let mut open_ports: Vec<u16> = futures_util::stream::iter(1u16..=65535)
+        let autopilot = &ctx.config.autopilot;
+        let limiter = autopilot.enabled.then(|| AdaptiveLimiter::new(max_concurrent, autopilot.clone()));
+        let ramp_down = &ctx.config.ramp_down;
+        let ports = ctx.config.tcp_ports();
+        let ramp = ramp_down.enabled.then(|| RampDown::new(ports.len(), max_concurrent, ramp_down));
+        let rtt = RttEstimator::new(Self::PORT_PROBE_TIMEOUT, ctx.config.adaptive_timeout.clone());
+        let rtt = &rtt;
+        let rate = ctx.config.max_connections_per_second.filter(|r| *r > 0.0).map(RateLimiter::new);
+        let rate = rate.as_ref();
+
+        let mut open_ports: Vec<u16> = futures_util::stream::iter(ports)
             .map(|port| {
                 let ip = ip.clone();
+                let limiter = limiter.clone();
+                let ramp = ramp.clone();
                 async move {
-                    if Self::is_port_open(&ip, port).await { Some(port) } else { None }
+                    let _tail_permit = match &ramp {
+                        Some(ramp) => Some(ramp.acquire().await),
+                        None => None,
+                    };
+                    let outcome = match limiter {
+                        Some(limiter) => {
+                            let _permit = limiter.acquire().await;
+                            let delay = limiter.delay();
+                            if !delay.is_zero() {
+                                tokio::time::sleep(delay).await;
+                            }
+                            let outcome = Self::probe_port(&ip, port, rtt, rate).await;
+                            if let Some(limit) = limiter.record(outcome) {
+                                ctx.events.send(WsEvent::log(format!(
+                                    "Scan autopilot adjusted concurrency on {} to {}",
+                                    ip, limit
+                                )));
+                            }
+                            outcome
+                        }
+                        None => Self::probe_port(&ip, port, rtt, rate).await,
+                    };
+                    (outcome == ProbeOutcome::Open).then_some(port)
                 }
             })
             .buffer_unordered(max_concurrent)
@@ -263,22 +345,31 @@ impl PortScanner {
         open_ports
     }
 
-    async fn is_port_open(ip: &str, port: u16) -> bool {
+    /// Starting connect timeout of the TCP scan, before `rtt` has samples.
+    const PORT_PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+    async fn probe_port(ip: &str, port: u16, rtt: &RttEstimator, rate: Option<&RateLimiter>) -> ProbeOutcome {
+        if let Some(rate) = rate {
+            rate.acquire().await;
+        }
         let addr = format!("{}:{}", ip, port);
-        matches!(
-            tokio::time::timeout(
-                Duration::from_millis(200),
-                tokio::net::TcpStream::connect(&addr),
-            )
-            .await,
-            Ok(Ok(_))
-        )
+        let started = Instant::now();
+        let outcome = match tokio::time::timeout(rtt.timeout(), tokio::net::TcpStream::connect(&addr)).await {
+            Ok(Ok(_)) => ProbeOutcome::Open,
+            Ok(Err(e)) => ProbeOutcome::from_io_error(&e),
+            Err(_) => ProbeOutcome::TimedOut,
+        };
+        // Open and closed ports both answered, so both measure the round trip
+        if matches!(outcome, ProbeOutcome::Open | ProbeOutcome::Closed) {
+            rtt.record(started.elapsed());
+        }
+        outcome
     }
 
     // ── Phase 2 ──────────────────────────────────────────────────────────────
 
-    async fn detect_services(ip: &str, open_ports: &[u16], state: &Arc<AppState>, job_id: &str) -> (Vec<ServiceInfo>, Option<String>, Option<String>) {
-        match Self::run_nmap(ip, open_ports, state, job_id).await {
+    async fn detect_services(ip: &str, open_ports: &[u16], ctx: &ScanContext, job_id: &str, banner_limit: &Semaphore) -> (Vec<ServiceInfo>, Option<String>, Option<String>) {
+        match Self::run_nmap(ip, open_ports, ctx, job_id).await {
             Ok(result) if !result.services.is_empty() => {
                 let svc_count = result.services.len();
                 let msg = format!(
@@ -286,11 +377,11 @@ impl PortScanner {
                     ip, svc_count
                 );
                 tracing::info!("{}", msg);
-                let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
-                let _ = state.broadcaster.send(format!(
-                    "scan_progress:{}:nmap done — {} service(s) identified on {}",
-                    job_id, svc_count, ip
-                ));
+                let _ = ctx.repo.add_log("INFO", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
+                ctx.report_progress(job_id, format!(
+                    "nmap done — {} service(s) identified on {}",
+                    svc_count, ip
+                )).await;
                 (result.services, result.os_name, result.os_version)
             }
             Ok(_) => {
@@ -299,25 +390,32 @@ impl PortScanner {
                     ip
                 );
                 tracing::warn!("{}", msg);
-                let _ = repository::add_log(&state.db, "WARN", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
-                let _ = state.broadcaster.send(format!("scan_progress:{}:nmap returned no services for {}, using banner fallback", job_id, ip));
-                (Self::banner_fallback(ip, open_ports).await, None, None)
+                let _ = ctx.repo.add_log("WARN", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
+                ctx.report_progress(job_id, format!(
+                    "nmap returned no services for {}, using banner fallback",
+                    ip
+                )).await;
+                (Self::banner_fallback(ip, open_ports, ctx, banner_limit).await, None, None)
             }
+            Err(ScanError::Cancelled) => (Vec::new(), None, None),
             Err(e) => {
                 let msg = format!(
                     "[port-scan] {} — nmap unavailable ({}); falling back to banner grabbing",
                     ip, e
                 );
                 tracing::warn!("{}", msg);
-                let _ = repository::add_log(&state.db, "WARN", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
-                let _ = state.broadcaster.send(format!("scan_progress:{}:nmap unavailable for {}, using banner fallback", job_id, ip));
-                (Self::banner_fallback(ip, open_ports).await, None, None)
+                let _ = ctx.repo.add_log("WARN", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
+                ctx.report_progress(job_id, format!(
+                    "nmap unavailable for {}, using banner fallback",
+                    ip
+                )).await;
+                (Self::banner_fallback(ip, open_ports, ctx, banner_limit).await, None, None)
             }
         }
     }
 
     /// Shell out to nmap for service/version detection on already-confirmed open ports.
-    async fn run_nmap(ip: &str, open_ports: &[u16], state: &Arc<AppState>, job_id: &str) -> Result<NmapScanResult, String> {
+    async fn run_nmap(ip: &str, open_ports: &[u16], ctx: &ScanContext, job_id: &str) -> Result<NmapScanResult, ScanError> {
         if open_ports.is_empty() {
             return Ok(NmapScanResult { services: vec![], os_name: None, os_version: None, mac_address: None, mac_vendor: None, hostname: None, scripts: vec![], os_cpe: None });
         }
@@ -334,26 +432,32 @@ impl PortScanner {
         );
         let msg = format!("[port-scan] {} — running nmap: `{}`", ip, cmd);
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!("scan_progress:{}:Running nmap -sV on {} port(s) for {}", job_id, open_ports.len(), ip));
-
-        let output = tokio::process::Command::new("nmap")
-            .args([
-                "-sV",
-                "--open",
-                "--max-retries", "1",
-                "--host-timeout", "120s",
-                "-p", &ports_arg,
-                "-oX", "-",
-                ip,
-            ])
-            .output()
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("nmap"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "Running nmap -sV on {} port(s) for {}",
+            open_ports.len(), ip
+        )).await;
+
+        let mut cmd = tokio::process::Command::new("nmap");
+        cmd.args([
+            "-sV",
+            "--open",
+            "--max-retries", "1",
+            "--host-timeout", "120s",
+            "-p", &ports_arg,
+            "-oX", "-",
+            ip,
+        ]);
+        let output = subprocess::run_cancellable(cmd, ctx, job_id)
             .await
-            .map_err(|e| format!("nmap not found or failed to start: {}", e))?;
+            .map_err(|e| match e {
+                ScanError::Cancelled => e,
+                e => ScanError::Nmap(format!("nmap not found or failed to start: {}", e)),
+            })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("nmap exited with error: {}", stderr.trim()));
+            return Err(ScanError::Nmap(format!("nmap exited with error: {}", stderr.trim())));
         }
 
         let xml = String::from_utf8_lossy(&output.stdout);
@@ -363,11 +467,11 @@ impl PortScanner {
     /// Run a full nmap scan (no pre-TCP scan).
     /// Tries with OS detection (-O) first; automatically falls back to service-only if
     /// raw socket access is unavailable (i.e. not root and no CAP_NET_RAW capability).
-    async fn run_full_nmap(ip: &str, state: &Arc<AppState>, job_id: &str) -> Result<NmapScanResult, String> {
+    async fn run_full_nmap(ip: &str, ctx: &ScanContext, job_id: &str) -> Result<NmapScanResult, ScanError> {
         // Attempt 1: with OS detection
-        match Self::run_nmap_cmd(ip, true, state, job_id).await {
+        match Self::run_nmap_cmd(ip, true, ctx, job_id).await {
             Ok(result) => return Ok(result),
-            Err(e) if e.contains("CAP_NET_RAW") || e.contains("root") || e.contains("no output") => {
+            Err(ScanError::Nmap(e)) if e.contains("CAP_NET_RAW") || e.contains("root") || e.contains("no output") => {
                 let msg = format!(
                     "[nmap-scan] {} — OS detection unavailable ({}); retrying without -O. \
                      To enable, add a sudoers rule: \
@@ -375,17 +479,17 @@ impl PortScanner {
                     ip, e
                 );
                 tracing::warn!("{}", msg);
-                let _ = repository::add_log(&state.db, "WARN", "port_scanner", Some("run_full_nmap"), Some(job_id), &msg).await;
-                let _ = state.broadcaster.send(format!(
-                    "scan_progress:{}:OS detection unavailable on {}, continuing with service scan only",
-                    job_id, ip
-                ));
+                let _ = ctx.repo.add_log("WARN", "port_scanner", Some("run_full_nmap"), Some(job_id), &msg).await;
+                ctx.report_progress(job_id, format!(
+                    "OS detection unavailable on {}, continuing with service scan only",
+                    ip
+                )).await;
             }
             Err(e) => return Err(e),
         }
 
         // Attempt 2: without OS detection
-        Self::run_nmap_cmd(ip, false, state, job_id).await
+        Self::run_nmap_cmd(ip, false, ctx, job_id).await
     }
 
     /// Execute nmap and return parsed results.
@@ -393,7 +497,7 @@ impl PortScanner {
     /// When `with_os` is true, nmap is invoked via `sudo` so that OS detection works without
     /// running the backend as root. Requires a NOPASSWD sudoers entry for nmap:
     ///   echo "$USER ALL=(root) NOPASSWD: /usr/bin/nmap" | sudo tee /etc/sudoers.d/decebalus-nmap
-    async fn run_nmap_cmd(ip: &str, with_os: bool, state: &Arc<AppState>, job_id: &str) -> Result<NmapScanResult, String> {
+    async fn run_nmap_cmd(ip: &str, with_os: bool, ctx: &ScanContext, job_id: &str) -> Result<NmapScanResult, ScanError> {
         let os_flags = if with_os { " -O --osscan-guess" } else { "" };
         let sudo_prefix = if with_os { "sudo " } else { "" };
         let cmd_str = format!(
@@ -402,11 +506,11 @@ impl PortScanner {
         );
         let msg = format!("[nmap-scan] {} — running: `{}`", ip, cmd_str);
         tracing::info!("{}", msg);
-        let _ = repository::add_log(&state.db, "INFO", "port_scanner", Some("run_nmap_cmd"), Some(job_id), &msg).await;
-        let _ = state.broadcaster.send(format!(
-            "scan_progress:{}:Running {}nmap{} on all ports for {} (this may take a few minutes)",
-            job_id, sudo_prefix, os_flags, ip
-        ));
+        let _ = ctx.repo.add_log("INFO", "port_scanner", Some("run_nmap_cmd"), Some(job_id), &msg).await;
+        ctx.report_progress(job_id, format!(
+            "Running {}nmap{} on all ports for {} (this may take a few minutes)",
+            sudo_prefix, os_flags, ip
+        )).await;
 
         let nmap_args = {
             let mut v = vec![
@@ -426,30 +530,32 @@ impl PortScanner {
 
         // Privileged scans go through sudo so nmap's root check passes without
         // running the entire backend as root.
-        let output = if with_os {
-            tokio::process::Command::new("sudo")
-                .args(std::iter::once("/usr/bin/nmap").chain(nmap_args))
-                .output()
-                .await
-                .map_err(|e| format!("sudo nmap failed to start: {}", e))?
+        let (cmd, start_error) = if with_os {
+            let mut cmd = tokio::process::Command::new("sudo");
+            cmd.args(std::iter::once("/usr/bin/nmap").chain(nmap_args));
+            (cmd, "sudo nmap failed to start")
         } else {
-            tokio::process::Command::new("nmap")
-                .args(&nmap_args)
-                .output()
-                .await
-                .map_err(|e| format!("nmap not found or failed to start: {}", e))?
+            let mut cmd = tokio::process::Command::new("nmap");
+            cmd.args(&nmap_args);
+            (cmd, "nmap not found or failed to start")
         };
+        let output = subprocess::run_cancellable(cmd, ctx, job_id)
+            .await
+            .map_err(|e| match e {
+                ScanError::Cancelled => e,
+                e => ScanError::Nmap(format!("{}: {}", start_error, e)),
+            })?;
 
         let stderr = String::from_utf8_lossy(&output.stderr);
         if !stderr.trim().is_empty() {
             let msg = format!("[nmap-scan] {} — nmap stderr: {}", ip, stderr.trim());
             tracing::debug!("{}", msg);
-            let _ = repository::add_log(&state.db, "DEBUG", "port_scanner", Some("run_nmap_cmd"), Some(job_id), &msg).await;
+            let _ = ctx.repo.add_log("DEBUG", "port_scanner", Some("run_nmap_cmd"), Some(job_id), &msg).await;
         }
 
         if output.stdout.is_empty() {
             // Propagate stderr so the caller can detect root/CAP issues
-            return Err(format!("no output: {}", stderr.trim()));
+            return Err(ScanError::Nmap(format!("no output: {}", stderr.trim())));
         }
 
         let xml = String::from_utf8_lossy(&output.stdout);
@@ -528,10 +634,19 @@ impl PortScanner {
                                     extra_info,
                                     tunnel,
                                     cpe: None, // filled in when </cpe> is processed
+                                    details: None,
                                 });
                             }
                         }
                         b"os" => { in_os = true; }
+                        // Real nmap output nests <osclass> in <osmatch>, so it arrives as Start
+                        b"osmatch" if in_os => {
+                            let (name, accuracy) = Self::osmatch_attrs(e);
+                            if accuracy > best_os_accuracy {
+                                best_os_accuracy = accuracy;
+                                best_os_name = name;
+                            }
+                        }
                         b"osclass" => { in_osclass = true; }
                         b"cpe" => {
                             collecting_cpe = true;
@@ -585,6 +700,7 @@ impl PortScanner {
                                     extra_info,
                                     tunnel,
                                     cpe: None,
+                                    details: None,
                                 });
                             }
                         }
@@ -609,23 +725,11 @@ impl PortScanner {
                                 }
                             }
                         }
-                        b"osmatch" => {
-                            if in_os {
-                                let mut name: Option<String> = None;
-                                let mut accuracy: u32 = 0;
-                                for attr in e.attributes().flatten() {
-                                    if let Ok(val) = std::str::from_utf8(&attr.value) {
-                                        match attr.key.as_ref() {
-                                            b"name"     => name     = Some(val.to_string()),
-                                            b"accuracy" => accuracy = val.parse().unwrap_or(0),
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                                if accuracy > best_os_accuracy {
-                                    best_os_accuracy = accuracy;
-                                    best_os_name = name;
-                                }
+                        b"osmatch" if in_os => {
+                            let (name, accuracy) = Self::osmatch_attrs(e);
+                            if accuracy > best_os_accuracy {
+                                best_os_accuracy = accuracy;
+                                best_os_name = name;
                             }
                         }
                         b"hostname" => {
@@ -671,10 +775,10 @@ impl PortScanner {
                 }
                 // ── Text content ─────────────────────────────────────────────
                 Ok(Event::Text(ref e)) => {
-                    if collecting_cpe {
-                        if let Ok(text) = std::str::from_utf8(e.as_ref()) {
-                            cpe_buf.push_str(text);
-                        }
+                    if collecting_cpe
+                        && let Ok(text) = std::str::from_utf8(e.as_ref())
+                    {
+                        cpe_buf.push_str(text);
                     }
                 }
                 // ── End elements ─────────────────────────────────────────────
@@ -684,21 +788,19 @@ impl PortScanner {
                         b"os"   => { in_os = false; }
                         b"service"    => { in_service = false; }
                         b"osclass"    => { in_osclass = false; }
-                        b"cpe" => {
-                            if collecting_cpe {
-                                collecting_cpe = false;
-                                let cpe = cpe_buf.trim().to_string();
-                                if !cpe.is_empty() {
-                                    if in_service {
-                                        // Attach to the service we're inside
-                                        if let Some(svc) = services.last_mut() {
-                                            if svc.cpe.is_none() {
-                                                svc.cpe = Some(cpe);
-                                            }
-                                        }
-                                    } else if in_osclass && os_cpe.is_none() {
-                                        os_cpe = Some(cpe);
+                        b"cpe" if collecting_cpe => {
+                            collecting_cpe = false;
+                            let cpe = cpe_buf.trim().to_string();
+                            if !cpe.is_empty() {
+                                if in_service {
+                                    // Attach to the service we're inside
+                                    if let Some(svc) = services.last_mut()
+                                        && svc.cpe.is_none()
+                                    {
+                                        svc.cpe = Some(cpe);
                                     }
+                                } else if in_osclass && os_cpe.is_none() {
+                                    os_cpe = Some(cpe);
                                 }
                             }
                         }
@@ -727,17 +829,37 @@ impl PortScanner {
         NmapScanResult { services, os_name, os_version, mac_address, mac_vendor, hostname, scripts, os_cpe }
     }
 
-    /// Fallback when nmap is unavailable: grab raw banners and fingerprint heuristically.
-    async fn banner_fallback(ip: &str, open_ports: &[u16]) -> Vec<ServiceInfo> {
-        let mut result = Vec::new();
-        for &port in open_ports {
-            let banner  = Self::grab_banner(ip, port).await.unwrap_or_default();
+    /// `name` and `accuracy` of an `<osmatch>` element.
+    fn osmatch_attrs(e: &quick_xml::events::BytesStart) -> (Option<String>, u32) {
+        let mut name: Option<String> = None;
+        let mut accuracy: u32 = 0;
+        for attr in e.attributes().flatten() {
+            if let Ok(val) = std::str::from_utf8(&attr.value) {
+                match attr.key.as_ref() {
+                    b"name"     => name     = Some(val.to_string()),
+                    b"accuracy" => accuracy = val.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+        }
+        (name, accuracy)
+    }
+
+    /// Fallback when nmap is unavailable: grab and fingerprint banners for
+    /// `open_ports`, at most `limit` at a time.
+    async fn banner_fallback(ip: &str, open_ports: &[u16], ctx: &ScanContext, limit: &Semaphore) -> Vec<ServiceInfo> {
+        let enabled = ctx.config.banner_parsers.as_deref();
+        let grabs = open_ports.iter().map(|&port| async move {
+            let banner = {
+                let _permit = limit.acquire().await.unwrap();
+                Self::grab_banner(ip, port).await.unwrap_or_default()
+            };
             let service = if !banner.is_empty() {
-                Self::fingerprint_service(port, &banner)
+                fingerprint::fingerprint_service(port, &banner, enabled)
             } else {
-                Service { name: Self::infer_protocol(port), version: None, description: None }
+                Service::new(&fingerprint::infer_protocol(port), None, None)
             };
-            result.push(ServiceInfo {
+            ServiceInfo {
                 port,
                 protocol:   "tcp".to_string(),
                 name:       service.name,
@@ -746,9 +868,10 @@ impl PortScanner {
                 extra_info: service.description,
                 tunnel:     None,
                 cpe:        None,
-            });
-        }
-        result
+                details:    service.details,
+            }
+        });
+        futures_util::future::join_all(grabs).await
     }
 
     /// Map a plain service name to its SSL/TLS variant when nmap reports tunnel="ssl".
@@ -767,16 +890,19 @@ impl PortScanner {
 
     // ── Phase 3 ──────────────────────────────────────────────────────────────
 
+    #[allow(clippy::too_many_arguments)]
     async fn update_host_scan_results(
-        state:       &Arc<AppState>,
+        ctx:         &ScanContext,
         ip:          &str,
         open_ports:  &[u16],
         services:    &[ServiceInfo],
         os_override:  Option<(Option<String>, Option<String>)>,
         mac_override: Option<(String, Option<String>)>,  // (mac_address, vendor)
         nmap_extra:   Option<NmapExtra>,
+        full_scan_of: Option<&str>,  // protocol whose every port was checked; unseen open ones get closed
+        ttl:          Option<u8>,    // TTL of the host's echo reply, if one could be read
     ) {
-        let mut host = match repository::get_host(&state.db, ip).await {
+        let mut host = match ctx.repo.get_host(ip).await {
             Ok(Some(h)) => h,
             _ => {
                 tracing::warn!("Host {} not found in DB during port scan; skipping save", ip);
@@ -784,6 +910,11 @@ impl PortScanner {
             }
         };
 
+        // Ports that stopped answering since the last full scan
+        if let Some(protocol) = full_scan_of {
+            host.close_unseen_ports(protocol, open_ports);
+        }
+
         // Ports — pass service name, version, and CPE per port.
         // Apply SSL tunnel service name correction (http→https, ftp→ftps, etc.).
         for &port_num in open_ports {
@@ -808,6 +939,7 @@ impl PortScanner {
         }
 
         // Services
+        let seen_at = chrono::Utc::now().to_rfc3339();
         for svc in services {
             let version_str = match (&svc.product, &svc.version) {
                 (Some(p), Some(v)) => Some(format!("{} {}", p, v)),
@@ -819,7 +951,12 @@ impl PortScanner {
                 name:        svc.name.clone(),
                 version:     version_str,
                 description: svc.extra_info.clone(),
+                details:     svc.details.clone(),
             };
+            // Long-term inventory, kept even after the host changes or disappears
+            if let Err(e) = ctx.repo.record_service_seen(&service.name, service.version.as_deref(), &seen_at).await {
+                tracing::warn!("Failed to record {} in service catalog: {}", service.name, e);
+            }
             if !host.services.iter().any(|s| s.name == service.name) {
                 host.services.push(service);
             }
@@ -844,27 +981,29 @@ impl PortScanner {
             if name.is_some() {
                 host.os         = name;
                 host.os_version = ver;
+                host.os_source  = Some("nmap".to_string());
             }
         } else {
             let info_strings: Vec<String> = services.iter()
                 .flat_map(|s| [s.extra_info.clone(), s.version.clone()])
                 .flatten()
                 .collect();
-            let (os, os_version) = Self::detect_os(open_ports, &info_strings);
+            let (os, os_version, source) = Self::detect_os(open_ports, &info_strings, ttl);
             if os.is_some() {
                 host.os         = os;
                 host.os_version = os_version;
+                host.os_source  = source.map(str::to_string);
             }
         }
 
         // MAC address — only set if not already known (discovery may have found it first)
-        if host.mac_address.is_none() {
-            if let Some((mac, vendor)) = mac_override {
-                host.mac_address = Some(mac);
-                // Store vendor in device_type if not already set
-                if host.device_type.is_none() {
-                    host.device_type = vendor;
-                }
+        if host.mac_address.is_none()
+            && let Some((mac, vendor)) = mac_override
+        {
+            host.mac_address = Some(mac);
+            // Store vendor in device_type if not already set
+            if host.device_type.is_none() {
+                host.device_type = vendor;
             }
         }
 
@@ -886,101 +1025,45 @@ impl PortScanner {
 
         host.update_last_seen();
 
-        if let Err(e) = repository::upsert_host(&state.db, &host).await {
+        if let Err(e) = ctx.save_host(&host).await {
             tracing::error!("Failed to update scan results for {}: {}", ip, e);
         }
     }
 
-    // ── Service fingerprinting (banner fallback) ──────────────────────────────
-
-    fn fingerprint_service(port: u16, banner: &str) -> Service {
-        if banner.starts_with("SSH-") {
-            return Self::parse_ssh_banner(banner);
-        }
-        if banner.contains("HTTP/") || banner.to_lowercase().contains("\nserver:") {
-            return Self::parse_http_banner(port, banner);
-        }
-        if banner.starts_with("220 ") || banner.starts_with("220-") {
-            return Self::parse_220_banner(port, banner);
-        }
-        if banner.starts_with("+OK") {
-            let description = banner.lines().next().map(|l| l.trim().to_string());
-            return Service { name: "pop3".to_string(), version: None, description };
-        }
-        if banner.starts_with("* OK") {
-            let description = banner.lines().next().map(|l| l.trim().to_string());
-            return Service { name: "imap".to_string(), version: None, description };
-        }
-        if banner.starts_with("+PONG") || banner.starts_with("-ERR") {
-            return Service { name: "redis".to_string(), version: None, description: None };
-        }
-        let description = banner.lines().next().map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
-        Service { name: Self::infer_protocol(port), version: None, description }
-    }
-
-    fn parse_ssh_banner(banner: &str) -> Service {
-        let first_line  = banner.lines().next().unwrap_or(banner);
-        let after_prefix = first_line.splitn(3, '-').nth(2).unwrap_or(first_line);
-        let (software, comment) = match after_prefix.split_once(' ') {
-            Some((s, c)) => (s, Some(c.trim())),
-            None         => (after_prefix, None),
+    async fn record_scan_duration(ctx: &ScanContext, ip: &str, elapsed: Duration) {
+        let Ok(Some(mut host)) = ctx.repo.get_host(ip).await else {
+            return;
         };
-        Service {
-            name:        "ssh".to_string(),
-            version:     Some(software.replace('_', " ")),
-            description: comment.filter(|s| !s.is_empty()).map(|s| s.to_string()),
+        host.last_scan_duration_ms = Some(elapsed.as_millis() as i64);
+        host.last_port_scan = Some(chrono::Utc::now().to_rfc3339());
+        if let Err(e) = ctx.repo.upsert_host(&host).await {
+            tracing::error!("Failed to record scan duration for {}: {}", ip, e);
         }
     }
 
-    fn parse_http_banner(port: u16, banner: &str) -> Service {
-        let name = if port == 443 || port == 8443 { "https" } else { "http" };
-        let mut server = None;
-        let mut status = None;
-        for line in banner.lines() {
-            if line.starts_with("HTTP/") && status.is_none() {
-                status = line.split_whitespace().skip(1).collect::<Vec<_>>().join(" ").into();
-            }
-            let lower = line.to_lowercase();
-            if lower.starts_with("server:") && server.is_none() {
-                server = Some(line[7..].trim().to_string());
-            }
-        }
-        Service { name: name.to_string(), version: server, description: status }
-    }
-
-    fn parse_220_banner(port: u16, banner: &str) -> Service {
-        let name = match port {
-            21           => "ftp".to_string(),
-            25 | 465 | 587 => "smtp".to_string(),
-            _            => Self::infer_protocol(port),
-        };
-        let content: Vec<&str> = banner
-            .lines()
-            .filter(|l| l.starts_with("220"))
-            .map(|l| l.trim_start_matches("220").trim_start_matches('-').trim())
-            .filter(|l| !l.is_empty())
-            .collect();
-        let description = if content.is_empty() { None } else { Some(content.join(" ")) };
-        Service { name, version: None, description }
-    }
-
     // ── OS detection ─────────────────────────────────────────────────────────
 
-    /// Heuristic OS detection from open ports and service info strings.
+    /// How long the TTL probe waits for an echo reply.
+    const TTL_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Heuristic OS detection from open ports, service info strings and the
+    /// TTL of the host's echo reply, in that order of trust: banners first,
+    /// then the TTL, then which ports are open.
     /// Accepts both raw SSH banners ("SSH-2.0-OpenSSH_9.1 Ubuntu-3")
     /// and nmap extrainfo strings ("Ubuntu Linux; protocol 2.0").
-    fn detect_os(open_ports: &[u16], info: &[String]) -> (Option<String>, Option<String>) {
+    /// The third value says which of those the guess came from.
+    fn detect_os(open_ports: &[u16], info: &[String], ttl: Option<u8>) -> (Option<String>, Option<String>, Option<&'static str>) {
         let combined = info.join("\n").to_lowercase();
 
         // Direct OS mentions (nmap extrainfo)
         if combined.contains("ubuntu") {
-            return (Some("Linux".to_string()), Some("Ubuntu".to_string()));
+            return (Some("Linux".to_string()), Some("Ubuntu".to_string()), Some("banner"));
         }
         if combined.contains("debian") {
-            return (Some("Linux".to_string()), Some("Debian".to_string()));
+            return (Some("Linux".to_string()), Some("Debian".to_string()), Some("banner"));
         }
         if combined.contains("freebsd") {
-            return (Some("FreeBSD".to_string()), None);
+            return (Some("FreeBSD".to_string()), None, Some("banner"));
         }
 
         // Raw SSH banner parsing
@@ -994,62 +1077,56 @@ impl PortScanner {
                     .unwrap_or("")
                     .to_lowercase();
                 if comment.contains("ubuntu") {
-                    return (Some("Linux".to_string()), Some("Ubuntu".to_string()));
+                    return (Some("Linux".to_string()), Some("Ubuntu".to_string()), Some("banner"));
                 }
                 if comment.contains("debian") {
-                    return (Some("Linux".to_string()), Some("Debian".to_string()));
+                    return (Some("Linux".to_string()), Some("Debian".to_string()), Some("banner"));
                 }
                 if comment.contains("freebsd") {
-                    return (Some("FreeBSD".to_string()), None);
+                    return (Some("FreeBSD".to_string()), None, Some("banner"));
                 }
                 if !comment.is_empty() {
-                    return (Some("Linux".to_string()), None);
+                    return (Some("Linux".to_string()), None, Some("banner"));
                 }
             }
         }
 
         // HTTP Server header
         if combined.contains("iis") || combined.contains("microsoft") {
-            return (Some("Windows".to_string()), None);
+            return (Some("Windows".to_string()), None, Some("banner"));
+        }
+
+        // TTL-based guess
+        if let Some(os) = ttl.and_then(Self::os_from_ttl) {
+            return (Some(os.to_string()), None, Some("ttl"));
         }
 
         // Port-based heuristics
         if open_ports.contains(&3389) || open_ports.contains(&445) || open_ports.contains(&139) {
-            return (Some("Windows".to_string()), None);
+            return (Some("Windows".to_string()), None, Some("ports"));
         }
         if open_ports.contains(&22) || open_ports.contains(&80) || open_ports.contains(&443) {
-            return (Some("Linux".to_string()), None);
+            return (Some("Linux".to_string()), None, Some("ports"));
         }
 
-        (None, None)
+        (None, None, None)
     }
 
-    // ── Helpers ──────────────────────────────────────────────────────────────
-
-    fn infer_protocol(port: u16) -> String {
-        match port {
-            80 | 8080 | 8000 => "http".to_string(),
-            443 | 8443        => "https".to_string(),
-            22                => "ssh".to_string(),
-            21 | 20           => "ftp".to_string(),
-            25 | 465 | 587    => "smtp".to_string(),
-            110 | 995         => "pop3".to_string(),
-            143 | 993         => "imap".to_string(),
-            3306              => "mysql".to_string(),
-            5432              => "postgresql".to_string(),
-            1433              => "mssql".to_string(),
-            27017             => "mongodb".to_string(),
-            139 | 445 | 135   => "smb".to_string(),
-            3389              => "rdp".to_string(),
-            53                => "dns".to_string(),
-            161               => "snmp".to_string(),
-            1521              => "oracle".to_string(),
-            6379              => "redis".to_string(),
-            9200              => "elasticsearch".to_string(),
-            _                 => "unknown".to_string(),
+    /// OS family from the TTL of a reply. Stacks start at 64 (Linux, BSD,
+    /// macOS), 128 (Windows) or 255 (routers, switches, printers), and each
+    /// hop on the way takes one off, so the smallest start at or above the
+    /// observed TTL is the likely one.
+    fn os_from_ttl(ttl: u8) -> Option<&'static str> {
+        match ttl {
+            0 => None,
+            1..=64 => Some("Linux"),
+            65..=128 => Some("Windows"),
+            129..=255 => Some("Network device"),
         }
     }
 
+    // ── Helpers ──────────────────────────────────────────────────────────────
+
     fn clean_banner(banner: &str) -> String {
         banner
             .lines()
@@ -1103,3 +1180,154 @@ impl PortScanner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use crate::db::inmemory_repository::InMemoryRepository;
+    use crate::models::{Host, ScanConfig};
+    use crate::services::EventSink;
+
+    #[tokio::test]
+    async fn banner_grabs_respect_banner_concurrency() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut ports = Vec::new();
+
+        // Slow SSH servers, so overlapping grabs would be visible
+        for _ in 0..6 {
+            let listener = TcpListener::bind("127.0.0.7:0").await.unwrap();
+            ports.push(listener.local_addr().unwrap().port());
+            let (active, peak) = (active.clone(), peak.clone());
+            tokio::spawn(async move {
+                while let Ok((mut socket, _)) = listener.accept().await {
+                    let (active, peak) = (active.clone(), peak.clone());
+                    tokio::spawn(async move {
+                        let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(150)).await;
+                        let _ = socket.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+            });
+        }
+
+        let config = ScanConfig { banner_concurrency: Some(2), ..Default::default() };
+        let ctx = ScanContext::new(Arc::new(InMemoryRepository::new()), EventSink::noop(), config);
+        let limit = Semaphore::new(ctx.config.banner_concurrency());
+
+        let services = PortScanner::banner_fallback("127.0.0.7", &ports, &ctx, &limit).await;
+
+        assert_eq!(services.len(), 6);
+        assert!(services.iter().all(|s| s.name == "ssh"));
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak <= 2, "{} banner grabs ran at once", peak);
+        assert!(peak >= 1);
+    }
+
+    #[tokio::test]
+    async fn full_port_range_scan_is_parallel_and_sorted() {
+        let mut listeners = Vec::new();
+        for _ in 0..40 {
+            listeners.push(TcpListener::bind("127.0.0.8:0").await.unwrap());
+        }
+        let expected: Vec<u16> = listeners.iter().map(|l| l.local_addr().unwrap().port()).collect();
+
+        let ctx = ScanContext::new(Arc::new(InMemoryRepository::new()), EventSink::noop(), ScanConfig::default());
+        let started = Instant::now();
+        let open = PortScanner::tcp_scan_concurrent("127.0.0.8", 500, &ctx).await;
+        let elapsed = started.elapsed();
+
+        // Wildcard listeners elsewhere on the machine answer on 127.0.0.8 as well
+        assert!(expected.iter().all(|p| open.contains(p)), "{:?} not all found in {:?}", expected, open);
+        assert!(open.is_sorted());
+        assert!(elapsed < Duration::from_secs(15), "65535-port scan took {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn probes_to_one_host_respect_the_connection_rate() {
+        let config = ScanConfig {
+            ports: Some((1..=10).collect()),
+            max_connections_per_second: Some(40.0),
+            ..Default::default()
+        };
+        let ctx = ScanContext::new(Arc::new(InMemoryRepository::new()), EventSink::noop(), config);
+
+        let started = Instant::now();
+        PortScanner::tcp_scan_concurrent("127.0.0.8", 500, &ctx).await;
+
+        // 10 attempts at 40/s: the last one starts 9 × 25 ms after the first
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(225), "10 probes took only {:?}", elapsed);
+    }
+
+    #[test]
+    fn parses_captured_nmap_xml() {
+        let result = PortScanner::parse_nmap_xml(include_str!("../../tests/fixtures/nmap_scan.xml"));
+
+        let ports: Vec<u16> = result.services.iter().map(|s| s.port).collect();
+        assert_eq!(ports, vec![22, 443, 9100]);
+
+        let ssh = &result.services[0];
+        assert_eq!(ssh.name, "ssh");
+        assert_eq!(ssh.product.as_deref(), Some("OpenSSH"));
+        assert_eq!(ssh.version.as_deref(), Some("9.6p1 Ubuntu 3ubuntu13"));
+        assert_eq!(ssh.cpe.as_deref(), Some("cpe:/a:openbsd:openssh:9.6p1"));
+        assert_eq!(result.services[1].tunnel.as_deref(), Some("ssl"));
+        assert_eq!(result.services[2].name, "jetdirect");
+        assert!(result.services[2].version.is_none());
+
+        // Best osmatch wins, split into name and version
+        assert_eq!(result.os_name.as_deref(), Some("Linux"));
+        assert_eq!(result.os_version.as_deref(), Some("5.0 - 5.14"));
+        assert_eq!(result.os_cpe.as_deref(), Some("cpe:/o:linux:linux_kernel:5"));
+        assert_eq!(result.mac_address.as_deref(), Some("00:31:92:C1:60:20"));
+        assert_eq!(result.mac_vendor.as_deref(), Some("TP-Link Limited"));
+        assert_eq!(result.hostname.as_deref(), Some("nas.home.arpa"));
+        assert_eq!(result.scripts, vec!["[http-title] NAS Login"]);
+    }
+
+    #[test]
+    fn ttl_maps_to_the_nearest_initial_ttl() {
+        // Replies straight from the host, and after a few hops
+        for (ttl, os) in [(64, "Linux"), (57, "Linux"), (128, "Windows"), (116, "Windows"), (255, "Network device"), (249, "Network device")] {
+            assert_eq!(PortScanner::os_from_ttl(ttl), Some(os), "TTL {}", ttl);
+        }
+        // Just past a boundary means the larger start, not a very short path
+        assert_eq!(PortScanner::os_from_ttl(65), Some("Windows"));
+        assert_eq!(PortScanner::os_from_ttl(129), Some("Network device"));
+        assert_eq!(PortScanner::os_from_ttl(0), None);
+    }
+
+    #[tokio::test]
+    async fn ttl_guess_is_recorded_as_its_source_not_as_a_banner() {
+        let ctx = ScanContext::new(Arc::new(InMemoryRepository::new()), EventSink::noop(), ScanConfig::default());
+        ctx.repo.upsert_host(&Host::new("10.0.0.5".to_string())).await.unwrap();
+
+        // A later scan sees a different TTL; the guess is replaced, not added to
+        for ttl in [63, 64, 120] {
+            PortScanner::update_host_scan_results(&ctx, "10.0.0.5", &[8080], &[], None, None, None, None, Some(ttl)).await;
+        }
+
+        let host = ctx.repo.get_host("10.0.0.5").await.unwrap().unwrap();
+        assert_eq!(host.os.as_deref(), Some("Windows"));
+        assert_eq!(host.os_source.as_deref(), Some("ttl"));
+        assert!(host.banners.is_empty(), "{:?}", host.banners);
+    }
+
+    #[test]
+    fn ttl_guess_ranks_between_banners_and_open_ports() {
+        let ssh_ubuntu = vec!["SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13".to_string()];
+        assert_eq!(
+            PortScanner::detect_os(&[22], &ssh_ubuntu, Some(128)),
+            (Some("Linux".to_string()), Some("Ubuntu".to_string()), Some("banner"))
+        );
+        assert_eq!(PortScanner::detect_os(&[22, 80], &[], Some(128)), (Some("Windows".to_string()), None, Some("ttl")));
+        assert_eq!(PortScanner::detect_os(&[22, 80], &[], None), (Some("Linux".to_string()), None, Some("ports")));
+    }
+}
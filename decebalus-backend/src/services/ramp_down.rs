@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::models::RampDownConfig;
+
+struct Taper {
+    /// Attempts that have been granted a permit so far.
+    started: usize,
+    /// Current concurrency target.
+    limit: usize,
+    /// Permits that must be forgotten as they come back to reach `limit`.
+    debt: usize,
+}
+
+/// Tapers concurrency over the tail of a scan of `total` attempts.
+///
+/// Runs at `max` until the remaining work drops below the configured tail, then
+/// shrinks linearly with the remaining work down to `min_concurrency`. The limit
+/// only ever goes down, so the last slow/filtered ports are probed a few at a
+/// time instead of all timing out together.
+pub struct RampDown {
+    sem: Arc<Semaphore>,
+    taper: Mutex<Taper>,
+    total: usize,
+    max: usize,
+    tail: usize,
+    min: usize,
+}
+
+/// Permit for one attempt. Returned on drop unless the limit has shrunk meanwhile.
+pub struct RampDownPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    ramp: Arc<RampDown>,
+}
+
+impl Drop for RampDownPermit {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else { return };
+        let mut taper = self.ramp.taper.lock().unwrap();
+        if taper.debt > 0 {
+            taper.debt -= 1;
+            permit.forget();
+        }
+    }
+}
+
+impl RampDown {
+    pub fn new(total: usize, max: usize, cfg: &RampDownConfig) -> Arc<Self> {
+        let max = max.max(1);
+        let tail = (total as f64 * cfg.tail_percent.clamp(0.0, 100.0) / 100.0).ceil() as usize;
+        Arc::new(Self {
+            sem: Arc::new(Semaphore::new(max)),
+            taper: Mutex::new(Taper { started: 0, limit: max, debt: 0 }),
+            total,
+            max,
+            tail,
+            min: cfg.min_concurrency.clamp(1, max),
+        })
+    }
+
+    /// Current concurrency target.
+    pub fn limit(&self) -> usize {
+        self.taper.lock().unwrap().limit
+    }
+
+    /// Concurrency allowed with `remaining` attempts left to start.
+    fn target(&self, remaining: usize) -> usize {
+        if remaining >= self.tail {
+            return self.max;
+        }
+        (self.max * remaining).div_ceil(self.tail).clamp(self.min, self.max)
+    }
+
+    pub async fn acquire(self: &Arc<Self>) -> RampDownPermit {
+        let permit = self.sem.clone().acquire_owned().await.unwrap();
+
+        let mut taper = self.taper.lock().unwrap();
+        taper.started += 1;
+        let target = self.target(self.total.saturating_sub(taper.started));
+        if target < taper.limit {
+            let shrink = taper.limit - target;
+            // Drop idle permits right away; the rest are forgotten as attempts finish
+            let forgotten = self.sem.forget_permits(shrink);
+            taper.debt += shrink - forgotten;
+            taper.limit = target;
+        }
+        drop(taper);
+
+        RampDownPermit { permit: Some(permit), ramp: self.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use futures_util::StreamExt;
+
+    fn cfg() -> RampDownConfig {
+        RampDownConfig { enabled: true, tail_percent: 10.0, min_concurrency: 2 }
+    }
+
+    #[test]
+    fn full_concurrency_until_the_tail() {
+        let ramp = RampDown::new(1000, 50, &cfg());
+
+        assert_eq!(ramp.target(500), 50);
+        assert_eq!(ramp.target(100), 50);
+        assert_eq!(ramp.target(50), 25);
+        assert_eq!(ramp.target(1), 2);
+        assert_eq!(ramp.target(0), 2);
+    }
+
+    #[tokio::test]
+    async fn fewer_attempts_in_flight_near_completion() {
+        let total = 1000;
+        let ramp = RampDown::new(total, 50, &cfg());
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let seen: Arc<Vec<AtomicUsize>> = Arc::new((0..total).map(|_| AtomicUsize::new(0)).collect());
+
+        futures_util::stream::iter(0..total)
+            .map(|i| {
+                let (ramp, in_flight, seen) = (ramp.clone(), in_flight.clone(), seen.clone());
+                async move {
+                    let _permit = ramp.acquire().await;
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    seen[i].store(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(2)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                }
+            })
+            .buffer_unordered(50)
+            .collect::<Vec<_>>()
+            .await;
+
+        let peak = |range: std::ops::Range<usize>| range.map(|i| seen[i].load(Ordering::SeqCst)).max().unwrap();
+        let mid_scan = peak(400..600);
+        let near_end = peak(990..total);
+
+        assert!(near_end < mid_scan, "{} in flight near the end vs {} mid-scan", near_end, mid_scan);
+        assert!(near_end <= 10);
+        assert_eq!(ramp.limit(), 2);
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Spaces out connection attempts against one host to at most `per_second`.
+///
+/// A token bucket holding a single token: each attempt books the next free
+/// slot, `1 / per_second` after the previous one, and waits for it. However
+/// many probes run concurrently, the host never sees a burst, which keeps
+/// printers and IoT devices from falling over mid-scan.
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Rates below one attempt per 1000 s are raised to that.
+    pub fn new(per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / per_second.max(0.001)),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until the next attempt may start.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn concurrent_attempts_are_spaced_by_the_rate() {
+        let limiter = Arc::new(RateLimiter::new(50.0));
+        let started = Instant::now();
+
+        let attempts: Vec<_> = (0..6)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire().await;
+                    Instant::now()
+                })
+            })
+            .collect();
+        let mut times = Vec::new();
+        for attempt in attempts {
+            times.push(attempt.await.unwrap());
+        }
+        times.sort();
+
+        // The first goes straight away, then one every 20 ms
+        assert!(times[0] - started < Duration::from_millis(15));
+        for pair in times.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(19), "{:?}", pair[1] - pair[0]);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use crate::db::repository_trait::Repository;
+use crate::models::{HostsConfig, Job, WsEvent};
+use crate::services::JobExecutor;
+use crate::state::AppState;
+
+const THIS_SERVICE: &str = "rescan_scheduler";
+
+/// Rescan Scheduler Service
+/// Periodically queues targeted port-scans for hosts whose last port scan is
+/// older than `hosts.rescan_after_hours`, a few at a time.
+pub struct RescanScheduler;
+
+impl RescanScheduler {
+    pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+        tokio::spawn(Self::run(state))
+    }
+
+    async fn run(state: Arc<AppState>) {
+        let repo = state.repo.clone();
+        tracing::info!("Rescan scheduler started...");
+
+        loop {
+            // Re-read each pass so config edits apply without a restart
+            let cfg = repo.get_config().await.map(|c| c.hosts_config()).unwrap_or_default();
+
+            match Self::enqueue_due(repo.as_ref(), &cfg, Utc::now()).await {
+                Ok(jobs) if !jobs.is_empty() => {
+                    for job in &jobs {
+                        let _ = state.broadcaster.send(WsEvent::JobQueued { job_id: job.id.clone(), job_type: job.job_type.clone() });
+                    }
+                    JobExecutor::run_queue(&state).await;
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Rescan scheduler failed: {}", e),
+            }
+
+            tokio::time::sleep(Duration::from_secs(cfg.rescan_check_interval_secs.max(1))).await;
+        }
+    }
+
+    /// Queue a port-scan for each host due for a rescan, oldest scan first,
+    /// at most `rescan_batch_size` per call. Hosts that already have a queued or
+    /// running port-scan are skipped. Returns the jobs created.
+    pub async fn enqueue_due(repo: &dyn Repository, cfg: &HostsConfig, now: DateTime<Utc>) -> Result<Vec<Job>, sqlx::Error> {
+        let Some(hours) = cfg.rescan_after_hours else {
+            return Ok(Vec::new());
+        };
+        let cutoff = now - chrono::Duration::hours(hours as i64);
+
+        let pending: HashSet<String> = repo
+            .get_queued_jobs()
+            .await?
+            .into_iter()
+            .chain(repo.get_running_jobs().await?)
+            .filter(|j| j.job_type == "port-scan")
+            .filter_map(|j| j.target().ok())
+            .collect();
+
+        let mut due: Vec<(DateTime<Utc>, String)> = repo
+            .list_hosts()
+            .await?
+            .into_iter()
+            .filter_map(|h| {
+                // Hosts never port-scanned are left to discovery's auto port-scan
+                let scanned = DateTime::parse_from_rfc3339(h.last_port_scan.as_deref()?).ok()?;
+                Some((scanned.with_timezone(&Utc), h.ip))
+            })
+            .filter(|(scanned, ip)| *scanned < cutoff && !pending.contains(ip))
+            .collect();
+        due.sort();
+
+        let mut jobs = Vec::new();
+        for (_, ip) in due.into_iter().take(cfg.rescan_batch_size) {
+            let mut job = Job::new("port-scan".to_string());
+            job.config = serde_json::json!({ "target": ip });
+            repo.create_job(&job).await?;
+
+            let msg = format!("Queued rescan of {} (job {})", ip, job.id);
+            tracing::info!("{}", msg);
+            let _ = repo.add_log("INFO", THIS_SERVICE, None, Some(&job.id), &msg).await;
+            jobs.push(job);
+        }
+        Ok(jobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::inmemory_repository::InMemoryRepository;
+    use crate::models::Host;
+
+    fn host_scanned_at(ip: &str, when: DateTime<Utc>) -> Host {
+        let mut host = Host::new(ip.to_string());
+        host.last_port_scan = Some(when.to_rfc3339());
+        host
+    }
+
+    fn cfg(hours: u64) -> HostsConfig {
+        HostsConfig { rescan_after_hours: Some(hours), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn stale_host_is_rescanned_and_fresh_host_is_not() {
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        repo.upsert_host(&host_scanned_at("10.0.0.1", now - chrono::Duration::hours(48))).await.unwrap();
+        repo.upsert_host(&host_scanned_at("10.0.0.2", now - chrono::Duration::hours(1))).await.unwrap();
+
+        let jobs = RescanScheduler::enqueue_due(&repo, &cfg(24), now).await.unwrap();
+
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_type, "port-scan");
+        assert_eq!(jobs[0].target().unwrap(), "10.0.0.1");
+        assert_eq!(repo.get_queued_jobs().await.unwrap().len(), 1);
+
+        // Already queued: not queued twice
+        let again = RescanScheduler::enqueue_due(&repo, &cfg(24), now).await.unwrap();
+        assert!(again.is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_size_limits_jobs_per_pass() {
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        for i in 1..=4 {
+            let ip = format!("10.0.1.{}", i);
+            repo.upsert_host(&host_scanned_at(&ip, now - chrono::Duration::hours(100 - i))).await.unwrap();
+        }
+        let cfg = HostsConfig { rescan_batch_size: 2, ..cfg(24) };
+
+        let jobs = RescanScheduler::enqueue_due(&repo, &cfg, now).await.unwrap();
+
+        // Oldest scans first
+        let targets: Vec<String> = jobs.iter().map(|j| j.target().unwrap()).collect();
+        assert_eq!(targets, ["10.0.1.1", "10.0.1.2"]);
+    }
+
+    #[tokio::test]
+    async fn disabled_without_threshold() {
+        let repo = InMemoryRepository::new();
+        repo.upsert_host(&host_scanned_at("10.0.0.1", Utc::now() - chrono::Duration::days(30))).await.unwrap();
+
+        let jobs = RescanScheduler::enqueue_due(&repo, &HostsConfig::default(), Utc::now()).await.unwrap();
+        assert!(jobs.is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::models::AdaptiveTimeoutConfig;
+
+/// Answered connects kept for the estimate.
+const WINDOW: usize = 64;
+
+/// Answers needed before the estimate replaces the starting timeout.
+const MIN_SAMPLES: usize = 5;
+
+/// Connect timeout that follows the network for one scan run.
+///
+/// Records the round-trip time of every connect that got an answer (accepted or
+/// refused) and sizes the timeout at `multiplier` × the median of the last
+/// `WINDOW` of them, clamped to `min_ms..=max_ms`. Until `MIN_SAMPLES` answers are
+/// in, or with the feature off, the run's starting timeout applies. A fast LAN
+/// then stops waiting the full default on silent addresses, while a slow VPN
+/// link gets more time than the default would give it.
+pub struct RttEstimator {
+    samples: Mutex<VecDeque<Duration>>,
+    initial: Duration,
+    cfg: AdaptiveTimeoutConfig,
+}
+
+impl RttEstimator {
+    pub fn new(initial: Duration, cfg: AdaptiveTimeoutConfig) -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::with_capacity(WINDOW)),
+            initial,
+            cfg,
+        }
+    }
+
+    /// Record the round-trip time of an answered connect.
+    pub fn record(&self, rtt: Duration) {
+        if !self.cfg.enabled {
+            return;
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    /// Median of the recorded round-trip times, once there are enough of them.
+    pub fn median(&self) -> Option<Duration> {
+        let samples = self.samples.lock().unwrap();
+        if samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+
+    /// Timeout for the next connect.
+    pub fn timeout(&self) -> Duration {
+        let Some(median) = self.median() else {
+            return self.initial;
+        };
+        let min = Duration::from_millis(self.cfg.min_ms);
+        let max = Duration::from_millis(self.cfg.max_ms).max(min);
+        median.mul_f64(self.cfg.multiplier.max(1.0)).clamp(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> AdaptiveTimeoutConfig {
+        AdaptiveTimeoutConfig {
+            enabled: true,
+            multiplier: 3.0,
+            min_ms: 50,
+            max_ms: 2000,
+        }
+    }
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn starts_from_the_initial_timeout() {
+        let rtt = RttEstimator::new(ms(500), cfg());
+        for _ in 0..MIN_SAMPLES - 1 {
+            rtt.record(ms(40));
+        }
+        assert_eq!(rtt.median(), None);
+        assert_eq!(rtt.timeout(), ms(500));
+    }
+
+    #[test]
+    fn timeout_is_three_times_the_median() {
+        let rtt = RttEstimator::new(ms(500), cfg());
+        // An outlier doesn't move the median
+        for sample in [30, 40, 40, 50, 900] {
+            rtt.record(ms(sample));
+        }
+        assert_eq!(rtt.median(), Some(ms(40)));
+        assert_eq!(rtt.timeout(), ms(120));
+    }
+
+    #[test]
+    fn timeout_is_clamped_to_the_bounds() {
+        let lan = RttEstimator::new(ms(500), cfg());
+        for _ in 0..10 {
+            lan.record(Duration::from_micros(300));
+        }
+        assert_eq!(lan.timeout(), ms(50));
+
+        let vpn = RttEstimator::new(ms(500), cfg());
+        for _ in 0..10 {
+            vpn.record(ms(1500));
+        }
+        assert_eq!(vpn.timeout(), ms(2000));
+    }
+
+    #[test]
+    fn old_samples_roll_out_of_the_window() {
+        let rtt = RttEstimator::new(ms(500), cfg());
+        for _ in 0..WINDOW {
+            rtt.record(ms(400));
+        }
+        for _ in 0..WINDOW / 2 + 1 {
+            rtt.record(ms(20));
+        }
+        assert_eq!(rtt.median(), Some(ms(20)));
+    }
+
+    #[test]
+    fn disabled_keeps_the_initial_timeout() {
+        let rtt = RttEstimator::new(ms(200), AdaptiveTimeoutConfig { enabled: false, ..cfg() });
+        for _ in 0..10 {
+            rtt.record(ms(5));
+        }
+        assert_eq!(rtt.timeout(), ms(200));
+    }
+}
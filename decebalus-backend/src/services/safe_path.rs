@@ -0,0 +1,101 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Directories export/import jobs may read from or write to.
+///
+/// Paths coming from config or requests are resolved against this list:
+/// relative paths land under the first allowed directory, absolute paths must
+/// already be inside one of them, and `..` components are always rejected.
+#[derive(Clone, Debug)]
+pub struct AllowedDirs {
+    dirs: Vec<PathBuf>,
+}
+
+impl AllowedDirs {
+    pub fn new<I, P>(dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let dirs: Vec<PathBuf> = dirs.into_iter().map(|d| normalize(&d.into())).collect();
+        Self { dirs }
+    }
+
+    /// Read `EXPORT_ALLOWED_DIRS` (comma-separated). Defaults to `data`.
+    pub fn from_env() -> Self {
+        let raw = std::env::var("EXPORT_ALLOWED_DIRS").unwrap_or_default();
+        let dirs: Vec<&str> = raw.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if dirs.is_empty() {
+            Self::new(["data"])
+        } else {
+            Self::new(dirs)
+        }
+    }
+
+    /// Resolve `requested` to a path inside one of the allowed directories.
+    pub fn resolve(&self, requested: &str) -> Result<PathBuf, String> {
+        let Some(base) = self.dirs.first() else {
+            return Err("No export directories are allowed".to_string());
+        };
+
+        let path = Path::new(requested);
+        if path.components().any(|c| matches!(c, Component::ParentDir)) {
+            return Err(format!("Path '{}' must not contain '..'", requested));
+        }
+
+        let resolved = if path.is_absolute() {
+            normalize(path)
+        } else {
+            normalize(&base.join(path))
+        };
+
+        if self.dirs.iter().any(|dir| resolved.starts_with(dir)) {
+            Ok(resolved)
+        } else {
+            Err(format!("Path '{}' is outside the allowed export directories", requested))
+        }
+    }
+}
+
+/// Lexically drop `.` components; `..` is rejected before this is relevant.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed() -> AllowedDirs {
+        AllowedDirs::new(["data", "/srv/decebalus/exports"])
+    }
+
+    #[test]
+    fn relative_paths_resolve_under_base() {
+        assert_eq!(allowed().resolve("exports").unwrap(), PathBuf::from("data/exports"));
+        assert_eq!(allowed().resolve("./exports/daily").unwrap(), PathBuf::from("data/exports/daily"));
+    }
+
+    #[test]
+    fn absolute_paths_inside_allowlist_are_accepted() {
+        assert_eq!(
+            allowed().resolve("/srv/decebalus/exports/weekly").unwrap(),
+            PathBuf::from("/srv/decebalus/exports/weekly")
+        );
+    }
+
+    #[test]
+    fn traversal_is_rejected() {
+        assert!(allowed().resolve("../etc").is_err());
+        assert!(allowed().resolve("exports/../../etc").is_err());
+        assert!(allowed().resolve("/srv/decebalus/exports/../../../etc").is_err());
+    }
+
+    #[test]
+    fn absolute_paths_outside_allowlist_are_rejected() {
+        assert!(allowed().resolve("/etc").is_err());
+        // Prefix match is per component, not per character
+        assert!(allowed().resolve("/srv/decebalus/exports-evil").is_err());
+    }
+}
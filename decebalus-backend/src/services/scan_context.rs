@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use crate::db::repository_trait::Repository;
+use crate::models::{Host, HostChange, ScanConfig, WsEvent};
+use crate::services::EventBus;
+use crate::state::AppState;
+
+/// Destination for scanner events (`HostFound`, `ScanProgress`, `Log`, …).
+/// In the running app this is the WebSocket broadcaster; tests can plug in any closure.
+#[derive(Clone)]
+pub struct EventSink {
+    emit: Arc<dyn Fn(WsEvent) + Send + Sync>,
+}
+
+impl EventSink {
+    /// Sink that forwards every event to `f`.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(WsEvent) + Send + Sync + 'static,
+    {
+        Self { emit: Arc::new(f) }
+    }
+
+    /// Sink backed by the app broadcaster. Events with no subscribers are dropped.
+    pub fn broadcast(bus: EventBus) -> Self {
+        Self::new(move |event| {
+            let _ = bus.send(event);
+        })
+    }
+
+    /// Sink that drops everything.
+    pub fn noop() -> Self {
+        Self::new(|_| {})
+    }
+
+    pub fn send(&self, event: WsEvent) {
+        (self.emit)(event)
+    }
+}
+
+/// Everything a scanner needs, without the rest of the application state.
+#[derive(Clone)]
+pub struct ScanContext {
+    pub repo: Arc<dyn Repository>,
+    pub events: EventSink,
+    pub config: ScanConfig,
+    /// Concurrent TCP connects per port scan (`MAX_SCAN_CONCURRENCY`).
+    pub max_scan_concurrency: usize,
+}
+
+impl ScanContext {
+    pub fn new(repo: Arc<dyn Repository>, events: EventSink, config: ScanConfig) -> Self {
+        Self {
+            repo,
+            events,
+            config,
+            max_scan_concurrency: 500,
+        }
+    }
+
+    /// Record that `job_id` entered `phase`, persisting it and emitting `JobPhase`.
+    pub async fn set_phase(&self, job_id: &str, phase: &str) {
+        if let Err(e) = self.repo.update_job_phase(job_id, Some(phase)).await {
+            tracing::error!("Failed to update phase of job {}: {}", job_id, e);
+        }
+        self.events.send(WsEvent::JobPhase { job_id: job_id.to_string(), phase: phase.to_string() });
+    }
+
+    /// Emit a `ScanProgress` message for `job_id` and record the progress.
+    pub async fn report_progress(&self, job_id: &str, message: String) {
+        self.record_progress(job_id).await;
+        self.events.send(WsEvent::scan_progress(job_id, message));
+    }
+
+    /// Bump `job_id`'s `updated_at` after real work, e.g. a host done. This is
+    /// what tells the stuck-job watchdog the job is still getting somewhere.
+    pub async fn record_progress(&self, job_id: &str) {
+        if let Err(e) = self.repo.touch_running_job(job_id).await {
+            tracing::warn!("Failed to record progress of job {}: {}", job_id, e);
+        }
+    }
+
+    /// Save `host`. If it had been port-scanned before, open ports or services
+    /// that appeared or went away since the stored copy are logged and sent
+    /// as `HostChanged`.
+    pub async fn save_host(&self, host: &Host) -> Result<(), sqlx::Error> {
+        let previous = self.repo.get_host(&host.ip).await?;
+        self.repo.upsert_host(host).await?;
+
+        // The first scan of a host is its baseline, not a change
+        let change = previous
+            .filter(|p| p.last_port_scan.is_some())
+            .and_then(|p| HostChange::between(&p, host));
+        if let Some(change) = change {
+            let msg = format!("Host {} changed: {}", host.ip, change.describe());
+            tracing::info!("{}", msg);
+            let _ = self.repo.add_log("INFO", "host_changes", None, None, &msg).await;
+            self.events.send(WsEvent::host_changed(&host.ip, change));
+        }
+        Ok(())
+    }
+
+    /// Build a context backed by the app database and broadcaster.
+    /// `scan_config` is read once here, so a running scan is not affected by config edits.
+    pub async fn from_state(state: &Arc<AppState>) -> Self {
+        let config = state.config
+            .get(state.repo.as_ref())
+            .await
+            .map(|c| c.scan_config())
+            .unwrap_or_default();
+
+        Self {
+            repo: state.repo.clone(),
+            events: EventSink::broadcast(state.broadcaster.clone()),
+            config,
+            max_scan_concurrency: state.max_scan_concurrency,
+        }
+    }
+}
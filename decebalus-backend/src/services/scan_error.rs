@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+/// Why a scan or job run failed. Its `Display` text is what ends up in the
+/// job's results, so messages are written for people.
+#[derive(Debug, thiserror::Error)]
+pub enum ScanError {
+    /// Discovery target that isn't an IPv4 CIDR.
+    #[error("Invalid network CIDR: {0}")]
+    InvalidCidr(String),
+
+    /// Job target that is missing or can't be scanned.
+    #[error("{0}")]
+    InvalidTarget(String),
+
+    /// No local network could be found for a `self` target.
+    #[error("No suitable local network interface found")]
+    NoNetwork,
+
+    /// Unusable job settings, e.g. an export directory outside the allowed dirs.
+    #[error("{0}")]
+    Config(String),
+
+    /// nmap missing, failing to start or exiting with an error.
+    #[error("{0}")]
+    Nmap(String),
+
+    /// The CVE source couldn't be reached or answered with an error.
+    #[error("{0}")]
+    CveLookup(String),
+
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("database error: {0}")]
+    Db(#[from] sqlx::Error),
+
+    #[error("Job timed out after {}s", .0.as_secs())]
+    Timeout(Duration),
+
+    /// The job was cancelled while running.
+    #[error("job cancelled")]
+    Cancelled,
+}
+
+impl ScanError {
+    /// I/O error with what was being attempted prepended, keeping the error kind.
+    pub fn io(context: impl std::fmt::Display, e: std::io::Error) -> Self {
+        Self::Io(std::io::Error::new(e.kind(), format!("{}: {}", context, e)))
+    }
+
+    /// Whether running the job again might succeed. Bad input and
+    /// cancellations won't change on a retry; I/O, database, CVE source
+    /// outages and timeouts may.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Io(_) | Self::Db(_) | Self::CveLookup(_) | Self::Timeout(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_errors_are_retryable() {
+        assert!(ScanError::from(sqlx::Error::PoolTimedOut).is_retryable());
+        assert!(ScanError::from(std::io::Error::other("disk full")).is_retryable());
+        assert!(ScanError::Timeout(Duration::from_secs(5)).is_retryable());
+    }
+
+    #[test]
+    fn input_errors_and_cancellation_are_fatal() {
+        assert!(!ScanError::InvalidCidr("nope".into()).is_retryable());
+        assert!(!ScanError::Config("bad dir".into()).is_retryable());
+        assert!(!ScanError::Cancelled.is_retryable());
+    }
+
+    #[test]
+    fn display_is_human_readable() {
+        assert_eq!(ScanError::InvalidCidr("10.0.0.0/99".into()).to_string(), "Invalid network CIDR: 10.0.0.0/99");
+        assert_eq!(ScanError::Timeout(Duration::from_secs(600)).to_string(), "Job timed out after 600s");
+        let e = ScanError::io("Failed to create data/x", std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert!(e.to_string().starts_with("Failed to create data/x: "));
+        assert!(matches!(e, ScanError::Io(io) if io.kind() == std::io::ErrorKind::PermissionDenied));
+    }
+}
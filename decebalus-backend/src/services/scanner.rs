@@ -1,65 +1,171 @@
 use std::collections::HashMap;
+use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use futures_util::stream::FuturesUnordered;
-use futures_util::StreamExt;
 use ipnet::{IpNet, Ipv4Net};
-use crate::models::{Host, HostStatus};
-use crate::state::AppState;
+use crate::models::{DiscoveryMethod, Host, HostStatus, WsEvent, max_discovery_threads};
+use crate::services::arp_table;
+use crate::services::icmp::IcmpPinger;
+use crate::services::rtt_estimator::RttEstimator;
+use crate::services::{ScanContext, ScanError};
 use tokio::sync::Semaphore;
-use crate::db::repository;
+use tokio::task::JoinSet;
 use pnet_datalink::{interfaces, Channel, MacAddr, NetworkInterface};
 use pnet_packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
 use pnet_packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
 use pnet_packet::Packet;
 
-/// Network Scanner Service
-/// Discovers alive hosts on the network
-pub struct NetworkScanner;
+const THIS_SERVICE: &str = "scanner";
 
-impl NetworkScanner {
-    /// Discover hosts on a network using ARP (primary) or TCP probing (fallback).
-    pub async fn discover_hosts(target: &str, state: &Arc<AppState>) -> Result<usize, String> {
-        let network = if target == "self" {
-            Self::detect_local_network()?
-        } else {
-            target
-                .parse::<IpNet>()
-                .map_err(|_| format!("Invalid network CIDR: {}", target))?
-        };
+/// Hosts written per transaction during discovery.
+const HOST_BATCH_SIZE: usize = 50;
 
-        Self::log_and_broadcast(state, &format!("Starting network discovery on {}", network));
+/// Longest a found host waits in a partly filled batch before it is saved.
+const HOST_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Hosts found during discovery, saved up to `HOST_BATCH_SIZE` at a time so a
+/// large sweep doesn't cost a write per host. A partly filled batch is saved
+/// after `HOST_FLUSH_INTERVAL`; with `stream_port_scan` every host is saved as
+/// soon as it is found. `HostFound`/`NewHost` go out once a host is saved, so
+/// listeners can read it back straight away. Hosts still pending when the batch
+/// is dropped (job timeout or cancel) are saved in the background.
+struct HostBatch {
+    pending: Arc<PendingHosts>,
+    size: usize,
+}
 
-        let ips: Vec<Ipv4Addr> = match &network {
-            IpNet::V4(net) => net.hosts().collect(),
-            IpNet::V6(_) => return Err("IPv6 scanning not supported".to_string()),
+struct PendingHosts {
+    ctx: ScanContext,
+    hosts: std::sync::Mutex<Vec<(Host, bool)>>,
+    // One save at a time, so `flush` counts every save started before it
+    saving: tokio::sync::Mutex<()>,
+    saved: AtomicUsize,
+}
+
+impl HostBatch {
+    fn new(ctx: &ScanContext) -> Self {
+        let pending = Arc::new(PendingHosts {
+            ctx: ctx.clone(),
+            hosts: std::sync::Mutex::new(Vec::new()),
+            saving: tokio::sync::Mutex::new(()),
+            saved: AtomicUsize::new(0),
+        });
+
+        // Stops on the first tick after the batch is gone
+        let ticking = Arc::downgrade(&pending);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + HOST_FLUSH_INTERVAL, HOST_FLUSH_INTERVAL);
+            loop {
+                ticks.tick().await;
+                let Some(pending) = ticking.upgrade() else { break };
+                pending.save_all().await;
+            }
+        });
+
+        let size = if ctx.config.stream_port_scan { 1 } else { HOST_BATCH_SIZE };
+        Self { pending, size }
+    }
+
+    /// Queue `host`, saving the batch once it is full.
+    async fn push(&self, host: Host, is_new: bool) {
+        let full = {
+            let mut hosts = self.pending.hosts.lock().unwrap();
+            hosts.push((host, is_new));
+            hosts.len() >= self.size
         };
+        if full {
+            self.pending.save_all().await;
+        }
+    }
+
+    /// Save whatever is left. Returns how many hosts were saved overall.
+    async fn flush(&self) -> usize {
+        self.pending.save_all().await;
+        self.pending.saved.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for HostBatch {
+    fn drop(&mut self) {
+        if self.pending.hosts.lock().unwrap().is_empty() {
+            return;
+        }
+        let pending = self.pending.clone();
+        tokio::spawn(async move { pending.save_all().await });
+    }
+}
+
+impl PendingHosts {
+    async fn save_all(&self) {
+        let _saving = self.saving.lock().await;
+        let batch = std::mem::take(&mut *self.hosts.lock().unwrap());
+        if batch.is_empty() {
+            return;
+        }
+
+        let hosts: Vec<Host> = batch.iter().map(|(host, _)| host.clone()).collect();
+        if let Err(e) = self.ctx.repo.upsert_hosts(&hosts).await {
+            tracing::error!("Failed to save {} host(s): {}", hosts.len(), e);
+            return;
+        }
+        for (host, is_new) in &batch {
+            self.ctx.events.send(WsEvent::HostFound { ip: host.ip.clone() });
+            if *is_new {
+                self.ctx.events.send(WsEvent::NewHost { ip: host.ip.clone() });
+            }
+        }
+        self.saved.fetch_add(batch.len(), Ordering::Relaxed);
+    }
+}
+
+/// Network Scanner Service
+/// Discovers alive hosts on the network
+pub struct NetworkScanner;
 
-        Self::log_and_broadcast(state, &format!("Scanning {} IPs", ips.len()));
+impl NetworkScanner {
+    /// Discover hosts on one or more networks using ARP (primary) or ICMP/TCP probing (fallback).
+    /// `target` is either `self` or a comma-separated list of CIDRs.
+    pub async fn discover_hosts(target: &str, ctx: &ScanContext) -> Result<usize, ScanError> {
+        let networks = Self::parse_targets(target)?;
+        let networks_display = networks.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+        Self::log_and_broadcast(ctx, &format!("Starting network discovery on {}", networks_display));
+
+        let groups = Self::without_exclusions(Self::target_groups(&networks), &ctx.config.excluded_networks());
+        let ips: Vec<Ipv4Addr> = groups.iter().flatten().cloned().collect();
+
+        Self::log_and_broadcast(ctx, &format!("Scanning {} IPs", ips.len()));
+
+        let (method, fallback) = Self::select_discovery_method(ctx.config.discovery_method, IcmpPinger::check_available);
+        if let Some(reason) = fallback {
+            tracing::warn!("{}", reason);
+            let _ = ctx.repo.add_log("WARN", THIS_SERVICE, Some("discover_hosts"), None, &reason).await;
+            ctx.events.send(WsEvent::log(reason));
+        }
 
         let arp_results = Self::arp_scan(&ips).await;
 
         let hosts_found = if arp_results.is_empty() {
             // ARP not available (no raw socket access) — use TCP only
-            Self::log_and_broadcast(state, "ARP unavailable, using TCP probe");
-            Self::tcp_discover(&ips, state).await
+            Self::log_and_broadcast(ctx, &format!("ARP unavailable, using {} probe", method.label()));
+            Self::probe_discover(groups, ctx, method).await
         } else {
-            Self::log_and_broadcast(state, &format!("ARP scan found {} hosts", arp_results.len()));
+            Self::log_and_broadcast(ctx, &format!("ARP scan found {} hosts", arp_results.len()));
             let arp_ips: std::collections::HashSet<Ipv4Addr> = arp_results.keys().cloned().collect();
-            let saved = Self::save_arp_results(state, arp_results).await;
+            let saved = Self::save_arp_results(ctx, arp_results).await;
 
             // TCP probe the IPs that didn't respond to ARP — catches hosts that
             // block ARP or only have open ports visible (e.g. firewalled devices).
-            let remaining: Vec<Ipv4Addr> = ips.iter()
-                .filter(|ip| !arp_ips.contains(ip))
-                .cloned()
+            let remaining: Vec<Vec<Ipv4Addr>> = groups.into_iter()
+                .map(|group| group.into_iter().filter(|ip| !arp_ips.contains(ip)).collect())
                 .collect();
-            if !remaining.is_empty() {
-                Self::log_and_broadcast(state, &format!(
-                    "TCP probing {} IPs that didn't respond to ARP", remaining.len()
+            let remaining_count: usize = remaining.iter().map(Vec::len).sum();
+            if remaining_count > 0 {
+                Self::log_and_broadcast(ctx, &format!(
+                    "{} probing {} IPs that didn't respond to ARP", method.label(), remaining_count
                 ));
-                saved + Self::tcp_discover(&remaining, state).await
+                saved + Self::probe_discover(remaining, ctx, method).await
             } else {
                 saved
             }
@@ -136,24 +242,25 @@ impl NetworkScanner {
             send_packet(&mut tx, target_ip);
         }
 
-        // Collect ARP replies for up to 3 seconds after the last send pass
+        // Collect ARP replies for up to 3 seconds after the last send pass.
+        // Replies from outside the target range (stray or gratuitous ARP) are ignored.
+        let wanted: std::collections::HashSet<Ipv4Addr> = targets.iter().cloned().collect();
         let deadline = std::time::Instant::now() + Duration::from_secs(3);
         let mut results = HashMap::new();
 
         while std::time::Instant::now() < deadline {
             match rx.next() {
                 Ok(packet) => {
-                    if let Some(eth) = EthernetPacket::new(packet) {
-                        if eth.get_ethertype() == EtherTypes::Arp {
-                            if let Some(arp) = ArpPacket::new(eth.payload()) {
-                                if arp.get_operation() == ArpOperations::Reply {
-                                    results.insert(
-                                        arp.get_sender_proto_addr(),
-                                        arp.get_sender_hw_addr().to_string(),
-                                    );
-                                }
-                            }
-                        }
+                    if let Some(eth) = EthernetPacket::new(packet)
+                        && eth.get_ethertype() == EtherTypes::Arp
+                        && let Some(arp) = ArpPacket::new(eth.payload())
+                        && arp.get_operation() == ArpOperations::Reply
+                        && wanted.contains(&arp.get_sender_proto_addr())
+                    {
+                        results.insert(
+                            arp.get_sender_proto_addr(),
+                            arp.get_sender_hw_addr().to_string(),
+                        );
                     }
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
@@ -165,76 +272,198 @@ impl NetworkScanner {
     }
 
     async fn save_arp_results(
-        state: &Arc<AppState>,
+        ctx: &ScanContext,
         results: HashMap<Ipv4Addr, String>,
     ) -> usize {
-        let mut count = 0;
+        let batch = HostBatch::new(ctx);
         for (ip, mac) in results {
             let ip_str = ip.to_string();
-            let hostname = Self::resolve_hostname(&ip_str).await;
+            let hostname = Self::lookup_hostname(ctx, &ip_str).await;
 
-            let mut host = match repository::get_host(&state.db, &ip_str).await {
-                Ok(Some(existing)) => existing,
-                _ => Host::new(ip_str.clone()),
-            };
+            let is_new = !matches!(ctx.repo.get_host(&ip_str).await, Ok(Some(_)));
 
+            // Only what discovery found out; a missing hostname keeps the stored one
+            let mut host = Host::new(ip_str);
             host.mac_address = Some(mac);
             host.hostname = hostname;
             host.status = HostStatus::Up;
-            host.update_last_seen();
 
-            if let Err(e) = repository::upsert_host(&state.db, &host).await {
-                tracing::error!("Failed to save host {}: {}", ip_str, e);
-            } else {
-                let _ = state.broadcaster.send(format!("host_found:{}", ip_str));
-                count += 1;
-            }
+            batch.push(host, is_new).await;
         }
-        count
+        batch.flush().await
     }
 
-    /// TCP-based host discovery (fallback when ARP is unavailable)
-    async fn tcp_discover(ips: &[Ipv4Addr], state: &Arc<AppState>) -> usize {
-        let hosts_found = Arc::new(tokio::sync::Mutex::new(0usize));
-        let max_threads = std::env::var("MAX_DISCOVER_THREADS")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or(256);
-        let sem = Arc::new(Semaphore::new(max_threads));
-        let mut futures = FuturesUnordered::new();
+    /// Discovery method actually used for `configured`. `icmp_available` is only
+    /// consulted when ICMP is configured; the second value explains a fallback to TCP.
+    pub(crate) fn select_discovery_method<F>(configured: DiscoveryMethod, icmp_available: F) -> (DiscoveryMethod, Option<String>)
+    where
+        F: FnOnce() -> Result<(), String>,
+    {
+        match configured {
+            DiscoveryMethod::Icmp => match icmp_available() {
+                Ok(()) => (DiscoveryMethod::Icmp, None),
+                Err(reason) => (
+                    DiscoveryMethod::Tcp,
+                    Some(format!("ICMP discovery unavailable, falling back to TCP probe: {}", reason)),
+                ),
+            },
+            DiscoveryMethod::Tcp => (DiscoveryMethod::Tcp, None),
+        }
+    }
 
-        for ip in ips {
-            let ip_str = ip.to_string();
-            let state_clone = state.clone();
-            let hosts_found_clone = hosts_found.clone();
-            let sem_clone = sem.clone();
-
-            futures.push(tokio::spawn(async move {
-                let _permit = sem_clone.acquire_owned().await.unwrap();
-                if Self::is_host_alive(&ip_str).await {
-                    let hostname = Self::resolve_hostname(&ip_str).await;
-
-                    let mut host = match repository::get_host(&state_clone.db, &ip_str).await {
-                        Ok(Some(existing)) => existing,
-                        _ => Host::new(ip_str.clone()),
-                    };
-                    host.hostname = hostname;
-                    host.status = HostStatus::Up;
-                    host.update_last_seen();
-
-                    if let Err(e) = repository::upsert_host(&state_clone.db, &host).await {
-                        tracing::error!("Failed to save host {}: {}", ip_str, e);
-                    } else {
-                        let _ = state_clone.broadcaster.send(format!("host_found:{}", ip_str));
-                        let mut count = hosts_found_clone.lock().await;
-                        *count += 1;
-                    }
+    /// Probe-based host discovery (fallback when ARP is unavailable).
+    /// `groups` holds the addresses of each target network; the per-network
+    /// concurrency cap applies within a single group on top of the global limit.
+    async fn probe_discover(groups: Vec<Vec<Ipv4Addr>>, ctx: &ScanContext, method: DiscoveryMethod) -> usize {
+        let max_threads = max_discovery_threads();
+        let per_network = ctx.config.per_network_concurrency.unwrap_or(max_threads);
+        let confirmations = ctx.config.alive_confirmations();
+        let batch = Arc::new(HostBatch::new(ctx));
+        let rtt = Arc::new(RttEstimator::new(Self::ALIVE_PROBE_TIMEOUT, ctx.config.adaptive_timeout.clone()));
+        let ctx = ctx.clone();
+
+        let probe_batch = batch.clone();
+        Self::probe_bounded(groups, max_threads, per_network, move |ip| {
+            let ctx = ctx.clone();
+            let batch = probe_batch.clone();
+            let rtt = rtt.clone();
+            async move {
+                let ip_str = ip.to_string();
+                // An echo reply counts as one confirmation; hosts that drop ICMP
+                // still get the full TCP check
+                let icmp_alive = method == DiscoveryMethod::Icmp
+                    && IcmpPinger::ping(ip, Duration::from_secs(1)).await;
+                let still_needed = confirmations - usize::from(icmp_alive);
+                if still_needed > 0 && !Self::is_host_alive(&ip_str, still_needed, &rtt).await {
+                    return false;
                 }
-            }));
+                let hostname = Self::lookup_hostname(&ctx, &ip_str).await;
+
+                let is_new = !matches!(ctx.repo.get_host(&ip_str).await, Ok(Some(_)));
+
+                // Only what discovery found out; a failed lookup keeps whatever
+                // name the host already had
+                let mut host = Host::new(ip_str);
+                host.hostname = hostname;
+                // The probe's connection leaves an ARP entry for local-subnet hosts
+                host.mac_address = arp_table::lookup(ip).await;
+                host.status = HostStatus::Up;
+
+                batch.push(host, is_new).await;
+                true
+            }
+        })
+        .await;
+
+        // Hosts that failed to save don't count as found
+        batch.flush().await
+    }
+
+    /// Run `probe` for every address, bounded by `global_limit` overall and by
+    /// `per_network_limit` within each group. Returns how many probes succeeded.
+    /// Dropping the future (e.g. on a job timeout) aborts the outstanding probes.
+    async fn probe_bounded<F, Fut>(
+        groups: Vec<Vec<Ipv4Addr>>,
+        global_limit: usize,
+        per_network_limit: usize,
+        probe: F,
+    ) -> usize
+    where
+        F: Fn(Ipv4Addr) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        let global_sem = Arc::new(Semaphore::new(global_limit.max(1)));
+        let found = Arc::new(AtomicUsize::new(0));
+        let mut probes = JoinSet::new();
+
+        for group in groups {
+            let network_sem = Arc::new(Semaphore::new(per_network_limit.max(1)));
+            for ip in group {
+                let global_sem = global_sem.clone();
+                let network_sem = network_sem.clone();
+                let found = found.clone();
+                let probe = probe.clone();
+
+                probes.spawn(async move {
+                    // Take the network slot first so a saturated segment doesn't hold global slots
+                    let _network_permit = network_sem.acquire_owned().await.unwrap();
+                    let _permit = global_sem.acquire_owned().await.unwrap();
+                    if probe(ip).await {
+                        found.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        }
+
+        while probes.join_next().await.is_some() {}
+        found.load(Ordering::Relaxed)
+    }
+
+    /// Parse a discovery target: `self` or a comma-separated list of IPv4 CIDRs.
+    fn parse_targets(target: &str) -> Result<Vec<Ipv4Net>, ScanError> {
+        let networks = if target == "self" {
+            vec![Self::detect_local_network()?]
+        } else {
+            target
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|cidr| cidr.parse::<IpNet>().map_err(|_| ScanError::InvalidCidr(cidr.to_string())))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        if networks.is_empty() {
+            return Err(ScanError::InvalidCidr(target.to_string()));
         }
 
-        while futures.next().await.is_some() {}
-        *hosts_found.lock().await
+        networks
+            .into_iter()
+            .map(|net| match net {
+                IpNet::V4(v4) => Ok(v4),
+                IpNet::V6(_) => Err(ScanError::InvalidTarget("IPv6 scanning not supported".to_string())),
+            })
+            .collect()
+    }
+
+    /// Addresses to probe, one group per target network so per-network limits can
+    /// be enforced. Network and broadcast addresses are skipped, except for /31
+    /// (both addresses) and /32 (the single address).
+    fn target_groups(networks: &[Ipv4Net]) -> Vec<Vec<Ipv4Addr>> {
+        networks.iter().map(|net| net.hosts().collect()).collect()
+    }
+
+    /// Drop addresses covered by `scan_config.exclusions`.
+    fn without_exclusions(groups: Vec<Vec<Ipv4Addr>>, exclusions: &[IpNet]) -> Vec<Vec<Ipv4Addr>> {
+        if exclusions.is_empty() {
+            return groups;
+        }
+        groups
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .filter(|&ip| {
+                        let excluded = exclusions.iter().any(|net| net.contains(&IpAddr::V4(ip)));
+                        if excluded {
+                            tracing::debug!("Skipping excluded address {}", ip);
+                        }
+                        !excluded
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// PTR name for a discovered host, unless `scan_config.reverse_dns` is off.
+    /// NXDOMAIN and lookups slower than `REVERSE_DNS_TIMEOUT` give `None`.
+    async fn lookup_hostname(ctx: &ScanContext, ip: &str) -> Option<String> {
+        if !ctx.config.reverse_dns() {
+            return None;
+        }
+        tokio::time::timeout(Self::REVERSE_DNS_TIMEOUT, Self::resolve_hostname(ip))
+            .await
+            .ok()
+            .flatten()
     }
 
     /// Reverse DNS lookup for a host IP.
@@ -281,51 +510,265 @@ impl NetworkScanner {
         None
     }
 
-    pub fn detect_local_network() -> Result<IpNet, String> {
+    pub fn detect_local_network() -> Result<IpNet, ScanError> {
         Self::detect_local_interface_info()
             .map(|(_, _, _, net)| IpNet::V4(net))
-            .ok_or_else(|| "No suitable local network interface found".to_string())
-    }
-
-    async fn is_host_alive(ip: &str) -> bool {
-        let ports = [
-            80, 443, 8080, 8443,
-            22, 23,
-            21,
-            25, 587,
-            445, 139,
-            3389,
-            3306, 5432,
-            6379,
-            9100,
-            1883, 8883,
-            5000, 8888,
-        ];
+            .ok_or(ScanError::NoNetwork)
+    }
+
+    /// Longest a reverse DNS lookup may hold up a discovered host.
+    const REVERSE_DNS_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Ports probed by the TCP alive check.
+    const ALIVE_PROBE_PORTS: [u16; 20] = [
+        80, 443, 8080, 8443,
+        22, 23,
+        21,
+        25, 587,
+        445, 139,
+        3389,
+        3306, 5432,
+        6379,
+        9100,
+        1883, 8883,
+        5000, 8888,
+    ];
+
+    /// Starting connect timeout of the TCP alive check, before `rtt` has samples.
+    const ALIVE_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    async fn is_host_alive(ip: &str, confirmations: usize, rtt: &Arc<RttEstimator>) -> bool {
+        Self::count_responding_ports(ip, &Self::ALIVE_PROBE_PORTS, confirmations, rtt).await >= confirmations
+    }
 
+    /// Probe `ports` concurrently and count how many accept a connection,
+    /// stopping as soon as `enough` have answered. Answers, refusals included,
+    /// feed `rtt`, which also sets the connect timeout.
+    async fn count_responding_ports(ip: &str, ports: &[u16], enough: usize, rtt: &Arc<RttEstimator>) -> usize {
         let mut handles = Vec::new();
-        for port in ports {
+        for &port in ports {
             let addr = format!("{}:{}", ip, port);
+            let rtt = rtt.clone();
             handles.push(tokio::spawn(async move {
-                tokio::time::timeout(
-                    Duration::from_millis(500),
-                    tokio::net::TcpStream::connect(&addr),
-                )
-                .await
-                .map(|r| r.is_ok())
-                .unwrap_or(false)
+                let started = std::time::Instant::now();
+                match tokio::time::timeout(rtt.timeout(), tokio::net::TcpStream::connect(&addr)).await {
+                    Ok(Ok(_)) => {
+                        rtt.record(started.elapsed());
+                        true
+                    }
+                    Ok(Err(e)) => {
+                        if e.kind() == std::io::ErrorKind::ConnectionRefused {
+                            rtt.record(started.elapsed());
+                        }
+                        false
+                    }
+                    Err(_) => false,
+                }
             }));
         }
 
+        let mut responding = 0;
         for handle in handles {
             if let Ok(true) = handle.await {
-                return true;
+                responding += 1;
+                if responding >= enough {
+                    break;
+                }
             }
         }
-        false
+        responding
     }
 
-    fn log_and_broadcast(state: &Arc<AppState>, message: &str) {
+    fn log_and_broadcast(ctx: &ScanContext, message: &str) {
         tracing::info!("{}", message);
-        let _ = state.broadcaster.send(format!("log:{}", message));
+        ctx.events.send(WsEvent::log(message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::models::ScanConfig;
+
+    #[test]
+    fn parse_targets_accepts_multiple_cidrs() {
+        let nets = NetworkScanner::parse_targets("10.0.0.0/30, 10.0.1.0/30").unwrap();
+        assert_eq!(nets.len(), 2);
+        assert_eq!(nets[1].to_string(), "10.0.1.0/30");
+    }
+
+    #[test]
+    fn parse_targets_rejects_invalid_entries() {
+        assert!(matches!(NetworkScanner::parse_targets("10.0.0.0/24,nope"), Err(ScanError::InvalidCidr(c)) if c == "nope"));
+        assert!(matches!(NetworkScanner::parse_targets(""), Err(ScanError::InvalidCidr(_))));
+        assert!(matches!(NetworkScanner::parse_targets("fe80::/64"), Err(ScanError::InvalidTarget(_))));
+    }
+
+    fn scanned(target: &str) -> Vec<Ipv4Addr> {
+        let nets = NetworkScanner::parse_targets(target).unwrap();
+        NetworkScanner::target_groups(&nets).concat()
+    }
+
+    fn excluding(target: &str, exclusions: &[&str]) -> Vec<Ipv4Addr> {
+        let nets = NetworkScanner::parse_targets(target).unwrap();
+        let exclusions: Vec<IpNet> = exclusions.iter().filter_map(|e| crate::models::parse_exclusion(e)).collect();
+        NetworkScanner::without_exclusions(NetworkScanner::target_groups(&nets), &exclusions).concat()
+    }
+
+    #[test]
+    fn single_ip_exclusion_is_not_scanned() {
+        let ips = excluding("192.168.1.0/29", &["192.168.1.1"]);
+        assert_eq!(ips.len(), 5);
+        assert!(!ips.contains(&Ipv4Addr::new(192, 168, 1, 1)));
+    }
+
+    #[test]
+    fn cidr_exclusion_removes_its_part_of_the_range() {
+        let ips = excluding("10.0.0.0/24, 10.0.1.0/30", &["10.0.0.0/25"]);
+        let ip = |s: &str| s.parse::<Ipv4Addr>().unwrap();
+        let mut expected = range(ip("10.0.0.128"), ip("10.0.0.254"));
+        expected.extend([ip("10.0.1.1"), ip("10.0.1.2")]);
+        assert_eq!(ips, expected);
+    }
+
+    fn range(first: Ipv4Addr, last: Ipv4Addr) -> Vec<Ipv4Addr> {
+        (u32::from(first)..=u32::from(last)).map(Ipv4Addr::from).collect()
+    }
+
+    #[test]
+    fn target_groups_cover_exact_host_range_for_any_prefix() {
+        let ip = |s: &str| s.parse::<Ipv4Addr>().unwrap();
+
+        assert_eq!(scanned("192.168.1.0/24"), range(ip("192.168.1.1"), ip("192.168.1.254")));
+        assert_eq!(scanned("192.168.1.128/25"), range(ip("192.168.1.129"), ip("192.168.1.254")));
+        assert_eq!(scanned("10.0.2.0/23"), range(ip("10.0.2.1"), ip("10.0.3.254")));
+        assert_eq!(scanned("10.0.2.0/23").len(), 510);
+        assert_eq!(scanned("10.9.8.7/32"), [ip("10.9.8.7")]);
+        assert_eq!(scanned("10.9.8.6/31"), [ip("10.9.8.6"), ip("10.9.8.7")]);
+    }
+
+    #[test]
+    fn target_groups_use_the_network_of_a_host_address() {
+        // A CIDR written with a host address still scans its whole network
+        assert_eq!(scanned("172.16.5.77/25"), scanned("172.16.5.0/25"));
+    }
+
+    #[tokio::test]
+    async fn single_open_port_is_not_enough_when_two_confirmations_are_required() {
+        let first = tokio::net::TcpListener::bind("127.0.0.5:0").await.unwrap();
+        let second = tokio::net::TcpListener::bind("127.0.0.5:0").await.unwrap();
+        let closed = {
+            let l = tokio::net::TcpListener::bind("127.0.0.5:0").await.unwrap();
+            l.local_addr().unwrap().port()
+        };
+        let first = first.local_addr().unwrap().port();
+        let second_port = second.local_addr().unwrap().port();
+
+        let rtt = Arc::new(RttEstimator::new(NetworkScanner::ALIVE_PROBE_TIMEOUT, Default::default()));
+
+        // Only one port answers: a decoy under the two-confirmation rule
+        let ports = [first, closed];
+        assert_eq!(NetworkScanner::count_responding_ports("127.0.0.5", &ports, 2, &rtt).await, 1);
+        // ...but still alive under the default single-port rule
+        assert_eq!(NetworkScanner::count_responding_ports("127.0.0.5", &ports, 1, &rtt).await, 1);
+
+        // A second port confirms
+        let ports = [first, closed, second_port];
+        assert_eq!(NetworkScanner::count_responding_ports("127.0.0.5", &ports, 2, &rtt).await, 2);
+        drop(second);
+    }
+
+    #[test]
+    fn icmp_is_used_only_when_configured_and_available() {
+        let (method, fallback) = NetworkScanner::select_discovery_method(DiscoveryMethod::Icmp, || Ok(()));
+        assert_eq!(method, DiscoveryMethod::Icmp);
+        assert!(fallback.is_none());
+
+        let (method, fallback) = NetworkScanner::select_discovery_method(DiscoveryMethod::Tcp, || Ok(()));
+        assert_eq!(method, DiscoveryMethod::Tcp);
+        assert!(fallback.is_none());
+
+        let (method, fallback) =
+            NetworkScanner::select_discovery_method(DiscoveryMethod::Tcp, || panic!("ICMP checked without being configured"));
+        assert_eq!(method, DiscoveryMethod::Tcp);
+        assert!(fallback.is_none());
+    }
+
+    #[test]
+    fn icmp_falls_back_to_tcp_with_a_reason_when_raw_sockets_are_unavailable() {
+        let (method, fallback) = NetworkScanner::select_discovery_method(
+            DiscoveryMethod::Icmp,
+            || Err("Operation not permitted".into()),
+        );
+        assert_eq!(method, DiscoveryMethod::Tcp);
+        let reason = fallback.unwrap();
+        assert!(reason.contains("falling back to TCP"));
+        assert!(reason.contains("Operation not permitted"));
+    }
+
+    #[tokio::test]
+    async fn reverse_dns_resolves_names_and_can_be_turned_off() {
+        // localhost comes from /etc/hosts, so no DNS server is involved
+        assert_eq!(NetworkScanner::resolve_hostname("127.0.0.1").await.as_deref(), Some("localhost"));
+
+        let repo = Arc::new(crate::db::inmemory_repository::InMemoryRepository::new());
+        let ctx = |reverse_dns| {
+            let config = ScanConfig { reverse_dns, ..Default::default() };
+            ScanContext::new(repo.clone(), crate::services::EventSink::noop(), config)
+        };
+        assert_eq!(NetworkScanner::lookup_hostname(&ctx(None), "127.0.0.1").await.as_deref(), Some("localhost"));
+        assert_eq!(NetworkScanner::lookup_hostname(&ctx(Some(false)), "127.0.0.1").await, None);
+    }
+
+    #[test]
+    fn alive_confirmations_defaults_to_one() {
+        assert_eq!(ScanConfig::default().alive_confirmations(), 1);
+        let cfg = ScanConfig { alive_confirmations: Some(0), ..Default::default() };
+        assert_eq!(cfg.alive_confirmations(), 1);
+    }
+
+    #[tokio::test]
+    async fn per_network_cap_limits_concurrency_within_a_subnet() {
+        let nets = NetworkScanner::parse_targets("10.0.0.0/27,10.0.1.0/27").unwrap();
+        let groups = NetworkScanner::target_groups(&nets);
+
+        // (in-flight, peak) per third octet, plus overall peak
+        let per_net: Arc<Mutex<HashMap<u8, (usize, usize)>>> = Arc::new(Mutex::new(HashMap::new()));
+        let overall = Arc::new(Mutex::new((0usize, 0usize)));
+
+        let probe = {
+            let per_net = per_net.clone();
+            let overall = overall.clone();
+            move |ip: Ipv4Addr| {
+                let per_net = per_net.clone();
+                let overall = overall.clone();
+                async move {
+                    let net = ip.octets()[2];
+                    {
+                        let mut map = per_net.lock().unwrap();
+                        let entry = map.entry(net).or_default();
+                        entry.0 += 1;
+                        entry.1 = entry.1.max(entry.0);
+                        let mut all = overall.lock().unwrap();
+                        all.0 += 1;
+                        all.1 = all.1.max(all.0);
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    per_net.lock().unwrap().get_mut(&net).unwrap().0 -= 1;
+                    overall.lock().unwrap().0 -= 1;
+                    true
+                }
+            }
+        };
+
+        let found = NetworkScanner::probe_bounded(groups, 64, 3, probe).await;
+
+        assert_eq!(found, 60);
+        for (net, (_, peak)) in per_net.lock().unwrap().iter() {
+            assert!(*peak <= 3, "network {} peaked at {} concurrent probes", net, peak);
+        }
+        // Both segments are still probed in parallel
+        assert!(overall.lock().unwrap().1 > 3);
     }
 }
@@ -0,0 +1,131 @@
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use crate::services::{ScanContext, ScanError};
+
+/// How often a running subprocess checks whether its job was cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether `job_id` has been cancelled, or failed by the stuck-job watchdog
+/// while still running. Unknown jobs are treated as still running.
+pub async fn is_cancelled(ctx: &ScanContext, job_id: &str) -> bool {
+    matches!(ctx.repo.get_job(job_id).await, Ok(Some(job)) if job.is_cancelled() || job.is_failed())
+}
+
+/// Run `cmd` to completion like `Command::output`, but kill the child as soon as
+/// `job_id` is cancelled so no scanning continues after the job has stopped.
+///
+/// Returns `Err(ScanError::Cancelled)` when the child was killed.
+pub async fn run_cancellable(mut cmd: Command, ctx: &ScanContext, job_id: &str) -> Result<Output, ScanError> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Backstop: if this future is dropped, the child goes with it
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Drain both pipes concurrently so a chatty child never blocks on a full pipe
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf).await;
+        buf
+    });
+    let stderr_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf).await;
+        buf
+    });
+
+    let cancelled = async {
+        loop {
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+            if is_cancelled(ctx, job_id).await {
+                break;
+            }
+        }
+    };
+
+    tokio::select! {
+        status = child.wait() => {
+            let status = status?;
+            Ok(Output {
+                status,
+                stdout: stdout_task.await.unwrap_or_default(),
+                stderr: stderr_task.await.unwrap_or_default(),
+            })
+        }
+        _ = cancelled => {
+            tracing::info!("Job {} cancelled, killing subprocess (pid {:?})", job_id, child.id());
+            if let Err(e) = child.kill().await {
+                tracing::warn!("Failed to kill subprocess for job {}: {}", job_id, e);
+            }
+            stdout_task.abort();
+            stderr_task.abort();
+            Err(ScanError::Cancelled)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use crate::db::inmemory_repository::InMemoryRepository;
+    use crate::db::repository_trait::Repository;
+    use crate::models::{Job, JobStatus, ScanConfig};
+    use crate::services::EventSink;
+
+    fn ctx(repo: Arc<InMemoryRepository>) -> ScanContext {
+        ScanContext::new(repo, EventSink::noop(), ScanConfig::default())
+    }
+
+    #[tokio::test]
+    async fn completes_normally_when_not_cancelled() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2"]);
+
+        let output = run_cancellable(cmd, &ctx(repo), "no-such-job").await.unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_job_kills_the_child() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let job = Job::new("nmap-scan".to_string());
+        repo.create_job(&job).await.unwrap();
+        repo.update_job_status(&job.id, JobStatus::Running).await.unwrap();
+
+        // The child records its pid, then runs far longer than the test
+        let pid_file = std::env::temp_dir().join(format!("decebalus-subprocess-{}", job.id));
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &format!("echo $$ > {}; exec sleep 30", pid_file.display())]);
+
+        let started = Instant::now();
+        let run = {
+            let ctx = ctx(repo.clone());
+            let job_id = job.id.clone();
+            tokio::spawn(async move { run_cancellable(cmd, &ctx, &job_id).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        repo.update_job_status(&job.id, JobStatus::Cancelled).await.unwrap();
+
+        let result = run.await.unwrap();
+        assert!(matches!(result.unwrap_err(), ScanError::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        // kill() reaps the child, so its /proc entry is gone
+        assert!(!std::path::Path::new(&format!("/proc/{}", pid.trim())).exists());
+    }
+}
@@ -0,0 +1,232 @@
+//! CVE matching for detected services. A `CveSource` answers "which CVEs
+//! mention this product and version"; `VulnLookup` sits in front of it and
+//! caches the answers, so a fleet of hosts running the same sshd costs one query.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use crate::models::{Service, Vulnerability};
+use crate::services::{fingerprint, ScanError};
+
+/// NVD CVE API 2.0.
+pub const NVD_API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+
+/// NVD allows 5 requests per 30s without an API key and 50 with one.
+const NVD_INTERVAL: Duration = Duration::from_secs(6);
+const NVD_INTERVAL_WITH_KEY: Duration = Duration::from_millis(600);
+const NVD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a lookup result is reused before asking the source again.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Where CVEs come from: the NVD API, a local feed, or a stub in tests.
+#[async_trait]
+pub trait CveSource: Send + Sync {
+    /// CVEs affecting `product` at `version`, e.g. `("OpenSSH", "7.2p2")`.
+    async fn lookup(&self, product: &str, version: &str) -> Result<Vec<Vulnerability>, ScanError>;
+}
+
+/// Keyword search against the NVD API, spaced out to stay under its rate limit.
+pub struct NvdSource {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    interval: Duration,
+    /// When the last request went out; held while waiting so requests queue up
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl NvdSource {
+    pub fn new(url: &str, api_key: Option<String>) -> Self {
+        let interval = if api_key.is_some() { NVD_INTERVAL_WITH_KEY } else { NVD_INTERVAL };
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key,
+            interval,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// `NVD_API_URL` overrides the endpoint (e.g. a mirror); `NVD_API_KEY`
+    /// raises the rate limit.
+    pub fn from_env() -> Self {
+        let url = std::env::var("NVD_API_URL").unwrap_or_else(|_| NVD_API_URL.to_string());
+        let api_key = std::env::var("NVD_API_KEY").ok().filter(|k| !k.is_empty());
+        Self::new(&url, api_key)
+    }
+
+    async fn wait_turn(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(at) = *last {
+            tokio::time::sleep_until((at + self.interval).into()).await;
+        }
+        *last = Some(Instant::now());
+    }
+}
+
+#[async_trait]
+impl CveSource for NvdSource {
+    async fn lookup(&self, product: &str, version: &str) -> Result<Vec<Vulnerability>, ScanError> {
+        self.wait_turn().await;
+
+        let keywords = format!("{} {}", product, version);
+        let mut request = self
+            .client
+            .get(&self.url)
+            .query(&[("keywordSearch", keywords.as_str())])
+            .timeout(NVD_TIMEOUT);
+        if let Some(key) = &self.api_key {
+            request = request.header("apiKey", key);
+        }
+
+        let body: Value = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ScanError::CveLookup(format!("NVD lookup for '{}' failed: {}", keywords, e)))?
+            .json()
+            .await
+            .map_err(|e| ScanError::CveLookup(format!("NVD returned an unreadable response: {}", e)))?;
+
+        Ok(parse_nvd_response(&body))
+    }
+}
+
+/// Vulnerabilities in an NVD 2.0 `cves` response. The CVSS score comes from
+/// the newest metric version present (3.1, then 3.0, then 2).
+pub fn parse_nvd_response(body: &Value) -> Vec<Vulnerability> {
+    let Some(items) = body["vulnerabilities"].as_array() else { return Vec::new() };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let cve = &item["cve"];
+            let id = cve["id"].as_str()?.to_string();
+            let description = cve["descriptions"]
+                .as_array()
+                .and_then(|d| d.iter().find(|d| d["lang"] == "en").or_else(|| d.first()))
+                .and_then(|d| d["value"].as_str())
+                .unwrap_or_default()
+                .to_string();
+
+            let metric = ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"]
+                .iter()
+                .find_map(|key| cve["metrics"][key].as_array().and_then(|m| m.first()));
+            let cvss_score = metric.and_then(|m| m["cvssData"]["baseScore"].as_f64()).map(|s| s as f32);
+            // v3 keeps the severity inside cvssData, v2 next to it
+            let severity = metric
+                .and_then(|m| m["cvssData"]["baseSeverity"].as_str().or_else(|| m["baseSeverity"].as_str()))
+                .unwrap_or("UNKNOWN")
+                .to_string();
+
+            Some(Vulnerability { id, description, severity, cvss_score, service: None })
+        })
+        .collect()
+}
+
+/// Product and version to search for, from a detected service's version string.
+/// `None` without a version number: a bare product name matches far too much.
+pub fn product_and_version(service: &Service) -> Option<(String, String)> {
+    fingerprint::split_product_version(service.version.as_deref()?)
+}
+
+/// Cached CVE lookups for services, shared through `AppState`.
+pub struct VulnLookup {
+    source: Arc<dyn CveSource>,
+    cache: Mutex<HashMap<String, (Instant, Vec<Vulnerability>)>>,
+}
+
+impl VulnLookup {
+    pub fn new(source: Arc<dyn CveSource>) -> Self {
+        Self { source, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// CVEs for `service`, tagged with the software they were matched on.
+    /// Services without a usable version yield nothing.
+    pub async fn for_service(&self, service: &Service) -> Result<Vec<Vulnerability>, ScanError> {
+        let Some((product, version)) = product_and_version(service) else { return Ok(Vec::new()) };
+        let key = format!("{} {}", product, version).to_lowercase();
+
+        if let Some((at, vulns)) = self.cache.lock().await.get(&key)
+            && at.elapsed() < CACHE_TTL
+        {
+            return Ok(vulns.clone());
+        }
+
+        let matched = format!("{} {}", product, version);
+        let vulns: Vec<Vulnerability> = self
+            .source
+            .lookup(&product, &version)
+            .await?
+            .into_iter()
+            .map(|v| Vulnerability { service: Some(matched.clone()), ..v })
+            .collect();
+        self.cache.lock().await.insert(key, (Instant::now(), vulns.clone()));
+        Ok(vulns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn service(version: &str) -> Service {
+        Service::new("x", Some(version.to_string()), None)
+    }
+
+    #[test]
+    fn splits_product_and_version() {
+        let pv = |v: &str| product_and_version(&service(v));
+        assert_eq!(pv("OpenSSH 8.2p1"), Some(("OpenSSH".into(), "8.2p1".into())));
+        assert_eq!(pv("Apache/2.4.41 (Ubuntu)"), Some(("Apache".into(), "2.4.41".into())));
+        assert_eq!(pv("Apache httpd 2.4.41"), Some(("Apache httpd".into(), "2.4.41".into())));
+        assert_eq!(pv("Sendmail 8.15.2/8.15.2"), Some(("Sendmail".into(), "8.15.2".into())));
+        assert_eq!(pv("Postfix"), None);
+        assert_eq!(pv("nginx"), None);
+        assert_eq!(product_and_version(&Service::new("ssh", None, None)), None);
+    }
+
+    #[test]
+    fn parses_nvd_response() {
+        let body = json!({
+            "vulnerabilities": [
+                { "cve": {
+                    "id": "CVE-2016-6210",
+                    "descriptions": [
+                        { "lang": "es", "value": "sshd en OpenSSH ..." },
+                        { "lang": "en", "value": "sshd in OpenSSH before 7.3 ..." }
+                    ],
+                    "metrics": {
+                        "cvssMetricV31": [{ "cvssData": { "baseScore": 5.9, "baseSeverity": "MEDIUM" } }],
+                        "cvssMetricV2": [{ "cvssData": { "baseScore": 4.3 }, "baseSeverity": "MEDIUM" }]
+                    }
+                }},
+                { "cve": {
+                    "id": "CVE-2008-5161",
+                    "descriptions": [{ "lang": "en", "value": "Error handling in the SSH protocol ..." }],
+                    "metrics": { "cvssMetricV2": [{ "cvssData": { "baseScore": 2.6 }, "baseSeverity": "LOW" }] }
+                }},
+                { "cve": { "id": "CVE-2099-0001", "descriptions": [] } }
+            ]
+        });
+
+        let vulns = parse_nvd_response(&body);
+        assert_eq!(vulns.len(), 3);
+        assert_eq!(vulns[0].id, "CVE-2016-6210");
+        assert_eq!(vulns[0].description, "sshd in OpenSSH before 7.3 ...");
+        assert_eq!(vulns[0].cvss_score, Some(5.9));
+        assert_eq!(vulns[0].severity, "MEDIUM");
+        assert_eq!(vulns[1].cvss_score, Some(2.6));
+        assert_eq!(vulns[1].severity, "LOW");
+        // Not yet analysed: no metrics
+        assert_eq!(vulns[2].cvss_score, None);
+        assert_eq!(vulns[2].severity, "UNKNOWN");
+
+        assert!(parse_nvd_response(&json!({ "message": "rate limited" })).is_empty());
+    }
+}
@@ -1,25 +1,48 @@
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use tokio::sync::{Semaphore, broadcast};
+use tokio::sync::Semaphore;
 use crate::db::DbPool;
+use crate::db::repository_trait::Repository;
+use crate::services::EventBus;
+use crate::services::config_cache::ConfigCache;
+use crate::services::job_tasks::JobTasks;
+use crate::services::notifier::Notifiers;
+use crate::services::vuln_lookup::{NvdSource, VulnLookup};
 
 #[derive(Clone)]
 pub struct AppState {
-    /// Broadcast channel for real-time events (WebSocket)
-    pub broadcaster: broadcast::Sender<String>,
+    /// Real-time events for WebSocket clients and notifiers, with recent history
+    pub broadcaster: EventBus,
     
-    /// Database connection pool
-    pub db: DbPool,
+    /// SQLite pool behind `repo`, `None` on Postgres.
+    /// Reads and writes go through `repo`.
+    pub db: Option<DbPool>,
+    /// Storage used by handlers and workers
+    pub repo: Arc<dyn Repository>,
+    /// Config as last read or stored, for jobs to read without a query
+    pub config: Arc<ConfigCache>,
     pub max_threads: usize,
     pub max_scan_concurrency: usize,
     pub semaphore: Arc<Semaphore>,
+    /// Running job executions, awaited on shutdown
+    pub jobs: Arc<JobTasks>,
+    /// Set by `POST /api/queue/pause`; queued jobs don't start until resumed
+    pub queue_paused: Arc<AtomicBool>,
+
+    /// Integrations driven by the notification hub
+    pub notifiers: Arc<Notifiers>,
+
+    /// Cached CVE lookups for `vuln-scan` jobs
+    pub vulns: Arc<VulnLookup>,
+
+    /// Bearer token required on `/api/*` and `/ws` (`API_TOKEN`); `None` leaves them open
+    pub api_token: Option<String>,
 }
 
 impl AppState {
     /// Create a new AppState
-    pub fn new(db: DbPool) -> Self {
-        let (tx, _rx) = broadcast::channel(100);
-
+    pub fn new(db: Option<DbPool>, repo: Arc<dyn Repository>) -> Self {
         let max_threads = std::env::var("MAX_THREADS")
             .ok()
             .and_then(|s| s.parse::<usize>().ok())
@@ -30,12 +53,21 @@ impl AppState {
             .and_then(|s| s.parse::<usize>().ok())
             .unwrap_or(500);
 
+        let api_token = std::env::var("API_TOKEN").ok().filter(|t| !t.is_empty());
+
         Self {
-            broadcaster: tx,
+            broadcaster: EventBus::new(100),
             db,
+            repo,
+            config: Arc::new(ConfigCache::new()),
             max_threads,
             max_scan_concurrency,
             semaphore: Arc::new(Semaphore::new(max_threads)),
+            jobs: Arc::new(JobTasks::new()),
+            queue_paused: Arc::new(AtomicBool::new(false)),
+            notifiers: Arc::new(Notifiers::new()),
+            vulns: Arc::new(VulnLookup::new(Arc::new(NvdSource::from_env()))),
+            api_token,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+// tests/attack_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use decebalus_backend::models::{Config, Host, Job, JobStatus, Vulnerability};
+use decebalus_backend::services::job_executor::JobExecutor;
+use decebalus_backend::state::AppState;
+
+fn vuln(id: &str) -> Vulnerability {
+    Vulnerability {
+        id: id.into(),
+        description: id.into(),
+        severity: "HIGH".into(),
+        cvss_score: None,
+        service: None,
+    }
+}
+
+async fn run(state: &Arc<AppState>, job: Job) -> Job {
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+    state.repo.get_job(&job.id).await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn attack_job_is_refused_unless_enabled() {
+    let state = common::in_memory_state();
+
+    let job = run(&state, Job::new("attack".into())).await;
+
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.results.unwrap().error().unwrap().contains("attacks.enabled"));
+}
+
+#[tokio::test]
+async fn attack_job_checks_open_ssh_ports_when_enabled() {
+    let state = common::in_memory_state();
+    let mut config = Config::new();
+    config.set("attacks".to_string(), json!({ "enabled": true }));
+    state.repo.update_config(&config).await.unwrap();
+
+    // Listens like an SSH port but hangs up without a banner
+    let listener = std::net::TcpListener::bind("127.0.0.30:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for socket in listener.incoming() {
+            drop(socket);
+        }
+    });
+    let mut host = Host::new("127.0.0.30".into());
+    host.add_port(port, "tcp", "open", Some("ssh".into()), None, None);
+    state.repo.upsert_host(&host).await.unwrap();
+    state.repo.upsert_host(&Host::new("127.0.0.31".into())).await.unwrap();
+
+    let job = run(&state, Job::new("attack".into())).await;
+
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.results);
+    let results = job.results.unwrap().success().unwrap().clone();
+    assert_eq!(results["hosts_scanned"], 2);
+    assert_eq!(results["services_checked"], 1);
+    assert_eq!(results["vulnerabilities_found"], 0);
+}
+
+#[tokio::test]
+async fn attack_job_keeps_host_changes_made_while_it_runs() {
+    let state = common::test_state().await;
+    let mut config = Config::new();
+    config.set("attacks".to_string(), json!({ "enabled": true }));
+    state.repo.update_config(&config).await.unwrap();
+
+    // Holds the first connection open, so the SSH check waits for a banner until released
+    let listener = std::net::TcpListener::bind("127.0.0.32:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (connected_tx, connected_rx) = std::sync::mpsc::channel();
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        for (n, socket) in listener.incoming().enumerate() {
+            if n == 0 {
+                let _ = connected_tx.send(());
+                let _ = release_rx.recv();
+            }
+            drop(socket);
+        }
+    });
+    let mut host = Host::new("127.0.0.32".into());
+    host.add_port(port, "tcp", "open", Some("ssh".into()), None, None);
+    host.vulnerabilities.push(vuln("DEFAULT-CREDS-SSH"));
+    state.repo.upsert_host(&host).await.unwrap();
+
+    let job = Job::new("attack".into());
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    let task = tokio::spawn(JobExecutor::execute_job(job.clone(), state.clone(), permit));
+
+    // A port scan and a vuln scan save the host while the SSH check is pending
+    tokio::task::spawn_blocking(move || connected_rx.recv().unwrap()).await.unwrap();
+    host.add_port(443, "tcp", "open", Some("https".into()), None, None);
+    host.vulnerabilities.push(vuln("CVE-2016-6210"));
+    state.repo.upsert_host(&host).await.unwrap();
+    release_tx.send(()).unwrap();
+    task.await.unwrap();
+
+    assert_eq!(state.repo.get_job(&job.id).await.unwrap().unwrap().status, JobStatus::Completed);
+    let host = state.repo.get_host("127.0.0.32").await.unwrap().unwrap();
+    assert!(host.has_open_port(443));
+    // The SSH check found nothing this time, so only its stale finding is gone
+    let ids: Vec<&str> = host.vulnerabilities.iter().map(|v| v.id.as_str()).collect();
+    assert_eq!(ids, ["CVE-2016-6210"]);
+}
@@ -0,0 +1,62 @@
+// tests/auth_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+
+use decebalus_backend::api;
+use decebalus_backend::AppState;
+
+/// Serve the full router, requiring `token`, on an ephemeral port and return its base URL.
+async fn serve_with_token(token: Option<&str>) -> String {
+    let state = common::test_state().await;
+    let state = Arc::new(AppState { api_token: token.map(String::from), ..(*state).clone() });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, api::router(state)).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn api_requires_the_configured_token() {
+    let base = serve_with_token(Some("s3cret")).await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{}/api/hosts", base)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let response = client.get(format!("{}/api/hosts", base)).bearer_auth("wrong").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client.get(format!("{}/api/hosts", base)).bearer_auth("s3cret").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // WebSocket and SSE clients in a browser can't set headers
+    let response = client.get(format!("{}/ws", base)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = client.get(format!("{}/api/hosts?access_token=s3cret", base)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn health_is_open_even_with_a_token() {
+    let base = serve_with_token(Some("s3cret")).await;
+
+    let response = reqwest::get(format!("{}/health", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["db"], "up");
+}
+
+#[tokio::test]
+async fn api_is_open_without_a_token() {
+    let base = serve_with_token(None).await;
+
+    let response = reqwest::get(format!("{}/api/hosts", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
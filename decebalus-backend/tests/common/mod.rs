@@ -0,0 +1,57 @@
+// tests/common/mod.rs
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use tokio::sync::Semaphore;
+
+use decebalus_backend::db::db_repository::DbRepository;
+use decebalus_backend::db::inmemory_repository::InMemoryRepository;
+use decebalus_backend::db::repository_trait::Repository;
+use decebalus_backend::services::EventBus;
+use decebalus_backend::services::config_cache::ConfigCache;
+use decebalus_backend::services::job_tasks::JobTasks;
+use decebalus_backend::services::notifier::Notifiers;
+use decebalus_backend::services::vuln_lookup::{NvdSource, VulnLookup};
+use decebalus_backend::state::AppState;
+
+/// AppState backed by a migrated in-memory SQLite database.
+#[allow(dead_code)] // not every test binary uses it
+pub async fn test_state() -> Arc<AppState> {
+    let db_pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("failed to create in-memory DB");
+
+    sqlx::migrate!("./migrations")
+        .run(&db_pool)
+        .await
+        .expect("Failed to run migrations");
+
+    let repo = Arc::new(DbRepository::new(db_pool.clone()));
+    state_with(Some(db_pool), repo)
+}
+
+/// AppState whose storage is an `InMemoryRepository`; no database is touched.
+#[allow(dead_code)] // not every test binary uses it
+pub fn in_memory_state() -> Arc<AppState> {
+    state_with(None, Arc::new(InMemoryRepository::new()))
+}
+
+fn state_with(db_pool: Option<sqlx::SqlitePool>, repo: Arc<dyn Repository>) -> Arc<AppState> {
+    Arc::new(AppState {
+        broadcaster: EventBus::new(32),
+        db: db_pool,
+        repo,
+        config: Arc::new(ConfigCache::new()),
+        max_threads: 5,
+        max_scan_concurrency: 500,
+        semaphore: Arc::new(Semaphore::new(5)),
+        jobs: Arc::new(JobTasks::new()),
+        queue_paused: Arc::new(AtomicBool::new(false)),
+        notifiers: Arc::new(Notifiers::new()),
+        vulns: Arc::new(VulnLookup::new(Arc::new(NvdSource::from_env()))),
+        api_token: None,
+    })
+}
@@ -0,0 +1,209 @@
+// tests/config_api_tests.rs
+
+mod common;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+use decebalus_backend::api::config::{export_config, import_config, update_config};
+use decebalus_backend::db::{self, repository};
+use decebalus_backend::models::{Config, WsEvent};
+use decebalus_backend::services::ScanContext;
+
+#[tokio::test]
+async fn update_config_broadcasts_changed_keys() {
+    let state = common::test_state().await;
+
+    let _ = update_config(State(state.clone()), Json(json!({ "device_name": "decebalus-01", "log_level": "info" })))
+        .await
+        .into_response();
+
+    let mut rx = state.broadcaster.subscribe();
+
+    let resp = update_config(
+        State(state.clone()),
+        Json(json!({ "device_name": "decebalus-02", "log_level": "info" })),
+    )
+    .await
+    .into_response();
+    assert!(resp.status().is_success());
+
+    assert_eq!(rx.try_recv().unwrap(), WsEvent::ConfigChanged { keys: vec!["device_name".into()] });
+}
+
+#[tokio::test]
+async fn update_config_reaches_the_next_scan() {
+    let state = common::test_state().await;
+
+    // The first scan loads the config into the cache
+    let ctx = ScanContext::from_state(&state).await;
+    assert_eq!(ctx.config.per_network_concurrency, None);
+
+    let resp = update_config(State(state.clone()), Json(json!({ "scan_config": { "per_network_concurrency": 16 } })))
+        .await
+        .into_response();
+    assert!(resp.status().is_success());
+
+    let ctx = ScanContext::from_state(&state).await;
+    assert_eq!(ctx.config.per_network_concurrency, Some(16));
+}
+
+#[tokio::test]
+async fn update_config_without_changes_is_silent() {
+    let state = common::test_state().await;
+
+    let _ = update_config(State(state.clone()), Json(json!({ "log_level": "info" })))
+        .await
+        .into_response();
+
+    let mut rx = state.broadcaster.subscribe();
+    let _ = update_config(State(state.clone()), Json(json!({ "log_level": "info" })))
+        .await
+        .into_response();
+
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn update_config_rejects_oversized_values() {
+    let state = common::test_state().await;
+
+    let resp = update_config(State(state.clone()), Json(json!({ "motd": "x".repeat(10_000) })))
+        .await
+        .into_response();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let huge: serde_json::Map<String, serde_json::Value> = (0..100)
+        .map(|i| (format!("key{}", i), json!("x".repeat(4000))))
+        .collect();
+    let resp = update_config(State(state.clone()), Json(serde_json::Value::Object(huge)))
+        .await
+        .into_response();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Nothing was stored
+    let config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    assert!(config.get("motd").is_none());
+    assert!(config.get("key0").is_none());
+}
+
+async fn error_fields(resp: axum::response::Response) -> Vec<String> {
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    body["error"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["field"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn update_config_accepts_known_fields_and_extra_keys() {
+    let state = common::test_state().await;
+    let settings = json!({
+        "device_name": "decebalus-01",
+        "scan_config": { "ports": [22, 80, 443], "target": "10.0.0.0/24", "my_note": "kept" },
+        "jobs": { "timeout_secs": 600 }
+    });
+
+    let resp = update_config(State(state.clone()), Json(settings.clone())).await.into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(repository::get_config(state.db.as_ref().unwrap()).await.unwrap().settings, settings);
+}
+
+#[tokio::test]
+async fn update_config_names_invalid_fields() {
+    let state = common::test_state().await;
+    let cases = [
+        (json!({ "scan_config": { "port_range": "ok", "ports": "80" } }), vec!["scan_config.ports"]),
+        (json!({ "scan_config": { "ports": [22], "max_connections_per_second": "fast", "reverse_dns": 1 } }),
+            vec!["scan_config.max_connections_per_second", "scan_config.reverse_dns"]),
+        (json!({ "scan_config": "fast" }), vec!["scan_config"]),
+        (json!({ "scan_config": { "ports": [0] } }), vec!["scan_config.ports"]),
+        (json!({ "jobs": { "timeout_secs": -1 } }), vec!["jobs.timeout_secs"]),
+        (json!({ "webhooks": { "urls": ["ftp://example.com/hook"] } }), vec!["webhooks.urls"]),
+    ];
+
+    for (settings, fields) in cases {
+        let resp = update_config(State(state.clone()), Json(settings.clone())).await.into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", settings);
+        assert_eq!(error_fields(resp).await, fields, "{}", settings);
+    }
+
+    // Nothing was stored
+    assert!(repository::get_config(state.db.as_ref().unwrap()).await.unwrap().settings.as_object().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn exported_config_imports_into_another_instance() {
+    let source = common::test_state().await;
+    let settings = json!({
+        "device_name": "decebalus-01",
+        "scan_config": { "banner_concurrency": 2, "autopilot": { "enabled": false } },
+        "integrations": { "notifiers": ["webhook"] }
+    });
+    let _ = update_config(State(source.clone()), Json(settings.clone())).await.into_response();
+
+    let resp = export_config(State(source.clone())).await.into_response();
+    assert!(resp.status().is_success());
+    assert!(resp.headers()["content-disposition"].to_str().unwrap().starts_with("attachment"));
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let exported: Config = serde_json::from_slice(&body).unwrap();
+
+    let target = common::test_state().await;
+    let resp = import_config(State(target.clone()), Json(exported)).await.into_response();
+    assert!(resp.status().is_success());
+
+    let imported = repository::get_config(target.db.as_ref().unwrap()).await.unwrap();
+    assert_eq!(imported.settings, settings);
+    assert_eq!(imported.scan_config().banner_concurrency, Some(2));
+}
+
+#[tokio::test]
+async fn import_rejects_non_object_settings() {
+    let state = common::test_state().await;
+
+    let resp = import_config(State(state.clone()), Json(Config { settings: json!(["not", "an", "object"]) }))
+        .await
+        .into_response();
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn import_is_validated_like_update() {
+    let state = common::test_state().await;
+    let _ = update_config(State(state.clone()), Json(json!({ "device_name": "decebalus-01" }))).await.into_response();
+
+    let bad = Config { settings: json!({ "scan_config": { "ports": "80" } }) };
+    let resp = import_config(State(state.clone()), Json(bad)).await.into_response();
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_fields(resp).await, ["scan_config.ports"]);
+    // The current config is left in place
+    let config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    assert_eq!(config.settings, json!({ "device_name": "decebalus-01" }));
+}
+
+#[tokio::test]
+async fn fresh_database_is_seeded_with_default_config() {
+    let state = common::test_state().await;
+    assert!(db::seed_default_config(state.repo.as_ref()).await.unwrap());
+
+    let config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    let scan_config = config.get("scan_config").unwrap();
+    for key in ["target", "ports", "adaptive_timeout", "per_network_concurrency", "banner_concurrency", "max_connections_per_second"] {
+        assert!(scan_config.get(key).is_some_and(|v| !v.is_null()), "scan_config.{} not seeded", key);
+    }
+    assert_eq!(scan_config["target"], "self");
+    assert_eq!(scan_config["ports"], json!(["1-65535"]));
+    assert_eq!(scan_config["banner_concurrency"], 1);
+    assert_eq!(config.get("jobs").unwrap()["timeout_secs"], 3600);
+
+    // An existing config is never overwritten
+    let _ = update_config(State(state.clone()), Json(json!({ "device_name": "decebalus-01" }))).await.into_response();
+    assert!(!db::seed_default_config(state.repo.as_ref()).await.unwrap());
+    let config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    assert!(config.get("scan_config").is_none());
+}
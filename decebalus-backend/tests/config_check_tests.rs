@@ -0,0 +1,65 @@
+// tests/config_check_tests.rs
+
+mod common;
+
+use serde_json::json;
+
+use decebalus_backend::config_check;
+use decebalus_backend::db::repository;
+use decebalus_backend::models::Config;
+
+fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+    move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+}
+
+#[tokio::test]
+async fn bad_config_fails_the_check() {
+    let state = common::test_state().await;
+    let config = Config {
+        settings: json!({
+            "scan_config": { "per_network_concurrency": "lots" },
+            "webhooks": { "urls": ["ftp://example.com/hook"] },
+            "integrations": { "notifiers": ["email", "pager"] }
+        }),
+    };
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let report = config_check::run(state.repo.as_ref(), env(&[("MAX_THREADS", "zero"), ("PORT", "http")])).await;
+
+    assert_ne!(report.exit_code(), 0);
+    let problems = report.problems.join("\n");
+    assert!(problems.contains("MAX_THREADS"), "{}", problems);
+    assert!(problems.contains("PORT"), "{}", problems);
+    assert!(problems.contains("scan_config"), "{}", problems);
+    assert!(problems.contains("ftp://example.com/hook"), "{}", problems);
+    assert!(problems.contains("'pager'"), "{}", problems);
+}
+
+#[tokio::test]
+async fn valid_config_passes_the_check() {
+    let state = common::test_state().await;
+    let config = Config {
+        settings: json!({
+            "scan_config": { "banner_concurrency": 2, "banner_parsers": ["ssh", "http"], "exclusions": ["192.168.1.1", "10.0.0.0/28"] },
+            "jobs": { "stuck_after_secs": 3600 },
+            "webhooks": { "urls": ["https://example.com/hook"] },
+            "integrations": { "notifiers": ["webhook"] }
+        }),
+    };
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let report = config_check::run(state.repo.as_ref(), env(&[("MAX_THREADS", "4")])).await;
+
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.exit_code(), 0);
+}
+
+#[tokio::test]
+async fn seeded_default_config_passes_the_check() {
+    let state = common::test_state().await;
+    repository::update_config(state.db.as_ref().unwrap(), &Config::defaults()).await.unwrap();
+
+    let report = config_check::run(state.repo.as_ref(), env(&[])).await;
+
+    assert!(report.is_ok(), "{:?}", report.problems);
+}
@@ -0,0 +1,167 @@
+// tests/display_api_tests.rs
+
+mod common;
+
+use std::io::Cursor;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::json;
+
+use decebalus_backend::api::display::{
+    clear_display_messages, get_display_image, list_display_messages, push_display_message, update_display,
+};
+use decebalus_backend::models::{DisplayConfig, PushDisplayMessageRequest};
+use decebalus_backend::services::display_rotator::DisplayRotator;
+
+fn panel_png() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::GrayImage::from_pixel(250, 122, image::Luma([255]))
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn valid_png_is_stored_and_served() {
+    let state = common::test_state().await;
+    let png = panel_png();
+
+    let resp = update_display(State(state.clone()), Json(json!({ "text": "Scanning", "image": STANDARD.encode(&png) })))
+        .await
+        .into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let status = state.repo.get_display_status().await.unwrap();
+    assert_eq!(status.status, "Scanning");
+    let path = status.image.expect("image path recorded");
+    assert!(path.starts_with("data/display/"), "{}", path);
+
+    let resp = get_display_image(State(state.clone())).await.into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body.as_ref(), png.as_slice());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn text_only_update_renders_the_panel_image() {
+    let state = common::test_state().await;
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("display".into(), json!({ "width": 200, "height": 96 }));
+    state.repo.update_config(&config).await.unwrap();
+
+    let resp = update_display(State(state.clone()), Json(json!({ "text": "3 hosts up" }))).await.into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = get_display_image(State(state.clone())).await.into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let rendered = image::load_from_memory(&body).unwrap().to_luma8();
+    assert_eq!(rendered.dimensions(), (200, 96));
+    assert!(rendered.pixels().any(|p| p.0[0] == 0));
+
+    let status = state.repo.get_display_status().await.unwrap();
+    assert_eq!(status.status, "3 hosts up");
+    std::fs::remove_file(status.image.unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn bad_image_payloads_are_rejected() {
+    let state = common::test_state().await;
+    let wrong_size = {
+        let mut bytes = Vec::new();
+        image::GrayImage::new(64, 64).write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png).unwrap();
+        STANDARD.encode(bytes)
+    };
+
+    for image in [json!("%%% not base64 %%%"), json!(STANDARD.encode(b"plain text")), json!(wrong_size), json!(42)] {
+        let resp = update_display(State(state.clone()), Json(json!({ "text": "Broken", "image": image })))
+            .await
+            .into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", image);
+    }
+
+    // Nothing changed, and there is no image to serve
+    let status = state.repo.get_display_status().await.unwrap();
+    assert_eq!(status.status, "idle");
+    assert!(status.image.is_none());
+    let resp = get_display_image(State(state)).await.into_response();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+fn push(text: &str) -> Json<PushDisplayMessageRequest> {
+    Json(PushDisplayMessageRequest { text: text.to_string() })
+}
+
+#[tokio::test]
+async fn pushed_messages_rotate_on_the_display() {
+    let state = common::test_state().await;
+    let cfg = DisplayConfig::default();
+    for text in ["12 hosts up", "Scan finished"] {
+        let resp = push_display_message(State(state.clone()), push(text)).await.into_response();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    let mut shown = Vec::new();
+    for _ in 0..3 {
+        let text = DisplayRotator::rotate(&state, &cfg).await.unwrap().expect("queue has messages");
+        let status = state.repo.get_display_status().await.unwrap();
+        assert_eq!(status.status, text);
+        assert!(status.image.is_some(), "rotation renders the panel image");
+        shown.push(text);
+    }
+    assert_eq!(shown, ["12 hosts up", "Scan finished", "12 hosts up"]);
+
+    std::fs::remove_file(state.repo.get_display_status().await.unwrap().image.unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn display_queue_can_be_listed_and_cleared() {
+    let state = common::test_state().await;
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("display".into(), json!({ "queue_size": 2 }));
+    state.repo.update_config(&config).await.unwrap();
+
+    for text in ["one", "two", "one", "three"] {
+        let _ = push_display_message(State(state.clone()), push(text)).await.unwrap();
+    }
+    // Re-pushing moves a message to the end; the oldest beyond queue_size is dropped
+    let Json(messages) = list_display_messages(State(state.clone())).await.unwrap();
+    let texts: Vec<&str> = messages.iter().map(|m| m.text.as_str()).collect();
+    assert_eq!(texts, ["one", "three"]);
+
+    let resp = push_display_message(State(state.clone()), push("  ")).await.into_response();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let Json(body) = clear_display_messages(State(state.clone())).await.unwrap();
+    assert_eq!(body["cleared"], 2);
+    assert!(list_display_messages(State(state.clone())).await.unwrap().0.is_empty());
+    // Nothing queued: the rotator leaves the panel alone
+    assert_eq!(DisplayRotator::rotate(&state, &DisplayConfig::default()).await.unwrap(), None);
+    assert_eq!(state.repo.get_display_status().await.unwrap().status, "idle");
+}
+
+#[tokio::test]
+async fn update_display_also_queues_its_text() {
+    let state = common::test_state().await;
+
+    let _ = update_display(State(state.clone()), Json(json!({ "text": "Scanning" }))).await.unwrap();
+
+    let Json(messages) = list_display_messages(State(state.clone())).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].text, "Scanning");
+    // Already on the panel, so a queue of one isn't redrawn
+    let before = state.repo.get_display_status().await.unwrap();
+    assert_eq!(DisplayRotator::rotate(&state, &DisplayConfig::default()).await.unwrap().as_deref(), Some("Scanning"));
+    assert_eq!(state.repo.get_display_status().await.unwrap().last_update, before.last_update);
+
+    std::fs::remove_file(before.image.unwrap()).unwrap();
+}
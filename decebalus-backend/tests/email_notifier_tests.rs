@@ -0,0 +1,155 @@
+// tests/email_notifier_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{Config, WsEvent};
+use decebalus_backend::services::email_notifier::EmailNotifier;
+use decebalus_backend::services::notifier::NotificationHub;
+
+/// Minimal SMTP server accepting one message and forwarding its DATA section.
+async fn mock_smtp_server() -> (u16, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = socket.into_split();
+                let mut lines = BufReader::new(read).lines();
+                write.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+
+                let mut in_data = false;
+                let mut data = String::new();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if in_data {
+                        if line == "." {
+                            in_data = false;
+                            let _ = tx.send(std::mem::take(&mut data));
+                            write.write_all(b"250 OK queued\r\n").await.unwrap();
+                        } else {
+                            data.push_str(&line);
+                            data.push('\n');
+                        }
+                        continue;
+                    }
+                    let cmd = line.to_uppercase();
+                    let reply: &[u8] = if cmd.starts_with("EHLO") || cmd.starts_with("HELO") {
+                        b"250 mock\r\n"
+                    } else if cmd.starts_with("DATA") {
+                        in_data = true;
+                        b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                    } else if cmd.starts_with("QUIT") {
+                        let _ = write.write_all(b"221 Bye\r\n").await;
+                        break;
+                    } else {
+                        b"250 OK\r\n"
+                    };
+                    write.write_all(reply).await.unwrap();
+                }
+            });
+        }
+    });
+
+    (port, rx)
+}
+
+#[tokio::test]
+async fn new_host_event_sends_email() {
+    let state = common::test_state().await;
+    let (port, mut received) = mock_smtp_server().await;
+
+    let mut config = Config::new();
+    config.set("integrations".to_string(), json!({
+        "smtp": {
+            "host": "127.0.0.1",
+            "port": port,
+            "tls": false,
+            "from": "decebalus@example.com",
+            "to": ["ops@example.com"]
+        }
+    }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    state.notifiers.register(Arc::new(EmailNotifier::new(state.repo.clone())));
+    NotificationHub::spawn(state.clone());
+
+    // Routine events are ignored, a first-seen host triggers an email
+    state.broadcaster.send(WsEvent::HostFound { ip: "10.0.0.5".into() }).unwrap();
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.5".into() }).unwrap();
+
+    let email = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .expect("no email received")
+        .unwrap();
+
+    assert!(email.contains("To: ops@example.com"));
+    assert!(email.contains("New host discovered: 10.0.0.5"));
+}
+
+#[tokio::test]
+async fn no_email_without_smtp_config() {
+    let state = common::test_state().await;
+    let (_port, mut received) = mock_smtp_server().await;
+
+    state.notifiers.register(Arc::new(EmailNotifier::new(state.repo.clone())));
+    NotificationHub::spawn(state.clone());
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.6".into() }).unwrap();
+
+    let result = tokio::time::timeout(Duration::from_millis(300), received.recv()).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn vulnerability_event_sends_email_above_min_severity() {
+    let state = common::test_state().await;
+    let (port, mut received) = mock_smtp_server().await;
+
+    let mut config = Config::new();
+    config.set("integrations".to_string(), json!({
+        "smtp": {
+            "host": "127.0.0.1",
+            "port": port,
+            "tls": false,
+            "from": "decebalus@example.com",
+            "to": ["ops@example.com"],
+            "min_severity": "HIGH"
+        }
+    }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    state.notifiers.register(Arc::new(EmailNotifier::new(state.repo.clone())));
+    NotificationHub::spawn(state.clone());
+
+    // A medium finding is below the threshold, the critical one is emailed
+    state.broadcaster.send(WsEvent::VulnerabilityFound {
+        ip: "10.0.0.5".into(),
+        id: "CVE-2000-0001".into(),
+        severity: "MEDIUM".into(),
+        description: "Minor issue".into(),
+    }).unwrap();
+    state.broadcaster.send(WsEvent::VulnerabilityFound {
+        ip: "10.0.0.5".into(),
+        id: "CVE-2024-6387".into(),
+        severity: "CRITICAL".into(),
+        description: "OpenSSH regreSSHion".into(),
+    }).unwrap();
+
+    let email = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .expect("no email received")
+        .unwrap();
+
+    assert!(email.contains("New CRITICAL vulnerability on 10.0.0.5: CVE-2024-6387"));
+    assert!(!email.contains("CVE-2000-0001"));
+}
@@ -0,0 +1,78 @@
+// tests/events_api_tests.rs
+
+mod common;
+
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use futures_util::StreamExt;
+
+use decebalus_backend::api::events::{event_stream, SseQuery};
+use decebalus_backend::models::WsEvent;
+
+/// Collect `data:` payloads off an SSE body until `count` have arrived.
+async fn read_events(body: axum::body::Body, count: usize) -> Vec<serde_json::Value> {
+    let mut stream = body.into_data_stream();
+    let mut buf = String::new();
+    let mut events = Vec::new();
+    while events.len() < count {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event from server")
+            .expect("stream ended")
+            .unwrap();
+        buf.push_str(std::str::from_utf8(&chunk).unwrap());
+        // Events are separated by a blank line
+        while let Some(end) = buf.find("\n\n") {
+            let block: String = buf.drain(..end + 2).collect();
+            if let Some(data) = block.lines().find_map(|l| l.strip_prefix("data: ")) {
+                events.push(serde_json::from_str(data).unwrap());
+            }
+        }
+    }
+    events
+}
+
+#[tokio::test]
+async fn streams_broadcast_events_as_json() {
+    let state = common::test_state().await;
+
+    let response = event_stream(State(state.clone()), Query(SseQuery::default())).await.into_response();
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    state.broadcaster.send(WsEvent::JobRunning { job_id: "abc".into() }).unwrap();
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.5".into() }).unwrap();
+
+    let events = read_events(response.into_body(), 2).await;
+    assert_eq!(events[0], serde_json::json!({ "seq": 1, "type": "job_running", "job_id": "abc" }));
+    assert_eq!(events[1], serde_json::json!({ "seq": 2, "type": "new_host", "ip": "10.0.0.5" }));
+}
+
+#[tokio::test]
+async fn replays_after_since_and_filters_by_topic() {
+    let state = common::test_state().await;
+    let _ = state.broadcaster.send(WsEvent::JobQueued { job_id: "abc".into(), job_type: "discovery".into() });
+    let _ = state.broadcaster.send(WsEvent::JobRunning { job_id: "abc".into() });
+
+    let query = SseQuery { since: Some(1), topics: Some("job:abc".into()) };
+    let response = event_stream(State(state.clone()), Query(query)).await.into_response();
+
+    state.broadcaster.send(WsEvent::JobRunning { job_id: "other".into() }).unwrap();
+    state.broadcaster.send(WsEvent::JobCompleted { job_id: "abc".into() }).unwrap();
+
+    let events = read_events(response.into_body(), 2).await;
+    assert_eq!(events[0]["seq"], 2);
+    assert_eq!(events[1], serde_json::json!({ "seq": 4, "type": "job_completed", "job_id": "abc" }));
+}
+
+#[tokio::test]
+async fn dropping_the_stream_ends_the_subscription() {
+    let state = common::test_state().await;
+
+    let response = event_stream(State(state.clone()), Query(SseQuery::default())).await.into_response();
+    assert_eq!(state.broadcaster.receiver_count(), 1);
+
+    drop(response);
+    assert_eq!(state.broadcaster.receiver_count(), 0);
+}
@@ -0,0 +1,34 @@
+// tests/health_tests.rs
+
+mod common;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+
+use decebalus_backend::api::health::health;
+
+#[tokio::test]
+async fn health_reports_db_up_and_busy_workers() {
+    let state = common::test_state().await;
+    let _permit = state.semaphore.clone().acquire_owned().await.unwrap();
+
+    let (status, body) = health(State(state)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body.0,
+        serde_json::json!({ "status": "ok", "db": "up", "running_jobs": 1, "max_threads": 5 })
+    );
+}
+
+#[tokio::test]
+async fn health_is_unavailable_when_the_db_is_unreachable() {
+    let state = common::test_state().await;
+    state.db.as_ref().unwrap().close().await;
+
+    let (status, body) = health(State(state)).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body.0["db"], "down");
+    assert_eq!(body.0["running_jobs"], 0);
+}
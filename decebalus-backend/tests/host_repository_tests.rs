@@ -0,0 +1,130 @@
+// tests/host_repository_tests.rs
+
+mod common;
+
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{Host, HostStatus, Port, Service, Vulnerability};
+
+#[tokio::test]
+async fn fully_populated_host_round_trips() {
+    let state = common::test_state().await;
+
+    let mut host = Host::new("10.0.0.9".to_string());
+    host.ports = vec![Port {
+        number: 443,
+        protocol: "tcp".into(),
+        status: "open".into(),
+        service: Some("https".into()),
+        version: Some("nginx 1.25.3".into()),
+        cpe: Some("cpe:/a:nginx:nginx:1.25.3".into()),
+    }];
+    host.banners = vec!["nginx".into()];
+    host.first_seen = "2024-01-01T00:00:00+00:00".into();
+    host.os = Some("Linux".into());
+    host.os_version = Some("6.1".into());
+    host.os_source = Some("banner".into());
+    host.device_type = Some("server".into());
+    host.mac_address = Some("aa:bb:cc:dd:ee:ff".into());
+    host.hostname = Some("web01.lan".into());
+    host.status = HostStatus::Up;
+    host.services = vec![Service::new("https", Some("nginx 1.25.3".into()), Some("web server".into()))];
+    host.vulnerabilities = vec![Vulnerability {
+        id: "CVE-2024-0001".into(),
+        description: "example".into(),
+        severity: "high".into(),
+        cvss_score: Some(8.1),
+        service: Some("nginx 1.25.3".into()),
+    }];
+    host.last_scan_duration_ms = Some(1234);
+    host.last_port_scan = Some("2024-01-02T00:00:00+00:00".into());
+
+    repository::upsert_host(state.db.as_ref().unwrap(), &host).await.unwrap();
+    let stored = repository::get_host(state.db.as_ref().unwrap(), &host.ip).await.unwrap().unwrap();
+
+    assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&host).unwrap());
+    let listed = repository::list_hosts(state.db.as_ref().unwrap()).await.unwrap();
+    assert_eq!(serde_json::to_value(&listed).unwrap(), serde_json::to_value(vec![host]).unwrap());
+}
+
+#[tokio::test]
+async fn bare_host_row_falls_back_to_defaults() {
+    let state = common::test_state().await;
+
+    sqlx::query("INSERT INTO hosts (ip, last_seen, status) VALUES ('10.0.0.10', '2024-01-01T00:00:00+00:00', 'garbage')")
+        .execute(state.db.as_ref().unwrap())
+        .await
+        .unwrap();
+
+    let host = repository::get_host(state.db.as_ref().unwrap(), "10.0.0.10").await.unwrap().unwrap();
+    assert_eq!(host.status, HostStatus::Unknown);
+    assert!(host.os.is_none());
+    assert!(host.ports.is_empty());
+    assert!(host.services.is_empty());
+    assert!(host.vulnerabilities.is_empty());
+    assert!(!host.first_seen.is_empty());
+}
+
+fn numbered_hosts(count: usize) -> Vec<Host> {
+    (0..count)
+        .map(|i| {
+            let mut host = Host::new(format!("10.1.{}.{}", i / 250, i % 250 + 1));
+            host.status = HostStatus::Up;
+            host
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn batch_upsert_saves_every_host() {
+    let state = common::test_state().await;
+    let mut hosts = numbered_hosts(100);
+
+    repository::upsert_hosts(state.db.as_ref().unwrap(), &hosts).await.unwrap();
+    assert_eq!(repository::list_hosts(state.db.as_ref().unwrap()).await.unwrap().len(), 100);
+
+    // A second batch updates in place rather than adding rows
+    hosts[42].hostname = Some("printer.lan".into());
+    repository::upsert_hosts(state.db.as_ref().unwrap(), &hosts).await.unwrap();
+    assert_eq!(repository::list_hosts(state.db.as_ref().unwrap()).await.unwrap().len(), 100);
+    let updated = repository::get_host(state.db.as_ref().unwrap(), &hosts[42].ip).await.unwrap().unwrap();
+    assert_eq!(updated.hostname.as_deref(), Some("printer.lan"));
+}
+
+#[tokio::test]
+async fn batch_upsert_keeps_port_scan_results() {
+    let state = common::test_state().await;
+    let mut scanned = Host::new("10.1.0.1".into());
+    scanned.add_port(22, "tcp", "open", Some("ssh".into()), None, None);
+    scanned.hostname = Some("nas.lan".into());
+    repository::upsert_host(state.db.as_ref().unwrap(), &scanned).await.unwrap();
+
+    // Discovery saw the host again but couldn't resolve its name
+    let mut found = Host::new("10.1.0.1".into());
+    found.status = HostStatus::Up;
+    found.mac_address = Some("aa:bb:cc:dd:ee:ff".into());
+    repository::upsert_hosts(state.db.as_ref().unwrap(), &[found]).await.unwrap();
+
+    let host = repository::get_host(state.db.as_ref().unwrap(), "10.1.0.1").await.unwrap().unwrap();
+    assert!(host.has_open_port(22));
+    assert_eq!(host.hostname.as_deref(), Some("nas.lan"));
+    assert_eq!(host.mac_address.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+    assert_eq!(host.status, HostStatus::Up);
+}
+
+#[tokio::test]
+async fn batch_upsert_is_all_or_nothing() {
+    let state = common::test_state().await;
+    let hosts = numbered_hosts(100);
+
+    // Fail the insert of one host in the middle of the batch
+    sqlx::query(&format!(
+        "CREATE TRIGGER reject_host BEFORE INSERT ON hosts WHEN NEW.ip = '{}' BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+        hosts[60].ip
+    ))
+    .execute(state.db.as_ref().unwrap())
+    .await
+    .unwrap();
+
+    assert!(repository::upsert_hosts(state.db.as_ref().unwrap(), &hosts).await.is_err());
+    assert!(repository::list_hosts(state.db.as_ref().unwrap()).await.unwrap().is_empty());
+}
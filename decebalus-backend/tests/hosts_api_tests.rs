@@ -0,0 +1,199 @@
+// tests/hosts_api_tests.rs
+
+mod common;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+
+use decebalus_backend::api::hosts::{delete_host, get_host, host_graph, list_hosts, ListHostsQuery};
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{GraphNode, Host, HostGraph, Service, WsEvent};
+
+async fn body_ips(response: axum::response::Response) -> Vec<String> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let hosts: Vec<Host> = serde_json::from_slice(&body).unwrap();
+    hosts.into_iter().map(|h| h.ip).collect()
+}
+
+#[tokio::test]
+async fn list_hosts_sorts_by_scan_time() {
+    let state = common::test_state().await;
+
+    for (ip, ms) in [("10.0.0.1", Some(200)), ("10.0.0.2", None), ("10.0.0.3", Some(9000))] {
+        let mut host = Host::new(ip.to_string());
+        host.last_scan_duration_ms = ms;
+        repository::upsert_host(state.db.as_ref().unwrap(), &host).await.unwrap();
+    }
+
+    let response = list_hosts(State(state.clone()), Query(ListHostsQuery { sort: Some("scan_time".into()), ..Default::default() }))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_ips(response).await, vec!["10.0.0.3", "10.0.0.1", "10.0.0.2"]);
+
+    let response = list_hosts(State(state.clone()), Query(ListHostsQuery::default()))
+        .await
+        .into_response();
+    assert_eq!(body_ips(response).await, vec!["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+}
+
+#[tokio::test]
+async fn list_hosts_sorts_by_data_quality() {
+    let state = common::test_state().await;
+
+    let mut stale = Host::new("10.0.0.1".to_string());
+    stale.last_seen = (chrono::Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+    repository::upsert_host(state.db.as_ref().unwrap(), &stale).await.unwrap();
+
+    let mut fresh = Host::new("10.0.0.2".to_string());
+    fresh.last_port_scan = Some(chrono::Utc::now().to_rfc3339());
+    repository::upsert_host(state.db.as_ref().unwrap(), &fresh).await.unwrap();
+
+    let response = list_hosts(State(state.clone()), Query(ListHostsQuery { sort: Some("data_quality".into()), ..Default::default() }))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let hosts: Vec<Host> = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(hosts[0].ip, "10.0.0.2");
+    assert!(hosts[0].data_quality > hosts[1].data_quality);
+    assert_eq!(hosts[1].data_quality, Some(0));
+}
+
+#[tokio::test]
+async fn list_hosts_rejects_unknown_sort() {
+    let state = common::test_state().await;
+
+    let response = list_hosts(State(state), Query(ListHostsQuery { sort: Some("bogus".into()), ..Default::default() }))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn list_hosts_filters_by_open_port_and_search() {
+    let state = common::test_state().await;
+
+    for (ip, hostname, ssh) in [
+        ("192.168.1.10", Some("nas"), true),
+        ("192.168.1.11", None, false),
+        ("10.0.0.5", Some("router"), true),
+    ] {
+        let mut host = Host::new(ip.to_string());
+        host.hostname = hostname.map(String::from);
+        host.add_port(80, "tcp", "open", None, None, None);
+        if ssh {
+            host.add_port(22, "tcp", "open", None, None, None);
+        }
+        repository::upsert_host(state.db.as_ref().unwrap(), &host).await.unwrap();
+    }
+
+    let by_port = ListHostsQuery { port: Some(22), ..Default::default() };
+    let response = list_hosts(State(state.clone()), Query(by_port)).await.into_response();
+    assert_eq!(body_ips(response).await, vec!["10.0.0.5", "192.168.1.10"]);
+
+    let by_ip = ListHostsQuery { q: Some("192.168".into()), ..Default::default() };
+    let response = list_hosts(State(state.clone()), Query(by_ip)).await.into_response();
+    assert_eq!(body_ips(response).await, vec!["192.168.1.10", "192.168.1.11"]);
+
+    let both = ListHostsQuery { port: Some(22), q: Some("ROUTER".into()), ..Default::default() };
+    let response = list_hosts(State(state), Query(both)).await.into_response();
+    assert_eq!(body_ips(response).await, vec!["10.0.0.5"]);
+}
+
+#[tokio::test]
+async fn get_host_returns_error_envelope_for_unknown_host() {
+    let state = common::test_state().await;
+
+    let response = get_host(State(state), Path("10.9.9.9".into())).await.into_response();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, serde_json::json!({
+        "error": { "code": "not_found", "message": "Host with IP 10.9.9.9 not found" }
+    }));
+}
+
+#[tokio::test]
+async fn host_graph_groups_hosts_by_subnet() {
+    let state = common::test_state().await;
+
+    for ip in ["192.168.1.10", "10.0.0.5", "192.168.1.20", "10.0.1.5"] {
+        let mut host = Host::new(ip.to_string());
+        host.services.push(Service::new("ssh", None, None));
+        if ip.starts_with("192.168") {
+            host.services.push(Service::new("http", None, None));
+        }
+        repository::upsert_host(state.db.as_ref().unwrap(), &host).await.unwrap();
+    }
+
+    let response = host_graph(State(state.clone())).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let graph: HostGraph = serde_json::from_slice(&body).unwrap();
+
+    let subnets: Vec<(String, usize)> = graph.nodes.iter().filter_map(|n| match n {
+        GraphNode::Subnet { cidr, host_count, .. } => Some((cidr.clone(), *host_count)),
+        _ => None,
+    }).collect();
+    assert_eq!(subnets, vec![
+        ("10.0.0.0/24".to_string(), 1),
+        ("10.0.1.0/24".to_string(), 1),
+        ("192.168.1.0/24".to_string(), 2),
+    ]);
+
+    let members: Vec<&str> = graph.edges.iter()
+        .filter(|e| e.relation == "member_of" && e.target == "subnet:192.168.1.0/24")
+        .map(|e| e.source.as_str())
+        .collect();
+    assert_eq!(members, vec!["host:192.168.1.10", "host:192.168.1.20"]);
+
+    // Shared services are a single node
+    let ssh = graph.nodes.iter().find(|n| n.id() == "service:ssh").unwrap();
+    assert!(matches!(ssh, GraphNode::Service { host_count: 4, .. }));
+    assert_eq!(graph.edges.iter().filter(|e| e.target == "service:http").count(), 2);
+}
+
+#[test]
+fn host_graph_orders_subnets_and_hosts_by_address() {
+    let hosts: Vec<Host> = ["10.0.10.5", "10.0.0.10", "10.0.2.5", "10.0.0.2", "10.0.0.1"]
+        .iter()
+        .map(|ip| Host::new(ip.to_string()))
+        .collect();
+    let graph = HostGraph::from_hosts(&hosts);
+
+    let subnets: Vec<&str> = graph.nodes.iter().filter_map(|n| match n {
+        GraphNode::Subnet { cidr, .. } => Some(cidr.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(subnets, vec!["10.0.0.0/24", "10.0.2.0/24", "10.0.10.0/24"]);
+
+    let ips: Vec<&str> = graph.nodes.iter().filter_map(|n| match n {
+        GraphNode::Host { ip, .. } => Some(ip.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(ips, vec!["10.0.0.1", "10.0.0.2", "10.0.0.10", "10.0.2.5", "10.0.10.5"]);
+}
+
+#[tokio::test]
+async fn delete_host_removes_it_then_is_not_found() {
+    let state = common::test_state().await;
+    repository::upsert_host(state.db.as_ref().unwrap(), &Host::new("10.0.0.7".into())).await.unwrap();
+    repository::upsert_host(state.db.as_ref().unwrap(), &Host::new("10.0.0.8".into())).await.unwrap();
+    let mut events = state.broadcaster.subscribe();
+
+    let response = delete_host(State(state.clone()), Path("10.0.0.7".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(matches!(events.try_recv(), Ok(WsEvent::HostRemoved { ip }) if ip == "10.0.0.7"));
+
+    let response = get_host(State(state.clone()), Path("10.0.0.7".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(repository::get_host(state.db.as_ref().unwrap(), "10.0.0.8").await.unwrap().is_some());
+
+    let response = delete_host(State(state.clone()), Path("10.0.0.7".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(events.try_recv().is_err());
+}
@@ -1,17 +1,30 @@
 // tests/job_executor_tests.rs
 
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use tokio::sync::{broadcast, Semaphore};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use tokio::sync::Semaphore;
 
-use decebalus_backend::db::repository;
+use decebalus_backend::api::jobs::{create_job, delete_job, get_job, get_job_children, list_jobs, retry_failed_jobs, retry_job, update_job, ListJobsQuery};
+
+use decebalus_backend::db::{self, repository};
+use decebalus_backend::db::db_repository::DbRepository;
+use decebalus_backend::db::inmemory_repository::InMemoryRepository;
+use decebalus_backend::db::repository_trait::Repository;
 use decebalus_backend::services::job_executor::JobExecutor;
+use decebalus_backend::services::EventBus;
+use decebalus_backend::services::config_cache::ConfigCache;
+use decebalus_backend::services::job_tasks::JobTasks;
+use decebalus_backend::services::notifier::Notifiers;
+use decebalus_backend::services::vuln_lookup::{NvdSource, VulnLookup};
 use decebalus_backend::state::AppState;
-use decebalus_backend::models::{Job, JobPriority};
+use decebalus_backend::models::{CreateJobRequest, Job, JobPriority, JobResult, JobStatus, RetryFailedRequest, UpdateJobRequest, WsEvent};
 
 async fn test_state() -> Arc<AppState> {
-    let (tx, _rx) = broadcast::channel(32);
-
     let db_pool = sqlx::sqlite::SqlitePoolOptions::new()
         .max_connections(5)
         .connect("sqlite::memory:")
@@ -24,16 +37,36 @@ async fn test_state() -> Arc<AppState> {
         .expect("Failed to run migrations");
 
     let state = AppState {
-        broadcaster: tx,
-        db: db_pool,
+        broadcaster: EventBus::new(32),
+        repo: Arc::new(DbRepository::new(db_pool.clone())),
+        db: Some(db_pool),
+        config: Arc::new(ConfigCache::new()),
         max_threads: 5,
         max_scan_concurrency: 500,
         semaphore: Arc::new(Semaphore::new(5)),
+        jobs: Arc::new(JobTasks::new()),
+        queue_paused: Arc::new(AtomicBool::new(false)),
+        notifiers: Arc::new(Notifiers::new()),
+        vulns: Arc::new(VulnLookup::new(Arc::new(NvdSource::from_env()))),
+        api_token: None,
     };
 
     Arc::new(state)
 }
 
+/// Poll until the job reaches `status` or the deadline passes.
+/// Discovery can take a few seconds when ARP (raw sockets) is available.
+async fn wait_for_status(state: &Arc<AppState>, id: &str, status: JobStatus) -> Job {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(15);
+    loop {
+        let job = repository::get_job(state.db.as_ref().unwrap(), id).await.unwrap().unwrap();
+        if job.status == status || std::time::Instant::now() > deadline {
+            return job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
 #[tokio::test]
 async fn scenario_job_executor_runs_discovery_successfully() {
     let state = test_state().await;
@@ -42,16 +75,16 @@ async fn scenario_job_executor_runs_discovery_successfully() {
     job.id = "job1".into();
     job.config = serde_json::json!({"target": "127.0.0.1/32"});
 
-    repository::create_job(&state.db, &job).await.unwrap();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
 
     let permit = state.semaphore.clone().acquire_owned().await.unwrap();
     JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
 
-    let updated = repository::get_job(&state.db, "job1").await.unwrap().unwrap();
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "job1").await.unwrap().unwrap();
 
-    assert_eq!(updated.status, "completed");
+    assert_eq!(updated.status, JobStatus::Completed);
     assert!(updated.results.is_some());
-    assert!(updated.results.as_ref().unwrap().contains("\"hosts_found\""));
+    assert!(updated.results.as_ref().unwrap().success().unwrap().get("hosts_found").is_some());
 }
 
 #[tokio::test]
@@ -68,19 +101,16 @@ async fn scenario_run_queue_spawns_jobs() {
     j2.priority = JobPriority::LOW;
     j2.config = serde_json::json!({"target": "127.0.0.1/32"});
 
-    repository::create_job(&state.db, &j1).await.unwrap();
-    repository::create_job(&state.db, &j2).await.unwrap();
+    repository::create_job(state.db.as_ref().unwrap(), &j1).await.unwrap();
+    repository::create_job(state.db.as_ref().unwrap(), &j2).await.unwrap();
 
     JobExecutor::run_queue(&state).await;
 
-    // Give spawned tasks time to complete
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-    let a = repository::get_job(&state.db, "jobA").await.unwrap().unwrap();
-    let b = repository::get_job(&state.db, "jobB").await.unwrap().unwrap();
+    let a = wait_for_status(&state, "jobA", JobStatus::Completed).await;
+    let b = wait_for_status(&state, "jobB", JobStatus::Completed).await;
 
-    assert_eq!(a.status, "completed");
-    assert_eq!(b.status, "completed");
+    assert_eq!(a.status, JobStatus::Completed);
+    assert_eq!(b.status, JobStatus::Completed);
 }
 
 #[tokio::test]
@@ -89,17 +119,1181 @@ async fn scenario_resume_incomplete_jobs_requeues_and_runs() {
 
     let mut job = Job::new("discovery".into());
     job.id = "jobR".into();
-    job.status = "running".into(); // leftover unfinished
+    job.status = JobStatus::Running; // leftover unfinished
     job.config = serde_json::json!({"target": "127.0.0.1/32"});
 
-    repository::create_job(&state.db, &job).await.unwrap();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
 
     JobExecutor::resume_incomplete_jobs(state.clone()).await;
 
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-    let updated = repository::get_job(&state.db, "jobR").await.unwrap().unwrap();
+    let updated = wait_for_status(&state, "jobR", JobStatus::Completed).await;
 
-    assert_eq!(updated.status, "completed");
+    assert_eq!(updated.status, JobStatus::Completed);
     assert!(updated.results.is_some());
 }
+
+#[tokio::test]
+async fn scenario_create_job_with_duplicate_id_is_a_conflict() {
+    let state = test_state().await;
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobDup".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let err = repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap_err();
+    assert!(db::is_unique_violation(&err));
+}
+
+#[tokio::test]
+async fn scenario_discovery_auto_port_scan_is_listed_as_child() {
+    let state = test_state().await;
+
+    // Something for discovery to find over the TCP probe
+    let _listener = tokio::net::TcpListener::bind("127.0.0.3:8888").await.unwrap();
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobParent".into();
+    job.config = serde_json::json!({"target": "127.0.0.3/32", "auto_port_scan": true});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+
+    let response = get_job_children(State(state.clone()), Path("jobParent".to_string()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let children: Vec<Job> = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].job_type, "port-scan");
+    assert_eq!(children[0].parent_job_id.as_deref(), Some("jobParent"));
+    assert_eq!(children[0].run_id.as_deref(), Some("jobParent"));
+
+    let parent = repository::get_job(state.db.as_ref().unwrap(), "jobParent").await.unwrap().unwrap();
+    assert_eq!(parent.results.unwrap().success().unwrap()["port_scan_job_id"], children[0].id.as_str());
+}
+
+#[tokio::test]
+async fn scenario_auto_port_scan_parent_reports_its_phases() {
+    let state = test_state().await;
+    let mut events = state.broadcaster.subscribe();
+    let _listener = tokio::net::TcpListener::bind("127.0.0.40:8888").await.unwrap();
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobPhases".into();
+    job.config = serde_json::json!({"target": "127.0.0.40/32", "auto_port_scan": true, "ports": [8888]});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+
+    let mut phases = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let WsEvent::JobPhase { job_id, phase } = event
+            && job_id == "jobPhases"
+        {
+            phases.push(phase);
+        }
+    }
+    assert_eq!(phases, ["discovery (1/2)", "queueing port-scan (2/2)"]);
+
+    // Cleared once the job is done
+    let parent = repository::get_job(state.db.as_ref().unwrap(), "jobPhases").await.unwrap().unwrap();
+    assert_eq!(parent.status, JobStatus::Completed);
+    assert!(parent.phase.is_none());
+}
+
+#[tokio::test]
+async fn scenario_streamed_port_scan_starts_before_discovery_finishes() {
+    let state = test_state().await;
+
+    // One live host near the start of the range and one near the end; probing
+    // one address at a time keeps discovery busy in between
+    let _first = tokio::net::TcpListener::bind("127.0.1.1:8888").await.unwrap();
+    let _last = tokio::net::TcpListener::bind("127.0.1.200:8888").await.unwrap();
+    let mut config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    config.set("scan_config".into(), serde_json::json!({ "stream_port_scan": true, "per_network_concurrency": 1 }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobStreaming".into();
+    job.config = serde_json::json!({"target": "127.0.1.0/23", "auto_port_scan": true});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut rx = state.broadcaster.subscribe();
+    let recorder = {
+        let events = events.clone();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => events.lock().unwrap().push(event),
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        })
+    };
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+    recorder.abort();
+
+    let parent = repository::get_job(state.db.as_ref().unwrap(), "jobStreaming").await.unwrap().unwrap();
+    assert_eq!(parent.status, JobStatus::Completed);
+
+    let children = repository::get_child_jobs(state.db.as_ref().unwrap(), "jobStreaming").await.unwrap();
+    assert_eq!(children.len(), 2);
+    assert!(children.iter().all(|c| c.job_type == "port-scan"));
+    let first = children.iter().find(|c| c.target().unwrap() == "127.0.1.1").unwrap();
+    assert!(children.iter().any(|c| c.target().unwrap() == "127.0.1.200"));
+    let results = parent.results.unwrap().success().unwrap().to_string();
+    assert!(children.iter().all(|c| results.contains(&c.id)));
+
+    // The first host's scan was queued before discovery got to the last host
+    let events = events.lock().unwrap();
+    let queued = events.iter().position(|e| matches!(e, WsEvent::JobQueued { job_id, .. } if *job_id == first.id));
+    let last_found = events.iter().position(|e| *e == WsEvent::HostFound { ip: "127.0.1.200".into() });
+    assert!(queued.unwrap() < last_found.unwrap(), "{:?}", *events);
+}
+
+#[tokio::test]
+async fn scenario_children_of_unknown_job_is_not_found() {
+    let state = test_state().await;
+
+    let response = get_job_children(State(state), Path("missing".to_string()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn scenario_concurrent_claims_never_take_the_same_job() {
+    // File-backed so each pool connection sees the same database
+    let path = std::env::temp_dir().join(format!("decebalus-claim-{}.db", uuid::Uuid::new_v4()));
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect(&format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    for i in 0..50 {
+        let mut job = Job::new("discovery".into());
+        job.id = format!("claim{}", i);
+        repository::create_job(&pool, &job).await.unwrap();
+    }
+
+    let loops: Vec<_> = (0..2)
+        .map(|_| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut claimed = Vec::new();
+                while let Some(job) = repository::claim_next_job(&pool).await.unwrap() {
+                    assert_eq!(job.status, JobStatus::Running);
+                    claimed.push(job.id);
+                }
+                claimed
+            })
+        })
+        .collect();
+
+    let mut all = Vec::new();
+    for handle in loops {
+        all.extend(handle.await.unwrap());
+    }
+    pool.close().await;
+    let _ = std::fs::remove_file(&path);
+
+    let unique: std::collections::HashSet<_> = all.iter().collect();
+    assert_eq!(all.len(), 50);
+    assert_eq!(unique.len(), 50);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn scenario_concurrent_run_queue_runs_each_job_once() {
+    // File-backed so each pool connection sees the same database
+    let path = std::env::temp_dir().join(format!("decebalus-queue-{}.db", uuid::Uuid::new_v4()));
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect(&format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let base = test_state().await;
+    let state = Arc::new(AppState {
+        repo: Arc::new(DbRepository::new(pool.clone())),
+        db: Some(pool.clone()),
+        ..(*base).clone()
+    });
+
+    // Unknown type: fails straight away without touching the network
+    for i in 0..40 {
+        let mut job = Job::new("noop".into());
+        job.id = format!("queue{}", i);
+        state.repo.create_job(&job).await.unwrap();
+    }
+
+    // Like a burst of create_job calls, each kicking the queue until it's drained
+    let passes: Vec<_> = (0..20)
+        .map(|_| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                while !state.repo.get_queued_jobs().await.unwrap().is_empty() {
+                    JobExecutor::run_queue(&state).await;
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for pass in passes {
+        pass.await.unwrap();
+    }
+    // Wait for the last jobs to release their worker slots
+    let _all = state.semaphore.acquire_many(state.max_threads as u32).await.unwrap();
+
+    let logs = state.repo.get_logs().await.unwrap();
+    for i in 0..40 {
+        let id = format!("queue{}", i);
+        let starts = logs
+            .iter()
+            .filter(|l| l.job_id.as_deref() == Some(id.as_str()) && l.content == "Starting job execution")
+            .count();
+        assert_eq!(starts, 1, "job {} started {} times", id, starts);
+        assert_eq!(state.repo.get_job(&id).await.unwrap().unwrap().status, JobStatus::Failed);
+    }
+
+    pool.close().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn scenario_claim_dispatches_every_priority_in_order() {
+    let db_repo = DbRepository::new(test_state().await.db.clone().unwrap());
+    let mem_repo = InMemoryRepository::new();
+    let repos: [&dyn Repository; 2] = [&db_repo, &mem_repo];
+
+    for repo in repos {
+        // Inserted out of order; the two NORMAL jobs tie and go oldest first
+        for (id, priority) in [
+            ("normal-1", JobPriority::NORMAL),
+            ("low", JobPriority::LOW),
+            ("critical", JobPriority::CRITICAL),
+            ("normal-2", JobPriority::NORMAL),
+            ("high", JobPriority::HIGH),
+        ] {
+            let mut job = Job::new("discovery".into());
+            job.id = id.into();
+            job.priority = priority;
+            repo.create_job(&job).await.unwrap();
+        }
+
+        let mut order = Vec::new();
+        while let Some(job) = repo.claim_next_job().await.unwrap() {
+            order.push(job.id);
+        }
+        assert_eq!(order, ["critical", "high", "normal-1", "normal-2", "low"]);
+    }
+}
+
+#[tokio::test]
+async fn scenario_claim_next_job_prefers_priority() {
+    let state = test_state().await;
+
+    let mut low = Job::new("discovery".into());
+    low.id = "claimLow".into();
+    low.priority = JobPriority::LOW;
+    let mut high = Job::new("discovery".into());
+    high.id = "claimHigh".into();
+    high.priority = JobPriority::HIGH;
+
+    repository::create_job(state.db.as_ref().unwrap(), &low).await.unwrap();
+    repository::create_job(state.db.as_ref().unwrap(), &high).await.unwrap();
+
+    let first = repository::claim_next_job(state.db.as_ref().unwrap()).await.unwrap().unwrap();
+    let second = repository::claim_next_job(state.db.as_ref().unwrap()).await.unwrap().unwrap();
+
+    assert_eq!(first.id, "claimHigh");
+    assert_eq!(second.id, "claimLow");
+    assert!(repository::claim_next_job(state.db.as_ref().unwrap()).await.unwrap().is_none());
+    // A claimed job can't be claimed again by id either
+    assert!(repository::claim_job(state.db.as_ref().unwrap(), "claimHigh").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn scenario_export_rejects_output_dir_outside_allowed_dirs() {
+    let state = test_state().await;
+
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "../../etc" }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobExportBad".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobExportBad").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Failed);
+    assert!(updated.results.unwrap().error().unwrap().contains(".."));
+}
+
+#[tokio::test]
+async fn scenario_export_writes_json_file() {
+    let state = test_state().await;
+
+    // Default allowed dir is `data`; keep this run's output in its own folder
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "exports/test-export-job" }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("10.0.0.1".into())).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobExport".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobExport").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Completed);
+    let results = updated.results.unwrap().success().unwrap().clone();
+    let path = std::path::PathBuf::from(results["path"].as_str().unwrap());
+    assert!(path.starts_with("data/exports/test-export-job"));
+    assert!(path.file_name().unwrap().to_str().unwrap().starts_with("export-jobExport-"));
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written["hosts"][0]["ip"], "10.0.0.1");
+    assert_eq!(written["jobs"][0]["id"], "jobExport");
+
+    std::fs::remove_dir_all("data/exports/test-export-job").unwrap();
+}
+
+#[tokio::test]
+async fn scenario_export_writes_csv_file() {
+    let state = test_state().await;
+
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "exports/test-export-csv" }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut web = decebalus_backend::models::Host::new("10.0.0.1".into());
+    web.hostname = Some("web01.lan".into());
+    web.os = Some("Linux 4.15 - 5.8, Ubuntu".into());
+    web.add_port(22, "tcp", "open", None, None, None);
+    web.add_port(443, "tcp", "open", None, None, None);
+    web.add_port(25, "tcp", "closed", None, None, None);
+    repository::upsert_host(state.db.as_ref().unwrap(), &web).await.unwrap();
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("10.0.0.2".into())).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobExportCsv".into();
+    job.config = serde_json::json!({ "format": "csv" });
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobExportCsv").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Completed, "{:?}", updated.results);
+    let results = updated.results.unwrap().success().unwrap().clone();
+    assert_eq!(results["format"], "csv");
+    let path = std::path::PathBuf::from(results["path"].as_str().unwrap());
+    assert_eq!(path.extension().unwrap(), "csv");
+
+    let mut reader = csv::Reader::from_path(&path).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["ip", "hostname", "os", "open_ports", "last_seen"]);
+    let mut rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    rows.sort_by(|a, b| a[0].cmp(&b[0]));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(&rows[0][0], "10.0.0.1");
+    assert_eq!(&rows[0][1], "web01.lan");
+    assert_eq!(&rows[0][2], "Linux 4.15 - 5.8, Ubuntu");
+    assert_eq!(&rows[0][3], "22/tcp;443/tcp");
+    assert_eq!(&rows[0][4], web.last_seen);
+    assert_eq!(&rows[1][0], "10.0.0.2");
+    assert_eq!(&rows[1][3], "");
+
+    std::fs::remove_dir_all("data/exports/test-export-csv").unwrap();
+}
+
+#[tokio::test]
+async fn scenario_export_writes_nmap_xml_file() {
+    let state = test_state().await;
+
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "exports/test-export-xml" }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut web = decebalus_backend::models::Host::new("10.0.0.1".into());
+    web.add_port(22, "tcp", "open", Some("ssh".into()), Some("OpenSSH 9.6p1".into()), None);
+    web.add_port(443, "tcp", "open", Some("https".into()), None, None);
+    repository::upsert_host(state.db.as_ref().unwrap(), &web).await.unwrap();
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("10.0.0.2".into())).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobExportXml".into();
+    job.config = serde_json::json!({ "format": "xml" });
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobExportXml").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Completed, "{:?}", updated.results);
+    let results = updated.results.unwrap().success().unwrap().clone();
+    let path = std::path::PathBuf::from(results["path"].as_str().unwrap());
+    assert_eq!(path.extension().unwrap(), "xml");
+
+    let xml = std::fs::read_to_string(&path).unwrap();
+    assert!(xml.contains("<nmaprun "));
+    assert_eq!(xml.matches("<host>").count(), 2);
+    assert!(xml.contains(r#"<port protocol="tcp" portid="22">"#));
+    assert!(xml.contains(r#"<service name="ssh" product="OpenSSH" version="9.6p1" method="probed" conf="10"/>"#));
+    assert!(xml.contains(r#"<port protocol="tcp" portid="443">"#));
+    assert!(xml.contains("<ports/>"));
+
+    std::fs::remove_dir_all("data/exports/test-export-xml").unwrap();
+}
+
+#[tokio::test]
+async fn scenario_create_export_job_rejects_unknown_format() {
+    let state = test_state().await;
+
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "export",
+        "format": "xlsx",
+    }))
+    .unwrap();
+    let response = create_job(State(state), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn scenario_port_scan_without_hosts_completes_with_zero_hosts() {
+    let state = test_state().await;
+
+    let mut job = Job::new("port-scan".into());
+    job.id = "jobNoHosts".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobNoHosts").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Completed);
+
+    let results = updated.results.unwrap().success().unwrap().clone();
+    assert_eq!(results["hosts_scanned"], 0);
+    assert!(results["hint"].as_str().unwrap().contains("discovery"));
+}
+
+#[tokio::test]
+async fn scenario_retry_failed_requeues_only_failed_jobs() {
+    let state = test_state().await;
+    // Hold every worker slot so requeued jobs stay queued while we inspect them
+    let _slots = state.semaphore.clone().acquire_many_owned(5).await.unwrap();
+
+    for (id, job_type, status) in [
+        ("failedScan", "port-scan", JobStatus::Failed),
+        ("failedDiscovery", "discovery", JobStatus::Failed),
+        ("doneScan", "port-scan", JobStatus::Completed),
+    ] {
+        let mut job = Job::new(job_type.into());
+        job.id = id.into();
+        job.status = status;
+        job.results = Some(JobResult::Error("previous run".into()));
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    }
+
+    let filter = RetryFailedRequest { job_type: Some("port-scan".into()), ..Default::default() };
+    let response = retry_failed_jobs(State(state.clone()), Some(Json(filter))).await.into_response();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["requeued"], 1);
+
+    let status = |id: &'static str| {
+        let state = state.clone();
+        async move { repository::get_job(state.db.as_ref().unwrap(), id).await.unwrap().unwrap() }
+    };
+    let requeued = status("failedScan").await;
+    assert_eq!(requeued.status, JobStatus::Queued);
+    assert!(requeued.results.is_none());
+    assert_eq!(status("failedDiscovery").await.status, JobStatus::Failed);
+    assert_eq!(status("doneScan").await.status, JobStatus::Completed);
+
+    // No filter: everything still failed is requeued, completed jobs are untouched
+    let response = retry_failed_jobs(State(state.clone()), None).await.into_response();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["requeued"], 1);
+    assert_eq!(status("failedDiscovery").await.status, JobStatus::Queued);
+    assert_eq!(status("doneScan").await.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn scenario_in_memory_retry_failed_respects_the_time_window() {
+    let repo = InMemoryRepository::new();
+    let now = chrono::Utc::now().timestamp();
+    for (id, failed_at) in [("old", now - 7200), ("recent", now - 60)] {
+        let mut job = Job::new("port-scan".into());
+        job.id = id.into();
+        job.status = JobStatus::Failed;
+        repo.create_job(&job).await.unwrap();
+        repo.set_job_updated_at(id, failed_at);
+    }
+
+    assert_eq!(repo.requeue_failed_jobs(None, Some(now - 3600), None).await.unwrap(), 1);
+    assert_eq!(repo.get_job("recent").await.unwrap().unwrap().status, JobStatus::Queued);
+    assert_eq!(repo.get_job("old").await.unwrap().unwrap().status, JobStatus::Failed);
+
+    assert_eq!(repo.requeue_failed_jobs(None, None, Some(now - 7300)).await.unwrap(), 0);
+    assert_eq!(repo.requeue_failed_jobs(None, None, Some(now - 3600)).await.unwrap(), 1);
+    assert_eq!(repo.get_job("old").await.unwrap().unwrap().status, JobStatus::Queued);
+}
+
+#[tokio::test]
+async fn scenario_create_job_with_scheduled_at_is_stored_as_scheduled() {
+    let state = test_state().await;
+    let start = chrono::Utc::now().timestamp() + 3600;
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "discovery",
+        "target": "10.20.0.0/30",
+        "scheduled_at": start,
+    }))
+    .unwrap();
+
+    let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Job = serde_json::from_slice(&body).unwrap();
+
+    // Not picked up by the queue before its time
+    JobExecutor::run_queue(&state).await;
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let job = repository::get_job(state.db.as_ref().unwrap(), &created.id).await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Scheduled);
+    assert_eq!(job.scheduled_at, Some(start));
+    assert_eq!(job.target().unwrap(), "10.20.0.0/30");
+}
+
+#[tokio::test]
+async fn scenario_recurring_job_runs_on_each_cron_fire() {
+    let state = test_state().await;
+    // Every second; with no hosts a vuln-scan completes straight away
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "vuln-scan",
+        "cron": "* * * * * *",
+    }))
+    .unwrap();
+
+    let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Job = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created.status, JobStatus::Scheduled);
+    assert_eq!(created.schedule.as_deref(), Some("* * * * * *"));
+    assert!(created.scheduled_at.is_some());
+
+    let runs = || async {
+        repository::get_logs_by_job_id(state.db.as_ref().unwrap(), created.id.clone())
+            .await
+            .unwrap()
+            .iter()
+            .filter(|l| l.content == "Starting job execution")
+            .count()
+    };
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(6);
+    while runs().await < 2 && std::time::Instant::now() < deadline {
+        JobExecutor::run_due_scheduled_jobs(&state).await;
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(runs().await, 2);
+
+    // Wait for the second run to finish and re-schedule itself
+    let _all = state.semaphore.acquire_many(state.max_threads as u32).await.unwrap();
+    let job = repository::get_job(state.db.as_ref().unwrap(), &created.id).await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Scheduled);
+    assert!(job.scheduled_at.unwrap() > created.scheduled_at.unwrap());
+    assert!(job.results.is_some());
+}
+
+#[tokio::test]
+async fn scenario_create_job_rejects_bad_cron() {
+    let state = test_state().await;
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "vuln-scan",
+        "cron": "every night",
+    }))
+    .unwrap();
+
+    let response = create_job(State(state), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn scenario_create_job_stores_request_target() {
+    let state = test_state().await;
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "port-scan",
+        "target": "127.0.0.9",
+    }))
+    .unwrap();
+
+    let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Job = serde_json::from_slice(&body).unwrap();
+
+    let job = repository::get_job(state.db.as_ref().unwrap(), &created.id).await.unwrap().unwrap();
+    assert!(job.scheduled_at.is_none());
+    assert_eq!(job.config["target"], "127.0.0.9");
+}
+
+#[tokio::test]
+async fn scenario_retry_job_requeues_failed_and_cancelled_jobs() {
+    let state = test_state().await;
+    // Hold every worker slot so the requeued jobs stay queued
+    let _slots = state.semaphore.clone().acquire_many_owned(5).await.unwrap();
+    let mut events = state.broadcaster.subscribe();
+
+    for (id, status) in [("retryFailed", JobStatus::Failed), ("retryCancelled", JobStatus::Cancelled)] {
+        let mut job = Job::new("port-scan".into());
+        job.id = id.into();
+        job.status = status;
+        job.results = Some(JobResult::Error("previous run".into()));
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+        let response = retry_job(State(state.clone()), Path(id.to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let job = repository::get_job(state.db.as_ref().unwrap(), id).await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+        assert!(job.results.is_none());
+        assert_eq!(events.recv().await.unwrap(), WsEvent::JobRetried { job_id: id.to_string() });
+    }
+}
+
+#[tokio::test]
+async fn scenario_retry_job_conflicts_for_running_and_completed_jobs() {
+    let state = test_state().await;
+
+    for (id, status) in [("retryRunning", JobStatus::Running), ("retryCompleted", JobStatus::Completed)] {
+        let mut job = Job::new("port-scan".into());
+        job.id = id.into();
+        job.status = status;
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+        let response = retry_job(State(state.clone()), Path(id.to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(repository::get_job(state.db.as_ref().unwrap(), id).await.unwrap().unwrap().status, status);
+    }
+
+    let response = retry_job(State(state), Path("missing".to_string())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn scenario_list_jobs_filters_by_status() {
+    let state = test_state().await;
+    for (id, status) in [("listDone1", JobStatus::Completed), ("listFailed", JobStatus::Failed), ("listDone2", JobStatus::Completed)] {
+        let mut job = Job::new("export".into());
+        job.id = id.into();
+        job.status = status;
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    }
+
+    let query = ListJobsQuery { status: Some(JobStatus::Completed), ..Default::default() };
+    let page = list_jobs(State(state), Query(query)).await.unwrap().0;
+
+    assert_eq!(page.total, 2);
+    assert!(page.items.iter().all(|j| j.status == JobStatus::Completed));
+    assert_eq!((page.limit, page.offset), (50, 0));
+}
+
+#[test]
+fn scenario_list_jobs_rejects_unknown_status() {
+    let uri: axum::http::Uri = "/api/jobs?status=completed".parse().unwrap();
+    let query = Query::<ListJobsQuery>::try_from_uri(&uri).unwrap();
+    assert_eq!(query.status, Some(JobStatus::Completed));
+
+    // A typo is a 400, not an empty page
+    let uri: axum::http::Uri = "/api/jobs?status=complete".parse().unwrap();
+    let err = Query::<ListJobsQuery>::try_from_uri(&uri).unwrap_err();
+    assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn scenario_list_jobs_pages_with_offset_newest_first() {
+    let state = test_state().await;
+    for i in 0..5 {
+        let mut job = Job::new("export".into());
+        job.id = format!("page{}", i);
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    }
+
+    let query = ListJobsQuery { limit: Some(2), offset: Some(2), status: None };
+    let page = list_jobs(State(state.clone()), Query(query)).await.unwrap().0;
+    assert_eq!(page.total, 5);
+    assert_eq!(page.items.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), ["page2", "page1"]);
+
+    let query = ListJobsQuery { limit: Some(2), offset: Some(4), status: None };
+    let page = list_jobs(State(state.clone()), Query(query)).await.unwrap().0;
+    assert_eq!(page.items.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), ["page0"]);
+
+    // Oversized pages are capped
+    let query = ListJobsQuery { limit: Some(10_000), ..Default::default() };
+    assert_eq!(list_jobs(State(state), Query(query)).await.unwrap().0.limit, 200);
+}
+
+#[tokio::test]
+async fn scenario_list_jobs_orders_jobs_created_milliseconds_apart() {
+    let db_repo = DbRepository::new(test_state().await.db.clone().unwrap());
+    let mem_repo = InMemoryRepository::new();
+    let repos: [&dyn Repository; 2] = [&db_repo, &mem_repo];
+
+    for repo in repos {
+        let mut first = Job::new("export".into());
+        first.id = "first".into();
+        repo.create_job(&first).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let mut second = Job::new("export".into());
+        second.id = "second".into();
+        repo.create_job(&second).await.unwrap();
+
+        let jobs = repo.list_jobs().await.unwrap();
+        assert_eq!(jobs.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), ["second", "first"]);
+        // The timestamp set on the job is what gets stored and returned
+        assert_eq!(jobs[1].created_at, first.created_at);
+        assert!(jobs[0].created_at > jobs[1].created_at);
+    }
+}
+
+#[tokio::test]
+async fn scenario_job_exceeding_timeout_is_failed() {
+    let state = test_state().await;
+    let mut events = state.broadcaster.subscribe();
+
+    // Probing a /21 one address at a time takes far longer than a second
+    let mut config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    config.set("jobs".into(), serde_json::json!({ "timeout_secs": 1 }));
+    config.set("scan_config".into(), serde_json::json!({ "per_network_concurrency": 1 }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobSlow".into();
+    job.config = serde_json::json!({"target": "127.0.8.0/21"});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let started = std::time::Instant::now();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobSlow").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Failed);
+    assert!(updated.results.unwrap().error().unwrap().contains("timed out"));
+    // The worker slot is free again
+    assert_eq!(state.semaphore.available_permits(), 5);
+
+    let mut failed = None;
+    while let Ok(event) = events.try_recv() {
+        if matches!(event, WsEvent::JobFailed { .. }) {
+            failed = Some(event);
+        }
+    }
+    assert_eq!(failed, Some(WsEvent::JobFailed { job_id: "jobSlow".into(), error: "timeout".into() }));
+}
+
+#[tokio::test]
+async fn scenario_failed_multi_phase_job_clears_its_phase() {
+    let state = test_state().await;
+
+    // Times out while still in its first phase
+    let mut config = repository::get_config(state.db.as_ref().unwrap()).await.unwrap();
+    config.set("jobs".into(), serde_json::json!({ "timeout_secs": 1 }));
+    config.set("scan_config".into(), serde_json::json!({ "per_network_concurrency": 1 }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobPhaseFailed".into();
+    job.config = serde_json::json!({"target": "127.0.16.0/21", "auto_port_scan": true});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobPhaseFailed").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Failed);
+    assert!(updated.phase.is_none());
+}
+
+#[tokio::test]
+async fn scenario_job_failing_twice_then_succeeding_is_retried() {
+    let state = test_state().await;
+    let mut events = state.broadcaster.subscribe();
+
+    // A file where the export directory should be makes the export fail
+    // until it is removed
+    std::fs::create_dir_all("data/exports").unwrap();
+    let blocker = "data/exports/test-retry-job";
+    std::fs::write(blocker, "").unwrap();
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "exports/test-retry-job/out" }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobRetry".into();
+    job.max_retries = Some(2);
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let run = |state: Arc<AppState>, job: Job| async move {
+        let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+        JobExecutor::execute_job(job, state.clone(), permit).await;
+        repository::get_job(state.db.as_ref().unwrap(), "jobRetry").await.unwrap().unwrap()
+    };
+
+    for attempt in 1..=2 {
+        let before = chrono::Utc::now().timestamp();
+        let updated = run(state.clone(), job.clone()).await;
+        assert_eq!(updated.status, JobStatus::Scheduled);
+        assert_eq!(updated.retries, attempt);
+        assert!(updated.scheduled_at.unwrap() > before);
+        assert!(updated.results.unwrap().error().unwrap().contains("Failed to create"));
+    }
+
+    std::fs::remove_file(blocker).unwrap();
+    let updated = run(state.clone(), job).await;
+    assert_eq!(updated.status, JobStatus::Completed);
+    assert_eq!(updated.retries, 2);
+    std::fs::remove_dir_all(blocker).unwrap();
+
+    let mut retries = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if matches!(event, WsEvent::JobRetryScheduled { .. } | WsEvent::JobFailed { .. }) {
+            retries.push(event);
+        }
+    }
+    let scheduled = |attempt| WsEvent::JobRetryScheduled { job_id: "jobRetry".into(), attempt };
+    assert_eq!(retries, [scheduled(1), scheduled(2)]);
+}
+
+#[tokio::test]
+async fn scenario_job_is_failed_once_retries_are_exhausted() {
+    let state = test_state().await;
+
+    std::fs::create_dir_all("data/exports").unwrap();
+    let blocker = "data/exports/test-retry-exhausted";
+    std::fs::write(blocker, "").unwrap();
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "exports/test-retry-exhausted/out" }));
+    config.set("jobs".to_string(), serde_json::json!({ "max_retries": 1 }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobExhausted".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    for expected in [JobStatus::Scheduled, JobStatus::Failed] {
+        let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+        JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+        let updated = repository::get_job(state.db.as_ref().unwrap(), "jobExhausted").await.unwrap().unwrap();
+        assert_eq!(updated.status, expected);
+        assert_eq!(updated.retries, 1);
+    }
+    std::fs::remove_file(blocker).unwrap();
+}
+
+#[tokio::test]
+async fn scenario_fatal_error_is_not_retried() {
+    let state = test_state().await;
+
+    // A rejected export directory won't fix itself, so retrying is pointless
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("export".to_string(), serde_json::json!({ "output_dir": "../../etc" }));
+    config.set("jobs".to_string(), serde_json::json!({ "max_retries": 3 }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    let mut job = Job::new("export".into());
+    job.id = "jobFatal".into();
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job, state.clone(), permit).await;
+
+    let updated = repository::get_job(state.db.as_ref().unwrap(), "jobFatal").await.unwrap().unwrap();
+    assert_eq!(updated.status, JobStatus::Failed);
+    assert_eq!(updated.retries, 0);
+    assert!(updated.results.unwrap().error().unwrap().contains(".."));
+}
+
+#[tokio::test]
+async fn scenario_delete_job_removes_it_and_its_logs() {
+    let state = test_state().await;
+    for id in ["doneJob", "otherJob"] {
+        let mut job = Job::new("port-scan".into());
+        job.id = id.into();
+        job.status = JobStatus::Completed;
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    }
+    for (job_id, content) in [(Some("doneJob"), "one"), (Some("doneJob"), "two"), (Some("otherJob"), "kept"), (None, "global")] {
+        repository::add_log(state.db.as_ref().unwrap(), "INFO", "test", None, job_id, content).await.unwrap();
+    }
+
+    let response = delete_job(State(state.clone()), Path("doneJob".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["logs_deleted"], 2);
+
+    assert!(repository::get_job(state.db.as_ref().unwrap(), "doneJob").await.unwrap().is_none());
+    assert!(state.repo.get_logs_by_job_id("doneJob".into()).await.unwrap().is_empty());
+    assert_eq!(state.repo.get_logs_by_job_id("otherJob".into()).await.unwrap().len(), 1);
+    assert_eq!(state.repo.get_logs().await.unwrap().len(), 2);
+
+    let response = get_job(State(state.clone()), Path("doneJob".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = delete_job(State(state.clone()), Path("doneJob".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn scenario_delete_running_job_is_a_conflict() {
+    let db_repo = DbRepository::new(test_state().await.db.clone().unwrap());
+    let mem_repo = InMemoryRepository::new();
+    let repos: [&dyn Repository; 2] = [&db_repo, &mem_repo];
+
+    for repo in repos {
+        let mut job = Job::new("nmap-scan".into());
+        job.id = "busyJob".into();
+        job.status = JobStatus::Running;
+        repo.create_job(&job).await.unwrap();
+        repo.add_log("INFO", "test", None, Some("busyJob"), "still going").await.unwrap();
+
+        // Refused at the repository level too, so a job claimed mid-request survives
+        assert_eq!(repo.delete_job("busyJob").await.unwrap(), None);
+        assert_eq!(repo.get_logs_by_job_id("busyJob".into()).await.unwrap().len(), 1);
+
+        repo.update_job_status("busyJob", JobStatus::Cancelled).await.unwrap();
+        assert_eq!(repo.delete_job("busyJob").await.unwrap(), Some(1));
+        assert_eq!(repo.delete_job("busyJob").await.unwrap(), None);
+    }
+
+    let state = test_state().await;
+    let mut job = Job::new("nmap-scan".into());
+    job.id = "busyJob".into();
+    job.status = JobStatus::Running;
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let response = delete_job(State(state.clone()), Path("busyJob".into())).await.into_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert!(repository::get_job(state.db.as_ref().unwrap(), "busyJob").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn scenario_update_job_priority_reorders_the_queue() {
+    let db_repo = DbRepository::new(test_state().await.db.clone().unwrap());
+    let mem_repo = InMemoryRepository::new();
+    let repos: [&dyn Repository; 2] = [&db_repo, &mem_repo];
+
+    for repo in repos {
+        for (id, priority) in [("first", JobPriority::HIGH), ("second", JobPriority::NORMAL), ("third", JobPriority::LOW)] {
+            let mut job = Job::new("discovery".into());
+            job.id = id.into();
+            job.priority = priority;
+            repo.create_job(&job).await.unwrap();
+        }
+
+        let bumped = repo.update_job_priority("third", JobPriority::CRITICAL).await.unwrap().unwrap();
+        assert_eq!(bumped.priority, JobPriority::CRITICAL);
+        assert_eq!(repo.get_job("third").await.unwrap().unwrap().priority, JobPriority::CRITICAL);
+
+        let mut order = Vec::new();
+        while let Some(job) = repo.claim_next_job().await.unwrap() {
+            order.push(job.id);
+        }
+        assert_eq!(order, ["third", "first", "second"]);
+
+        // Running now, and a missing job, are left alone
+        assert!(repo.update_job_priority("first", JobPriority::LOW).await.unwrap().is_none());
+        assert!(repo.update_job_priority("missing", JobPriority::LOW).await.unwrap().is_none());
+        assert_eq!(repo.get_job("first").await.unwrap().unwrap().priority, JobPriority::HIGH);
+    }
+}
+
+#[tokio::test]
+async fn scenario_patch_job_priority() {
+    let state = test_state().await;
+    for (id, status) in [("waiting", JobStatus::Queued), ("busy", JobStatus::Running), ("done", JobStatus::Completed)] {
+        let mut job = Job::new("discovery".into());
+        job.id = id.into();
+        job.status = status;
+        repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    }
+    let critical = || Json(serde_json::from_value::<UpdateJobRequest>(serde_json::json!({ "priority": "CRITICAL" })).unwrap());
+
+    let response = update_job(State(state.clone()), Path("waiting".into()), critical()).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let job: Job = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job.priority, JobPriority::CRITICAL);
+    assert_eq!(job.status, JobStatus::Queued);
+
+    for id in ["busy", "done"] {
+        let response = update_job(State(state.clone()), Path(id.into()), critical()).await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT, "{}", id);
+        assert_eq!(repository::get_job(state.db.as_ref().unwrap(), id).await.unwrap().unwrap().priority, JobPriority::NORMAL);
+    }
+
+    let response = update_job(State(state.clone()), Path("missing".into()), critical()).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+async fn set_scan_config(state: &Arc<AppState>, scan_config: serde_json::Value) {
+    let mut config = decebalus_backend::models::Config::new();
+    config.set("scan_config".to_string(), scan_config);
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+}
+
+#[tokio::test]
+async fn scenario_job_target_overrides_configured_target() {
+    let state = test_state().await;
+    set_scan_config(&state, serde_json::json!({ "target": "127.0.0.20/32" })).await;
+
+    let mut job = Job::new("discovery".into());
+    job.id = "jobOwnTarget".into();
+    job.config = serde_json::json!({"target": "127.0.0.21/32"});
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    let mut fallback = Job::new("discovery".into());
+    fallback.id = "jobConfigTarget".into();
+    repository::create_job(state.db.as_ref().unwrap(), &fallback).await.unwrap();
+
+    for job in [job, fallback] {
+        let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+        JobExecutor::execute_job(job, state.clone(), permit).await;
+    }
+
+    let target_of = |job: Job| job.results.unwrap().success().unwrap()["target_network"].clone();
+    let own = repository::get_job(state.db.as_ref().unwrap(), "jobOwnTarget").await.unwrap().unwrap();
+    assert_eq!(target_of(own), "127.0.0.21/32");
+    let fallback = repository::get_job(state.db.as_ref().unwrap(), "jobConfigTarget").await.unwrap().unwrap();
+    assert_eq!(target_of(fallback), "127.0.0.20/32");
+}
+
+#[tokio::test]
+async fn scenario_job_ports_override_configured_ports() {
+    let state = test_state().await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.22:0").await.unwrap();
+    let open = listener.local_addr().unwrap().port();
+    set_scan_config(&state, serde_json::json!({ "ports": [1] })).await;
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("127.0.0.22".into())).await.unwrap();
+
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({
+        "job_type": "port-scan",
+        "target": "127.0.0.22",
+        "ports": [open, 1, open],
+    }))
+    .unwrap();
+    let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Job = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created.config["ports"], serde_json::json!([1, open]));
+
+    let job = wait_for_status(&state, &created.id, JobStatus::Completed).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.results);
+    let host = repository::get_host(state.db.as_ref().unwrap(), "127.0.0.22").await.unwrap().unwrap();
+    assert!(host.has_open_port(open));
+}
+
+#[tokio::test]
+async fn scenario_create_job_rejects_bad_ports_and_target() {
+    let state = test_state().await;
+    let bad = [
+        serde_json::json!({ "job_type": "port-scan", "ports": [] }),
+        serde_json::json!({ "job_type": "port-scan", "ports": [0, 22] }),
+        serde_json::json!({ "job_type": "export", "ports": [22] }),
+        serde_json::json!({ "job_type": "discovery", "target": "10.0.0.0/33" }),
+    ];
+
+    for body in bad {
+        let payload: CreateJobRequest = serde_json::from_value(body.clone()).unwrap();
+        let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+    }
+}
+
+#[tokio::test]
+async fn scenario_discovery_job_needs_a_target() {
+    let state = test_state().await;
+    let discovery = || serde_json::from_value::<CreateJobRequest>(serde_json::json!({ "job_type": "discovery" })).unwrap();
+    let scheduled = || serde_json::from_value::<CreateJobRequest>(serde_json::json!({ "job_type": "discovery", "cron": "0 0 2 * * *" })).unwrap();
+
+    // Neither the request nor scan_config has one
+    for payload in [discovery(), scheduled()] {
+        let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["message"], "target is required for discovery jobs");
+    }
+
+    // scan_config.target stands in for it
+    set_scan_config(&state, serde_json::json!({ "target": "127.0.0.24/32" })).await;
+    state.config.reload(state.repo.as_ref()).await.unwrap();
+    for payload in [discovery(), scheduled()] {
+        let response = create_job(State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+}
+
+#[tokio::test]
+async fn scenario_shutdown_waits_for_a_job_in_flight() {
+    let state = test_state().await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.23:0").await.unwrap();
+    let open = listener.local_addr().unwrap().port();
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("127.0.0.23".into())).await.unwrap();
+
+    let mut job = Job::new("port-scan".into());
+    job.id = "shutdown-finish".into();
+    job.config = serde_json::json!({ "target": "127.0.0.23", "ports": [open] });
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    JobExecutor::run_queue(&state).await;
+
+    JobExecutor::shutdown(&state, std::time::Duration::from_secs(10)).await;
+
+    let job = repository::get_job(state.db.as_ref().unwrap(), "shutdown-finish").await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.results);
+}
+
+#[tokio::test]
+async fn scenario_shutdown_requeues_a_job_that_outlives_the_timeout() {
+    let state = test_state().await;
+    // Two probes a second over 100 ports keeps the job busy well past the timeout
+    set_scan_config(&state, serde_json::json!({ "max_connections_per_second": 2.0 })).await;
+    repository::upsert_host(state.db.as_ref().unwrap(), &decebalus_backend::models::Host::new("127.0.0.24".into())).await.unwrap();
+
+    let mut job = Job::new("port-scan".into());
+    job.id = "shutdown-slow".into();
+    job.config = serde_json::json!({ "target": "127.0.0.24", "ports": (1..=100).collect::<Vec<u16>>() });
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+    JobExecutor::run_queue(&state).await;
+    wait_for_status(&state, "shutdown-slow", JobStatus::Running).await;
+
+    JobExecutor::shutdown(&state, std::time::Duration::from_millis(200)).await;
+
+    let job = repository::get_job(state.db.as_ref().unwrap(), "shutdown-slow").await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Queued);
+
+    // Nothing new starts once shutdown has begun
+    JobExecutor::run_queue(&state).await;
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let job = repository::get_job(state.db.as_ref().unwrap(), "shutdown-slow").await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Queued);
+}
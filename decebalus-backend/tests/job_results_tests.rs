@@ -0,0 +1,71 @@
+// tests/job_results_tests.rs
+
+mod common;
+
+use sqlx::Row;
+
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{Job, JobResult, JobStatus};
+use decebalus_backend::services::job_executor::JobExecutor;
+
+#[tokio::test]
+async fn large_results_are_compressed_and_read_back_intact() {
+    let state = common::test_state().await;
+    let job = Job::new("nmap-scan".into());
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    // Well above the default 64 KiB threshold
+    let results = JobResult::Success(serde_json::json!({
+        "job_id": job.id,
+        "hosts": (0..5000).map(|i| serde_json::json!({
+            "ip": format!("10.{}.{}.{}", i / 65536, (i / 256) % 256, i % 256),
+            "ports": [22, 80, 443],
+            "banner": "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13"
+        })).collect::<Vec<_>>()
+    }));
+    assert!(results.to_stored().len() > 64 * 1024);
+
+    repository::update_job_results(state.db.as_ref().unwrap(), &job.id, Some(results.clone())).await.unwrap();
+
+    let row = sqlx::query("SELECT results, results_compressed FROM jobs WHERE id = ?1")
+        .bind(&job.id)
+        .fetch_one(state.db.as_ref().unwrap())
+        .await
+        .unwrap();
+    assert!(row.get::<bool, _>("results_compressed"));
+    assert!(row.get::<String, _>("results").len() < results.to_stored().len() / 4);
+
+    let loaded = repository::get_job(state.db.as_ref().unwrap(), &job.id).await.unwrap().unwrap();
+    assert_eq!(loaded.results, Some(results));
+
+    // Small results stay plain text
+    let ok = JobResult::Success(serde_json::json!({ "ok": true }));
+    repository::update_job_results(state.db.as_ref().unwrap(), &job.id, Some(ok)).await.unwrap();
+    let row = sqlx::query("SELECT results, results_compressed FROM jobs WHERE id = ?1")
+        .bind(&job.id)
+        .fetch_one(state.db.as_ref().unwrap())
+        .await
+        .unwrap();
+    assert!(!row.get::<bool, _>("results_compressed"));
+    assert_eq!(row.get::<String, _>("results"), "{\"success\":{\"ok\":true}}");
+}
+
+#[tokio::test]
+async fn failed_job_result_is_the_error_variant() {
+    let state = common::test_state().await;
+    let job = Job::new("no-such-type".into());
+    repository::create_job(state.db.as_ref().unwrap(), &job).await.unwrap();
+
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+
+    let failed = repository::get_job(state.db.as_ref().unwrap(), &job.id).await.unwrap().unwrap();
+    assert_eq!(failed.status, JobStatus::Failed);
+    assert_eq!(failed.results, Some(JobResult::Error("Unknown job type: no-such-type".into())));
+
+    // What the API returns, and what a client reads back
+    let body = serde_json::to_value(&failed).unwrap();
+    assert!(body["results"]["error"].is_string());
+    let parsed: Job = serde_json::from_value(body).unwrap();
+    assert!(matches!(parsed.results, Some(JobResult::Error(_))));
+}
@@ -0,0 +1,134 @@
+// tests/logs_api_tests.rs
+
+mod common;
+
+use decebalus_backend::api;
+use decebalus_backend::db::repository;
+
+/// Serve the full router on an ephemeral port and return its base URL.
+async fn serve(state: std::sync::Arc<decebalus_backend::AppState>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, api::router(state)).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn logs_routes_return_json_arrays() {
+    let state = common::test_state().await;
+    repository::add_log(state.db.as_ref().unwrap(), "INFO", "tests", None, Some("job-1"), "first").await.unwrap();
+    repository::add_log(state.db.as_ref().unwrap(), "WARN", "tests", None, None, "second").await.unwrap();
+    let base = serve(state).await;
+
+    let response = reqwest::get(format!("{}/api/logs", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let logs: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(logs.as_array().unwrap().len(), 2);
+
+    let response = reqwest::get(format!("{}/api/logs/job-1", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let logs: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(logs.as_array().unwrap().len(), 1);
+    assert_eq!(logs[0]["content"], "first");
+}
+
+#[tokio::test]
+async fn get_log_fetches_one_entry_by_its_id() {
+    let state = common::test_state().await;
+    repository::add_log(state.db.as_ref().unwrap(), "INFO", "tests", None, Some("job-1"), "first").await.unwrap();
+    repository::add_log(state.db.as_ref().unwrap(), "ERROR", "tests", Some("probe"), Some("job-1"), "second").await.unwrap();
+
+    let logs = repository::get_logs_by_job_id(state.db.as_ref().unwrap(), "job-1".into()).await.unwrap();
+    let second = logs.iter().find(|l| l.content == "second").unwrap();
+
+    let log = repository::get_log(state.db.as_ref().unwrap(), second.id.clone()).await.unwrap().unwrap();
+    assert_eq!(log.id, second.id);
+    assert_eq!(log.severity, "ERROR");
+    assert_eq!(log.module.as_deref(), Some("probe"));
+    assert_eq!(log.content, "second");
+    // A job id is not a log id
+    assert!(repository::get_log(state.db.as_ref().unwrap(), "job-1".into()).await.unwrap().is_none());
+
+    let base = serve(state).await;
+    let response = reqwest::get(format!("{}/api/logs/id/{}", base, second.id)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["content"], "second");
+
+    let response = reqwest::get(format!("{}/api/logs/id/nope", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+/// Log with a fixed `created_at` (stored as `YYYY-MM-DD HH:MM:SS` UTC).
+async fn insert_log(db: &sqlx::SqlitePool, severity: &str, service: &str, created_at: &str, content: &str) {
+    sqlx::query("INSERT INTO logs (id, severity, service, content, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(severity)
+        .bind(service)
+        .bind(content)
+        .bind(created_at)
+        .execute(db)
+        .await
+        .unwrap();
+}
+
+async fn contents(url: String) -> Vec<String> {
+    let response = reqwest::get(url).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let logs: Vec<serde_json::Value> = response.json().await.unwrap();
+    logs.iter().map(|l| l["content"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn logs_filter_by_severity_and_service() {
+    let state = common::test_state().await;
+    insert_log(state.db.as_ref().unwrap(), "INFO", "scanner", "2024-05-01 10:00:00", "started").await;
+    insert_log(state.db.as_ref().unwrap(), "ERROR", "scanner", "2024-05-01 10:05:00", "nmap crashed").await;
+    insert_log(state.db.as_ref().unwrap(), "ERROR", "port_scanner", "2024-05-01 10:06:00", "connect refused").await;
+    insert_log(state.db.as_ref().unwrap(), "WARN", "scanner", "2024-05-01 10:07:00", "slow host").await;
+    let base = serve(state).await;
+
+    // Newest first
+    assert_eq!(contents(format!("{}/api/logs?severity=ERROR", base)).await, ["connect refused", "nmap crashed"]);
+    assert_eq!(contents(format!("{}/api/logs?severity=error&service=scanner", base)).await, ["nmap crashed"]);
+    assert_eq!(contents(format!("{}/api/logs?limit=2", base)).await, ["slow host", "connect refused"]);
+    assert_eq!(contents(format!("{}/api/logs", base)).await.len(), 4);
+}
+
+#[tokio::test]
+async fn logs_filter_by_time_window() {
+    let state = common::test_state().await;
+    insert_log(state.db.as_ref().unwrap(), "INFO", "scanner", "2024-05-01 09:59:59", "before").await;
+    insert_log(state.db.as_ref().unwrap(), "INFO", "scanner", "2024-05-01 10:00:00", "at start").await;
+    insert_log(state.db.as_ref().unwrap(), "INFO", "scanner", "2024-05-01 10:30:00", "inside").await;
+    insert_log(state.db.as_ref().unwrap(), "INFO", "scanner", "2024-05-01 11:00:01", "after").await;
+    let base = serve(state).await;
+
+    let window = "since=2024-05-01T10:00:00Z&until=2024-05-01T11:00:00Z";
+    assert_eq!(contents(format!("{}/api/logs?{}", base, window)).await, ["inside", "at start"]);
+    // Offsets are converted to UTC
+    let url = format!("{}/api/logs?since=2024-05-01T12:30:00%2B02:00", base);
+    assert_eq!(contents(url).await, ["after", "inside"]);
+
+    let response = reqwest::get(format!("{}/api/logs?since=yesterday", base)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn in_memory_logs_filter_the_same_way() {
+    use decebalus_backend::models::LogFilter;
+
+    let state = common::in_memory_state();
+    for (severity, content) in [("INFO", "one"), ("ERROR", "two"), ("ERROR", "three")] {
+        state.repo.add_log(severity, "scanner", None, None, content).await.unwrap();
+    }
+
+    let filter = LogFilter { severity: Some("error".into()), limit: Some(1), ..Default::default() };
+    let logs = state.repo.query_logs(&filter).await.unwrap();
+    assert_eq!(logs.iter().map(|l| l.content.as_str()).collect::<Vec<_>>(), ["three"]);
+
+    let filter = LogFilter { since: Some(chrono::Utc::now() + chrono::Duration::hours(1)), ..Default::default() };
+    assert!(state.repo.query_logs(&filter).await.unwrap().is_empty());
+}
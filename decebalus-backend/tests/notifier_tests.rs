@@ -0,0 +1,70 @@
+// tests/notifier_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{Config, WsEvent};
+use decebalus_backend::services::notifier::{NotificationHub, Notifier};
+
+/// Notifier forwarding every event it receives to a channel.
+struct MockNotifier {
+    name: &'static str,
+    tx: mpsc::UnboundedSender<WsEvent>,
+}
+
+#[async_trait]
+impl Notifier for MockNotifier {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn notify(&self, event: &WsEvent) {
+        let _ = self.tx.send(event.clone());
+    }
+}
+
+async fn enable(state: &Arc<decebalus_backend::AppState>, notifiers: serde_json::Value) {
+    let mut config = Config::new();
+    config.set("integrations".to_string(), json!({ "notifiers": notifiers }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+}
+
+#[tokio::test]
+async fn enabled_notifier_receives_job_completed() {
+    let state = common::test_state().await;
+    enable(&state, json!(["mock"])).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    state.notifiers.register(Arc::new(MockNotifier { name: "mock", tx }));
+    NotificationHub::spawn(state.clone());
+
+    state.broadcaster.send(WsEvent::JobCompleted { job_id: "job-42".to_string() }).unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("notifier was not called")
+        .unwrap();
+    assert_eq!(event, WsEvent::JobCompleted { job_id: "job-42".to_string() });
+}
+
+#[tokio::test]
+async fn disabled_notifier_is_not_called() {
+    let state = common::test_state().await;
+    enable(&state, json!(["email"])).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    state.notifiers.register(Arc::new(MockNotifier { name: "mock", tx }));
+    NotificationHub::spawn(state.clone());
+
+    state.broadcaster.send(WsEvent::JobCompleted { job_id: "job-43".to_string() }).unwrap();
+
+    let result = tokio::time::timeout(Duration::from_millis(300), rx.recv()).await;
+    assert!(result.is_err());
+}
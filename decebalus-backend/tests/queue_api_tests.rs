@@ -0,0 +1,64 @@
+// tests/queue_api_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::Json;
+
+use decebalus_backend::api::jobs::create_job;
+use decebalus_backend::api::queue::{pause_queue, queue_status, resume_queue};
+use decebalus_backend::models::{CreateJobRequest, Job, JobStatus};
+use decebalus_backend::services::JobExecutor;
+use decebalus_backend::state::AppState;
+
+async fn job_status(state: &Arc<AppState>, id: &str) -> JobStatus {
+    state.repo.get_job(id).await.unwrap().unwrap().status
+}
+
+#[tokio::test]
+async fn job_created_while_paused_stays_queued_until_resume() {
+    let state = common::test_state().await;
+    let Json(status) = pause_queue(State(state.clone())).await.unwrap();
+    assert_eq!(status["paused"], true);
+
+    // With no hosts a vuln-scan completes straight away once it starts
+    let payload: CreateJobRequest = serde_json::from_value(serde_json::json!({ "job_type": "vuln-scan" })).unwrap();
+    let (_, Json(job)) = create_job(State(state.clone()), Json(payload)).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(job_status(&state, &job.id).await, JobStatus::Queued);
+    let Json(status) = queue_status(State(state.clone())).await.unwrap();
+    assert_eq!(status["queued_jobs"], 1);
+
+    let Json(status) = resume_queue(State(state.clone())).await.unwrap();
+    assert_eq!(status["paused"], false);
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while job_status(&state, &job.id).await != JobStatus::Completed && std::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(job_status(&state, &job.id).await, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn due_scheduled_job_waits_while_paused() {
+    let state = common::test_state().await;
+    let mut job = Job::new("vuln-scan".into());
+    job.status = JobStatus::Scheduled;
+    job.scheduled_at = Some(chrono::Utc::now().timestamp() - 1);
+    state.repo.create_job(&job).await.unwrap();
+
+    let _ = pause_queue(State(state.clone())).await.unwrap();
+    JobExecutor::run_due_scheduled_jobs(&state).await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(job_status(&state, &job.id).await, JobStatus::Scheduled);
+
+    let _ = resume_queue(State(state.clone())).await.unwrap();
+    JobExecutor::run_due_scheduled_jobs(&state).await;
+    let _all = state.semaphore.acquire_many(state.max_threads as u32).await.unwrap();
+    assert_eq!(job_status(&state, &job.id).await, JobStatus::Completed);
+}
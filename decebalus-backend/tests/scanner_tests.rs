@@ -0,0 +1,248 @@
+// tests/scanner_tests.rs
+
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpListener;
+
+use decebalus_backend::db::inmemory_repository::InMemoryRepository;
+use decebalus_backend::db::repository_trait::Repository;
+use decebalus_backend::models::{DiscoveryMethod, Host, Job, ScanConfig, WsEvent};
+use decebalus_backend::services::icmp::IcmpPinger;
+use decebalus_backend::services::port_scanner::PortScanner;
+use decebalus_backend::services::scanner::NetworkScanner;
+use decebalus_backend::services::{EventSink, ScanContext};
+
+#[tokio::test]
+async fn discovery_runs_against_in_memory_repo_and_mock_sink() {
+    // 127.0.0.2 never answers ARP, so discovery falls through to the TCP probe,
+    // which finds the listener on one of the probed ports.
+    let _listener = TcpListener::bind("127.0.0.2:8888").await.unwrap();
+
+    let repo = Arc::new(InMemoryRepository::new());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let ctx = ScanContext::new(repo.clone(), sink, ScanConfig::default());
+
+    let found = NetworkScanner::discover_hosts("127.0.0.2/32", &ctx).await.unwrap();
+
+    assert_eq!(found, 1);
+    assert!(repo.get_host("127.0.0.2").await.unwrap().is_some());
+
+    let events = events.lock().unwrap();
+    assert!(events.iter().any(|e| matches!(e, WsEvent::Log { message }
+        if message.starts_with("Starting network discovery on 127.0.0.2/32"))));
+    assert!(events.contains(&WsEvent::HostFound { ip: "127.0.0.2".into() }));
+    assert!(events.contains(&WsEvent::NewHost { ip: "127.0.0.2".into() }));
+}
+
+#[tokio::test]
+async fn discovery_keeps_a_known_hostname_when_reverse_dns_finds_none() {
+    // 127.0.0.7 has no PTR record
+    let _listener = TcpListener::bind("127.0.0.7:8888").await.unwrap();
+
+    let repo = Arc::new(InMemoryRepository::new());
+    let mut known = Host::new("127.0.0.7".to_string());
+    known.hostname = Some("nas.home.arpa".to_string());
+    repo.upsert_host(&known).await.unwrap();
+    let ctx = ScanContext::new(repo.clone(), EventSink::noop(), ScanConfig::default());
+
+    NetworkScanner::discover_hosts("127.0.0.7/32", &ctx).await.unwrap();
+
+    let host = repo.get_host("127.0.0.7").await.unwrap().unwrap();
+    assert_eq!(host.hostname.as_deref(), Some("nas.home.arpa"));
+}
+
+#[tokio::test]
+async fn icmp_echo_counts_as_one_alive_confirmation() {
+    if IcmpPinger::check_available().is_err() {
+        return;
+    }
+    // Loopback answers the echo; only one probed port is open
+    let _listener = TcpListener::bind("127.0.0.42:8888").await.unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let config = |n| ScanConfig {
+        discovery_method: DiscoveryMethod::Icmp,
+        alive_confirmations: Some(n),
+        ..Default::default()
+    };
+
+    let ctx = ScanContext::new(repo.clone(), EventSink::noop(), config(3));
+    assert_eq!(NetworkScanner::discover_hosts("127.0.0.42/32", &ctx).await.unwrap(), 0);
+
+    let ctx = ScanContext::new(repo.clone(), EventSink::noop(), config(2));
+    assert_eq!(NetworkScanner::discover_hosts("127.0.0.42/32", &ctx).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn icmp_fallback_reason_is_logged() {
+    if IcmpPinger::check_available().is_ok() {
+        return;
+    }
+    let repo = Arc::new(InMemoryRepository::new());
+    let config = ScanConfig { discovery_method: DiscoveryMethod::Icmp, ..Default::default() };
+    let ctx = ScanContext::new(repo.clone(), EventSink::noop(), config);
+
+    NetworkScanner::discover_hosts("127.0.0.43/32", &ctx).await.unwrap();
+
+    let logs = repo.get_logs().await.unwrap();
+    assert!(logs.iter().any(|l| l.severity == "WARN" && l.module.as_deref() == Some("discover_hosts")));
+}
+
+#[tokio::test]
+async fn concurrent_discovery_finds_every_alive_host_in_a_range() {
+    // .1–.6 are probed; only the hosts with a listener are up
+    let alive = ["127.0.2.2", "127.0.2.3", "127.0.2.5"];
+    let mut listeners = Vec::new();
+    for ip in alive {
+        listeners.push(TcpListener::bind((ip, 8888)).await.unwrap());
+    }
+
+    let repo = Arc::new(InMemoryRepository::new());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let config = ScanConfig { per_network_concurrency: Some(2), ..Default::default() };
+    let ctx = ScanContext::new(repo.clone(), sink, config);
+
+    let found = NetworkScanner::discover_hosts("127.0.2.0/29", &ctx).await.unwrap();
+
+    assert_eq!(found, alive.len());
+    let mut stored: Vec<String> = repo.list_hosts().await.unwrap().into_iter().map(|h| h.ip).collect();
+    stored.sort();
+    assert_eq!(stored, alive);
+
+    // Probes finish in any order, but every alive host is reported exactly once
+    let events = events.lock().unwrap();
+    for ip in alive {
+        let reported = events.iter().filter(|e| matches!(e, WsEvent::HostFound { ip: found } if found == ip)).count();
+        assert_eq!(reported, 1, "{} reported {} times", ip, reported);
+    }
+}
+
+#[tokio::test]
+async fn port_scan_records_duration_on_host() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.upsert_host(&Host::new("127.0.0.4".to_string())).await.unwrap();
+    let ctx = ScanContext::new(repo.clone(), EventSink::noop(), ScanConfig::default());
+
+    PortScanner::scan_host("127.0.0.4", &ctx, "job-duration").await.unwrap();
+
+    let host = repo.get_host("127.0.0.4").await.unwrap().unwrap();
+    assert!(host.last_scan_duration_ms.is_some_and(|ms| ms > 0));
+}
+
+#[tokio::test]
+async fn port_scan_moves_job_through_its_phases() {
+    let _listener = TcpListener::bind("127.0.0.6:8888").await.unwrap();
+
+    let repo = Arc::new(InMemoryRepository::new());
+    let job = Job::new("port-scan".to_string());
+    repo.create_job(&job).await.unwrap();
+    repo.upsert_host(&Host::new("127.0.0.6".to_string())).await.unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let config = ScanConfig { ports: Some(vec![8888]), ..Default::default() };
+    let ctx = ScanContext::new(repo.clone(), sink, config);
+
+    PortScanner::scan_host("127.0.0.6", &ctx, &job.id).await.unwrap();
+
+    let phases: Vec<String> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|e| match e {
+            WsEvent::JobPhase { job_id, phase } if *job_id == job.id => Some(phase.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(phases, ["tcp-scan (1/3)", "service-detection (2/3)", "saving (3/3)"]);
+
+    let job = repo.get_job(&job.id).await.unwrap().unwrap();
+    assert_eq!(job.phase.as_deref(), Some("saving (3/3)"));
+}
+
+#[tokio::test]
+async fn saving_a_rescanned_host_with_a_new_port_emits_host_changed() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let mut host = Host::new("10.0.0.9".to_string());
+    host.add_port(22, "tcp", "open", None, None, None);
+    host.last_port_scan = Some(chrono::Utc::now().to_rfc3339());
+    repo.upsert_host(&host).await.unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let ctx = ScanContext::new(repo.clone(), sink, ScanConfig::default());
+
+    host.add_port(443, "tcp", "open", None, None, None);
+    ctx.save_host(&host).await.unwrap();
+
+    assert!(events.lock().unwrap().contains(&WsEvent::HostChanged {
+        ip: "10.0.0.9".into(),
+        added_ports: vec![443],
+        removed_ports: vec![],
+        added_services: vec![],
+        removed_services: vec![],
+    }));
+    let logs = repo.get_logs().await.unwrap();
+    assert!(logs.iter().any(|l| l.service == "host_changes" && l.content == "Host 10.0.0.9 changed: ports +443"));
+}
+
+#[tokio::test]
+async fn full_port_scan_finding_nothing_closes_the_last_open_port() {
+    // TCP connects to a multicast address fail at once, so the sweep finds nothing
+    let repo = Arc::new(InMemoryRepository::new());
+    let mut host = Host::new("224.0.0.7".to_string());
+    host.add_port(8888, "tcp", "open", None, None, None);
+    host.last_port_scan = Some(chrono::Utc::now().to_rfc3339());
+    repo.upsert_host(&host).await.unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let ctx = ScanContext::new(repo.clone(), sink, ScanConfig::default());
+
+    assert_eq!(PortScanner::scan_host("224.0.0.7", &ctx, "job-closed").await.unwrap(), 0);
+
+    let host = repo.get_host("224.0.0.7").await.unwrap().unwrap();
+    assert!(!host.has_open_port(8888));
+    assert!(events.lock().unwrap().contains(&WsEvent::HostChanged {
+        ip: "224.0.0.7".into(),
+        added_ports: vec![],
+        removed_ports: vec![8888],
+        added_services: vec![],
+        removed_services: vec![],
+    }));
+}
+
+#[tokio::test]
+async fn first_port_scan_of_a_host_is_not_a_change() {
+    let repo = Arc::new(InMemoryRepository::new());
+    repo.upsert_host(&Host::new("10.0.0.10".to_string())).await.unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        EventSink::new(move |event| events.lock().unwrap().push(event))
+    };
+    let ctx = ScanContext::new(repo.clone(), sink, ScanConfig::default());
+
+    let mut host = Host::new("10.0.0.10".to_string());
+    host.add_port(80, "tcp", "open", None, None, None);
+    ctx.save_host(&host).await.unwrap();
+
+    assert!(events.lock().unwrap().is_empty());
+}
@@ -0,0 +1,150 @@
+// tests/schedules_tests.rs
+
+mod common;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{Duration, Utc};
+
+use decebalus_backend::api::schedules::{create_schedule, delete_schedule, get_schedule, list_schedules, update_schedule};
+use decebalus_backend::db::db_repository::DbRepository;
+use decebalus_backend::db::inmemory_repository::InMemoryRepository;
+use decebalus_backend::db::repository;
+use decebalus_backend::db::repository_trait::Repository;
+use decebalus_backend::models::{CreateScheduleRequest, Job, JobStatus, Schedule, UpdateScheduleRequest};
+use decebalus_backend::services::JobExecutor;
+
+fn nightly_discovery() -> CreateScheduleRequest {
+    CreateScheduleRequest {
+        name: "Nightly discovery".into(),
+        job_type: "discovery".into(),
+        target: Some("192.168.1.0/24".into()),
+        cron: "0 0 2 * * *".into(),
+        enabled: true,
+    }
+}
+
+async fn body<T: serde::de::DeserializeOwned>(response: axum::response::Response) -> T {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn create_get_list_and_delete_schedule() {
+    let state = common::test_state().await;
+
+    let response = create_schedule(State(state.clone()), Json(nightly_discovery())).await.into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Schedule = body(response).await;
+    assert_eq!(created.name, "Nightly discovery");
+    assert!(created.enabled);
+    assert!(created.last_run_at.is_none());
+
+    let response = get_schedule(State(state.clone()), Path(created.id.clone())).await.into_response();
+    assert_eq!(body::<Schedule>(response).await, created);
+
+    let response = list_schedules(State(state.clone())).await.into_response();
+    assert_eq!(body::<Vec<Schedule>>(response).await, vec![created.clone()]);
+
+    let response = delete_schedule(State(state.clone()), Path(created.id.clone())).await.into_response();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = get_schedule(State(state.clone()), Path(created.id)).await.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invalid_schedules_are_rejected() {
+    let state = common::test_state().await;
+
+    let bad_cron = CreateScheduleRequest { cron: "every night".into(), ..nightly_discovery() };
+    let bad_target = CreateScheduleRequest { target: Some("not-a-network".into()), ..nightly_discovery() };
+    let bad_type = CreateScheduleRequest { job_type: "reboot".into(), ..nightly_discovery() };
+
+    for request in [bad_cron, bad_target, bad_type] {
+        let response = create_schedule(State(state.clone()), Json(request)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    assert!(repository::list_schedules(state.db.as_ref().unwrap()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn discovery_schedule_without_any_target_is_rejected() {
+    let state = common::test_state().await;
+
+    let request = CreateScheduleRequest { target: None, ..nightly_discovery() };
+    let response = create_schedule(State(state.clone()), Json(request)).await.into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = body(response).await;
+    assert_eq!(error["error"]["message"], "target is required for discovery jobs");
+    assert!(repository::list_schedules(state.db.as_ref().unwrap()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn disabled_schedule_creates_no_jobs() {
+    let state = common::test_state().await;
+
+    let response = create_schedule(State(state.clone()), Json(nightly_discovery())).await.into_response();
+    let created: Schedule = body(response).await;
+
+    let disable = UpdateScheduleRequest { enabled: Some(false), ..Default::default() };
+    let response = update_schedule(State(state.clone()), Path(created.id.clone()), Json(disable)).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!body::<Schedule>(response).await.enabled);
+
+    // Well past the first fire time
+    let repo = DbRepository::new(state.db.clone().unwrap());
+    let queued = JobExecutor::materialize_due_schedules(&repo, Utc::now() + Duration::days(2)).await.unwrap();
+
+    assert!(queued.is_empty());
+    assert!(repository::list_jobs(state.db.as_ref().unwrap()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn due_enabled_schedule_spawns_a_job() {
+    let repo = InMemoryRepository::new();
+    let mut schedule = Schedule::new("Nightly discovery".into(), "discovery".into(), Some("192.168.1.0/24".into()), "0 0 2 * * *".into());
+    schedule.created_at = (Utc::now() - Duration::days(2)).timestamp();
+    repo.create_schedule(&schedule).await.unwrap();
+
+    let now = Utc::now();
+    let queued = JobExecutor::materialize_due_schedules(&repo, now).await.unwrap();
+
+    assert_eq!(queued.len(), 1);
+    let jobs = repo.list_jobs().await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].job_type, "discovery");
+    assert_eq!(jobs[0].status, JobStatus::Queued);
+    assert_eq!(jobs[0].config["target"], "192.168.1.0/24");
+    assert_eq!(jobs[0].config["schedule_id"], schedule.id.as_str());
+    assert_eq!(repo.get_schedule(&schedule.id).await.unwrap().unwrap().last_run_at, Some(now.timestamp()));
+
+    // Not due again until the next night
+    assert!(JobExecutor::materialize_due_schedules(&repo, now).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn scheduler_runs_past_due_scheduled_job() {
+    let state = common::in_memory_state();
+
+    // Port-scan with no known hosts: completes right away
+    let mut job = Job::new("port-scan".into());
+    job.status = JobStatus::Scheduled;
+    job.scheduled_at = Some((Utc::now() - Duration::minutes(5)).timestamp());
+    state.repo.create_job(&job).await.unwrap();
+
+    let scheduler = tokio::spawn(JobExecutor::check_and_run_scheduled_jobs(state.clone()));
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let status = loop {
+        let status = state.repo.get_job(&job.id).await.unwrap().unwrap().status;
+        if status == JobStatus::Completed || std::time::Instant::now() > deadline {
+            break status;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    };
+    scheduler.abort();
+
+    assert_eq!(status, JobStatus::Completed);
+}
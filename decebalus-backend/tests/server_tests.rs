@@ -0,0 +1,120 @@
+// tests/server_tests.rs
+
+mod common;
+
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use decebalus_backend::{api, server};
+
+/// Serve the full router behind the CORS layer configured by `vars` and return its base URL.
+async fn serve_with_cors(vars: &'static [(&'static str, &'static str)]) -> String {
+    let env = |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string());
+    let cors = server::cors_layer(env).unwrap().unwrap();
+    let app = api::router(common::test_state().await).layer(cors);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+const CERT: &str = "tests/fixtures/tls/cert.pem";
+const KEY: &str = "tests/fixtures/tls/key.pem";
+
+#[tokio::test]
+async fn serves_https_with_a_self_signed_cert_and_shuts_down() {
+    let tls = server::load_tls(CERT.as_ref(), KEY.as_ref()).await.unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let app = api::router(common::test_state().await);
+    let server = tokio::spawn(server::run(listener, app, Some(tls), async {
+        let _ = stopped.await;
+    }));
+
+    // The fixture cert is self-signed, for localhost
+    let client = reqwest::Client::builder().danger_accept_invalid_certs(true).build().unwrap();
+    let response = client.get(format!("https://localhost:{}/health", port)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // Plain HTTP isn't served on the TLS port
+    assert!(reqwest::get(format!("http://localhost:{}/health", port)).await.is_err());
+
+    stop.send(()).unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(15), server).await.expect("server didn't stop");
+    result.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn serves_plain_http_without_tls() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let app = api::router(common::test_state().await);
+    let server = tokio::spawn(server::run(listener, app, None, async {
+        let _ = stopped.await;
+    }));
+
+    let response = reqwest::get(format!("http://127.0.0.1:{}/health", port)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    stop.send(()).unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(15), server).await.expect("server didn't stop");
+    result.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn missing_key_file_is_reported() {
+    let err = server::load_tls(CERT.as_ref(), "tests/fixtures/tls/missing.pem".as_ref()).await.unwrap_err();
+    assert!(err.to_string().contains("missing.pem"), "{}", err);
+}
+
+#[tokio::test]
+async fn cors_headers_are_sent_for_a_configured_origin() {
+    let base = serve_with_cors(&[("CORS_ALLOWED_ORIGINS", "http://dash.lan:5173")]).await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{}/api/hosts", base)).header("Origin", "http://dash.lan:5173").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(response.headers()["access-control-allow-origin"], "http://dash.lan:5173");
+    assert_eq!(response.headers()["access-control-allow-credentials"], "true");
+
+    // Preflight for a JSON POST
+    let response = client
+        .request(reqwest::Method::OPTIONS, format!("{}/api/jobs", base))
+        .header("Origin", "http://dash.lan:5173")
+        .header("Access-Control-Request-Method", "POST")
+        .header("Access-Control-Request-Headers", "content-type")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["access-control-allow-origin"], "http://dash.lan:5173");
+
+    // Other origins get no CORS headers
+    let response = client.get(format!("{}/api/hosts", base)).header("Origin", "http://evil.lan").send().await.unwrap();
+    assert!(response.headers().get("access-control-allow-origin").is_none());
+}
+
+#[tokio::test]
+async fn websocket_upgrade_passes_through_cors() {
+    let base = serve_with_cors(&[("CORS_ALLOWED_ORIGINS", "http://dash.lan:5173")]).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/ws", base))
+        .header("Origin", "http://dash.lan:5173")
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(response.headers()["access-control-allow-credentials"], "true");
+}
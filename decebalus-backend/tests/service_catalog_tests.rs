@@ -0,0 +1,41 @@
+// tests/service_catalog_tests.rs
+
+mod common;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+
+use decebalus_backend::api::services::service_catalog;
+use decebalus_backend::db::repository;
+use decebalus_backend::models::CatalogEntry;
+
+#[tokio::test]
+async fn second_sighting_moves_last_seen_and_keeps_first_seen() {
+    let state = common::test_state().await;
+
+    repository::record_service_seen(state.db.as_ref().unwrap(), "http", Some("log4j 2.14.1"), "2024-01-01T10:00:00+00:00").await.unwrap();
+    repository::record_service_seen(state.db.as_ref().unwrap(), "ssh", None, "2024-01-01T10:00:00+00:00").await.unwrap();
+    repository::record_service_seen(state.db.as_ref().unwrap(), "http", Some("log4j 2.14.1"), "2024-03-01T10:00:00+00:00").await.unwrap();
+
+    let response = service_catalog(State(state.clone())).await.into_response();
+    assert!(response.status().is_success());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let catalog: Vec<CatalogEntry> = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(catalog, vec![
+        CatalogEntry {
+            name: "http".into(),
+            version: Some("log4j 2.14.1".into()),
+            first_seen: "2024-01-01T10:00:00+00:00".into(),
+            last_seen: "2024-03-01T10:00:00+00:00".into(),
+            sightings: 2,
+        },
+        CatalogEntry {
+            name: "ssh".into(),
+            version: None,
+            first_seen: "2024-01-01T10:00:00+00:00".into(),
+            last_seen: "2024-01-01T10:00:00+00:00".into(),
+            sightings: 1,
+        },
+    ]);
+}
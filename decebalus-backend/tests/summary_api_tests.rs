@@ -0,0 +1,98 @@
+// tests/summary_api_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use chrono::{Duration, Utc};
+
+use decebalus_backend::api::summary::get_summary;
+use decebalus_backend::models::{Host, Job, JobStatus, PortCount, Summary};
+use decebalus_backend::state::AppState;
+
+/// Three hosts (one last seen two days ago) and four jobs.
+async fn seed(state: &Arc<AppState>) {
+    let mut web = Host::new("10.0.0.1".into());
+    web.add_port(22, "tcp", "open", Some("ssh".into()), None, None);
+    web.add_port(443, "tcp", "open", Some("https".into()), None, None);
+    web.last_port_scan = Some("2024-05-01T12:00:00+00:00".into());
+
+    let mut nas = Host::new("10.0.0.2".into());
+    nas.add_port(22, "tcp", "open", Some("ssh".into()), None, None);
+    nas.add_port(445, "tcp", "open", Some("microsoft-ds".into()), None, None);
+
+    let mut stale = Host::new("10.0.0.3".into());
+    stale.add_port(22, "tcp", "open", None, None, None);
+    stale.add_port(8080, "tcp", "closed", None, None, None);
+    stale.last_seen = (Utc::now() - Duration::days(2)).to_rfc3339();
+
+    for host in [&web, &nas, &stale] {
+        state.repo.upsert_host(host).await.unwrap();
+    }
+
+    for (i, status) in [JobStatus::Completed, JobStatus::Completed, JobStatus::Failed, JobStatus::Queued].into_iter().enumerate() {
+        let mut job = Job::new("discovery".into());
+        job.id = format!("job{}", i);
+        job.status = status;
+        state.repo.create_job(&job).await.unwrap();
+    }
+}
+
+async fn fetch_summary(state: &Arc<AppState>) -> Summary {
+    let response = get_summary(State(state.clone())).await.into_response();
+    assert!(response.status().is_success());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn summary_counts_hosts_jobs_and_ports() {
+    let state = common::test_state().await;
+    seed(&state).await;
+
+    let summary = fetch_summary(&state).await;
+
+    assert_eq!(summary.total_hosts, 3);
+    assert_eq!(summary.hosts_seen_24h, 2);
+    assert_eq!(summary.jobs_by_status["completed"], 2);
+    assert_eq!(summary.jobs_by_status["failed"], 1);
+    assert_eq!(summary.jobs_by_status["queued"], 1);
+    assert_eq!(summary.jobs_by_status["running"], 0);
+    assert_eq!(summary.jobs_by_status.len(), JobStatus::ALL.len());
+    // Closed ports don't count
+    assert_eq!(summary.top_ports, vec![
+        PortCount { port: 22, protocol: "tcp".into(), hosts: 3 },
+        PortCount { port: 443, protocol: "tcp".into(), hosts: 1 },
+        PortCount { port: 445, protocol: "tcp".into(), hosts: 1 },
+    ]);
+    // The newest host sighting beats the older port scan
+    let last_scan = summary.last_scan_at.unwrap();
+    assert!(last_scan.as_str() > "2024-05-01T12:00:00+00:00", "{}", last_scan);
+}
+
+#[tokio::test]
+async fn in_memory_summary_matches_sqlite() {
+    let sqlite = common::test_state().await;
+    let memory = common::in_memory_state();
+    seed(&sqlite).await;
+    seed(&memory).await;
+
+    let (from_sqlite, from_memory) = (fetch_summary(&sqlite).await, fetch_summary(&memory).await);
+    assert_eq!(from_memory.total_hosts, from_sqlite.total_hosts);
+    assert_eq!(from_memory.hosts_seen_24h, from_sqlite.hosts_seen_24h);
+    assert_eq!(from_memory.jobs_by_status, from_sqlite.jobs_by_status);
+    assert_eq!(from_memory.top_ports, from_sqlite.top_ports);
+}
+
+#[tokio::test]
+async fn empty_database_has_an_empty_summary() {
+    let summary = fetch_summary(&common::test_state().await).await;
+
+    assert_eq!(summary.total_hosts, 0);
+    assert_eq!(summary.hosts_seen_24h, 0);
+    assert!(summary.jobs_by_status.values().all(|&count| count == 0));
+    assert!(summary.top_ports.is_empty());
+    assert_eq!(summary.last_scan_at, None);
+}
@@ -0,0 +1,272 @@
+// tests/vuln_scan_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use tokio::sync::Notify;
+
+use decebalus_backend::models::{Host, Job, JobStatus, JobsConfig, Service, Vulnerability, WsEvent};
+use decebalus_backend::services::job_executor::JobExecutor;
+use decebalus_backend::services::job_watchdog::JobWatchdog;
+use decebalus_backend::services::vuln_lookup::{CveSource, VulnLookup};
+use decebalus_backend::services::ScanError;
+use decebalus_backend::state::AppState;
+
+/// Knows one CVE for OpenSSH 7.2p2 and counts how often it's asked.
+#[derive(Default)]
+struct StubCveSource {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl CveSource for StubCveSource {
+    async fn lookup(&self, product: &str, version: &str) -> Result<Vec<Vulnerability>, ScanError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if product == "OpenSSH" && version == "7.2p2" {
+            return Ok(vec![Vulnerability {
+                id: "CVE-2016-6210".into(),
+                description: "sshd in OpenSSH before 7.3 allows user enumeration via timing".into(),
+                severity: "MEDIUM".into(),
+                cvss_score: Some(5.9),
+                service: None,
+            }]);
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Answers only once released, to hold a vuln-scan mid-run.
+#[derive(Default)]
+struct BlockingCveSource {
+    started: Notify,
+    release: Notify,
+}
+
+#[async_trait]
+impl CveSource for BlockingCveSource {
+    async fn lookup(&self, _product: &str, _version: &str) -> Result<Vec<Vulnerability>, ScanError> {
+        self.started.notify_one();
+        self.release.notified().await;
+        Ok(Vec::new())
+    }
+}
+
+/// Lookups of nginx fail; everything else is answered like `StubCveSource`.
+#[derive(Default)]
+struct FlakyCveSource {
+    stub: StubCveSource,
+}
+
+#[async_trait]
+impl CveSource for FlakyCveSource {
+    async fn lookup(&self, product: &str, version: &str) -> Result<Vec<Vulnerability>, ScanError> {
+        if product == "nginx" {
+            return Err(ScanError::CveLookup("NVD returned HTTP 503".into()));
+        }
+        self.stub.lookup(product, version).await
+    }
+}
+
+fn host_with(ip: &str, services: Vec<Service>) -> Host {
+    let mut host = Host::new(ip.into());
+    host.services = services;
+    host
+}
+
+async fn run(state: &Arc<AppState>, job: Job) -> Job {
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    JobExecutor::execute_job(job.clone(), state.clone(), permit).await;
+    state.repo.get_job(&job.id).await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn vuln_scan_attaches_cves_for_known_service_versions() {
+    let source = Arc::new(StubCveSource::default());
+    let base = common::in_memory_state();
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(source.clone())), ..(*base).clone() });
+
+    let openssh = Service::new("ssh", Some("OpenSSH 7.2p2".into()), Some("Ubuntu-4ubuntu2.10".into()));
+    let nginx = Service::new("http", Some("nginx/1.18.0 (Ubuntu)".into()), None);
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![openssh.clone(), nginx])).await.unwrap();
+    state.repo.upsert_host(&host_with("10.0.0.6", vec![openssh])).await.unwrap();
+    // No version to match on: never looked up
+    state.repo.upsert_host(&host_with("10.0.0.7", vec![Service::new("smtp", None, None)])).await.unwrap();
+
+    let job = run(&state, Job::new("vuln-scan".into())).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.results);
+    let results = job.results.unwrap().success().unwrap().clone();
+    assert_eq!(results["hosts_scanned"], 3);
+    assert_eq!(results["vulnerabilities_found"], 2);
+
+    let host = state.repo.get_host("10.0.0.5").await.unwrap().unwrap();
+    assert_eq!(host.vulnerabilities.len(), 1);
+    let vuln = &host.vulnerabilities[0];
+    assert_eq!(vuln.id, "CVE-2016-6210");
+    assert_eq!(vuln.cvss_score, Some(5.9));
+    assert_eq!(vuln.service.as_deref(), Some("OpenSSH 7.2p2"));
+    assert!(state.repo.get_host("10.0.0.7").await.unwrap().unwrap().vulnerabilities.is_empty());
+
+    // OpenSSH on the second host came from the cache
+    assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn vuln_scan_of_a_single_target_leaves_other_hosts_alone() {
+    let source = Arc::new(StubCveSource::default());
+    let base = common::test_state().await;
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(source.clone())), ..(*base).clone() });
+
+    let openssh = Service::new("ssh", Some("OpenSSH 7.2p2".into()), None);
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![openssh.clone()])).await.unwrap();
+    state.repo.upsert_host(&host_with("10.0.0.6", vec![openssh])).await.unwrap();
+
+    let mut job = Job::new("vuln-scan".into());
+    job.config = serde_json::json!({ "target": "10.0.0.6" });
+    assert_eq!(run(&state, job).await.status, JobStatus::Completed);
+
+    assert!(state.repo.get_host("10.0.0.5").await.unwrap().unwrap().vulnerabilities.is_empty());
+    assert_eq!(state.repo.get_host("10.0.0.6").await.unwrap().unwrap().vulnerabilities.len(), 1);
+}
+
+#[tokio::test]
+async fn vuln_scan_announces_only_new_findings() {
+    let base = common::in_memory_state();
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(Arc::new(StubCveSource::default()))), ..(*base).clone() });
+    let openssh = Service::new("ssh", Some("OpenSSH 7.2p2".into()), None);
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![openssh])).await.unwrap();
+
+    let mut events = state.broadcaster.subscribe();
+    assert_eq!(run(&state, Job::new("vuln-scan".into())).await.status, JobStatus::Completed);
+    // The same CVE on a rescan isn't new
+    assert_eq!(run(&state, Job::new("vuln-scan".into())).await.status, JobStatus::Completed);
+
+    let mut found = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let WsEvent::VulnerabilityFound { ip, id, severity, .. } = event {
+            found.push((ip, id, severity));
+        }
+    }
+    assert_eq!(found, vec![("10.0.0.5".to_string(), "CVE-2016-6210".to_string(), "MEDIUM".to_string())]);
+}
+
+#[tokio::test]
+async fn vuln_scan_skips_services_whose_lookup_fails() {
+    let base = common::in_memory_state();
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(Arc::new(FlakyCveSource::default()))), ..(*base).clone() });
+    let nginx = Service::new("http", Some("nginx/1.18.0".into()), None);
+    let openssh = Service::new("ssh", Some("OpenSSH 7.2p2".into()), None);
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![nginx, openssh])).await.unwrap();
+
+    let job = run(&state, Job::new("vuln-scan".into())).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.results);
+
+    let host = state.repo.get_host("10.0.0.5").await.unwrap().unwrap();
+    assert_eq!(host.vulnerabilities.len(), 1);
+    assert_eq!(host.vulnerabilities[0].id, "CVE-2016-6210");
+    let logs = state.repo.get_logs_by_job_id(job.id.clone()).await.unwrap();
+    assert!(logs.iter().any(|l| l.severity == "WARN" && l.content.contains("CVE lookup for http nginx/1.18.0 failed")));
+}
+
+#[tokio::test]
+async fn vuln_scan_keeps_host_changes_made_while_it_runs() {
+    let source = Arc::new(BlockingCveSource::default());
+    let base = common::test_state().await;
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(source.clone())), ..(*base).clone() });
+    let openssh = Service::new("ssh", Some("OpenSSH 7.2p2".into()), None);
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![openssh.clone()])).await.unwrap();
+
+    let job = Job::new("vuln-scan".into());
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    let task = tokio::spawn(JobExecutor::execute_job(job.clone(), state.clone(), permit));
+
+    // A port scan and an attack job save the host while the lookup is pending
+    source.started.notified().await;
+    let mut changed = host_with("10.0.0.5", vec![openssh]);
+    changed.add_port(443, "tcp", "open", Some("https".into()), None, None);
+    changed.vulnerabilities.push(Vulnerability {
+        id: "DEFAULT-CREDS-SSH".into(),
+        description: "SSH accepts default credentials".into(),
+        severity: "CRITICAL".into(),
+        cvss_score: None,
+        service: None,
+    });
+    state.repo.upsert_host(&changed).await.unwrap();
+    source.release.notify_one();
+    task.await.unwrap();
+
+    assert_eq!(state.repo.get_job(&job.id).await.unwrap().unwrap().status, JobStatus::Completed);
+    let host = state.repo.get_host("10.0.0.5").await.unwrap().unwrap();
+    assert!(host.has_open_port(443));
+    assert_eq!(host.vulnerabilities.len(), 1);
+    assert_eq!(host.vulnerabilities[0].id, "DEFAULT-CREDS-SSH");
+}
+
+#[tokio::test]
+async fn job_failed_by_the_watchdog_stays_failed() {
+    let source = Arc::new(BlockingCveSource::default());
+    let base = common::in_memory_state();
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(source.clone())), ..(*base).clone() });
+    // Different versions, so the second host would need another (blocking) lookup
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![Service::new("ssh", Some("OpenSSH 7.2p2".into()), None)])).await.unwrap();
+    state.repo.upsert_host(&host_with("10.0.0.6", vec![Service::new("ssh", Some("OpenSSH 8.9p1".into()), None)])).await.unwrap();
+
+    let job = Job::new("vuln-scan".into());
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    let task = tokio::spawn(JobExecutor::execute_job(job.clone(), state.clone(), permit));
+
+    // Give up on it while it is busy with the first host
+    source.started.notified().await;
+    let cfg = JobsConfig { stuck_after_secs: Some(60), ..Default::default() };
+    let failed = JobWatchdog::fail_stuck(state.repo.as_ref(), &cfg, chrono::Utc::now() + chrono::Duration::hours(1)).await.unwrap();
+    assert_eq!(failed.len(), 1);
+    source.release.notify_one();
+    task.await.unwrap();
+
+    // The task stopped before the second host and didn't overwrite the failure
+    let job = state.repo.get_job(&job.id).await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.results.unwrap().error().unwrap().contains("watchdog"));
+}
+
+#[tokio::test]
+async fn hung_job_without_progress_is_failed_by_the_watchdog() {
+    let source = Arc::new(BlockingCveSource::default());
+    let base = common::in_memory_state();
+    let state = Arc::new(AppState { vulns: Arc::new(VulnLookup::new(source.clone())), ..(*base).clone() });
+    state.repo.upsert_host(&host_with("10.0.0.5", vec![Service::new("ssh", Some("OpenSSH 7.2p2".into()), None)])).await.unwrap();
+    let mut config = state.repo.get_config().await.unwrap();
+    config.set("jobs".into(), serde_json::json!({ "stuck_after_secs": 1, "watchdog_interval_secs": 1 }));
+    state.repo.update_config(&config).await.unwrap();
+    let mut events = state.broadcaster.subscribe();
+
+    // The lookup is never answered: the job neither returns nor reports progress
+    let job = Job::new("vuln-scan".into());
+    state.repo.create_job(&job).await.unwrap();
+    let permit = state.semaphore.clone().acquire_owned().await.unwrap();
+    let task = tokio::spawn(JobExecutor::execute_job(job.clone(), state.clone(), permit));
+    source.started.notified().await;
+    let watchdog = JobWatchdog::spawn(state.clone());
+
+    let failed = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            if let Ok(WsEvent::JobFailed { job_id, error }) = events.recv().await
+                && job_id == job.id
+            {
+                break error;
+            }
+        }
+    })
+    .await
+    .expect("watchdog never failed the hung job");
+    assert!(failed.contains("watchdog"));
+    assert_eq!(state.repo.get_job(&job.id).await.unwrap().unwrap().status, JobStatus::Failed);
+
+    watchdog.abort();
+    task.abort();
+}
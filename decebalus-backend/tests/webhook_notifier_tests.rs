@@ -0,0 +1,88 @@
+// tests/webhook_notifier_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+use decebalus_backend::db::repository;
+use decebalus_backend::models::{Config, WsEvent};
+use decebalus_backend::services::notifier::{NotificationHub, WebhookNotifier};
+
+#[derive(Clone)]
+struct MockHook {
+    tx: mpsc::UnboundedSender<Value>,
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+}
+
+async fn hook(State(mock): State<MockHook>, Json(body): Json<Value>) -> StatusCode {
+    if mock.calls.fetch_add(1, Ordering::SeqCst) < mock.failures {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    let _ = mock.tx.send(body);
+    StatusCode::OK
+}
+
+/// HTTP server answering `POST /hook` with 500 for the first `failures` requests,
+/// then 200. Every payload it accepts is forwarded to the returned channel.
+async fn mock_webhook_server(failures: usize) -> (String, mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let app = Router::new()
+        .route("/hook", post(hook))
+        .with_state(MockHook { tx, calls: Arc::new(AtomicUsize::new(0)), failures });
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (url, rx)
+}
+
+async fn enable_webhook(state: &Arc<decebalus_backend::AppState>, url: &str) {
+    let mut config = Config::new();
+    config.set("webhooks".to_string(), json!({ "urls": [url] }));
+    config.set("integrations".to_string(), json!({ "notifiers": ["webhook"] }));
+    repository::update_config(state.db.as_ref().unwrap(), &config).await.unwrap();
+
+    state.notifiers.register(Arc::new(WebhookNotifier::new(state.repo.clone())));
+    NotificationHub::spawn(state.clone());
+}
+
+#[tokio::test]
+async fn job_completed_is_posted_to_webhook() {
+    let state = common::test_state().await;
+    let (url, mut received) = mock_webhook_server(0).await;
+    enable_webhook(&state, &url).await;
+
+    // Progress chatter is skipped, the completion is delivered
+    state.broadcaster.send(WsEvent::ScanProgress { job_id: "job-7".into(), message: "50%".into() }).unwrap();
+    state.broadcaster.send(WsEvent::JobCompleted { job_id: "job-7".into() }).unwrap();
+
+    let payload = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .expect("no webhook received")
+        .unwrap();
+    assert_eq!(payload, json!({ "type": "job_completed", "job_id": "job-7" }));
+}
+
+#[tokio::test]
+async fn failed_webhook_is_retried() {
+    let state = common::test_state().await;
+    let (url, mut received) = mock_webhook_server(1).await;
+    enable_webhook(&state, &url).await;
+
+    state.broadcaster.send(WsEvent::HostRemoved { ip: "10.0.0.8".into() }).unwrap();
+
+    let payload = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .expect("webhook was not retried")
+        .unwrap();
+    assert_eq!(payload, json!({ "type": "host_removed", "ip": "10.0.0.8" }));
+}
@@ -0,0 +1,118 @@
+// tests/websocket_tests.rs
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use futures_util::{sink, stream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use decebalus_backend::api::websocket::serve;
+use decebalus_backend::models::WsEvent;
+use decebalus_backend::state::AppState;
+
+/// A fake client socket served by `serve`: frames sent on the first channel
+/// reach the server, the server's frames come out of the second. Returns once
+/// the server is subscribed to the broadcaster.
+async fn connect(
+    state: &Arc<AppState>,
+    since: Option<u64>,
+) -> (mpsc::UnboundedSender<Message>, mpsc::UnboundedReceiver<Message>, JoinHandle<()>) {
+    let (to_server, incoming) = mpsc::unbounded_channel::<Message>();
+    let (outgoing, frames) = mpsc::unbounded_channel::<Message>();
+    let receiver = stream::unfold(incoming, |mut rx| async move {
+        rx.recv().await.map(|msg| (Ok::<_, axum::Error>(msg), rx))
+    });
+    let sender = sink::unfold(outgoing, |tx, msg: Message| async move { tx.send(msg).map(|_| tx) });
+
+    let subscribers = state.broadcaster.receiver_count();
+    let server = tokio::spawn(serve(Box::pin(sender), Box::pin(receiver), state.clone(), since));
+    while state.broadcaster.receiver_count() == subscribers {
+        tokio::task::yield_now().await;
+    }
+    (to_server, frames, server)
+}
+
+/// Next text frame the server sent, as JSON.
+async fn next_frame(frames: &mut mpsc::UnboundedReceiver<Message>) -> serde_json::Value {
+    let msg = tokio::time::timeout(Duration::from_secs(5), frames.recv())
+        .await
+        .expect("no frame from server")
+        .expect("server went away");
+    match msg {
+        Message::Text(t) => serde_json::from_str(&t).unwrap(),
+        other => panic!("unexpected frame {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn client_subscribed_to_one_job_only_gets_that_jobs_events() {
+    let state = common::test_state().await;
+    let (to_server, mut frames, server) = connect(&state, None).await;
+
+    to_server.send(Message::Text(r#"{"subscribe":["job:abc123"]}"#.into())).unwrap();
+    let ack = next_frame(&mut frames).await;
+    assert_eq!(ack, serde_json::json!({ "type": "subscribed", "topics": ["job:abc123"] }));
+
+    state.broadcaster.send(WsEvent::JobRunning { job_id: "other".into() }).unwrap();
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.5".into() }).unwrap();
+    state.broadcaster.send(WsEvent::JobCompleted { job_id: "abc123".into() }).unwrap();
+
+    // Only the subscribed job's event comes through
+    let event = next_frame(&mut frames).await;
+    assert_eq!(event, serde_json::json!({ "seq": 3, "type": "job_completed", "job_id": "abc123" }));
+    assert!(frames.try_recv().is_err());
+
+    // Closing the client side ends the connection
+    drop(to_server);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn client_without_subscription_gets_every_event() {
+    let state = common::test_state().await;
+    let (_to_server, mut frames, _server) = connect(&state, None).await;
+
+    state.broadcaster.send(WsEvent::JobRunning { job_id: "other".into() }).unwrap();
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.5".into() }).unwrap();
+
+    assert_eq!(next_frame(&mut frames).await["type"], "job_running");
+    assert_eq!(next_frame(&mut frames).await["type"], "new_host");
+}
+
+#[tokio::test]
+async fn reconnecting_client_gets_the_events_it_missed() {
+    let state = common::test_state().await;
+    let (to_server, mut frames, server) = connect(&state, None).await;
+
+    state.broadcaster.send(WsEvent::JobQueued { job_id: "a".into(), job_type: "discovery".into() }).unwrap();
+    state.broadcaster.send(WsEvent::JobRunning { job_id: "a".into() }).unwrap();
+    assert_eq!(next_frame(&mut frames).await["seq"], 1);
+    let last_seen = next_frame(&mut frames).await["seq"].as_u64().unwrap();
+    assert_eq!(last_seen, 2);
+
+    // Connection drops; the job finishes meanwhile
+    drop(to_server);
+    server.await.unwrap();
+    let _ = state.broadcaster.send(WsEvent::JobCompleted { job_id: "a".into() });
+
+    // Picking up from the last seen event replays only what was missed, then goes live
+    let (_to_server, mut frames, _server) = connect(&state, Some(last_seen)).await;
+    assert_eq!(
+        next_frame(&mut frames).await,
+        serde_json::json!({ "seq": 3, "type": "job_completed", "job_id": "a" })
+    );
+    state.broadcaster.send(WsEvent::NewHost { ip: "10.0.0.5".into() }).unwrap();
+    assert_eq!(next_frame(&mut frames).await["seq"], 4);
+
+    // Without `since`, a new client gets the whole buffer first
+    let (_to_server, mut frames, _server) = connect(&state, None).await;
+    let mut seqs = Vec::new();
+    for _ in 0..4 {
+        seqs.push(next_frame(&mut frames).await["seq"].as_u64().unwrap());
+    }
+    assert_eq!(seqs, [1, 2, 3, 4]);
+}
@@ -22,21 +22,30 @@ export interface Host {
   banners: string[];
 }
 
+// Outcome of a job run: the payload it produced, or why it failed
+export type JobResult = { success: unknown } | { error: string };
+
 export interface Job {
   id: string;
   job_type: string;
   status: string;
   config: { target?: string; [key: string]: any };
-  results: string | null;
+  results: JobResult | null;
   created_at: string;
   scheduled_at: number | null;
 }
 
+// Set when the backend runs with API_TOKEN: localStorage.setItem('apiToken', '<token>')
+export const apiToken = (): string | null => localStorage.getItem('apiToken');
+
 async function req<T>(path: string, init?: RequestInit): Promise<T> {
-  const r = await fetch(`${BASE}${path}`, init);
+  const headers = new Headers(init?.headers);
+  const token = apiToken();
+  if (token) headers.set('Authorization', `Bearer ${token}`);
+  const r = await fetch(`${BASE}${path}`, { ...init, headers });
   if (!r.ok) {
-    const body = await r.json().catch(() => ({ error: r.statusText }));
-    throw new Error(body.error ?? r.statusText);
+    const body = await r.json().catch(() => ({}));
+    throw new Error(body.error?.message ?? r.statusText);
   }
   return r.json();
 }
@@ -51,10 +60,20 @@ export interface Log {
   content: string;
 }
 
+export interface Summary {
+  total_hosts: number;
+  hosts_seen_24h: number;
+  jobs_by_status: Record<string, number>;
+  top_ports: { port: number; protocol: string; hosts: number }[];
+  last_scan_at: string | null;
+}
+
+export const getSummary = () => req<Summary>('/summary');
+
 export const getLogs       = ()              => req<Log[]>('/logs');
 export const getLogsByJob  = (jobId: string) => req<Log[]>(`/logs/${encodeURIComponent(jobId)}`);
 
-export const getJobs  = ()           => req<Job[]>('/jobs');
+export const getJobs  = ()           => req<{ items: Job[] }>('/jobs?limit=200').then(p => p.items);
 export const getJob   = (id: string) => req<Job>(`/jobs/${id}`);
 export const getHosts = ()           => req<Host[]>('/hosts');
 export const getHost  = (ip: string) => req<Host>(`/hosts/${encodeURIComponent(ip)}`);
@@ -10,7 +10,7 @@ export interface WebSocketOptions {
  * A lightweight WebSocket wrapper with auto-reconnect and typed handlers.
  */
 export class WebSocketClient {
-  private url: string;
+  private url: string | (() => string);
   private ws: WebSocket | null = null;
   private onOpen: (event: Event) => void;
   private onMessage: (data: any) => void;
@@ -18,7 +18,8 @@ export class WebSocketClient {
   private reconnect: boolean;
   private reconnectDelay: number;
 
-  constructor(url: string, options: WebSocketOptions = {}) {
+  /** `url` can be a function, re-evaluated on every (re)connect. */
+  constructor(url: string | (() => string), options: WebSocketOptions = {}) {
     this.url = url;
     this.onOpen = options.onOpen ?? (() => {});
     this.onMessage = options.onMessage ?? (() => {});
@@ -29,7 +30,7 @@ export class WebSocketClient {
 
   /** Connect to the WebSocket server */
   public connect(): void {
-    this.ws = new WebSocket(this.url);
+    this.ws = new WebSocket(typeof this.url === "function" ? this.url() : this.url);
 
     this.ws.onopen = (event) => this.onOpen(event);
     this.ws.onmessage = (event) => {
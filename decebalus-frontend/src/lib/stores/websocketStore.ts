@@ -1,12 +1,24 @@
 import { writable } from 'svelte/store';
 import { WebSocketClient } from './websocket';
+import { apiToken } from '../api';
 
 export const connectionStatus = writable<'connected' | 'connecting' | 'disconnected'>('disconnected');
 
-// Every incoming WS message is pushed here. Pages subscribe to this store.
-export const wsMessages = writable<string | object | null>(null);
+// Server event, e.g. { type: 'job_completed', job_id: 'abc' }. Fields besides
+// `type` depend on the event.
+export interface WsEvent {
+  type: string;
+  seq?: number;
+  job_id?: string;
+  [field: string]: any;
+}
+
+// Every incoming WS event is pushed here. Pages subscribe to this store.
+export const wsMessages = writable<WsEvent | null>(null);
 
 let activeConnection: WebSocketClient | null = null;
+// Last event seen, so a reconnect only replays what was missed
+let lastSeq: number | null = null;
 
 export function connectWebSocket(): WebSocketClient {
   if (activeConnection && activeConnection.readyState === WebSocket.OPEN) {
@@ -14,12 +26,27 @@ export function connectWebSocket(): WebSocketClient {
   }
 
   // Relative URL works in dev (Vite proxies /ws) and in prod (served from same host)
-  const wsUrl = `ws://${window.location.host}/ws`;
-
-  const ws = new WebSocketClient(wsUrl, {
+  const scheme = window.location.protocol === 'https:' ? 'wss' : 'ws';
+  const wsUrl = `${scheme}://${window.location.host}/ws`;
+
+  const url = () => {
+    const params = new URLSearchParams();
+    if (lastSeq !== null) params.set('since', String(lastSeq));
+    const token = apiToken();
+    if (token) params.set('access_token', token);
+    const query = params.toString();
+    return query ? `${wsUrl}?${query}` : wsUrl;
+  };
+
+  const ws = new WebSocketClient(url, {
     onOpen:    () => connectionStatus.set('connected'),
     onClose:   () => connectionStatus.set('disconnected'),
-    onMessage: (data) => wsMessages.set(data),
+    onMessage: (data) => {
+      if (data && typeof data === 'object' && typeof data.type === 'string') {
+        if (typeof data.seq === 'number') lastSeq = data.seq;
+        wsMessages.set(data);
+      }
+    },
   });
 
   connectionStatus.set('connecting');
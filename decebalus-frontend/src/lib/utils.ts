@@ -1,3 +1,5 @@
+import type { JobResult } from './api';
+
 export function fmtDate(s: string): string {
   try {
     // SQLite stores UTC datetimes without a timezone marker — append Z so JS
@@ -11,7 +13,8 @@ export function fmtUnixTs(ts: number): string {
   try { return new Date(ts * 1000).toLocaleString(); } catch { return String(ts); }
 }
 
-export function fmtResults(raw: string | null): string {
-  if (!raw) return '';
-  try { return JSON.stringify(JSON.parse(raw), null, 2); } catch { return raw; }
+export function fmtResults(result: JobResult | null): string {
+  if (!result) return '';
+  if ('error' in result) return result.error;
+  return JSON.stringify(result.success, null, 2);
 }
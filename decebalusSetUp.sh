@@ -106,6 +106,7 @@ LOG_RETENTION_DAYS=30
 MAX_THREADS=5
 MAX_DISCOVER_THREADS=256
 MAX_SCAN_CONCURRENCY=500
+EXPORT_ALLOWED_DIRS=data
 EOF
     ok ".env created with defaults"
 fi